@@ -1,5 +1,5 @@
 //! Module containing artifacts that can be resolved and installed.
-mod sdist;
+pub(crate) mod sdist;
 
 /// Module for working with PyPA wheels. Contains the [`Wheel`] type, and related functionality.
 pub mod wheel;
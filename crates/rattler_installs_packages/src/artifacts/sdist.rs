@@ -41,6 +41,11 @@ pub trait SourceArtifact: Sync {
     /// for stree we move it
     /// as example this method is used by install_build_files
     fn extract_to(&self, work_dir: &Path) -> std::io::Result<()>;
+
+    /// The metadata that the artifact declares without building it, even if it is not reliable.
+    /// This is the `PKG-INFO` if its metadata version can declare dependencies, or otherwise the
+    /// `[project]` table of the `pyproject.toml` if it declares all dependencies statically.
+    fn declared_metadata(&self) -> Result<Option<WheelCoreMetadata>, SDistError>;
 }
 
 /// Represents a source tree which can be a simple directory on filesystem
@@ -72,6 +77,92 @@ impl STree {
         }
         Ok(())
     }
+
+    /// Returns the metadata of the source tree if it can be determined without building it. This
+    /// is the case if the source tree contains a `PKG-INFO` that implements PEP 643 or if its
+    /// `pyproject.toml` declares the version and all dependencies statically (PEP 621).
+    pub fn static_metadata(&self) -> Result<Option<(Vec<u8>, WheelCoreMetadata)>, SDistError> {
+        let location = self.lock_data().clone();
+
+        if let Ok(bytes) = fs::read(location.join("PKG-INFO")) {
+            let metadata = WheelCoreMetadata::try_from(bytes.as_slice())?;
+            if metadata.metadata_version.implements_pep643() {
+                return Ok(Some((bytes, metadata)));
+            }
+        }
+
+        let Ok(bytes) = fs::read(location.join("pyproject.toml")) else {
+            return Ok(None);
+        };
+        pyproject_static_metadata(bytes)
+    }
+}
+
+/// Parses the contents of a `pyproject.toml` and returns the metadata of its `[project]` table if
+/// it declares all dependencies statically.
+fn pyproject_static_metadata(
+    bytes: Vec<u8>,
+) -> Result<Option<(Vec<u8>, WheelCoreMetadata)>, SDistError> {
+    let source = String::from_utf8(bytes).map_err(|e| {
+        SDistError::PyProjectTomlParseError(format!(
+            "could not parse pyproject.toml (bad encoding): {}",
+            e
+        ))
+    })?;
+    let pyproject = pyproject_toml::PyProjectToml::new(&source).map_err(|e| {
+        SDistError::PyProjectTomlParseError(format!(
+            "could not parse pyproject.toml (bad toml): {}",
+            e
+        ))
+    })?;
+    let Some(bytes) = pyproject.project.as_ref().and_then(pyproject_metadata) else {
+        return Ok(None);
+    };
+    let metadata = WheelCoreMetadata::try_from(bytes.as_slice())?;
+    Ok(Some((bytes, metadata)))
+}
+
+/// Converts the `[project]` table of a `pyproject.toml` to core metadata. Returns `None` if the
+/// version, the python requirement or any of the dependencies are computed by the build backend.
+fn pyproject_metadata(project: &pyproject_toml::Project) -> Option<Vec<u8>> {
+    const STATIC_FIELDS: [&str; 4] = [
+        "version",
+        "requires-python",
+        "dependencies",
+        "optional-dependencies",
+    ];
+    let dynamic = project.dynamic.as_deref().unwrap_or_default();
+    if dynamic
+        .iter()
+        .any(|field| STATIC_FIELDS.contains(&field.as_str()))
+    {
+        return None;
+    }
+
+    let mut metadata = format!(
+        "Metadata-Version: 2.1\nName: {}\nVersion: {}\n",
+        project.name,
+        project.version.as_ref()?
+    );
+    if let Some(requires_python) = &project.requires_python {
+        metadata.push_str(&format!("Requires-Python: {requires_python}\n"));
+    }
+    for requirement in project.dependencies.iter().flatten() {
+        metadata.push_str(&format!("Requires-Dist: {requirement}\n"));
+    }
+    for (extra, requirements) in project.optional_dependencies.iter().flatten() {
+        metadata.push_str(&format!("Provides-Extra: {extra}\n"));
+        for requirement in requirements {
+            // The requirement only applies if the extra is requested
+            let mut requirement = requirement.clone();
+            let marker = match requirement.marker.take() {
+                Some(marker) => format!("({marker}) and extra == \"{extra}\""),
+                None => format!("extra == \"{extra}\""),
+            };
+            metadata.push_str(&format!("Requires-Dist: {requirement} ; {marker}\n"));
+        }
+    }
+    Some(metadata.into_bytes())
 }
 
 /// Represents a source distribution artifact.
@@ -99,6 +190,9 @@ pub enum SDistError {
 
     #[error("Could not parse metadata")]
     WheelCoreMetaDataError(#[from] WheelCoreMetaDataError),
+
+    #[error("source distribution {name} appears corrupt (gzip/tar error): {detail}")]
+    Corrupt { name: String, detail: String },
 }
 
 impl SDist {
@@ -118,8 +212,22 @@ impl SDist {
         Self::new(name, Box::new(bytes))
     }
 
-    /// Find entry in tar archive
-    fn find_entry(&self, name: impl AsRef<Path>) -> std::io::Result<Option<Vec<u8>>> {
+    /// Find entry in tar archive. Failing to read the archive is reported as
+    /// [`SDistError::Corrupt`], e.g. if the download was truncated.
+    fn find_entry(&self, name: impl AsRef<Path>) -> Result<Option<Vec<u8>>, SDistError> {
+        self.find_archive_entry(name).map_err(|err| {
+            if err.kind() == ErrorKind::Unsupported {
+                SDistError::Io(err)
+            } else {
+                SDistError::Corrupt {
+                    name: self.name.to_string(),
+                    detail: err.to_string(),
+                }
+            }
+        })
+    }
+
+    fn find_archive_entry(&self, name: impl AsRef<Path>) -> std::io::Result<Option<Vec<u8>>> {
         let mut lock = self.file.lock();
         let archives = generic_archive_reader(&mut lock, self.name.format)?;
 
@@ -165,6 +273,12 @@ impl SDist {
         }
     }
 
+    /// Returns the contents of the `PKG-INFO` file of the archive without parsing them.
+    pub fn raw_package_info(&self) -> Result<Vec<u8>, SDistError> {
+        self.find_entry("PKG-INFO")?
+            .ok_or(SDistError::NoPkgInfoFound)
+    }
+
     /// Read .PKG-INFO from the archive
     pub fn read_package_info(&self) -> Result<(Vec<u8>, WheelCoreMetadata), SDistError> {
         if let Some(bytes) = self.find_entry("PKG-INFO")? {
@@ -269,6 +383,20 @@ impl SourceArtifact for SDist {
             }
         }
     }
+
+    fn declared_metadata(&self) -> Result<Option<WheelCoreMetadata>, SDistError> {
+        match self.read_package_info() {
+            Ok((_, metadata)) if metadata.metadata_version.declares_dependencies() => {
+                return Ok(Some(metadata))
+            }
+            Ok(_) | Err(SDistError::NoPkgInfoFound) => {}
+            Err(err) => return Err(err),
+        }
+        match self.find_entry("pyproject.toml")? {
+            Some(bytes) => Ok(pyproject_static_metadata(bytes)?.map(|(_, metadata)| metadata)),
+            None => Ok(None),
+        }
+    }
 }
 
 impl SourceArtifact for STree {
@@ -329,6 +457,20 @@ impl SourceArtifact for STree {
         let src = self.lock_data();
         Self::copy_dir_all(src.as_path(), work_dir)
     }
+
+    fn declared_metadata(&self) -> Result<Option<WheelCoreMetadata>, SDistError> {
+        let location = self.lock_data().clone();
+        if let Ok(bytes) = fs::read(location.join("PKG-INFO")) {
+            let metadata = WheelCoreMetadata::try_from(bytes.as_slice())?;
+            if metadata.metadata_version.declares_dependencies() {
+                return Ok(Some(metadata));
+            }
+        }
+        let Ok(bytes) = fs::read(location.join("pyproject.toml")) else {
+            return Ok(None);
+        };
+        Ok(pyproject_static_metadata(bytes)?.map(|(_, metadata)| metadata))
+    }
 }
 
 enum RawAndGzReader<'a> {
@@ -367,7 +509,7 @@ fn generic_archive_reader(
             Ok(Archives::Zip(Box::new(zip)))
         },
         unsupported_format => Err(std::io::Error::new(
-            ErrorKind::InvalidData,
+            ErrorKind::Unsupported,
             format!("sdist archive format currently {unsupported_format} unsupported (only tar | tar.gz | zip are supported)"),
         )),
     }
@@ -375,19 +517,22 @@ fn generic_archive_reader(
 
 #[cfg(test)]
 mod tests {
+    use super::SDistError;
     use crate::artifacts::{SDist, SourceArtifact};
     use crate::python_env::Pep508EnvMakers;
     use crate::resolve::PypiVersion;
     use crate::resolve::SDistResolution;
     use crate::types::Extra;
-    use crate::types::PackageName;
+    use crate::types::{Artifact, PackageName, SDistFilename};
     use crate::wheel_builder::WheelBuilder;
     use crate::{index::PackageDb, resolve::ResolveOptions};
+    use fs_err as fs;
     use insta::{assert_debug_snapshot, assert_ron_snapshot};
     use reqwest::Client;
     use reqwest_middleware::ClientWithMiddleware;
     use std::collections::{HashMap, HashSet};
     use std::env;
+    use std::io::Cursor;
     use std::path::Path;
     use std::str::FromStr;
     use std::sync::Arc;
@@ -445,6 +590,22 @@ mod tests {
         "###);
     }
 
+    #[test]
+    pub fn read_legacy_package_info() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../test-data/sdists/legacy-0.1.tar.gz");
+        let sdist = SDist::from_path(&path, &"legacy".parse().unwrap()).unwrap();
+
+        // A Metadata-Version 1.0 PKG-INFO can be read, but it is not reliable and cannot declare
+        // any dependencies
+        let (_, metadata) = sdist.read_package_info().unwrap();
+        assert_eq!(metadata.name.as_str(), "legacy");
+        assert_eq!(metadata.version.to_string(), "0.1");
+        assert_eq!(metadata.metadata_version.0.to_string(), "1.0");
+        assert!(sdist.pep643_metadata().unwrap().is_none());
+        assert!(sdist.declared_metadata().unwrap().is_none());
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     pub async fn sdist_metadata() {
         let path =
@@ -693,6 +854,34 @@ mod tests {
         assert_debug_snapshot!(init_file_text);
     }
 
+    #[test]
+    pub fn truncated_tar_gz_is_reported_as_corrupt() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../test-data/sdists/rich-13.6.0.tar.gz");
+        let bytes = fs::read(path).unwrap();
+        let truncated = |len: usize| {
+            let name = SDistFilename::from_filename("rich-13.6.0.tar.gz", &"rich".parse().unwrap())
+                .unwrap();
+            SDist::new(name, Box::new(Cursor::new(bytes[..len].to_vec()))).unwrap()
+        };
+
+        for err in [
+            truncated(200).read_package_info().err().unwrap(),
+            truncated(bytes.len() / 2)
+                .find_entry("does-not-exist.txt")
+                .err()
+                .unwrap(),
+        ] {
+            assert!(matches!(err, SDistError::Corrupt { .. }), "{err:?}");
+            assert!(
+                err.to_string().starts_with(
+                    "source distribution rich-13.6.0.tar.gz appears corrupt (gzip/tar error): "
+                ),
+                "{err}"
+            );
+        }
+    }
+
     #[tracing_test::traced_test]
     #[tokio::test(flavor = "multi_thread")]
     pub async fn build_wheel_with_backend_path() {
@@ -1,4 +1,4 @@
-use crate::python_env::{ByteCodeCompiler, CompilationError};
+use crate::python_env::{ByteCodeCompiler, CompilationError, TargetArch, TargetOs, TargetPlatform};
 use crate::types::DirectUrlJson;
 use crate::{
     python_env::PythonInterpreterVersion,
@@ -11,7 +11,7 @@ use crate::{
     types::WheelFilename,
     types::{Record, RecordEntry},
     types::{WheelCoreMetaDataError, WheelCoreMetadata},
-    utils::ReadAndSeek,
+    utils::{deflate64, ReadAndSeek},
 };
 use async_http_range_reader::AsyncHttpRangeReader;
 use async_zip::base::read::seek::ZipFileReader;
@@ -26,19 +26,24 @@ use std::io::{BufRead, BufReader};
 use std::sync::mpsc::channel;
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     collections::HashMap,
     collections::HashSet,
     ffi::OsStr,
     io::{Read, Write},
     iter::FromIterator,
+    ops::Range,
     path::{Component, Path, PathBuf},
     str::FromStr,
 };
 use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncSeek};
 use tokio_util::compat::TokioAsyncReadCompatExt;
-use zip::{result::ZipError, ZipArchive};
+use tokio_util::io::SyncIoBridge;
+use zip::{result::ZipError, CompressionMethod, ZipArchive};
 
-use crate::win::launcher::{build_windows_launcher, LauncherType, WindowsLauncherArch};
+use crate::win::launcher::build_windows_launcher;
+pub use crate::win::launcher::{LauncherType, WindowsLauncherArch};
 
 /// Wheel file in the PyPI ecosystem.
 /// See the [Reference Page](https://packaging.python.org/en/latest/specifications/binary-distribution-format/#binary-distribution-format)
@@ -139,39 +144,60 @@ impl Wheel {
         Ok(Some(candidate))
     }
 
-    async fn get_lazy_vitals(
+    async fn get_lazy_vitals<S: LazyReadStream>(
         name: &WheelFilename,
-        stream: &mut AsyncHttpRangeReader,
+        mut stream: S,
     ) -> Result<(Vec<u8>, WheelCoreMetadata), WheelVitalsError> {
         // Make sure we have the back part of the stream.
         // Best guess for the central directory size inside the zip
         const CENTRAL_DIRECTORY_SIZE: u64 = 16384;
         // Because the zip index is at the back
+        let len = stream.stream_len();
         stream
-            .prefetch(stream.len().saturating_sub(CENTRAL_DIRECTORY_SIZE)..stream.len())
+            .prefetch(len.saturating_sub(CENTRAL_DIRECTORY_SIZE)..len)
             .await;
 
+        match Self::read_lazy_metadata(name, &mut stream).await {
+            Err(WheelVitalsError::UnsupportedCompression(file, method)) => {
+                // The synchronous reader supports more compression methods, e.g. deflate64 and
+                // zstd.
+                tracing::debug!(
+                    "async zip reader does not support the {method} compression of '{file}' in {name}, falling back to the synchronous reader"
+                );
+                Self::read_lazy_metadata_blocking(name.clone(), stream).await
+            }
+            Err(WheelVitalsError::UnsupportedZip64(file)) => {
+                // The async zip reader does not understand every valid zip64 layout, fall back to
+                // the synchronous reader which does.
+                tracing::debug!(
+                    "async zip reader could not read zip64 records of '{file}' in {name}, falling back to the synchronous reader"
+                );
+                Self::read_lazy_metadata_blocking(name.clone(), stream).await
+            }
+            result => result,
+        }
+    }
+
+    /// Reads the METADATA file from a lazily fetched stream using the async zip reader. Only the
+    /// parts of the stream that are required are fetched.
+    async fn read_lazy_metadata<S: LazyReadStream>(
+        name: &WheelFilename,
+        stream: &mut S,
+    ) -> Result<(Vec<u8>, WheelCoreMetadata), WheelVitalsError> {
         // Construct a zip reader to uses the stream.
         let mut reader = ZipFileReader::new(stream.compat())
             .await
             .map_err(|err| WheelVitalsError::from_async_zip("/".into(), err))?;
 
-        // Collect all top-level filenames
-        let top_level_names = reader
-            .file()
-            .entries()
-            .iter()
-            .filter_map(|e| e.entry().filename().as_str().ok())
-            .map(|filename| {
-                filename
-                    .split_once(['/', '\\'])
-                    .map_or_else(|| filename, |(base, _)| base)
-            })
-            .collect::<HashSet<_>>();
-
         // Determine the name of the dist-info directory
         let dist_info = Wheel::find_special_wheel_dir(
-            top_level_names.iter().copied(),
+            top_level_names(
+                reader
+                    .file()
+                    .entries()
+                    .iter()
+                    .filter_map(|e| e.entry().filename().as_str().ok()),
+            ),
             &name.distribution,
             &name.version,
             ".dist-info",
@@ -227,25 +253,47 @@ impl Wheel {
             "fetched {} ranges, total of {} bytes, total file length {} ({}%)",
             ranges.len(),
             total_bytes_fetched,
-            stream.len(),
-            (total_bytes_fetched as f64 / stream.len() as f64 * 100000.0).round() / 100.0
+            stream.stream_len(),
+            (total_bytes_fetched as f64 / stream.stream_len() as f64 * 100000.0).round() / 100.0
         );
 
         Ok((contents, metadata))
     }
 
+    /// Reads the METADATA file from a lazily fetched stream using the synchronous zip reader. The
+    /// reader runs on a blocking task and fetches the parts of the stream it reads on demand.
+    async fn read_lazy_metadata_blocking<S: LazyReadStream>(
+        name: WheelFilename,
+        stream: S,
+    ) -> Result<(Vec<u8>, WheelCoreMetadata), WheelVitalsError> {
+        let handle = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || {
+            let mut archive = ZipArchive::new(SyncIoBridge::new_with_handle(stream, handle))
+                .map_err(|err| WheelVitalsError::from_zip("/".into(), err))?;
+
+            // Determine the name of the dist-info directory
+            let dist_info = Wheel::find_special_wheel_dir(
+                top_level_names(archive.file_names()),
+                &name.distribution,
+                &name.version,
+                ".dist-info",
+            )?
+            .ok_or(WheelVitalsError::DistInfoMissing)?
+            .to_owned();
+
+            let metadata_blob = read_entry_to_end(&mut archive, &format!("{dist_info}/METADATA"))?;
+            let metadata = WheelCoreMetadata::try_from(metadata_blob.as_slice())?;
+            Ok((metadata_blob, metadata))
+        })
+        .await
+        .map_err(std::io::Error::from)?
+    }
+
     fn get_vitals(&self) -> Result<WheelVitals, WheelVitalsError> {
         let mut archive = self.archive.lock();
 
         // Determine the top level filenames in the wheel
-        let top_level_names = archive
-            .file_names()
-            .map(|filename| {
-                filename
-                    .split_once(['/', '\\'])
-                    .map_or_else(|| filename, |(base, _)| base)
-            })
-            .collect::<HashSet<_>>();
+        let top_level_names = top_level_names(archive.file_names());
 
         // Determine the name of the dist-info directory
         let dist_info = Wheel::find_special_wheel_dir(
@@ -328,10 +376,43 @@ impl Wheel {
         Ok((metadata_blob, metadata))
     }
 
+    /// Returns the entry points of the wheel from its `entry_points.txt`, by group and sorted by
+    /// name, e.g. the commands in the `console_scripts` and `gui_scripts` groups or the plugins
+    /// in a group of another package. Returns an empty map if the wheel has no entry points.
+    pub fn entry_points(&self) -> Result<BTreeMap<String, Vec<EntryPoint>>, UnpackError> {
+        let vitals = self
+            .get_vitals()
+            .map_err(UnpackError::FailedToParseWheelVitals)?;
+        let mut archive = self.archive.lock();
+        read_entry_points(&mut archive, &vitals.dist_info)?
+            .into_iter()
+            .map(|(group, entries)| {
+                let mut entry_points = parse_entry_points_from_ini_section(entries, None)?;
+                entry_points.sort_by(|a, b| a.script_name.cmp(&b.script_name));
+                Ok((group, entry_points))
+            })
+            .collect()
+    }
+
+    /// Returns the contents of the `METADATA` file of the wheel without parsing them, e.g. to
+    /// inspect metadata that cannot be parsed with [`crate::types::MetadataHeaders`].
+    pub fn raw_metadata(&self) -> Result<Vec<u8>, WheelVitalsError> {
+        let mut archive = self.archive.lock();
+        let dist_info = Wheel::find_special_wheel_dir(
+            top_level_names(archive.file_names()),
+            &self.name.distribution,
+            &self.name.version,
+            ".dist-info",
+        )?
+        .ok_or(WheelVitalsError::DistInfoMissing)?
+        .to_owned();
+        read_entry_to_end(&mut archive, &format!("{dist_info}/METADATA"))
+    }
+
     /// Read metadata from bytes-stream
     pub async fn read_metadata_bytes(
         name: &WheelFilename,
-        stream: &mut AsyncHttpRangeReader,
+        stream: AsyncHttpRangeReader,
     ) -> miette::Result<(Vec<u8>, WheelCoreMetadata)> {
         Self::get_lazy_vitals(name, stream).await.into_diagnostic()
     }
@@ -380,6 +461,12 @@ pub enum WheelVitalsError {
     #[error("Failed to read the wheel file {0}: {1}")]
     AsyncZipError(String, #[source] async_zip::error::ZipError),
 
+    #[error("Failed to read the wheel file {0}: zip64 extended information is not supported by the async zip reader")]
+    UnsupportedZip64(String),
+
+    #[error("Failed to read the wheel file {0}: unsupported compression method {1}")]
+    UnsupportedCompression(String, String),
+
     #[error("missing key from WHEEL '{0}'")]
     MissingKeyInWheel(String),
 }
@@ -404,6 +491,12 @@ impl WheelVitalsError {
     pub(crate) fn from_async_zip(file: String, err: async_zip::error::ZipError) -> Self {
         match err {
             async_zip::error::ZipError::UpstreamReadError(err) => WheelVitalsError::IoError(err),
+            async_zip::error::ZipError::Zip64ExtendedFieldIncomplete => {
+                WheelVitalsError::UnsupportedZip64(file)
+            }
+            async_zip::error::ZipError::CompressionNotSupported(method) => {
+                WheelVitalsError::UnsupportedCompression(file, compression_method_name(method))
+            }
             _ => WheelVitalsError::AsyncZipError(file, err),
         }
     }
@@ -426,20 +519,135 @@ fn parse_format_metadata_and_check_version(
     Ok(parsed)
 }
 
+/// Returns the unique top-level directory (or file) names from the paths of the entries in a wheel.
+fn top_level_names<'a>(filenames: impl IntoIterator<Item = &'a str>) -> HashSet<&'a str> {
+    filenames
+        .into_iter()
+        .map(|filename| {
+            filename
+                .split_once(['/', '\\'])
+                .map_or_else(|| filename, |(base, _)| base)
+        })
+        .collect()
+}
+
+/// A stream of which parts can be fetched on demand, like an [`AsyncHttpRangeReader`]. This is
+/// used to read the metadata of a wheel without downloading the entire file.
+#[async_trait::async_trait]
+pub(crate) trait LazyReadStream: AsyncRead + AsyncSeek + Unpin + Send + 'static {
+    /// Returns the total length of the stream in bytes.
+    fn stream_len(&self) -> u64;
+
+    /// Ensures that the bytes in the given range are fetched.
+    async fn prefetch(&mut self, range: Range<u64>);
+
+    /// Returns the ranges that were fetched so far.
+    async fn requested_ranges(&self) -> Vec<Range<u64>>;
+}
+
+#[async_trait::async_trait]
+impl LazyReadStream for AsyncHttpRangeReader {
+    fn stream_len(&self) -> u64 {
+        self.len()
+    }
+
+    async fn prefetch(&mut self, range: Range<u64>) {
+        AsyncHttpRangeReader::prefetch(self, range).await
+    }
+
+    async fn requested_ranges(&self) -> Vec<Range<u64>> {
+        AsyncHttpRangeReader::requested_ranges(self).await
+    }
+}
+
 /// Helper method to read a particular file from a zip archive.
 fn read_entry_to_end<R: ReadAndSeek>(
     archive: &mut ZipArchive<R>,
     name: &str,
 ) -> Result<Vec<u8>, WheelVitalsError> {
-    let mut bytes = Vec::new();
-    archive
-        .by_name(name)
-        .map_err(|err| WheelVitalsError::from_zip(name.to_string(), err))?
-        .read_to_end(&mut bytes)?;
+    let err = match archive.by_name(name) {
+        Ok(mut entry) => {
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            return Ok(bytes);
+        }
+        Err(err) => err,
+    };
+
+    match err {
+        ZipError::UnsupportedArchive(_) => read_raw_entry_to_end(archive, name, err),
+        err => Err(WheelVitalsError::from_zip(name.to_string(), err)),
+    }
+}
+
+/// Reads a file from a zip archive that the zip reader refused to read. Files compressed with
+/// deflate64 are decompressed here, for other compression methods that are not supported the error
+/// names the method. If the compression method is supported, `err` is returned.
+fn read_raw_entry_to_end<R: ReadAndSeek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    err: ZipError,
+) -> Result<Vec<u8>, WheelVitalsError> {
+    // The dist-info files are usually stored at the end of the archive
+    let index = (0..archive.len())
+        .rev()
+        .find(|&idx| {
+            archive
+                .by_index_raw(idx)
+                .map_or(false, |entry| entry.name() == name)
+        })
+        .ok_or_else(|| WheelVitalsError::from_zip(name.to_string(), ZipError::FileNotFound))?;
+    let mut entry = archive
+        .by_index_raw(index)
+        .map_err(|err| WheelVitalsError::from_zip(name.to_string(), err))?;
+
+    #[allow(deprecated)]
+    let CompressionMethod::Unsupported(method) = entry.compression() else {
+        return Err(WheelVitalsError::from_zip(name.to_string(), err));
+    };
+    if entry.compression() != CompressionMethod::DEFLATE64 {
+        return Err(WheelVitalsError::UnsupportedCompression(
+            name.to_string(),
+            compression_method_name(method),
+        ));
+    }
+
+    let mut compressed = Vec::new();
+    entry.read_to_end(&mut compressed)?;
+    let bytes = deflate64::inflate(&compressed)?;
+    let mut crc = flate2::Crc::new();
+    crc.update(&bytes);
+    if crc.sum() != entry.crc32() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid checksum for {name}"),
+        )
+        .into());
+    }
 
     Ok(bytes)
 }
 
+/// Returns a human readable name of a zip compression method.
+fn compression_method_name(method: u16) -> String {
+    let name = match method {
+        0 => "stored",
+        1 => "shrink",
+        2..=5 => "reduce",
+        6 => "implode",
+        8 => "deflate",
+        9 => "deflate64",
+        10 => "PKWARE implode",
+        12 => "bzip2",
+        14 => "lzma",
+        93 => "zstd",
+        95 => "xz",
+        98 => "ppmd",
+        _ => return format!("{method}"),
+    };
+    format!("{name} ({method})")
+}
+
 /// A struct of installation categories to where they should be stored relative to the
 /// installation destination.
 #[derive(Debug, Clone)]
@@ -954,18 +1162,7 @@ fn write_script_entrypoint(
 
     for entry_point in entry_points {
         // Determine the name of the script
-        let script_name = if install_paths.is_windows() {
-            // Convert the entry point filename. We strip `.py` from the filename and add `.exe`.
-            Cow::Owned(format!(
-                "{}.exe",
-                entry_point
-                    .script_name
-                    .strip_suffix(".py")
-                    .unwrap_or(&entry_point.script_name)
-            ))
-        } else {
-            Cow::Borrowed(entry_point.script_name.as_str())
-        };
+        let script_name = launcher_file_name(entry_point, install_paths.is_windows());
 
         // Construct the trampoline
         let launch_script = entry_point.launch_script();
@@ -985,6 +1182,67 @@ fn write_script_entrypoint(
     Ok(())
 }
 
+/// Returns the filename of the launcher of an entry point. On windows the launcher is an
+/// executable, so `.py` is stripped from the name of the script and `.exe` is added.
+fn launcher_file_name(entry_point: &EntryPoint, is_windows: bool) -> Cow<'_, str> {
+    if is_windows {
+        Cow::Owned(format!(
+            "{}.exe",
+            entry_point
+                .script_name
+                .strip_suffix(".py")
+                .unwrap_or(&entry_point.script_name)
+        ))
+    } else {
+        Cow::Borrowed(entry_point.script_name.as_str())
+    }
+}
+
+/// The launcher of an entry point for a specific platform, see [`generate_launcher`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Launcher {
+    /// The filename of the launcher in the scripts directory
+    pub file_name: String,
+
+    /// The contents of the launcher: a script with a `#!` line on unix or an executable with the
+    /// script appended on windows
+    pub contents: Vec<u8>,
+}
+
+/// Generates the launcher that runs `entry_point` with the interpreter at `python_executable` on
+/// `platform`, the same launcher that is installed when unpacking a wheel for that platform.
+///
+/// On unix the launcher is a script that starts with `#!<python_executable>`. On windows it is
+/// the launcher executable of the architecture of `platform`, which runs the interpreter in a
+/// console or, for [`LauncherType::Gui`], without one.
+pub fn generate_launcher(
+    entry_point: &EntryPoint,
+    launcher_type: LauncherType,
+    python_executable: &Path,
+    platform: TargetPlatform,
+) -> Result<Launcher, UnpackError> {
+    let is_windows = platform.os == TargetOs::Windows;
+    let trampoline_maker = TrampolineMaker {
+        python_executable: python_executable.to_path_buf(),
+        kind: if is_windows {
+            TrampolineMakerKind::Windows {
+                arch: Some(match platform.arch {
+                    TargetArch::X86_64 => WindowsLauncherArch::X86_64,
+                    TargetArch::Aarch64 => WindowsLauncherArch::Arm64,
+                }),
+            }
+        } else {
+            TrampolineMakerKind::Unix
+        },
+    };
+
+    Ok(Launcher {
+        file_name: launcher_file_name(entry_point, is_windows).into_owned(),
+        contents: trampoline_maker
+            .make_trampoline(launcher_type, entry_point.launch_script().as_bytes())?,
+    })
+}
+
 /// An object that can be used to generate trampolines.
 ///
 /// Trampolines are executable that execute a certain python script using a certain python
@@ -1060,32 +1318,7 @@ impl Scripts {
         dist_info_prefix: &str,
         extras: Option<&HashSet<Extra>>,
     ) -> Result<Self, UnpackError> {
-        // Read the `entry_points.txt` file from the archive
-        let entry_points_path = format!("{dist_info_prefix}/entry_points.txt");
-        let mut entry_points_file = match archive.by_name(&entry_points_path) {
-            Err(ZipError::FileNotFound) => return Ok(Default::default()),
-            Ok(file) => file,
-            Err(err) => return Err(UnpackError::from_zip_error(entry_points_path, err)),
-        };
-
-        // Parse the `entry_points.txt` file as an ini file.
-        let mut entry_points_mapping = {
-            let mut ini_contents = String::new();
-            entry_points_file
-                .read_to_string(&mut ini_contents)
-                .map_err(|err| {
-                    UnpackError::EntryPointsInvalid(format!(
-                        "failed to read entry_points.txt contents: {}",
-                        err
-                    ))
-                })?;
-            Ini::new_cs().read(ini_contents).map_err(|err| {
-                UnpackError::EntryPointsInvalid(format!(
-                    "failed to parse entry_points.txt contents: {}",
-                    err
-                ))
-            })?
-        };
+        let mut entry_points_mapping = read_entry_points(archive, dist_info_prefix)?;
 
         // Parse the script entry points
         let console_scripts = entry_points_mapping
@@ -1132,6 +1365,38 @@ impl Scripts {
     }
 }
 
+/// Reads the `entry_points.txt` file from the wheel archive and returns its sections, which are the
+/// groups of entry points. Returns an empty map if the file does not exist.
+fn read_entry_points(
+    archive: &mut ZipArchive<Box<dyn ReadAndSeek + Send>>,
+    dist_info_prefix: &str,
+) -> Result<HashMap<String, HashMap<String, Option<String>>>, UnpackError> {
+    // Read the `entry_points.txt` file from the archive
+    let entry_points_path = format!("{dist_info_prefix}/entry_points.txt");
+    let mut entry_points_file = match archive.by_name(&entry_points_path) {
+        Err(ZipError::FileNotFound) => return Ok(Default::default()),
+        Ok(file) => file,
+        Err(err) => return Err(UnpackError::from_zip_error(entry_points_path, err)),
+    };
+
+    // Parse the `entry_points.txt` file as an ini file.
+    let mut ini_contents = String::new();
+    entry_points_file
+        .read_to_string(&mut ini_contents)
+        .map_err(|err| {
+            UnpackError::EntryPointsInvalid(format!(
+                "failed to read entry_points.txt contents: {}",
+                err
+            ))
+        })?;
+    Ini::new_cs().read(ini_contents).map_err(|err| {
+        UnpackError::EntryPointsInvalid(format!(
+            "failed to parse entry_points.txt contents: {}",
+            err
+        ))
+    })
+}
+
 /// Parse entry points from a section in the `entry_points.txt` file.
 fn parse_entry_points_from_ini_section(
     entry_points: HashMap<String, Option<String>>,
@@ -1291,6 +1556,57 @@ mod test {
 
     const INSTALLER: &str = "pixi_test";
 
+    #[test]
+    fn test_generate_launcher() {
+        let entry_point = EntryPoint::parse(String::from("blackd"), "blackd:patched_main", None)
+            .unwrap()
+            .unwrap();
+
+        let launcher = generate_launcher(
+            &entry_point,
+            LauncherType::Console,
+            Path::new("/usr/bin/python3"),
+            "linux-x86_64".parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(launcher.file_name, "blackd");
+        let script = String::from_utf8(launcher.contents).unwrap();
+        assert_eq!(
+            script,
+            format!("#!/usr/bin/python3\n{}", entry_point.launch_script())
+        );
+        assert!(script.contains("from blackd import patched_main"));
+
+        // On windows the launcher executable of the architecture is followed by the shebang and
+        // the zipped script
+        for (platform, launcher_type, arch) in [
+            (
+                "windows-amd64",
+                LauncherType::Console,
+                WindowsLauncherArch::X86_64,
+            ),
+            (
+                "windows-arm64",
+                LauncherType::Gui,
+                WindowsLauncherArch::Arm64,
+            ),
+        ] {
+            let launcher = generate_launcher(
+                &entry_point,
+                launcher_type,
+                Path::new(r"C:\Python311\python.exe"),
+                platform.parse().unwrap(),
+            )
+            .unwrap();
+            assert_eq!(launcher.file_name, "blackd.exe");
+            let executable = arch.launcher_bytes(launcher_type);
+            assert!(launcher.contents.starts_with(executable), "{platform}");
+            assert!(
+                launcher.contents[executable.len()..].starts_with(br"#!C:\Python311\python.exe")
+            );
+        }
+    }
+
     #[rstest]
     #[case("https://files.pythonhosted.org/packages/58/76/705b5c776f783d1ba7c630347463d4ae323282bbd859a8e9420c7ff79581/selenium-4.1.0-py3-none-any.whl", "27e7b64df961d609f3d57237caa0df123abbbe22d038f2ec9e332fb90ec1a939")]
     #[case("https://files.pythonhosted.org/packages/1e/27/47f73510c6b80d1ff0829474947537ae9ab8d516cc48c6320b7f3677fa54/selenium-2.53.2-py2.py3-none-any.whl", "fa8333cf3013497e60d87ba68cae65ead8e7fa208be88ab9c561556103f540ef")]
@@ -1320,6 +1636,221 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_wheel_entry_points() {
+        let wheel = Wheel::from_path(
+            &Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels/miniblack-23.1.0-py3-none-any.whl"),
+            &"miniblack".parse().unwrap(),
+        )
+        .unwrap();
+        let entry_points = wheel.entry_points().unwrap();
+        assert_eq!(entry_points.keys().collect::<Vec<_>>(), ["console_scripts"]);
+
+        // Entry points that require an extra are included as well
+        let scripts = entry_points["console_scripts"]
+            .iter()
+            .map(|e| {
+                (
+                    e.script_name.as_str(),
+                    e.module.as_str(),
+                    e.function.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            scripts,
+            [
+                ("black", "black", Some("patched_main")),
+                ("blackd", "blackd", Some("patched_main"))
+            ]
+        );
+
+        // A wheel without entry points
+        let wheel = Wheel::from_path(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join(
+                "../../test-data/wheels/purelib_and_platlib-1.0.0-cp38-cp38-linux_x86_64.whl",
+            ),
+            &"purelib_and_platlib".parse().unwrap(),
+        )
+        .unwrap();
+        assert!(wheel.entry_points().unwrap().is_empty());
+    }
+
+    /// Path to a wheel with the same contents as miniblack but written with zip64 extensions, both
+    /// in the local and central directory headers as well as a zip64 end of central directory.
+    fn zip64_wheel_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test-data/wheels/zip64/miniblack-23.1.0-py3-none-any.whl")
+    }
+
+    /// Path to a wheel with the same contents as miniblack but with every file compressed with
+    /// deflate64.
+    fn deflate64_wheel_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test-data/wheels/deflate64/miniblack-23.1.0-py3-none-any.whl")
+    }
+
+    /// Path to a wheel with the same contents as miniblack but with every file stored
+    /// uncompressed.
+    fn stored_wheel_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test-data/wheels/stored/miniblack-23.1.0-py3-none-any.whl")
+    }
+
+    /// An in-memory stream to exercise the lazy wheel reading without a server.
+    #[async_trait::async_trait]
+    impl LazyReadStream for std::io::Cursor<Vec<u8>> {
+        fn stream_len(&self) -> u64 {
+            self.get_ref().len() as u64
+        }
+
+        async fn prefetch(&mut self, _range: Range<u64>) {}
+
+        async fn requested_ranges(&self) -> Vec<Range<u64>> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn test_zip64_wheel_metadata() {
+        let path = zip64_wheel_path();
+        let wheel = Wheel::from_path(&path, &"miniblack".parse().unwrap()).unwrap();
+
+        let (_, metadata) = wheel.metadata().unwrap();
+        assert_eq!(metadata.name.as_str(), "miniblack");
+        assert_eq!(metadata.version, Version::from_str("23.1.0").unwrap());
+    }
+
+    #[rstest]
+    #[case("../../test-data/wheels/miniblack-23.1.0-py3-none-any.whl")]
+    #[case(zip64_wheel_path())]
+    #[case(deflate64_wheel_path())]
+    #[case(stored_wheel_path())]
+    #[tokio::test]
+    async fn test_lazy_wheel_metadata(#[case] path: PathBuf) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+        let name = WheelFilename::from_filename(
+            path.file_name().and_then(OsStr::to_str).unwrap(),
+            &"miniblack".parse().unwrap(),
+        )
+        .unwrap();
+        let stream = std::io::Cursor::new(fs::read(&path).unwrap());
+
+        let (blob, metadata) = Wheel::get_lazy_vitals(&name, stream).await.unwrap();
+        assert_eq!(metadata.name.as_str(), "miniblack");
+        assert_eq!(metadata.version, Version::from_str("23.1.0").unwrap());
+
+        // The metadata should be identical to what the eager reader returns
+        let wheel = Wheel::from_path(&path, &"miniblack".parse().unwrap()).unwrap();
+        assert_eq!(blob, wheel.metadata().unwrap().0);
+    }
+
+    #[tokio::test]
+    async fn test_compression_methods() {
+        let name = WheelFilename::from_filename(
+            "miniblack-23.1.0-py3-none-any.whl",
+            &"miniblack".parse().unwrap(),
+        )
+        .unwrap();
+        let expected = Wheel::from_path(
+            &Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels/miniblack-23.1.0-py3-none-any.whl"),
+            &"miniblack".parse().unwrap(),
+        )
+        .unwrap()
+        .metadata()
+        .unwrap()
+        .0;
+
+        // Deflate64 is decoded by us
+        let wheel =
+            Wheel::from_path(&deflate64_wheel_path(), &"miniblack".parse().unwrap()).unwrap();
+        assert_eq!(wheel.metadata().unwrap().0, expected);
+
+        // Zstd is only supported by the synchronous zip reader
+        let deflate64_bytes = fs::read(deflate64_wheel_path()).unwrap();
+        let mut archive = ZipArchive::new(std::io::Cursor::new(deflate64_bytes.clone())).unwrap();
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for idx in 0..archive.len() {
+            let name = archive.by_index_raw(idx).unwrap().name().to_owned();
+            let contents = read_entry_to_end(&mut archive, &name).unwrap();
+            writer
+                .start_file(
+                    name,
+                    zip::write::FileOptions::default().compression_method(CompressionMethod::Zstd),
+                )
+                .unwrap();
+            writer.write_all(&contents).unwrap();
+        }
+        let zstd_bytes = writer.finish().unwrap().into_inner();
+        let (blob, _) = Wheel::get_lazy_vitals(&name, std::io::Cursor::new(zstd_bytes))
+            .await
+            .unwrap();
+        assert_eq!(blob, expected);
+
+        // Other compression methods are reported by name, here lzma
+        let mut lzma_bytes = deflate64_bytes;
+        for idx in 0..archive.len() {
+            let entry = archive.by_index_raw(idx).unwrap();
+            lzma_bytes[entry.header_start() as usize + 8] = 14;
+            lzma_bytes[entry.central_header_start() as usize + 10] = 14;
+        }
+        let wheel = Wheel::new(
+            name.clone(),
+            Box::new(std::io::Cursor::new(lzma_bytes.clone())),
+        )
+        .unwrap();
+        assert_eq!(
+            wheel.metadata().unwrap_err().to_string(),
+            "Failed to read the wheel file miniblack-23.1.0.dist-info/WHEEL: unsupported compression method lzma (14)"
+        );
+        let err = Wheel::get_lazy_vitals(&name, std::io::Cursor::new(lzma_bytes))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to read the wheel file miniblack-23.1.0.dist-info/METADATA: unsupported compression method lzma (14)"
+        );
+    }
+
+    #[test]
+    fn test_stored_wheel() {
+        let path = stored_wheel_path();
+        let mut archive = ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(
+            archive
+                .by_name("miniblack-23.1.0.dist-info/METADATA")
+                .unwrap()
+                .compression(),
+            CompressionMethod::Stored
+        );
+
+        let wheel = Wheel::from_path(&path, &"miniblack".parse().unwrap()).unwrap();
+        let (_, metadata) = wheel.metadata().unwrap();
+        assert_eq!(metadata.name.as_str(), "miniblack");
+        assert_eq!(metadata.version, Version::from_str("23.1.0").unwrap());
+
+        // Extracting the stored files installs exactly the same files as the deflated wheel
+        let record = |path: &Path| {
+            let unpacked = unpack_wheel(path, &"miniblack".parse().unwrap(), None);
+            fs::read_to_string(
+                unpacked
+                    .tmpdir
+                    .path()
+                    .join(unpacked.dist_info.join("RECORD")),
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            record(&path),
+            record(
+                &Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("../../test-data/wheels/miniblack-23.1.0-py3-none-any.whl")
+            )
+        );
+    }
+
     struct UnpackedWheel {
         tmpdir: TempDir,
         _metadata: WheelCoreMetadata,
@@ -1399,7 +1930,7 @@ mod test {
             "b2df2c373e85871086bd55271c929670cd4e1dba63e94a08d442db830646203b").unwrap();
 
         let python_path = system_python_executable().unwrap();
-        let compiler = ByteCodeCompiler::new(&python_path).unwrap();
+        let compiler = ByteCodeCompiler::new(python_path).unwrap();
         let unpacked = unpack_wheel(&package_path, &"debugpy".parse().unwrap(), Some(&compiler));
 
         // Determine the location where we would expect the RECORD file to exist
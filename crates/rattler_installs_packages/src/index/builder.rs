@@ -0,0 +1,208 @@
+use super::{
+    IndexApi, KeyringAuthMiddleware, KeyringProvider, PackageDb, ProxyConfig, RateLimit,
+    RateLimitMiddleware, TlsConfig, TlsConfigError,
+};
+use miette::Diagnostic;
+use reqwest::Client;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+/// Constructs a [`PackageDb`] with named options, see [`PackageDb::builder`].
+///
+/// Unless a preconfigured http client is passed with [`PackageDbBuilder::client`], the builder
+/// constructs a client from the user agent, timeout, proxy, TLS, keyring and rate limit options.
+pub struct PackageDbBuilder {
+    cache_dir: PathBuf,
+    index_urls: Vec<Url>,
+    index_api: IndexApi,
+    refresh: bool,
+    find_links: Vec<PathBuf>,
+    client: Option<ClientWithMiddleware>,
+    user_agent: Option<String>,
+    timeout: Option<Duration>,
+    proxy_config: Option<ProxyConfig>,
+    tls_config: Option<TlsConfig>,
+    keyring_provider: KeyringProvider,
+    rate_limit: RateLimit,
+}
+
+/// An error that can occur when building a [`PackageDb`] with a [`PackageDbBuilder`].
+#[derive(Debug, Error, Diagnostic)]
+pub enum PackageDbBuildError {
+    /// Index urls must use http, https or file urls.
+    #[error("the index url '{0}' is not supported, only http, https and file urls are")]
+    UnsupportedIndexUrl(Url),
+
+    /// The options that configure the http client were combined with a preconfigured client.
+    #[error("the {0} cannot be set when a preconfigured http client is used")]
+    #[diagnostic(help("configure the http client directly instead"))]
+    ConflictingClientOption(&'static str),
+
+    /// The http client could not be constructed.
+    #[error("failed to construct the http client")]
+    HttpClient(#[source] reqwest::Error),
+
+    /// The certificates of the http client could not be loaded.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Tls(#[from] TlsConfigError),
+
+    /// The cache directories could not be created.
+    #[error("failed to create the cache directory")]
+    Io(#[from] std::io::Error),
+}
+
+impl PackageDbBuilder {
+    pub(crate) fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            cache_dir: cache_dir.into(),
+            index_urls: Vec::new(),
+            index_api: IndexApi::default(),
+            refresh: false,
+            find_links: Vec::new(),
+            client: None,
+            user_agent: None,
+            timeout: None,
+            proxy_config: None,
+            tls_config: None,
+            keyring_provider: KeyringProvider::default(),
+            rate_limit: RateLimit::default(),
+        }
+    }
+
+    /// Adds an index that is queried for packages. Indexes are queried in the order in which
+    /// they are added.
+    pub fn index_url(mut self, url: Url) -> Self {
+        self.index_urls.push(url);
+        self
+    }
+
+    /// Adds several indexes, see [`Self::index_url`].
+    pub fn index_urls(mut self, urls: impl IntoIterator<Item = Url>) -> Self {
+        self.index_urls.extend(urls);
+        self
+    }
+
+    /// Sets the API that is used to query the indexes, see [`IndexApi`].
+    pub fn index_api(mut self, index_api: IndexApi) -> Self {
+        self.index_api = index_api;
+        self
+    }
+
+    /// Always downloads the pages of the indexes again, see [`PackageDb::with_refresh`].
+    pub fn refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Adds local directories that are searched for wheels and source distributions, see
+    /// [`PackageDb::with_find_links`].
+    pub fn find_links(mut self, dirs: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.find_links.extend(dirs);
+        self
+    }
+
+    /// Use a preconfigured http client, e.g. with custom middleware. This cannot be combined with
+    /// the options that configure the client.
+    pub fn client(mut self, client: ClientWithMiddleware) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Sets the user agent that is sent with every request.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the timeout of a single request, from connecting until the body has been read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Routes requests through the proxies of `proxy_config`.
+    pub fn proxy_config(mut self, proxy_config: ProxyConfig) -> Self {
+        self.proxy_config = Some(proxy_config);
+        self
+    }
+
+    /// Uses the certificates of `tls_config` to establish TLS connections.
+    pub fn tls_config(mut self, tls_config: TlsConfig) -> Self {
+        self.tls_config = Some(tls_config);
+        self
+    }
+
+    /// Looks up credentials for the indexes with `keyring_provider`.
+    pub fn keyring_provider(mut self, keyring_provider: KeyringProvider) -> Self {
+        self.keyring_provider = keyring_provider;
+        self
+    }
+
+    /// Limits the rate of requests and the bandwidth that is used, see [`RateLimit`].
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Validates the options and constructs the [`PackageDb`].
+    pub fn build(self) -> Result<PackageDb, PackageDbBuildError> {
+        if let Some(url) = self
+            .index_urls
+            .iter()
+            .find(|url| !matches!(url.scheme(), "http" | "https" | "file"))
+        {
+            return Err(PackageDbBuildError::UnsupportedIndexUrl(url.clone()));
+        }
+
+        let client = match self.client {
+            Some(client) => {
+                let conflicting = [
+                    (self.user_agent.is_some(), "user agent"),
+                    (self.timeout.is_some(), "timeout"),
+                    (self.proxy_config.is_some(), "proxy configuration"),
+                    (self.tls_config.is_some(), "TLS configuration"),
+                    (
+                        self.keyring_provider != KeyringProvider::default(),
+                        "keyring provider",
+                    ),
+                    (!self.rate_limit.is_unlimited(), "rate limit"),
+                ];
+                if let Some((_, option)) = conflicting.into_iter().find(|(set, _)| *set) {
+                    return Err(PackageDbBuildError::ConflictingClientOption(option));
+                }
+                client
+            }
+            None => {
+                let mut builder = Client::builder();
+                if let Some(user_agent) = self.user_agent {
+                    builder = builder.user_agent(user_agent);
+                }
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(proxy_config) = self.proxy_config {
+                    builder = proxy_config.apply(builder);
+                }
+                if let Some(tls_config) = self.tls_config {
+                    builder = tls_config.apply(builder)?;
+                }
+                let client = builder.build().map_err(PackageDbBuildError::HttpClient)?;
+                let mut client = ClientBuilder::new(client)
+                    .with(KeyringAuthMiddleware::new(self.keyring_provider));
+                if !self.rate_limit.is_unlimited() {
+                    client = client.with(RateLimitMiddleware::new(self.rate_limit));
+                }
+                client.build()
+            }
+        };
+
+        Ok(PackageDb::new(client, &self.index_urls, &self.cache_dir)?
+            .with_index_api(self.index_api)
+            .with_refresh(self.refresh)
+            .with_find_links(self.find_links))
+    }
+}
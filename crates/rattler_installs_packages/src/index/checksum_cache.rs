@@ -0,0 +1,134 @@
+//! Remembers the sha256 hashes of cached files so that verifying them again is cheap.
+
+use fs_err as fs;
+use rattler_digest::{compute_bytes_digest, parse_digest_from_hex, Sha256, Sha256Hash};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Stores the sha256 hash that was computed for a cached file, next to the size and modification
+/// time of that file. As long as the size and the modification time of the file do not change
+/// the stored hash is reused instead of reading the whole file again.
+#[derive(Debug)]
+pub(crate) struct ChecksumCache {
+    dir: PathBuf,
+}
+
+/// The information that is stored for a single file.
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+struct ChecksumEntry {
+    size: u64,
+    modified_nanos: u128,
+    sha256: String,
+}
+
+impl ChecksumCache {
+    /// Constructs a new cache that stores its entries in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the hash that belongs to the file at `path`. The hash is computed with `compute` if
+    /// it is not known yet or if the file changed since the hash was stored.
+    ///
+    /// Failing to read or write the cache is not an error, the hash is simply computed.
+    pub fn get_or_compute(
+        &self,
+        path: &Path,
+        compute: impl FnOnce() -> io::Result<Sha256Hash>,
+    ) -> io::Result<Sha256Hash> {
+        let metadata = fs::metadata(path)?;
+        let size = metadata.len();
+        let modified_nanos = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default();
+
+        let entry_path = self.entry_path(path);
+        let cached = fs::read(&entry_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<ChecksumEntry>(&bytes).ok())
+            .filter(|entry| entry.size == size && entry.modified_nanos == modified_nanos)
+            .and_then(|entry| parse_digest_from_hex::<Sha256>(&entry.sha256));
+        if let Some(hash) = cached {
+            return Ok(hash);
+        }
+
+        let hash = compute()?;
+        let entry = ChecksumEntry {
+            size,
+            modified_nanos,
+            sha256: format!("{hash:x}"),
+        };
+        if let Err(err) = self.write_entry(&entry_path, &entry) {
+            tracing::warn!("failed to store the checksum of {}: {err}", path.display());
+        }
+        Ok(hash)
+    }
+
+    /// Forgets the hash of the file at `path`, e.g. because the file is replaced by a file with
+    /// the same size that may also have the same modification time.
+    pub fn remove(&self, path: &Path) {
+        let _ = fs::remove_file(self.entry_path(path));
+    }
+
+    /// Returns the path of the entry that stores the hash of the file at `path`.
+    fn entry_path(&self, path: &Path) -> PathBuf {
+        let key = compute_bytes_digest::<Sha256>(path.to_string_lossy().as_bytes());
+        self.dir.join(format!("{key:x}.json"))
+    }
+
+    /// Atomically writes an entry so that concurrent readers never see a partial entry.
+    fn write_entry(&self, entry_path: &Path, entry: &ChecksumEntry) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let mut file = tempfile::NamedTempFile::new_in(&self.dir)?;
+        file.write_all(&serde_json::to_vec(entry)?)?;
+        file.persist(entry_path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_checksum_is_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ChecksumCache::new(dir.path().join("checksums"));
+        let file = dir.path().join("artifact.whl");
+        fs::write(&file, b"wheel contents").unwrap();
+
+        let computations = Cell::new(0);
+        let checksum = || {
+            cache
+                .get_or_compute(&file, || {
+                    computations.set(computations.get() + 1);
+                    Ok(compute_bytes_digest::<Sha256>(fs::read(&file)?))
+                })
+                .unwrap()
+        };
+
+        // The hash is only computed the first time
+        let expected = compute_bytes_digest::<Sha256>(b"wheel contents");
+        assert_eq!(checksum(), expected);
+        assert_eq!(checksum(), expected);
+        assert_eq!(computations.get(), 1);
+
+        // Changing the file invalidates the stored hash
+        fs::write(&file, b"other wheel contents").unwrap();
+        assert_eq!(
+            checksum(),
+            compute_bytes_digest::<Sha256>(b"other wheel contents")
+        );
+        assert_eq!(computations.get(), 2);
+        assert_eq!(
+            checksum(),
+            compute_bytes_digest::<Sha256>(b"other wheel contents")
+        );
+        assert_eq!(computations.get(), 2);
+    }
+}
@@ -55,6 +55,12 @@ impl CacheKey for ArtifactHashes {
         if let Some(sha256) = &self.sha256 {
             path.push("sha256");
             path.push(bytes_to_path_suffix(sha256.as_slice()))
+        } else if let Some(sha384) = &self.sha384 {
+            path.push("sha384");
+            path.push(bytes_to_path_suffix(sha384.as_slice()))
+        } else if let Some(sha512) = &self.sha512 {
+            path.push("sha512");
+            path.push(bytes_to_path_suffix(sha512.as_slice()))
         } else {
             unreachable!("should never have an artifact hash without any hashes")
         }
@@ -115,6 +121,14 @@ impl FileStore {
         None
     }
 
+    /// Removes the entry with the specified key from the store, if it exists.
+    pub async fn remove<K: CacheKey>(&self, key: &K) -> io::Result<()> {
+        match self.lock_if_exists(key).await {
+            Some(lock) => lock.remove(),
+            None => Ok(()),
+        }
+    }
+
     /// Locks a certain file in the cache for exclusive access.
     pub async fn lock<K: CacheKey>(&self, key: &K) -> io::Result<FileLock> {
         let path = self.base.join(key.key());
@@ -222,6 +236,11 @@ pub struct FileLock {
 }
 
 impl FileLock {
+    /// Returns the path of the file that is locked.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     /// Creates a reader to read the contents of the locked file. Returns `None` if the file could
     /// not be opened.
     pub fn reader(&self) -> Option<LockedReader> {
@@ -240,10 +259,12 @@ impl FileLock {
         })
     }
 
-    /// Removes the file from the store.
+    /// Removes the file from the store, if it exists.
     pub fn remove(self) -> io::Result<()> {
-        fs::remove_file(self.path)?;
-        Ok(())
+        match fs::remove_file(self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
     }
 }
 
@@ -3,7 +3,7 @@ use std::fmt;
 use std::{
     fmt::{Display, Formatter},
     io::IsTerminal,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::Command,
     str::FromStr,
 };
@@ -12,7 +12,6 @@ use fs_extra::dir::remove;
 use miette::IntoDiagnostic;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use tempfile::TempDir;
 use url::Url;
 
 /// A Git repository URL or a local path to a Git repository
@@ -301,7 +300,9 @@ fn get_revision_sha(dest: &PathBuf, rev: Option<String>) -> Result<GitRev, Sourc
 }
 
 /// Fetch the git repository specified by the given source and place it in the cache directory.
-pub fn git_clone(source: &GitSource, tmp_dir: &TempDir) -> Result<PathBuf, SourceError> {
+///
+/// A remote repository that was cloned into `dir` before is updated instead of cloned again.
+pub fn git_clone(source: &GitSource, dir: &Path) -> Result<PathBuf, SourceError> {
     // test if git is available locally as we fetch the git from PATH,
     if !Command::new("git")
         .arg("--version")
@@ -314,8 +315,8 @@ pub fn git_clone(source: &GitSource, tmp_dir: &TempDir) -> Result<PathBuf, Sourc
         ));
     }
 
-    let cache_dir = tmp_dir.path().join("rip-git-cache");
-    let recipe_dir = tmp_dir.path().join("rip-clone-dir");
+    let cache_dir = dir.join("rip-git-cache");
+    let recipe_dir = dir.join("rip-clone-dir");
 
     let filename = match &source.url() {
         GitUrl::Url(url) => (|| Some(url.path_segments()?.last()?.to_string()))()
@@ -354,6 +355,15 @@ pub fn git_clone(source: &GitSource, tmp_dir: &TempDir) -> Result<PathBuf, Sourc
                 if !output.status.success() {
                     return Err(SourceError::GitErrorStr("Git clone failed for source"));
                 }
+            } else {
+                let output = git_command("fetch")
+                    .args(["--tags", "origin"])
+                    .current_dir(&cache_path)
+                    .output()
+                    .map_err(|_e| SourceError::GitErrorStr("Failed to execute fetch command"))?;
+                if !output.status.success() {
+                    return Err(SourceError::GitErrorStr("Git fetch failed for source"));
+                }
             }
         }
         GitUrl::Path(path) => {
@@ -1,4 +1,6 @@
 //! Module for parsing different HTML pages from PyPI repository
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::{borrow::Borrow, default::Default};
 
@@ -7,20 +9,94 @@ use miette::{miette, IntoDiagnostic};
 use pep440_rs::VersionSpecifiers;
 
 use rattler_digest::{parse_digest_from_hex, Sha256};
+use sha2::{Sha384, Sha512};
+use std::fmt::LowerHex;
 
 use tl::HTMLTag;
 use url::Url;
 
 use crate::types::{ArtifactInfo, DistInfoMetadata, ProjectInfo, Yanked};
 
-/// Parse a hash from url fragment
+/// Parse the hashes from a url fragment, e.g. `sha256=...&sha512=...`. Returns `None` if the
+/// fragment does not contain a valid hash of a supported algorithm.
 pub fn parse_hash(s: &str) -> Option<ArtifactHashes> {
-    if let Some(("sha256", hex)) = s.split_once('=') {
-        Some(ArtifactHashes {
-            sha256: parse_digest_from_hex::<Sha256>(hex),
+    // The fragment may contain other parameters as well (e.g. `sha256=...&egg=...`)
+    let mut hashes = ArtifactHashes::default();
+    for part in s.split('&') {
+        match part.split_once('=') {
+            Some(("sha256", hex)) => hashes.sha256 = parse_digest_from_hex::<Sha256>(hex),
+            Some(("sha384", hex)) => hashes.sha384 = parse_digest_from_hex::<Sha384>(hex),
+            Some(("sha512", hex)) => hashes.sha512 = parse_digest_from_hex::<Sha512>(hex),
+            _ => {}
+        }
+    }
+    (!hashes.is_empty()).then_some(hashes)
+}
+
+/// Combines two sets of hashes of the same artifact. `conflict` constructs the error if both
+/// contain a different hash with the same algorithm, it receives the name of the algorithm and
+/// both hex encoded hashes.
+fn combine_hashes(
+    a: ArtifactHashes,
+    b: ArtifactHashes,
+    conflict: impl Fn(&str, String, String) -> miette::Report,
+) -> miette::Result<ArtifactHashes> {
+    fn combine<T: PartialEq + LowerHex>(
+        algorithm: &str,
+        a: Option<T>,
+        b: Option<T>,
+        conflict: &impl Fn(&str, String, String) -> miette::Report,
+    ) -> miette::Result<Option<T>> {
+        match (a, b) {
+            (Some(a), Some(b)) if a != b => {
+                Err(conflict(algorithm, format!("{a:x}"), format!("{b:x}")))
+            }
+            (a, b) => Ok(a.or(b)),
+        }
+    }
+
+    Ok(ArtifactHashes {
+        sha256: combine("sha256", a.sha256, b.sha256, &conflict)?,
+        sha384: combine("sha384", a.sha384, b.sha384, &conflict)?,
+        sha512: combine("sha512", a.sha512, b.sha512, &conflict)?,
+    })
+}
+
+/// Combines the hashes found in the url fragment and in the `data-hash` attribute of a link. Both
+/// sources must agree if they specify a hash with the same algorithm.
+fn merge_hashes(
+    url: &Url,
+    fragment: Option<ArtifactHashes>,
+    attribute: Option<ArtifactHashes>,
+) -> miette::Result<Option<ArtifactHashes>> {
+    match (fragment, attribute) {
+        (Some(fragment), Some(attribute)) => combine_hashes(fragment, attribute, |algorithm, a, b| {
+            miette!(
+                "the {algorithm} hash in the url fragment of '{url}' ({a}) does not match the hash of its data-hash attribute ({b})"
+            )
         })
-    } else {
-        None
+        .map(Some),
+        (fragment, attribute) => Ok(fragment.or(attribute)),
+    }
+}
+
+/// Combines the hashes of an artifact that is listed more than once on a page. The entries must
+/// agree if they both specify a hash with the same algorithm.
+fn merge_duplicate_hashes(
+    existing: &ArtifactInfo,
+    duplicate: Option<ArtifactHashes>,
+) -> miette::Result<Option<ArtifactHashes>> {
+    match (existing.hashes.clone(), duplicate) {
+        (Some(existing_hashes), Some(duplicate)) => {
+            combine_hashes(existing_hashes, duplicate, |algorithm, a, b| {
+                miette!(
+                    "'{}' is listed more than once with different {algorithm} hashes ({a} and {b})",
+                    existing.filename
+                )
+            })
+            .map(Some)
+        }
+        (existing, duplicate) => Ok(existing.or(duplicate)),
     }
 }
 
@@ -28,12 +104,35 @@ fn into_artifact_info(
     base: &Url,
     normalized_package_name: &NormalizedPackageName,
     tag: &HTMLTag,
+) -> miette::Result<Option<ArtifactInfo>> {
+    let Some(artifact_info) = into_artifact_info_inner(base, normalized_package_name, tag) else {
+        return Ok(None);
+    };
+
+    let attribute_hash = tag
+        .attributes()
+        .get("data-hash")
+        .flatten()
+        .and_then(|a| parse_hash(a.as_utf8_str().as_ref()));
+    let hashes = merge_hashes(&artifact_info.url, artifact_info.hashes, attribute_hash)?;
+
+    Ok(Some(ArtifactInfo {
+        hashes,
+        ..artifact_info
+    }))
+}
+
+fn into_artifact_info_inner(
+    base: &Url,
+    normalized_package_name: &NormalizedPackageName,
+    tag: &HTMLTag,
 ) -> Option<ArtifactInfo> {
     let attributes = tag.attributes();
     // Get first href attribute to use as filename
     let href = attributes.get("href").flatten()?.as_utf8_str();
+    let href = html_escape::decode_html_entities(href.as_ref());
 
-    // Join with base
+    // Join with base, this turns relative hrefs into absolute urls
     let url = base.join(href.as_ref()).ok()?;
     let filename = url.path_segments().and_then(|mut s| s.next_back());
     let filename = filename
@@ -75,18 +174,17 @@ fn into_artifact_info(
         },
     };
 
-    let yanked_reason = attributes
-        .get("data-yanked")
-        .flatten()
-        .map(|a| a.as_utf8_str());
-    let yanked = match yanked_reason {
+    // PEP 592: the presence of the attribute marks the file as yanked, its value is the reason
+    let yanked = match attributes.get("data-yanked") {
         None => Yanked {
             yanked: false,
             reason: None,
         },
         Some(reason) => Yanked {
             yanked: true,
-            reason: Some(reason.to_string()),
+            reason: reason
+                .map(|reason| html_escape::decode_html_entities(&reason.as_utf8_str()).into_owned())
+                .filter(|reason| !reason.trim().is_empty()),
         },
     };
 
@@ -97,6 +195,7 @@ fn into_artifact_info(
         requires_python,
         dist_info_metadata,
         yanked,
+        index_url: None,
     })
 }
 
@@ -156,8 +255,11 @@ pub fn parse_project_info_html(base: &Url, body: &str) -> miette::Result<Project
         .and_then(|v| v.attributes().get("href"))
         // Get the version
         .and_then(|v| v.map(|v| v.as_utf8_str().to_string()))
-        // Parse the url
-        .and_then(|v| Url::parse(&v).ok())
+        // Parse the url, the base itself may be relative to the url of the page
+        .and_then(|v| {
+            base.join(html_escape::decode_html_entities(&v).as_ref())
+                .ok()
+        })
         // If we didn't find a base, use the one we were given
         .unwrap_or_else(|| base.clone());
 
@@ -167,11 +269,25 @@ pub fn parse_project_info_html(base: &Url, body: &str) -> miette::Result<Project
             .filter_map(|a| a.get(dom.parser()))
             .filter_map(|h| h.as_tag());
 
-        // Parse and add <a></a> tags
+        // Parse and add <a></a> tags. Some pages list the same file more than once, those
+        // entries are merged so they do not become redundant candidates.
+        let mut seen: HashMap<(String, Url), usize> = HashMap::new();
         for a in a_tags {
-            let artifact_info = into_artifact_info(&base, &normalized_package_name, a);
-            if let Some(artifact_info) = artifact_info {
-                project_info.files.push(artifact_info);
+            let Some(artifact_info) = into_artifact_info(&base, &normalized_package_name, a)?
+            else {
+                continue;
+            };
+            let mut url = artifact_info.url.clone();
+            url.set_fragment(None);
+            match seen.entry((artifact_info.filename.to_string(), url)) {
+                Entry::Occupied(entry) => {
+                    let existing = &mut project_info.files[*entry.get()];
+                    existing.hashes = merge_duplicate_hashes(existing, artifact_info.hashes)?;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(project_info.files.len());
+                    project_info.files.push(artifact_info);
+                }
             }
         }
     };
@@ -304,6 +420,225 @@ mod test {
         "###);
     }
 
+    #[test]
+    fn test_yanked() {
+        let parsed = parse_project_info_html(
+            &Url::parse("https://example.com/simple/link/").unwrap(),
+            r#"<html>
+                <body>
+                  <a href="link-1.0.tar.gz" data-yanked="security">link1</a>
+                  <a href="link-2.0.tar.gz" data-yanked>link2</a>
+                  <a href="link-3.0.tar.gz" data-yanked="">link3</a>
+                  <a href="link-4.0.tar.gz" data-yanked="broken &amp; insecure">link4</a>
+                  <a href="link-5.0.tar.gz">link5</a>
+                </body>
+              </html>
+            "#,
+        )
+        .unwrap();
+
+        let yanked = parsed
+            .files
+            .iter()
+            .map(|file| (file.yanked.yanked, file.yanked.reason.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            yanked,
+            [
+                (true, Some("security")),
+                (true, None),
+                (true, None),
+                (true, Some("broken & insecure")),
+                (false, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fragment_hashes() {
+        let base = Url::parse("https://example.com/simple/link/").unwrap();
+        let sha_a = "a".repeat(64);
+        let sha_b = "b".repeat(64);
+        let page = format!(
+            r#"<html>
+                <body>
+                  <a href="link-1.0.tar.gz#sha256={sha_a}">link1</a>
+                  <a href="link-2.0.tar.gz#egg=link&amp;sha256={sha_b}">link2</a>
+                  <a href="link-3.0.tar.gz#sha256={sha_a}" data-hash="sha256={sha_a}">link3</a>
+                  <a href="link-4.0.tar.gz" data-hash="sha256={sha_b}">link4</a>
+                  <a href="link-5.0.tar.gz#md5=0123">link5</a>
+                </body>
+              </html>
+            "#
+        );
+
+        let hashes = parse_project_info_html(&base, &page)
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|f| f.hashes.and_then(|h| h.sha256).map(|h| format!("{h:x}")))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hashes,
+            vec![
+                Some(sha_a.clone()),
+                Some(sha_b.clone()),
+                Some(sha_a.clone()),
+                Some(sha_b.clone()),
+                None
+            ]
+        );
+
+        // The fragment and the attribute disagree
+        let page = format!(
+            r#"<a href="link-1.0.tar.gz#sha256={sha_a}" data-hash="sha256={sha_b}">link1</a>"#
+        );
+        let err = parse_project_info_html(&base, &page).unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err}");
+    }
+
+    #[test]
+    fn test_multiple_hash_algorithms() {
+        let base = Url::parse("https://example.com/simple/link/").unwrap();
+        let sha256 = "a".repeat(64);
+        let sha512 = "b".repeat(128);
+
+        // The hashes of the fragment and the attribute are combined
+        let page = format!(
+            r#"<a href="link-1.0.tar.gz#sha512={sha512}" data-hash="sha256={sha256}">link1</a>"#
+        );
+        let hashes = parse_project_info_html(&base, &page).unwrap().files[0]
+            .hashes
+            .clone()
+            .unwrap();
+        assert_eq!(
+            hashes.hex_digests(),
+            [("sha256", sha256.clone()), ("sha512", sha512.clone())]
+        );
+        assert_eq!(
+            parse_hash(&format!("sha384={}&sha256={sha256}", "c".repeat(96)))
+                .unwrap()
+                .hex_digests()
+                .into_iter()
+                .map(|(algorithm, _)| algorithm)
+                .collect::<Vec<_>>(),
+            ["sha256", "sha384"]
+        );
+        assert_eq!(parse_hash("md5=0123&sha256=invalid"), None);
+
+        let page = format!(
+            r#"<a href="link-1.0.tar.gz#sha512={sha512}" data-hash="sha512={}">link1</a>"#,
+            "c".repeat(128)
+        );
+        let err = parse_project_info_html(&base, &page).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("the sha512 hash in the url fragment"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_links() {
+        let base = Url::parse("https://example.com/simple/link/").unwrap();
+        let sha_a = "a".repeat(64);
+        let sha_b = "b".repeat(64);
+
+        // Identical links and links that only add a hash are merged
+        let page = format!(
+            r#"<html>
+                <body>
+                  <a href="link-1.0.tar.gz">link1</a>
+                  <a href="link-1.0.tar.gz#sha256={sha_a}">link1</a>
+                  <a href="link-2.0.tar.gz#sha256={sha_b}">link2</a>
+                  <a href="/simple/link/link-2.0.tar.gz#sha256={sha_b}">link2</a>
+                  <a href="https://mirror.example.com/link-2.0.tar.gz#sha256={sha_b}">link2</a>
+                </body>
+              </html>
+            "#
+        );
+        let files = parse_project_info_html(&base, &page)
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|f| {
+                (
+                    f.url.to_string(),
+                    f.hashes.and_then(|h| h.sha256).map(|h| format!("{h:x}")),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            vec![
+                (
+                    "https://example.com/simple/link/link-1.0.tar.gz".to_string(),
+                    Some(sha_a.clone())
+                ),
+                (
+                    format!("https://example.com/simple/link/link-2.0.tar.gz#sha256={sha_b}"),
+                    Some(sha_b.clone())
+                ),
+                (
+                    format!("https://mirror.example.com/link-2.0.tar.gz#sha256={sha_b}"),
+                    Some(sha_b.clone())
+                ),
+            ]
+        );
+
+        // The same file with a different hash
+        let page = format!(
+            r#"<a href="link-1.0.tar.gz#sha256={sha_a}">link1</a>
+               <a href="link-1.0.tar.gz#sha256={sha_b}">link1</a>"#
+        );
+        let err = parse_project_info_html(&base, &page).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("'link-1.0.tar.gz' is listed more than once"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_relative_urls() {
+        let page = r#"<html>
+                <body>
+                  <a href="../../packages/link-1.0.tar.gz">link1</a>
+                  <a href="/packages/link-2.0.tar.gz">link2</a>
+                  <a href="link-3.0.tar.gz?a=1&amp;b=2">link3</a>
+                </body>
+              </html>
+            "#;
+        let urls = |base: &str, page: &str| {
+            parse_project_info_html(&Url::parse(base).unwrap(), page)
+                .unwrap()
+                .files
+                .into_iter()
+                .map(|f| f.url.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            urls("https://example.com/index/simple/link/", page),
+            vec![
+                "https://example.com/index/packages/link-1.0.tar.gz",
+                "https://example.com/packages/link-2.0.tar.gz",
+                "https://example.com/index/simple/link/link-3.0.tar.gz?a=1&b=2",
+            ]
+        );
+
+        // A relative base is resolved against the url of the page
+        let page = page.replace("<body>", r#"<head><base href="../../files/"></head><body>"#);
+        assert_eq!(
+            urls("https://example.com/index/simple/link/", &page),
+            vec![
+                "https://example.com/packages/link-1.0.tar.gz",
+                "https://example.com/packages/link-2.0.tar.gz",
+                "https://example.com/index/files/link-3.0.tar.gz?a=1&b=2",
+            ]
+        );
+    }
+
     #[test]
     fn test_package_name_parsing() {
         let html = r#"
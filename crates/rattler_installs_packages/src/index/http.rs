@@ -12,6 +12,7 @@ use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::io;
 use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::SystemTime;
@@ -29,6 +30,11 @@ pub enum CacheStatus {
     Uncacheable,
 }
 
+/// Attached to HTTP responses whose body is read from the cache, this is the path of the file in
+/// the cache that contains the body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry(pub PathBuf);
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 /// Different caching semantics that can be applied to a request.
 pub enum CacheMode {
@@ -38,8 +44,15 @@ pub enum CacheMode {
     OnlyIfCached,
     /// Don't look in cache, and don't write to cache
     NoStore,
+    /// Don't use a cached response but store the new response in the cache, this replaces a
+    /// cached response that turned out to be broken
+    Reload,
 }
 
+/// Called with the number of bytes of a response body that have been received so far and the
+/// size of the body if the server reported it.
+pub type DownloadProgress = Box<dyn FnMut(u64, Option<u64>) + Send>;
+
 #[derive(Debug, Clone)]
 pub struct Http {
     pub(crate) client: ClientWithMiddleware,
@@ -81,6 +94,20 @@ impl Http {
         method: Method,
         headers: HeaderMap,
         cache_mode: CacheMode,
+    ) -> Result<http::Response<StreamingOrLocal>, HttpRequestError> {
+        self.request_with_progress(url, method, headers, cache_mode, None)
+            .await
+    }
+
+    /// Performs a single request like [`Self::request`]. If the body is received from the
+    /// server, `progress` is called whenever a part of it has been received.
+    pub async fn request_with_progress(
+        &self,
+        url: Url,
+        method: Method,
+        headers: HeaderMap,
+        cache_mode: CacheMode,
+        progress: Option<DownloadProgress>,
     ) -> Result<http::Response<StreamingOrLocal>, HttpRequestError> {
         tracing::info!(url=%url, cache_mode=?cache_mode, "executing request");
 
@@ -92,9 +119,10 @@ impl Http {
             .build()?;
 
         if cache_mode == CacheMode::NoStore {
-            let mut response =
-                convert_response(self.client.execute(request).await?.error_for_status()?)
-                    .map(body_to_streaming_or_local);
+            let response = self.client.execute(request).await?.error_for_status()?;
+            let total = content_length(&response);
+            let mut response = convert_response(response)
+                .map(|body| body_to_streaming_or_local(report_progress(body, total, progress)));
 
             // Add the `CacheStatus` to the response
             response.extensions_mut().insert(CacheStatus::Uncacheable);
@@ -103,11 +131,22 @@ impl Http {
         } else {
             let key = key_for_request(&url, method, &headers);
             let lock = self.http_cache.lock(&key.as_slice()).await?;
+            let cache_entry = CacheEntry(lock.path().to_owned());
 
-            if let Some((old_policy, final_url, old_body)) = lock
-                .reader()
-                .and_then(|reader| read_cache(reader.detach_unlocked()).ok())
-            {
+            let cached = if cache_mode == CacheMode::Reload {
+                None
+            } else {
+                // A corrupt entry is treated as missing, it is replaced by the new response
+                lock.reader()
+                    .and_then(|reader| match read_cache(reader.detach_unlocked()) {
+                        Ok(cached) => Some(cached),
+                        Err(err) => {
+                            tracing::warn!(url=%url, "discarding corrupt http cache entry: {err}");
+                            None
+                        }
+                    })
+            };
+            let response = if let Some((old_policy, final_url, old_body)) = cached {
                 match old_policy.before_request(&request, SystemTime::now()) {
                     BeforeRequest::Fresh(parts) => {
                         tracing::debug!(url=%url, "is fresh");
@@ -130,10 +169,13 @@ impl Http {
                         // Perform the request with the new headers to determine if the cache is up
                         // to date or not.
                         let request = convert_request(self.client.clone(), new_parts)?;
+                        // A 304 is not an error, but any other error status must not replace the
+                        // cached response.
                         let response = self
                             .client
                             .execute(request.try_clone().expect("clone of request cannot fail"))
-                            .await?;
+                            .await?
+                            .error_for_status()?;
                         let final_url = response.url().clone();
 
                         // Determine what to do based on the response headers.
@@ -151,18 +193,17 @@ impl Http {
                             AfterResponse::Modified(new_policy, parts) => {
                                 tracing::debug!(url=%url, "stale, but *and* modified");
                                 drop(old_body);
+                                let total = content_length(&response);
+                                let body =
+                                    report_progress(response.bytes_stream(), total, progress);
                                 let new_body = if new_policy.is_storable() {
-                                    let new_body = fill_cache_async(
-                                        &new_policy,
-                                        &final_url,
-                                        response.bytes_stream(),
-                                        lock,
-                                    )
-                                    .await?;
+                                    let new_body =
+                                        fill_cache_async(&new_policy, &final_url, body, lock)
+                                            .await?;
                                     StreamingOrLocal::Local(Box::new(new_body))
                                 } else {
                                     lock.remove()?;
-                                    body_to_streaming_or_local(response.bytes_stream())
+                                    body_to_streaming_or_local(body)
                                 };
                                 Ok(make_response(
                                     parts,
@@ -185,7 +226,9 @@ impl Http {
                     .await?
                     .error_for_status()?;
                 let final_url = response.url().clone();
-                let response = convert_response(response);
+                let total = content_length(&response);
+                let response =
+                    convert_response(response).map(|body| report_progress(body, total, progress));
 
                 let new_policy = CachePolicy::new(&request, &response);
                 let (parts, body) = response.into_parts();
@@ -198,7 +241,15 @@ impl Http {
                     body_to_streaming_or_local(body)
                 };
                 Ok(make_response(parts, new_body, CacheStatus::Miss, final_url))
-            }
+            };
+
+            // A local body is always read from the cache
+            response.map(|mut response| {
+                if matches!(response.body(), StreamingOrLocal::Local(_)) {
+                    response.extensions_mut().insert(cache_entry);
+                }
+                response
+            })
         }
     }
 }
@@ -256,11 +307,38 @@ where
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
     let start = f.stream_position()?;
     let end = f.seek(SeekFrom::End(0))?;
+    if end < start + BODY_LEN_TRAILER_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "the entry is truncated",
+        ));
+    }
+    let end = end - BODY_LEN_TRAILER_SIZE;
+    f.seek(SeekFrom::Start(end))?;
+    let mut trailer = [0; BODY_LEN_TRAILER_SIZE as usize];
+    f.read_exact(&mut trailer)?;
+    let stored_len = u64::from_le_bytes(trailer);
+    if stored_len != end - start {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "the body is {} bytes but {stored_len} bytes were stored",
+                end - start
+            ),
+        ));
+    }
     let mut body = SeekSlice::new(f, start, end)?;
     body.rewind()?;
     Ok((data.policy, data.url, body))
 }
 
+/// The size of the trailer that stores the length of the body of a cache entry. The body is
+/// followed by its length as a little endian `u64` to detect entries that were truncated.
+///
+/// Older versions stored entries without the trailer, changing the layout of an entry requires a
+/// new cache directory, see [`super::PackageDb::new`].
+const BODY_LEN_TRAILER_SIZE: u64 = 8;
+
 #[derive(Serialize, Deserialize)]
 struct CacheData {
     policy: CachePolicy,
@@ -287,6 +365,7 @@ fn fill_cache<R: Read>(
     std::io::copy(&mut body, &mut cache_writer)?;
     drop(body);
     let body_end = cache_writer.stream_position()?;
+    cache_writer.write_all(&(body_end - body_start).to_le_bytes())?;
     let cache_entry = cache_writer.commit()?.detach_unlocked();
     SeekSlice::new(cache_entry, body_start, body_end)
 }
@@ -318,6 +397,7 @@ async fn fill_cache_async(
     }
 
     let body_end = cache_writer.stream_position()?;
+    cache_writer.write_all(&(body_end - body_start).to_le_bytes())?;
     let cache_entry = cache_writer.commit()?.detach_unlocked();
     SeekSlice::new(cache_entry, body_start, body_end)
 }
@@ -347,7 +427,6 @@ fn convert_response(
     // Take the headers from the response
     let headers = builder.headers_mut().unwrap();
     *headers = std::mem::take(response.headers_mut());
-    std::mem::swap(response.headers_mut(), headers);
 
     // Take the extensions from the response
     let extensions = builder.extensions_mut().unwrap();
@@ -359,6 +438,36 @@ fn convert_response(
         .expect("building should never fail")
 }
 
+/// Calls `progress` with the number of bytes that have been received whenever a part of the body
+/// is received.
+fn report_progress(
+    body: impl Stream<Item = reqwest::Result<Bytes>> + Send + Unpin + 'static,
+    total: Option<u64>,
+    mut progress: Option<DownloadProgress>,
+) -> impl Stream<Item = reqwest::Result<Bytes>> + Send + Unpin + 'static {
+    let mut received = 0;
+    body.inspect(move |chunk| {
+        if let (Ok(chunk), Some(progress)) = (chunk, progress.as_mut()) {
+            received += chunk.len() as u64;
+            progress(received, total);
+        }
+    })
+}
+
+/// Returns the size of the body of `response`. Middleware can replace the body with a stream of
+/// unknown size, e.g. to limit the bandwidth, so fall back to the `Content-Length` header.
+fn content_length(response: &reqwest::Response) -> Option<u64> {
+    response.content_length().or_else(|| {
+        response
+            .headers()
+            .get(http::header::CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    })
+}
+
 fn body_to_streaming_or_local(
     stream: impl Stream<Item = reqwest::Result<Bytes>> + Send + Unpin + 'static,
 ) -> StreamingOrLocal {
@@ -369,3 +478,209 @@ fn body_to_streaming_or_local(
             .compat(),
     ))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parking_lot::Mutex;
+    use reqwest::Client;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serves the `responses` in order on a random local port, the heads of the requests that
+    /// were received are recorded in the returned list.
+    async fn serve(responses: Vec<&'static str>) -> (Url, Arc<Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!(
+            "http://{}/simple/foo/",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buf = vec![0; 4096];
+                let len = stream.read(&mut buf).await.unwrap_or(0);
+                received
+                    .lock()
+                    .push(String::from_utf8_lossy(&buf[..len]).to_lowercase());
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, requests)
+    }
+
+    async fn get(http: &Http, url: &Url, headers: HeaderMap) -> (CacheStatus, String) {
+        let response = http
+            .request(url.clone(), Method::GET, headers, CacheMode::Default)
+            .await
+            .unwrap();
+        let status = *response.extensions().get::<CacheStatus>().unwrap();
+        let mut body = Vec::new();
+        response.into_body().read_to_end(&mut body).await.unwrap();
+        (status, String::from_utf8(body).unwrap())
+    }
+
+    fn http(cache_dir: &tempfile::TempDir) -> Http {
+        Http::new(
+            Client::new().into(),
+            FileStore::new(cache_dir.path()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_response_keeps_headers() {
+        const RESPONSE: &str = "HTTP/1.1 200 OK\r\ncontent-length: 5\r\ncontent-type: text/html\r\ncache-control: max-age=3600\r\nconnection: close\r\n\r\nhello";
+        let (url, requests) = serve(vec![RESPONSE, RESPONSE]).await;
+        let cache_dir = tempfile::tempdir().unwrap();
+        let http = http(&cache_dir);
+
+        // The headers of the server are passed on to the caller
+        for cache_mode in [CacheMode::NoStore, CacheMode::Default] {
+            let response = http
+                .request(url.clone(), Method::GET, HeaderMap::new(), cache_mode)
+                .await
+                .unwrap();
+            assert_eq!(
+                response.headers().get(http::header::CONTENT_TYPE).unwrap(),
+                "text/html"
+            );
+        }
+
+        // The cache policy is derived from the headers, without them the response would not be
+        // stored
+        assert_eq!(
+            get(&http, &url, HeaderMap::new()).await,
+            (CacheStatus::Fresh, "hello".to_string())
+        );
+        assert_eq!(requests.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_revalidate_with_etag() {
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 200 OK\r\ncontent-length: 5\r\netag: \"v1\"\r\ncache-control: no-cache\r\nconnection: close\r\n\r\nhello",
+            "HTTP/1.1 304 Not Modified\r\netag: \"v1\"\r\ncache-control: no-cache\r\nconnection: close\r\n\r\n",
+        ])
+        .await;
+        let cache_dir = tempfile::tempdir().unwrap();
+        let http = http(&cache_dir);
+
+        assert_eq!(
+            get(&http, &url, HeaderMap::new()).await,
+            (CacheStatus::Miss, "hello".to_string())
+        );
+
+        // The cached response must be revalidated, the server reports that it did not change
+        assert_eq!(
+            get(&http, &url, HeaderMap::new()).await,
+            (CacheStatus::StaleButValidated, "hello".to_string())
+        );
+        let requests = requests.lock();
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(
+            requests[1].contains("if-none-match: \"v1\""),
+            "{}",
+            requests[1]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revalidate_with_last_modified() {
+        const RESPONSE: &str = "HTTP/1.1 200 OK\r\ncontent-length: 5\r\nlast-modified: Mon, 01 Jan 2024 00:00:00 GMT\r\ncache-control: max-age=3600\r\nconnection: close\r\n\r\nhello";
+        let (url, requests) = serve(vec![
+            RESPONSE,
+            RESPONSE,
+            "HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n",
+        ])
+        .await;
+        let cache_dir = tempfile::tempdir().unwrap();
+        let http = http(&cache_dir);
+
+        // Within the max-age the cached response is used without contacting the server
+        assert_eq!(
+            get(&http, &url, HeaderMap::new()).await.0,
+            CacheStatus::Miss
+        );
+        assert_eq!(
+            get(&http, &url, HeaderMap::new()).await,
+            (CacheStatus::Fresh, "hello".to_string())
+        );
+        assert_eq!(requests.lock().len(), 1);
+
+        // Unless the request asks for a revalidation, like the requests for project pages do
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_CONTROL, "max-age=0".parse().unwrap());
+        assert_eq!(get(&http, &url, headers.clone()).await.0, CacheStatus::Miss);
+        assert_eq!(
+            get(&http, &url, headers).await,
+            (CacheStatus::StaleButValidated, "hello".to_string())
+        );
+        let requests = requests.lock();
+        assert_eq!(requests.len(), 3);
+        assert!(
+            requests[2].contains("if-modified-since: mon, 01 jan 2024 00:00:00 gmt"),
+            "{}",
+            requests[2]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_uncacheable_response() {
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 200 OK\r\ncontent-length: 5\r\ncache-control: no-store\r\nconnection: close\r\n\r\nhello",
+            "HTTP/1.1 200 OK\r\ncontent-length: 5\r\ncache-control: no-store\r\nconnection: close\r\n\r\nworld",
+        ])
+        .await;
+        let cache_dir = tempfile::tempdir().unwrap();
+        let http = http(&cache_dir);
+
+        assert_eq!(
+            get(&http, &url, HeaderMap::new()).await,
+            (CacheStatus::Miss, "hello".to_string())
+        );
+        assert_eq!(
+            get(&http, &url, HeaderMap::new()).await,
+            (CacheStatus::Miss, "world".to_string())
+        );
+        assert_eq!(requests.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_changed_response_replaces_cache() {
+        let (url, _requests) = serve(vec![
+            "HTTP/1.1 200 OK\r\ncontent-length: 5\r\netag: \"v1\"\r\ncache-control: no-cache\r\nconnection: close\r\n\r\nhello",
+            "HTTP/1.1 200 OK\r\ncontent-length: 5\r\netag: \"v2\"\r\ncache-control: no-cache\r\nconnection: close\r\n\r\nworld",
+            "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 304 Not Modified\r\netag: \"v2\"\r\nconnection: close\r\n\r\n",
+        ])
+        .await;
+        let cache_dir = tempfile::tempdir().unwrap();
+        let http = http(&cache_dir);
+
+        assert_eq!(get(&http, &url, HeaderMap::new()).await.1, "hello");
+        assert_eq!(
+            get(&http, &url, HeaderMap::new()).await,
+            (CacheStatus::StaleAndChanged, "world".to_string())
+        );
+
+        // A failed revalidation is an error and leaves the cached response untouched
+        assert!(http
+            .request(
+                url.clone(),
+                Method::GET,
+                HeaderMap::new(),
+                CacheMode::Default
+            )
+            .await
+            .is_err());
+        assert_eq!(
+            get(&http, &url, HeaderMap::new()).await,
+            (CacheStatus::StaleButValidated, "world".to_string())
+        );
+    }
+}
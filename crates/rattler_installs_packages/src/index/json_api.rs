@@ -0,0 +1,178 @@
+//! Module for parsing the responses of the PyPI JSON API (`/pypi/<name>/json`)
+use crate::types::{
+    ArtifactHashes, ArtifactInfo, ArtifactName, DistInfoMetadata, NormalizedPackageName,
+    ProjectInfo, Yanked,
+};
+use indexmap::IndexMap;
+use miette::IntoDiagnostic;
+use pep440_rs::VersionSpecifiers;
+use serde::Deserialize;
+use std::str::FromStr;
+use url::Url;
+
+/// The parts of a response of the PyPI JSON API that describe the available files.
+#[derive(Debug, Deserialize)]
+struct JsonApiResponse {
+    /// The files of every release by version
+    #[serde(default)]
+    releases: IndexMap<String, Vec<JsonApiFile>>,
+
+    /// The files of the latest release, only used if the response contains no releases
+    #[serde(default)]
+    urls: Vec<JsonApiFile>,
+}
+
+/// A single file of a release in a response of the PyPI JSON API.
+#[derive(Debug, Deserialize)]
+struct JsonApiFile {
+    filename: String,
+    url: String,
+    #[serde(default)]
+    digests: ArtifactHashes,
+    #[serde(default)]
+    requires_python: Option<String>,
+    #[serde(default)]
+    yanked: bool,
+    #[serde(default)]
+    yanked_reason: Option<String>,
+}
+
+/// Parses the files of a response of the PyPI JSON API for `package_name` into a [`ProjectInfo`],
+/// the same structure that is parsed from the simple API. `base` is the url of the response,
+/// relative file urls are resolved against it.
+///
+/// Files whose name or `requires_python` cannot be parsed are skipped, like on the simple API.
+/// The JSON API does not report whether the metadata of a file is available separately, so the
+/// metadata is always read from the artifacts themselves.
+pub fn parse_project_info_json_api(
+    base: &Url,
+    package_name: &NormalizedPackageName,
+    body: &[u8],
+) -> miette::Result<ProjectInfo> {
+    let response: JsonApiResponse = serde_json::from_slice(body).into_diagnostic()?;
+
+    // Older releases are listed first, the latest release is also listed under `urls`
+    let files = if response.releases.is_empty() {
+        response.urls
+    } else {
+        response.releases.into_values().flatten().collect()
+    };
+
+    Ok(ProjectInfo {
+        files: files
+            .into_iter()
+            .filter_map(|file| into_artifact_info(base, package_name, file))
+            .collect(),
+        ..ProjectInfo::default()
+    })
+}
+
+fn into_artifact_info(
+    base: &Url,
+    package_name: &NormalizedPackageName,
+    file: JsonApiFile,
+) -> Option<ArtifactInfo> {
+    let filename = ArtifactName::from_filename(&file.filename, package_name).ok()?;
+    let url = base.join(&file.url).ok()?;
+    let requires_python = file
+        .requires_python
+        .filter(|requires_python| !requires_python.trim().is_empty())
+        .map(|requires_python| VersionSpecifiers::from_str(&requires_python))
+        .transpose()
+        .ok()?;
+
+    Some(ArtifactInfo {
+        filename,
+        url,
+        hashes: (!file.digests.is_empty()).then_some(file.digests),
+        requires_python,
+        dist_info_metadata: DistInfoMetadata::default(),
+        yanked: Yanked {
+            yanked: file.yanked,
+            reason: file
+                .yanked_reason
+                .filter(|reason| file.yanked && !reason.trim().is_empty()),
+        },
+        index_url: None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::index::html::parse_project_info_html;
+
+    #[test]
+    fn test_parse_json_api_like_simple_api() {
+        let name: NormalizedPackageName = "link".parse().unwrap();
+        let json = parse_project_info_json_api(
+            &Url::parse("https://example.com/pypi/link/json").unwrap(),
+            &name,
+            br#"{
+                "info": {"name": "link", "version": "3.0"},
+                "last_serial": 1,
+                "releases": {
+                    "1.0": [{
+                        "filename": "link-1.0.tar.gz",
+                        "url": "https://example.com/files/link-1.0.tar.gz",
+                        "digests": {
+                            "md5": "00000000000000000000000000000000",
+                            "sha256": "0000000000000000000000000000000000000000000000000000000000000000"
+                        },
+                        "requires_python": null,
+                        "packagetype": "sdist",
+                        "yanked": false,
+                        "yanked_reason": null
+                    }],
+                    "2.0": [{
+                        "filename": "link-2.0-py3-none-any.whl",
+                        "url": "/files/link-2.0-py3-none-any.whl",
+                        "digests": {},
+                        "requires_python": "",
+                        "yanked": true,
+                        "yanked_reason": "some reason"
+                    }, {
+                        "filename": "link-2.0.exe",
+                        "url": "https://example.com/files/link-2.0.exe",
+                        "digests": {}
+                    }],
+                    "3.0": [{
+                        "filename": "link-3.0.tar.gz",
+                        "url": "https://example.com/files/link-3.0.tar.gz",
+                        "digests": {},
+                        "requires_python": ">= 3.17",
+                        "yanked": false,
+                        "yanked_reason": null
+                    }]
+                },
+                "urls": []
+            }"#,
+        )
+        .unwrap();
+
+        let html = parse_project_info_html(
+            &Url::parse("https://example.com/simple/link/").unwrap(),
+            r#"<html>
+                <body>
+                  <a href="/files/link-1.0.tar.gz#sha256=0000000000000000000000000000000000000000000000000000000000000000">link1</a>
+                  <a href="/files/link-2.0-py3-none-any.whl" data-yanked="some reason" data-requires-python="">link2</a>
+                  <a href="/files/link-2.0.exe">link2</a>
+                  <a href="/files/link-3.0.tar.gz" data-requires-python=">= 3.17">link3</a>
+                </body>
+              </html>
+            "#,
+        )
+        .unwrap();
+
+        // The simple API keeps the hash in the url fragment
+        let without_fragment = |info: ProjectInfo| {
+            let mut files = info.files;
+            for file in &mut files {
+                file.url.set_fragment(None);
+            }
+            files
+        };
+        assert_eq!(json.files.len(), 3);
+        assert_eq!(without_fragment(json), without_fragment(html));
+    }
+}
@@ -1,13 +1,25 @@
 //! This module contains functions for working with PyPA packaging repositories.
 
+mod builder;
+mod checksum_cache;
 mod file_store;
 
 mod git;
 pub mod html;
 mod http;
+pub mod json_api;
+mod keyring;
 mod package_database;
+mod proxy;
+mod rate_limit;
+mod tls;
 
-pub use package_database::PackageDb;
+pub use builder::{PackageDbBuildError, PackageDbBuilder};
+pub use keyring::{KeyringAuthMiddleware, KeyringProvider};
+pub use package_database::{IndexApi, PackageDb};
+pub use proxy::ProxyConfig;
+pub use rate_limit::{RateLimit, RateLimitMiddleware};
+pub use tls::{TlsConfig, TlsConfigError};
 
 pub use self::http::CacheMode;
 pub use html::parse_hash;
@@ -1,46 +1,70 @@
 use super::git::git_clone;
 use super::git::GitSource;
+use crate::artifacts::sdist::SDistError;
 use crate::artifacts::{SDist, STree, Wheel};
+use crate::index::builder::PackageDbBuilder;
+use crate::index::checksum_cache::ChecksumCache;
 use crate::index::file_store::FileStore;
 use crate::index::git::ParsedUrl;
 use crate::index::html::{parse_package_names_html, parse_project_info_html};
-use crate::index::http::{CacheMode, Http, HttpRequestError};
-use crate::resolve::PypiVersion;
+use crate::index::http::{
+    CacheEntry, CacheMode, CacheStatus, DownloadProgress, Http, HttpRequestError,
+};
+use crate::index::json_api::parse_project_info_json_api;
+use crate::resolve::{ProgressObserver, PypiVersion};
+use crate::types::split_sdist_extension;
 use crate::types::{
-    ArtifactHashes, ArtifactInfo, ArtifactName, DistInfoMetadata, PackageName, ProjectInfo,
-    SDistFilename, SDistFormat, STreeFilename, WheelCoreMetadata, Yanked,
+    ArtifactHashes, ArtifactInfo, ArtifactName, DistInfoMetadata, PackageName,
+    ParseArtifactNameError, ProjectInfo, SDistFilename, SDistFormat, STreeFilename,
+    WheelCoreMetadata, Yanked,
 };
-use crate::utils::ReadAndSeek;
-use crate::wheel_builder::{WheelBuildError, WheelBuilder, WheelCache};
+use crate::utils::{ReadAndSeek, StreamingOrLocal};
+use crate::wheel_builder::{SDistBuildError, WheelBuildError, WheelBuilder, WheelCache};
 use crate::{
     types::Artifact, types::InnerAsArtifactName, types::NormalizedPackageName, types::Version,
     types::WheelFilename,
 };
 use async_http_range_reader::{AsyncHttpRangeReader, CheckSupportMethod};
 use async_recursion::async_recursion;
-use elsa::sync::FrozenMap;
-use futures::{pin_mut, stream, StreamExt};
-use http::{header::CONTENT_TYPE, HeaderMap, HeaderValue, Method};
+use futures::{pin_mut, stream, FutureExt, StreamExt};
+use http::{
+    header::{CONTENT_ENCODING, CONTENT_TYPE},
+    HeaderMap, HeaderValue, Method,
+};
 use indexmap::IndexMap;
-use miette::{self, Diagnostic, IntoDiagnostic};
+use miette::{self, Context, Diagnostic, IntoDiagnostic};
 use parking_lot::Mutex;
 use rattler_digest::{compute_bytes_digest, Sha256};
 use reqwest::{header::CACHE_CONTROL, StatusCode};
 use reqwest_middleware::ClientWithMiddleware;
+use sha2::{Sha384, Sha512};
 use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Seek;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::{fmt::Display, io::Read, path::Path};
-use tempfile::tempdir;
 use url::Url;
 
 use super::parse_hash;
 
 type VersionArtifacts = IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>;
 
+/// The API that is used to list the artifacts of a package on the indexes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexApi {
+    /// The simple repository API of PEP 503, e.g. `https://pypi.org/simple/<name>/`
+    #[default]
+    Simple,
+
+    /// The PyPI JSON API, e.g. `https://pypi.org/pypi/<name>/json`. The index urls point to
+    /// the base of the API, e.g. `https://pypi.org/pypi/`. This is meant for interoperability
+    /// with indexes that only implement this API.
+    Json,
+}
+
 /// Cache of the available packages, artifacts and their metadata.
 pub struct PackageDb {
     http: Http,
@@ -48,11 +72,29 @@ pub struct PackageDb {
     /// Index URLS to query
     index_urls: Vec<Url>,
 
+    /// The API that is used to query the indexes
+    index_api: IndexApi,
+
+    /// Whether the pages of the indexes are always downloaded again instead of read from or
+    /// revalidated against the http cache
+    refresh: bool,
+
+    /// Local directories that contain wheels and source distributions, these are searched in
+    /// addition to the indexes
+    find_links: Vec<PathBuf>,
+
     /// A file store that stores metadata by hashes
     metadata_cache: FileStore,
 
+    /// The hashes of the artifacts in the http cache, to avoid rehashing them on every run
+    checksum_cache: ChecksumCache,
+
     /// A cache of package name to version to artifacts.
-    artifacts: FrozenMap<NormalizedPackageName, Box<VersionArtifacts>>,
+    artifacts: Mutex<HashMap<NormalizedPackageName, Arc<VersionArtifacts>>>,
+
+    /// A cache of the artifacts of direct url requirements by package name and url, so an
+    /// artifact that is referenced multiple times is only downloaded and inspected once.
+    direct_url_artifacts: Mutex<HashMap<(NormalizedPackageName, Url), Arc<VersionArtifacts>>>,
 
     /// Cache to locally built wheels
     local_wheel_cache: WheelCache,
@@ -62,22 +104,89 @@ pub struct PackageDb {
 }
 
 impl PackageDb {
-    /// Constructs a new [`PackageDb`] that reads information from the specified URLs.
+    /// Returns a builder to construct a [`PackageDb`] that stores its caches in `cache_dir`.
+    ///
+    /// ```no_run
+    /// # use rattler_installs_packages::index::PackageDb;
+    /// # use std::time::Duration;
+    /// let package_db = PackageDb::builder("/tmp/rip-cache")
+    ///     .index_url("https://pypi.org/simple/".parse().unwrap())
+    ///     .timeout(Duration::from_secs(30))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder(cache_dir: impl Into<PathBuf>) -> PackageDbBuilder {
+        PackageDbBuilder::new(cache_dir)
+    }
+
+    /// Constructs a new [`PackageDb`] that reads information from the specified URLs. See
+    /// [`PackageDb::builder`] for more options.
     pub fn new(
         client: ClientWithMiddleware,
         index_urls: &[Url],
         cache_dir: &Path,
     ) -> std::io::Result<Self> {
         Ok(Self {
-            http: Http::new(client, FileStore::new(&cache_dir.join("http"))?),
+            // The entries of older versions are stored in `http`, they have a different layout
+            http: Http::new(client, FileStore::new(&cache_dir.join("http-v2"))?),
             index_urls: index_urls.into(),
+            index_api: IndexApi::default(),
+            refresh: false,
+            find_links: Vec::new(),
             metadata_cache: FileStore::new(&cache_dir.join("metadata"))?,
+            checksum_cache: ChecksumCache::new(cache_dir.join("checksums")),
             artifacts: Default::default(),
+            direct_url_artifacts: Default::default(),
             local_wheel_cache: WheelCache::new(cache_dir.join("local_wheels")),
             cache_dir: cache_dir.to_owned(),
         })
     }
 
+    /// Returns the urls of the indexes that are queried
+    pub fn index_urls(&self) -> &[Url] {
+        &self.index_urls
+    }
+
+    /// Queries the indexes with the given API instead of the simple repository API.
+    pub fn with_index_api(mut self, index_api: IndexApi) -> Self {
+        self.index_api = index_api;
+        self
+    }
+
+    /// Returns the API that is used to query the indexes
+    pub fn index_api(&self) -> IndexApi {
+        self.index_api
+    }
+
+    /// Always downloads the pages of the indexes again instead of reading them from the http
+    /// cache, to see newly published versions even if a server or proxy wrongly reports that a
+    /// cached page is up to date. The downloaded pages still replace the cached ones and the
+    /// cached artifacts are still used.
+    pub fn with_refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Returns true if the pages of the indexes are always downloaded again
+    pub fn refresh(&self) -> bool {
+        self.refresh
+    }
+
+    /// Also searches the given local directories for wheels and source distributions. Artifacts
+    /// are matched by their filename, the directories are not searched recursively.
+    ///
+    /// Together with an empty list of index urls this allows resolving completely offline from
+    /// a directory of vendored artifacts.
+    pub fn with_find_links(mut self, find_links: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.find_links.extend(find_links);
+        self
+    }
+
+    /// Returns the local directories that are searched for artifacts
+    pub fn find_links(&self) -> &[PathBuf] {
+        &self.find_links
+    }
+
     /// Returns the cache directory
     pub fn cache_dir(&self) -> &Path {
         &self.cache_dir
@@ -88,35 +197,100 @@ impl PackageDb {
         &self.local_wheel_cache
     }
 
+    /// Drops all information that is memoized in memory, such as the lists of available artifacts
+    /// per package. Anything that has been written to the on-disk caches is left untouched, so
+    /// subsequent requests are served from disk where possible.
+    ///
+    /// This is useful for long-running processes that want to bound their memory usage. Artifact
+    /// lists that were previously handed out remain valid, they are simply no longer shared.
+    pub fn clear_memory_cache(&self) {
+        self.artifacts.lock().clear();
+        self.direct_url_artifacts.lock().clear();
+    }
+
+    /// Stores the artifacts of a package in the in-memory cache. If another task already stored
+    /// artifacts for the same package, those are returned instead.
+    pub(crate) fn insert_artifacts(
+        &self,
+        name: NormalizedPackageName,
+        artifacts: VersionArtifacts,
+    ) -> Arc<VersionArtifacts> {
+        self.artifacts
+            .lock()
+            .entry(name)
+            .or_insert_with(|| Arc::new(artifacts))
+            .clone()
+    }
+
     /// Downloads and caches information about available artifiacts of a package from the index.
     pub async fn available_artifacts<P: Into<NormalizedPackageName>>(
         &self,
         p: P,
-    ) -> miette::Result<&IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>> {
+    ) -> miette::Result<Arc<IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>>> {
         let p = p.into();
-        if let Some(cached) = self.artifacts.get(&p) {
+        let cached = self.artifacts.lock().get(&p).cloned();
+        if let Some(cached) = cached {
             Ok(cached)
         } else {
             // Start downloading the information for each url.
             let http = self.http.clone();
-            let request_iter = stream::iter(self.index_urls.iter())
-                .map(|url| url.join(&format!("{}/", p.as_str())).expect("invalid url"))
-                .map(|url| fetch_simple_api(&http, url))
+            let index_api = self.index_api;
+            let cache_mode = if self.refresh {
+                CacheMode::Reload
+            } else {
+                CacheMode::Default
+            };
+            // The urls are iterated by value, a stream over borrowed urls makes the future of this
+            // function not provably `Send`
+            let request_iter = stream::iter(self.index_urls.clone())
+                .map(|index_url| {
+                    let request = match index_api {
+                        IndexApi::Simple => {
+                            let url = index_url
+                                .join(&format!("{}/", p.as_str()))
+                                .expect("invalid url");
+                            fetch_simple_api(&http, url, cache_mode).left_future()
+                        }
+                        IndexApi::Json => {
+                            let url = index_url
+                                .join(&format!("{}/json", p.as_str()))
+                                .expect("invalid url");
+                            fetch_json_api(&http, url, &p, cache_mode).right_future()
+                        }
+                    };
+                    request.map(move |result| Ok(result?.map(|info| (index_url, info))))
+                })
                 .buffer_unordered(10)
-                .filter_map(|result| async { result.transpose() });
+                .filter_map(|result: miette::Result<_>| async { result.transpose() });
 
             pin_mut!(request_iter);
 
             // Add all the incoming results to the set of results
             let mut result = IndexMap::default();
             while let Some(response) = request_iter.next().await {
-                for artifact in response?.files {
+                let (index_url, project_info) = response?;
+                for mut artifact in project_info.files {
+                    artifact.index_url = Some(index_url.clone());
                     result
                         .entry(artifact.filename.version().clone())
                         .or_insert_with(Vec::new)
                         .push(Arc::new(artifact));
                 }
             }
+            for artifact in self.find_links_artifacts(&p)? {
+                result
+                    .entry(artifact.filename.version().clone())
+                    .or_insert_with(Vec::new)
+                    .push(Arc::new(artifact));
+            }
+
+            if result.is_empty() && self.index_urls.is_empty() {
+                miette::bail!(
+                    help = "pass an index url or add the artifacts of the package to one of the find-links directories",
+                    "'{}' was not found in any of the find-links directories and no index is configured",
+                    p.as_str()
+                );
+            }
 
             // Sort the artifact infos by name, this is just to have a consistent order and make
             // the resolution output consistent.
@@ -127,10 +301,40 @@ impl PackageDb {
             // Sort in descending order by version
             result.sort_unstable_by(|v1, _, v2, _| v2.cmp(v1));
 
-            Ok(self.artifacts.insert(p.clone(), Box::new(result)))
+            Ok(self.insert_artifacts(p.clone(), result))
         }
     }
 
+    /// Returns the artifacts of a package that are located in the find-links directories.
+    fn find_links_artifacts(&self, p: &NormalizedPackageName) -> miette::Result<Vec<ArtifactInfo>> {
+        let mut artifacts = Vec::new();
+        for dir in &self.find_links {
+            let entries = fs_err::read_dir(dir)
+                .into_diagnostic()
+                .wrap_err("failed to read find-links directory")?;
+            for entry in entries {
+                let path = entry.into_diagnostic()?.path();
+                let Some(filename) = path.file_name().and_then(|f| f.to_str()) else {
+                    continue;
+                };
+                let Ok(filename) = ArtifactName::from_filename(filename, p) else {
+                    continue;
+                };
+                let path = fs_err::canonicalize(&path).into_diagnostic()?;
+                artifacts.push(ArtifactInfo {
+                    filename,
+                    url: Url::from_file_path(&path).expect("the path is absolute"),
+                    hashes: None,
+                    requires_python: None,
+                    dist_info_metadata: DistInfoMetadata::default(),
+                    yanked: Yanked::default(),
+                    index_url: None,
+                });
+            }
+        }
+        Ok(artifacts)
+    }
+
     /// Return an sdist from file path
     pub async fn get_sdist_from_file_path(
         &self,
@@ -223,7 +427,7 @@ impl PackageDb {
         p: P,
         url: Url,
         wheel_builder: &WheelBuilder,
-    ) -> miette::Result<&IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>> {
+    ) -> miette::Result<Arc<IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>>> {
         let path = if let Ok(path) = url.to_file_path() {
             path
         } else {
@@ -271,6 +475,7 @@ impl PackageDb {
                 sha256: Some(rattler_digest::compute_bytes_digest::<Sha256>(
                     metadata_bytes.clone(),
                 )),
+                ..ArtifactHashes::default()
             }
         };
 
@@ -281,6 +486,7 @@ impl PackageDb {
             requires_python: metadata.requires_python,
             dist_info_metadata: DistInfoMetadata::default(),
             yanked: Yanked::default(),
+            index_url: None,
         });
 
         let mut result = IndexMap::default();
@@ -289,12 +495,15 @@ impl PackageDb {
         self.put_metadata_in_cache(&artifact_info, &metadata_bytes)
             .await?;
 
-        Ok(self
-            .artifacts
-            .insert(normalized_package_name.clone(), Box::new(result)))
+        Ok(Arc::new(result))
     }
 
-    /// Return an sdist from file path
+    /// Return an sdist from a direct http url
+    ///
+    /// The filename of the archive does not have to follow the naming convention of source
+    /// distributions, e.g. the archive of a tag on GitHub (`.../archive/refs/tags/v1.0.zip`). The
+    /// name and version are taken from the metadata instead, the name has to match
+    /// `normalized_package_name`.
     pub async fn get_sdist_from_http(
         &self,
         normalized_package_name: &NormalizedPackageName,
@@ -319,10 +528,22 @@ impl PackageDb {
         // and populate it with correct metadata after calling `get_sdist_metadata`
         let dummy_sdist = SDist::new(dummy_sdist_file_name, Box::new(bytes))?;
 
-        let wheel_metadata = wheel_builder
-            .get_sdist_metadata(&dummy_sdist)
-            .await
-            .into_diagnostic()?;
+        // Use the PKG-INFO directly if it is reliable
+        let wheel_metadata = match dummy_sdist.pep643_metadata() {
+            Ok(Some(metadata)) => metadata,
+            _ => wheel_builder
+                .get_sdist_metadata(&dummy_sdist)
+                .await
+                .into_diagnostic()?,
+        };
+
+        if &NormalizedPackageName::from(wheel_metadata.1.name.clone()) != normalized_package_name {
+            miette::bail!(
+                "the url '{url}' refers to a source distribution of '{}' instead of '{}'",
+                wheel_metadata.1.name.as_source_str(),
+                normalized_package_name
+            );
+        }
 
         // construct a real sdist filename
         let sdist_filename = SDistFilename {
@@ -341,11 +562,12 @@ impl PackageDb {
         p: P,
         url: Url,
         wheel_builder: &WheelBuilder,
-    ) -> miette::Result<&IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>> {
+    ) -> miette::Result<Arc<IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>>> {
         let str_name = url.path();
         let url_hash = url.fragment().and_then(parse_hash);
 
         let normalized_package_name = p.into();
+        let filename = direct_url_artifact_name(&url, &normalized_package_name)?;
 
         // Get the contents of the artifact
         let artifact_bytes = self
@@ -358,41 +580,56 @@ impl PackageDb {
             )
             .await?;
 
-        let mut bytes = artifact_bytes
-            .into_body()
-            .into_local()
-            .await
-            .into_diagnostic()?;
+        let mut bytes = artifact_body(artifact_bytes, str_name).await?;
 
         let artifact_hash = {
             let mut bytes_for_hash = vec![];
             bytes.rewind().into_diagnostic()?;
             bytes.read_to_end(&mut bytes_for_hash).into_diagnostic()?;
             bytes.rewind().into_diagnostic()?;
+            // Also compute the other hashes that the url specifies, to compare them
+            let url_hashes = url_hash.clone().unwrap_or_default();
             ArtifactHashes {
+                sha384: url_hashes
+                    .sha384
+                    .map(|_| compute_bytes_digest::<Sha384>(&bytes_for_hash)),
+                sha512: url_hashes
+                    .sha512
+                    .map(|_| compute_bytes_digest::<Sha512>(&bytes_for_hash)),
                 sha256: Some(rattler_digest::compute_bytes_digest::<Sha256>(
                     bytes_for_hash,
                 )),
             }
         };
 
-        if let Some(hash) = url_hash.clone() {
-            assert_eq!(hash, artifact_hash);
+        if let Some(hash) = &url_hash {
+            let actual = artifact_hash.hex_digests();
+            for (algorithm, expected) in hash.hex_digests() {
+                if !actual.contains(&(algorithm, expected.clone())) {
+                    miette::bail!(
+                        "the {algorithm} hash of '{url}' does not match the hash in its url ({expected})"
+                    );
+                }
+            }
         };
 
-        let (filename, data_bytes, metadata) = if str_name.ends_with(".whl") {
-            let wheel = Wheel::from_url_and_bytes(url.path(), &normalized_package_name, bytes)?;
-
-            let filename = ArtifactName::Wheel(wheel.name().clone());
-            let (data_bytes, metadata) = wheel.metadata()?;
-
-            (filename, data_bytes, metadata)
-        } else {
-            let (wheel_metadata, filename) = self
-                .get_sdist_from_http(&normalized_package_name, url.clone(), bytes, wheel_builder)
-                .await?;
-
-            (filename, wheel_metadata.0, wheel_metadata.1)
+        let (filename, data_bytes, metadata) = match filename {
+            Some(ArtifactName::Wheel(name)) => {
+                let (data_bytes, metadata) =
+                    Wheel::new(name.clone(), Box::new(bytes))?.metadata()?;
+                (ArtifactName::Wheel(name), data_bytes, metadata)
+            }
+            _ => {
+                let ((data_bytes, metadata), filename) = self
+                    .get_sdist_from_http(
+                        &normalized_package_name,
+                        url.clone(),
+                        bytes,
+                        wheel_builder,
+                    )
+                    .await?;
+                (filename, data_bytes, metadata)
+            }
         };
 
         let artifact_info = Arc::new(ArtifactInfo {
@@ -402,6 +639,7 @@ impl PackageDb {
             requires_python: metadata.requires_python,
             dist_info_metadata: DistInfoMetadata::default(),
             yanked: Yanked::default(),
+            index_url: None,
         });
 
         let mut result = IndexMap::default();
@@ -410,18 +648,20 @@ impl PackageDb {
         self.put_metadata_in_cache(&artifact_info, &data_bytes)
             .await?;
 
-        Ok(self
-            .artifacts
-            .insert(normalized_package_name.clone(), Box::new(result)))
+        Ok(Arc::new(result))
     }
 
-    /// Get artifact by git reference
+    /// Get artifact by git reference.
+    ///
+    /// The repository is cloned into the cache directory. If the metadata of the package is
+    /// declared statically (see [`STree::static_metadata`]) it is used as is, otherwise the
+    /// metadata is obtained from the build backend.
     pub async fn get_git_artifact<P: Into<NormalizedPackageName>>(
         &self,
         p: P,
         url: Url,
         wheel_builder: &WheelBuilder,
-    ) -> miette::Result<&IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>> {
+    ) -> miette::Result<Arc<IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>>> {
         let normalized_package_name = p.into();
 
         let parsed_url = ParsedUrl::new(&url)?;
@@ -431,9 +671,12 @@ impl PackageDb {
             rev: parsed_url.revision,
         };
 
-        let temp_dir = tempdir().unwrap();
+        let clone_dir = self.cache_dir.join("git").join(format!(
+            "{:x}",
+            compute_bytes_digest::<Sha256>(url.as_str().as_bytes())
+        ));
 
-        let mut location = git_clone(&git_source, &temp_dir).into_diagnostic()?;
+        let mut location = git_clone(&git_source, &clone_dir).into_diagnostic()?;
 
         if let Some(subdirectory) = parsed_url.subdirectory {
             location.push(&subdirectory);
@@ -446,14 +689,40 @@ impl PackageDb {
             }
         };
 
-        let (wheel_metadata, filename) = self
-            .get_stree_from_file_path(
-                &normalized_package_name,
-                url.clone(),
-                Some(location),
-                wheel_builder,
-            )
-            .await?;
+        let stree = STree {
+            name: STreeFilename {
+                distribution: normalized_package_name.clone().into(),
+                version: url.clone(),
+            },
+            location: Mutex::new(location.clone()),
+        };
+        let (wheel_metadata, filename) = match stree.static_metadata().into_diagnostic()? {
+            Some((bytes, metadata)) => {
+                let filename = ArtifactName::STree(STreeFilename {
+                    distribution: metadata.name.clone(),
+                    version: url.clone(),
+                });
+                ((bytes, metadata), filename)
+            }
+            None => {
+                self.get_stree_from_file_path(
+                    &normalized_package_name,
+                    url.clone(),
+                    Some(location),
+                    wheel_builder,
+                )
+                .await?
+            }
+        };
+
+        let metadata_name: NormalizedPackageName = wheel_metadata.1.name.clone().into();
+        if metadata_name != normalized_package_name {
+            miette::bail!(
+                "the repository at {url} contains the package '{}' instead of '{}'",
+                metadata_name.as_str(),
+                normalized_package_name.as_str()
+            );
+        }
 
         let requires_python = wheel_metadata.1.requires_python;
 
@@ -469,6 +738,7 @@ impl PackageDb {
 
         let project_hash = ArtifactHashes {
             sha256: Some(compute_bytes_digest::<Sha256>(url.as_str().as_bytes())),
+            ..ArtifactHashes::default()
         };
 
         let artifact_info = Arc::new(ArtifactInfo {
@@ -478,6 +748,7 @@ impl PackageDb {
             requires_python,
             dist_info_metadata,
             yanked,
+            index_url: None,
         });
 
         let mut result = IndexMap::default();
@@ -486,35 +757,52 @@ impl PackageDb {
         self.put_metadata_in_cache(&artifact_info, &wheel_metadata.0)
             .await?;
 
-        Ok(self
-            .artifacts
-            .insert(normalized_package_name.clone(), Box::new(result)))
+        Ok(Arc::new(result))
     }
 
     /// Get artifact directly from file, vcs, or url
+    ///
+    /// The result is memoized by package name and url, so requirements that refer to the same url
+    /// (e.g. with different extras, or from different packages) only fetch it once.
     pub async fn get_artifact_by_direct_url<P: Into<NormalizedPackageName>>(
         &self,
         p: P,
         url: Url,
         wheel_builder: &WheelBuilder,
-    ) -> miette::Result<&IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>> {
-        let p = p.into();
+    ) -> miette::Result<Arc<IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>>> {
+        let key = (p.into(), url);
+        let cached = self.direct_url_artifacts.lock().get(&key).cloned();
+        if let Some(cached) = cached {
+            return Ok(cached);
+        }
 
-        if let Some(cached) = self.artifacts.get(&p) {
-            Ok(cached)
-        } else if url.scheme() == "file" {
-            self.get_file_artifact(p, url, wheel_builder).await
+        let (p, url) = key.clone();
+        let artifacts = if url.scheme() == "file" {
+            self.get_file_artifact(p, url, wheel_builder).await?
         } else if url.scheme() == "https" {
             self.get_direct_http_url_artifact(p, url, wheel_builder)
-                .await
+                .await?
         } else if url.scheme() == "git+https" || url.scheme() == "git+file" {
-            self.get_git_artifact(p, url, wheel_builder).await
+            self.get_git_artifact(p, url, wheel_builder).await?
         } else {
-            Err(miette::miette!(
+            return Err(miette::miette!(
                 "Usage of insecure protocol or unsupported scheme {:?}",
                 url.scheme()
-            ))
-        }
+            ));
+        };
+
+        // The artifacts are also available by name, like the artifacts of an index
+        self.artifacts
+            .lock()
+            .entry(key.0.clone())
+            .or_insert_with(|| artifacts.clone());
+
+        Ok(self
+            .direct_url_artifacts
+            .lock()
+            .entry(key)
+            .or_insert(artifacts)
+            .clone())
     }
 
     /// Reads the metadata for the given artifact from the cache or return `None` if the metadata
@@ -526,9 +814,21 @@ impl PackageDb {
         Some(bytes)
     }
 
+    /// Removes the metadata of the given artifact from the cache, if it is cached.
+    async fn remove_metadata_from_cache(&self, ai: &ArtifactInfo) -> miette::Result<()> {
+        if let Some(hash) = &ai.hashes {
+            self.metadata_cache.remove(&hash).await.into_diagnostic()?;
+        }
+        Ok(())
+    }
+
     /// Writes the metadata for the given artifact into the cache. If the metadata already exists
     /// its not overwritten.
-    async fn put_metadata_in_cache(&self, ai: &ArtifactInfo, blob: &[u8]) -> miette::Result<()> {
+    pub(crate) async fn put_metadata_in_cache(
+        &self,
+        ai: &ArtifactInfo,
+        blob: &[u8],
+    ) -> miette::Result<()> {
         if let Some(hash) = &ai.hashes {
             self.metadata_cache
                 .get_or_set(&hash, |w| w.write_all(blob))
@@ -549,7 +849,11 @@ impl PackageDb {
             let artifact_info_ref = artifact_info.borrow();
             if artifact_info_ref.is::<Wheel>() {
                 let result = self
-                    .get_artifact_with_cache::<Wheel>(artifact_info_ref, CacheMode::OnlyIfCached)
+                    .get_artifact_with_cache::<Wheel>(
+                        artifact_info_ref,
+                        CacheMode::OnlyIfCached,
+                        None,
+                    )
                     .await;
                 match result {
                     Ok(artifact) => {
@@ -580,7 +884,11 @@ impl PackageDb {
             // We know that it is an sdist
             else {
                 let result = self
-                    .get_artifact_with_cache::<SDist>(artifact_info_ref, CacheMode::OnlyIfCached)
+                    .get_artifact_with_cache::<SDist>(
+                        artifact_info_ref,
+                        CacheMode::OnlyIfCached,
+                        None,
+                    )
                     .await;
 
                 match result {
@@ -605,6 +913,7 @@ impl PackageDb {
     async fn get_metadata_wheels<'a, A: Borrow<ArtifactInfo>>(
         &self,
         artifacts: &'a [A],
+        progress: Option<&Arc<dyn ProgressObserver>>,
     ) -> miette::Result<Option<(&'a A, WheelCoreMetadata)>> {
         let wheels = artifacts
             .iter()
@@ -628,7 +937,7 @@ impl PackageDb {
 
             // Otherwise download the entire artifact
             let artifact = self
-                .get_artifact_with_cache::<Wheel>(ai, CacheMode::Default)
+                .get_artifact_with_cache::<Wheel>(ai, CacheMode::Default, progress)
                 .await?;
             let metadata = artifact.metadata();
             match metadata {
@@ -661,22 +970,80 @@ impl PackageDb {
         // Keep track of errors
         // only print these if we have not been able to find any metadata
         let mut errors = Vec::new();
+        let mut build_error = None;
+        let progress = wheel_builder.resolve_options().progress_observer.as_ref();
         for ai in sdists {
             let artifact_info = ai.borrow();
-            let artifact = self
-                .get_artifact_with_cache::<SDist>(artifact_info, CacheMode::Default)
+            let mut artifact = self
+                .get_artifact_with_cache::<SDist>(artifact_info, CacheMode::Default, progress)
                 .await?;
+            let mut pep643_metadata = artifact.pep643_metadata();
+
+            // A corrupt archive is most likely the result of an interrupted download or a flaky
+            // mirror, replace the cached copy by downloading it again.
+            if let Err(err @ SDistError::Corrupt { .. }) = &pep643_metadata {
+                tracing::warn!("{err}, downloading it again");
+                artifact = self
+                    .get_artifact_with_cache::<SDist>(artifact_info, CacheMode::Reload, progress)
+                    .await?;
+                pep643_metadata = artifact.pep643_metadata();
+            }
+
+            // Use the PKG-INFO directly if it is reliable
+            match pep643_metadata {
+                Ok(Some((blob, metadata))) => {
+                    self.put_metadata_in_cache(artifact_info, &blob).await?;
+                    return Ok(Some((ai, metadata)));
+                }
+                Ok(None) => {}
+                Err(err @ SDistError::Corrupt { .. }) => {
+                    errors.push(err.to_string());
+                    continue;
+                }
+                Err(err) => tracing::debug!(
+                    "could not read PKG-INFO of '{}': {err}",
+                    artifact_info.filename
+                ),
+            }
+
+            if wheel_builder.resolve_options().no_build {
+                errors.push(format!(
+                    "the metadata of source distribution '{}' is not reliable (PEP 643) and building source distributions is not allowed",
+                    artifact_info.filename
+                ));
+                continue;
+            }
+            if wheel_builder.resolve_options().skip_unreliable_sdists {
+                errors.push(format!(
+                    "the metadata of source distribution '{}' is not reliable (PEP 643) and building source distributions to determine their metadata is disabled",
+                    artifact_info.filename
+                ));
+                continue;
+            }
+
+            tracing::info!(
+                "building '{}' to determine its metadata because its PKG-INFO is not reliable",
+                artifact_info.filename
+            );
             let metadata = wheel_builder.get_sdist_metadata(&artifact).await;
             match metadata {
                 Ok((blob, metadata)) => {
                     self.put_metadata_in_cache(artifact_info, &blob).await?;
                     return Ok(Some((ai, metadata)));
                 }
+                // Other source distributions of the same package would run into the same cycle
+                Err(WheelBuildError::BuildDependencyCycle(cycle)) => return Err(cycle.into()),
                 Err(err) => {
                     errors.push(format!(
                         "error while processing source distribution '{}': \n {}",
                         artifact_info.filename, err
                     ));
+                    if let WheelBuildError::BackendFailed(output) = err {
+                        build_error.get_or_insert(SDistBuildError {
+                            filename: artifact_info.filename.to_string(),
+                            output,
+                        });
+                    }
                     continue;
                 }
             }
@@ -685,6 +1052,13 @@ impl PackageDb {
         // Check if errors is empty and if not return an error
         if !errors.is_empty() {
             tracing::warn!("errors while processing source distributions:");
+            // A failing build backend is the most actionable error, the others are only logged
+            if let Some(build_error) = build_error {
+                for error in &errors {
+                    tracing::warn!("{error}");
+                }
+                return Err(build_error.into());
+            }
             miette::bail!("{}", errors.join("\n"));
         }
 
@@ -702,10 +1076,18 @@ impl PackageDb {
         // Return if we do
         for artifact_info in artifacts.iter() {
             if let Some(metadata_bytes) = self.metadata_from_cache(artifact_info.borrow()).await {
-                return Ok(Some((
-                    artifact_info,
-                    WheelCoreMetadata::try_from(metadata_bytes.as_slice()).into_diagnostic()?,
-                )));
+                match WheelCoreMetadata::try_from(metadata_bytes.as_slice()) {
+                    Ok(metadata) => return Ok(Some((artifact_info, metadata))),
+                    // The cached metadata is corrupt, discard it so that it is fetched again
+                    Err(err) => {
+                        tracing::warn!(
+                            "discarding the corrupt cached metadata of {}: {err}",
+                            artifact_info.borrow().filename
+                        );
+                        self.remove_metadata_from_cache(artifact_info.borrow())
+                            .await?;
+                    }
+                }
             }
         }
 
@@ -721,7 +1103,8 @@ impl PackageDb {
         // network to get to the information.
         // Let's try to get information for any wheels that we have
         // first
-        let result = self.get_metadata_wheels(artifacts).await?;
+        let progress = wheel_builder.and_then(|b| b.resolve_options().progress_observer.as_ref());
+        let result = self.get_metadata_wheels(artifacts, progress).await?;
         if result.is_some() {
             return Ok(result);
         }
@@ -743,20 +1126,25 @@ impl PackageDb {
         &self,
         artifact_info: &ArtifactInfo,
     ) -> miette::Result<Option<WheelCoreMetadata>> {
+        // Local files are read completely, that is cheap enough
+        if artifact_info.url.scheme() == "file" {
+            return Ok(None);
+        }
+
         tracing::info!(url=%artifact_info.url, "lazy reading artifact");
 
         // Check if the artifact is the same type as the info.
         let name = WheelFilename::try_as(&artifact_info.filename)
             .expect("the specified artifact does not refer to type requested to read");
 
-        if let Ok((mut reader, _)) = AsyncHttpRangeReader::new(
+        if let Ok((reader, _)) = AsyncHttpRangeReader::new(
             self.http.client.clone(),
             artifact_info.url.clone(),
             CheckSupportMethod::Head,
         )
         .await
         {
-            match Wheel::read_metadata_bytes(name, &mut reader).await {
+            match Wheel::read_metadata_bytes(name, reader).await {
                 Ok((blob, metadata)) => {
                     self.put_metadata_in_cache(artifact_info, &blob).await?;
                     return Ok(Some(metadata));
@@ -801,6 +1189,13 @@ impl PackageDb {
         Ok((artifact_info, metadata))
     }
 
+    /// Opens the source distribution that is described by `artifact_info`, downloading it if it
+    /// is not cached yet.
+    pub async fn get_sdist(&self, artifact_info: &ArtifactInfo) -> miette::Result<SDist> {
+        self.get_artifact_with_cache::<SDist>(artifact_info, CacheMode::Default, None)
+            .await
+    }
+
     /// Get all package names in the index.
     pub async fn get_package_names(&self) -> miette::Result<Vec<String>> {
         let index_url = self.index_urls.first();
@@ -825,11 +1220,13 @@ impl PackageDb {
     }
 
     /// Opens the specified artifact info. Depending on the specified `cache_mode`, downloads the
-    /// artifact data from the remote location if the information is not already cached.
+    /// artifact data from the remote location if the information is not already cached. The
+    /// progress of the download is reported to `progress`.
     async fn get_artifact_with_cache<A: Artifact>(
         &self,
         artifact_info: &ArtifactInfo,
         cache_mode: CacheMode,
+        progress: Option<&Arc<dyn ProgressObserver>>,
     ) -> miette::Result<A> {
         // Check if the artifact is the same type as the info.
         let name = A::Name::try_as(&artifact_info.filename).unwrap_or_else(|| {
@@ -839,24 +1236,159 @@ impl PackageDb {
             )
         });
 
+        let bytes = self
+            .get_artifact_bytes(artifact_info, cache_mode, progress)
+            .await?;
+        A::new(name.clone(), bytes)
+    }
+
+    /// Returns the contents of the specified artifact, after verifying its hash. See
+    /// [`Self::get_artifact_with_cache`].
+    async fn get_artifact_bytes(
+        &self,
+        artifact_info: &ArtifactInfo,
+        cache_mode: CacheMode,
+        progress: Option<&Arc<dyn ProgressObserver>>,
+    ) -> miette::Result<Box<dyn ReadAndSeek + Send>> {
+        // Artifacts from a find-links directory are read directly
+        if artifact_info.url.scheme() == "file" {
+            let path = artifact_info
+                .url
+                .to_file_path()
+                .map_err(|_| miette::miette!("invalid file url {}", artifact_info.url))?;
+            let file = File::open(&path)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to open {}", path.display()))?;
+            let mut bytes: Box<dyn ReadAndSeek + Send> = Box::new(file);
+            self.verify_artifact_hash(artifact_info, &mut bytes, None)?;
+            return Ok(bytes);
+        }
+
+        let (mut bytes, downloaded, cache_entry) = self
+            .fetch_artifact_bytes(artifact_info, cache_mode, progress)
+            .await?;
+        match self.verify_artifact_hash(artifact_info, &mut bytes, cache_entry.as_ref()) {
+            Ok(()) => Ok(bytes),
+            // The cached copy is corrupt, e.g. because a disk error damaged it. Download it again,
+            // which also replaces the cached copy.
+            Err(err) if !downloaded => {
+                tracing::warn!(
+                    "discarding the corrupt cached copy of {}: {err}",
+                    artifact_info.filename
+                );
+                if let Some(CacheEntry(path)) = &cache_entry {
+                    self.checksum_cache.remove(path);
+                }
+                let (mut bytes, _, cache_entry) = self
+                    .fetch_artifact_bytes(artifact_info, CacheMode::Reload, progress)
+                    .await?;
+                self.verify_artifact_hash(artifact_info, &mut bytes, cache_entry.as_ref())?;
+                Ok(bytes)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Requests the contents of an artifact through the http cache. Also returns whether the
+    /// contents were downloaded and the cache entry that contains them, if any.
+    async fn fetch_artifact_bytes(
+        &self,
+        artifact_info: &ArtifactInfo,
+        cache_mode: CacheMode,
+        progress: Option<&Arc<dyn ProgressObserver>>,
+    ) -> miette::Result<(Box<dyn ReadAndSeek + Send>, bool, Option<CacheEntry>)> {
         // Get the contents of the artifact
+        let on_progress = progress.map(|progress| {
+            let (progress, url) = (progress.clone(), artifact_info.url.clone());
+            Box::new(move |bytes, total| progress.on_download_progress(&url, bytes, total))
+                as DownloadProgress
+        });
         let artifact_bytes = self
             .http
-            .request(
+            .request_with_progress(
                 artifact_info.url.clone(),
                 Method::GET,
                 HeaderMap::default(),
                 cache_mode,
+                on_progress,
             )
             .await?;
 
+        // Responses that are served from the cache were not downloaded
+        let downloaded = !matches!(
+            artifact_bytes.extensions().get::<CacheStatus>(),
+            Some(CacheStatus::Fresh | CacheStatus::StaleButValidated)
+        );
+
         // Turn the response into a seekable response.
-        let bytes = artifact_bytes
-            .into_body()
-            .into_local()
-            .await
-            .into_diagnostic()?;
-        A::new(name.clone(), bytes)
+        let cache_entry = artifact_bytes.extensions().get::<CacheEntry>().cloned();
+        let body = artifact_body(artifact_bytes, &artifact_info.filename.to_string()).await;
+        if let Some(progress) = progress.filter(|_| downloaded) {
+            progress.on_download_finished(&artifact_info.url);
+        }
+        Ok((body?, downloaded, cache_entry))
+    }
+
+    /// Downloads the specified artifact into the directory `dest` under its original filename and
+    /// returns the path of the file. The hash of the artifact is verified, the directory is
+    /// created if it does not exist yet.
+    pub async fn download_artifact(
+        &self,
+        artifact_info: &ArtifactInfo,
+        dest: &Path,
+        progress: Option<&Arc<dyn ProgressObserver>>,
+    ) -> miette::Result<PathBuf> {
+        let mut bytes = self
+            .get_artifact_bytes(artifact_info, CacheMode::Default, progress)
+            .await?;
+
+        // Write to a temporary file first so an interrupted download leaves no partial file behind
+        fs_err::create_dir_all(dest).into_diagnostic()?;
+        let path = dest.join(artifact_info.filename.to_string());
+        let mut file = tempfile::NamedTempFile::new_in(dest).into_diagnostic()?;
+        std::io::copy(&mut bytes, &mut file).into_diagnostic()?;
+        file.persist(&path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Verifies that the contents of an artifact match the sha256 hash that the index reported
+    /// for it, if any.
+    ///
+    /// Hashing a large artifact is expensive, so if the artifact was read from the http cache the
+    /// hash is remembered for as long as the cached file does not change.
+    fn verify_artifact_hash(
+        &self,
+        artifact_info: &ArtifactInfo,
+        bytes: &mut Box<dyn ReadAndSeek + Send>,
+        cache_entry: Option<&CacheEntry>,
+    ) -> miette::Result<()> {
+        let Some(expected) = artifact_info.hashes.as_ref().and_then(|h| h.sha256) else {
+            return Ok(());
+        };
+
+        let mut compute = || {
+            bytes.rewind()?;
+            let mut reader = rattler_digest::HashingReader::<_, Sha256>::new(&mut *bytes);
+            std::io::copy(&mut reader, &mut std::io::sink())?;
+            let (_, hash) = reader.finalize();
+            bytes.rewind()?;
+            Ok(hash)
+        };
+        let actual = match cache_entry {
+            Some(CacheEntry(path)) => self.checksum_cache.get_or_compute(path, &mut compute),
+            None => compute(),
+        }
+        .into_diagnostic()?;
+
+        if actual != expected {
+            miette::bail!(
+                "the sha256 hash of {} is {actual:x} but the index reported {expected:x}",
+                artifact_info.filename
+            );
+        }
+        Ok(())
     }
 
     /// Opens the specified artifact info. Downloads the artifact data from the remote location if
@@ -871,28 +1403,149 @@ impl PackageDb {
         if artifact_info.is::<SDist>() {
             if let Some(builder) = builder {
                 let sdist = self
-                    .get_artifact_with_cache::<SDist>(artifact_info, CacheMode::Default)
+                    .get_artifact_with_cache::<SDist>(
+                        artifact_info,
+                        CacheMode::Default,
+                        builder.resolve_options().progress_observer.as_ref(),
+                    )
                     .await?;
 
-                return builder.build_wheel(&sdist).await.into_diagnostic();
+                return match builder.build_wheel(&sdist).await {
+                    Err(WheelBuildError::BuildDependencyCycle(cycle)) => Err(cycle.into()),
+                    result => result.into_diagnostic(),
+                };
             } else {
                 miette::bail!("cannot build wheel without a wheel builder");
             }
         }
 
         // Otherwise just retrieve the wheel
-        self.get_artifact_with_cache::<Wheel>(artifact_info, CacheMode::Default)
+        let progress = builder.and_then(|b| b.resolve_options().progress_observer.as_ref());
+        self.get_artifact_with_cache::<Wheel>(artifact_info, CacheMode::Default, progress)
             .await
     }
 }
 
-async fn fetch_simple_api(http: &Http, url: Url) -> miette::Result<Option<ProjectInfo>> {
+/// Determines the artifact that a direct url refers to from the filename at the end of its path.
+///
+/// The filename has to be a valid wheel filename of the package `name` or the filename of a source
+/// distribution archive, this ensures that a misformatted url (e.g. one that refers to a directory
+/// listing or to another package) is rejected instead of installing the wrong thing.
+///
+/// Returns `None` for an archive whose filename does not follow the naming convention of source
+/// distributions, e.g. the archive of a tag on GitHub (`v1.0.zip`). Its name and version are only
+/// known from its metadata, see [`PackageDb::get_sdist_from_http`].
+fn direct_url_artifact_name(
+    url: &Url,
+    name: &NormalizedPackageName,
+) -> miette::Result<Option<ArtifactName>> {
+    let filename = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|filename| !filename.is_empty())
+        .ok_or_else(|| {
+            miette::miette!(
+                help = format!("the url should end in the filename of a wheel or source distribution, e.g. `{name}-1.0.tar.gz` or `{name}-1.0-py3-none-any.whl`"),
+                "the url '{url}' of '{name}' does not refer to a wheel or source distribution"
+            )
+        })?;
+    let filename = percent_encoding::percent_decode_str(filename).decode_utf8_lossy();
+
+    let wrong_artifact =
+        || format!("the url '{url}' does not refer to a wheel or source distribution of '{name}'");
+    if filename.ends_with(".whl") {
+        return WheelFilename::from_filename(&filename, name)
+            .map(|filename| Some(ArtifactName::Wheel(filename)))
+            .into_diagnostic()
+            .wrap_err_with(wrong_artifact);
+    }
+
+    let (stem, _) = split_sdist_extension(&filename)
+        .ok_or_else(|| ParseArtifactNameError::InvalidExtension(filename.to_string()))
+        .into_diagnostic()
+        .wrap_err_with(wrong_artifact)?;
+    match SDistFilename::from_filename(&filename, name) {
+        Ok(filename) => Ok(Some(ArtifactName::SDist(filename))),
+        // Only reject filenames that clearly belong to another package, e.g. `django-5.0.tar.gz`
+        Err(err)
+            if stem.rsplit_once('-').is_some_and(|(other, version)| {
+                PackageName::from_str(other).is_ok() && Version::from_str(version).is_ok()
+            }) =>
+        {
+            Err(err).into_diagnostic().wrap_err_with(wrong_artifact)
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Returns the contents of the artifact `filename` from a response.
+///
+/// Some servers compress artifacts once more when sending them, which is indicated by the
+/// `Content-Encoding` header. This is a property of the transfer and not of the artifact, so the
+/// encoding is undone here. Servers are also known to send a `.tar.gz` file as is but label it
+/// with `Content-Encoding: gzip`, in that case the body is the artifact itself.
+async fn artifact_body(
+    response: http::Response<StreamingOrLocal>,
+    filename: &str,
+) -> miette::Result<Box<dyn ReadAndSeek + Send>> {
+    let encoding = response
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|h| h.to_str().ok())
+        .map(|encoding| encoding.trim().to_ascii_lowercase());
+    let mut body = response.into_body().into_local().await.into_diagnostic()?;
+
+    match encoding.as_deref() {
+        None | Some("identity") => return Ok(body),
+        Some("gzip" | "x-gzip") => {}
+        Some(encoding) => {
+            miette::bail!("'{filename}' was sent with an unsupported content encoding '{encoding}'")
+        }
+    }
+
+    // The body may already have been decoded along the way
+    if !starts_with_gzip_magic(&mut body).into_diagnostic()? {
+        return Ok(body);
+    }
+
+    let mut decoded = tempfile::SpooledTempFile::new(5 * 1024 * 1024);
+    std::io::copy(&mut flate2::read::GzDecoder::new(&mut body), &mut decoded)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to decode the gzip content encoding of '{filename}'"))?;
+    decoded.rewind().into_diagnostic()?;
+
+    // A gzipped artifact that decodes to something other than gzip was not encoded twice
+    let is_gzip_artifact = filename.ends_with(".tar.gz") || filename.ends_with(".tgz");
+    if is_gzip_artifact && !starts_with_gzip_magic(&mut decoded).into_diagnostic()? {
+        body.rewind().into_diagnostic()?;
+        return Ok(body);
+    }
+
+    Ok(Box::new(decoded))
+}
+
+/// Returns true if the stream starts with the gzip magic bytes. The stream is rewound afterwards.
+fn starts_with_gzip_magic(stream: &mut impl ReadAndSeek) -> std::io::Result<bool> {
+    let mut magic = [0u8; 2];
+    stream.rewind()?;
+    let result = stream.read_exact(&mut magic);
+    stream.rewind()?;
+    match result {
+        Ok(()) => Ok(magic == [0x1f, 0x8b]),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+async fn fetch_simple_api(
+    http: &Http,
+    url: Url,
+    cache_mode: CacheMode,
+) -> miette::Result<Option<ProjectInfo>> {
     let mut headers = HeaderMap::new();
     headers.insert(CACHE_CONTROL, HeaderValue::from_static("max-age=0"));
 
-    let response = http
-        .request(url, Method::GET, headers, CacheMode::Default)
-        .await?;
+    let response = http.request(url, Method::GET, headers, cache_mode).await?;
 
     // If the resource could not be found we simply return.
     if response.status() == StatusCode::NOT_FOUND {
@@ -916,28 +1569,125 @@ async fn fetch_simple_api(http: &Http, url: Url) -> miette::Result<Option<Projec
         .await
         .into_diagnostic()?;
 
-    let content_type: mime::Mime = content_type.parse().into_diagnostic()?;
-    match (
-        content_type.type_().as_str(),
-        content_type.subtype().as_str(),
-    ) {
-        ("text", "html") => {
-            parse_project_info_html(&url, std::str::from_utf8(&bytes).into_diagnostic()?).map(Some)
+    let is_html = content_type
+        .parse::<mime::Mime>()
+        .is_ok_and(|mime| mime.type_() == mime::TEXT && mime.subtype() == mime::HTML);
+    if !is_html {
+        // Some self-hosted indexes serve the pages with the wrong content type, accept those as
+        // long as the body looks like html.
+        if !looks_like_html(&bytes) {
+            miette::bail!(
+                "simple API page expected Content-Type: text/html, but got {}",
+                &content_type
+            );
         }
-        _ => miette::bail!(
-            "simple API page expected Content-Type: text/html, but got {}",
-            &content_type
-        ),
+        tracing::warn!(
+            "the simple API page {url} has the unexpected Content-Type '{content_type}', parsing it as html"
+        );
     }
+
+    parse_project_info_html(&url, std::str::from_utf8(&bytes).into_diagnostic()?).map(Some)
+}
+
+async fn fetch_json_api(
+    http: &Http,
+    url: Url,
+    package_name: &NormalizedPackageName,
+    cache_mode: CacheMode,
+) -> miette::Result<Option<ProjectInfo>> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("max-age=0"));
+
+    let response = http.request(url, Method::GET, headers, cache_mode).await?;
+
+    // If the resource could not be found we simply return.
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let url = response.extensions().get::<Url>().unwrap().to_owned();
+
+    let mut bytes = Vec::new();
+    response
+        .into_body()
+        .read_to_end(&mut bytes)
+        .await
+        .into_diagnostic()?;
+
+    parse_project_info_json_api(&url, package_name, &bytes)
+        .wrap_err_with(|| format!("failed to parse the JSON API response of {url}"))
+        .map(Some)
+}
+
+/// Returns true if the document starts like an html document or contains a link.
+fn looks_like_html(bytes: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return false;
+    };
+    let text = text
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .to_ascii_lowercase();
+    text.starts_with("<!doctype html") || text.starts_with("<html") || text.contains("<a ")
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::index::{KeyringProvider, PackageDbBuildError, ProxyConfig};
+    use crate::python_env::Pep508EnvMakers;
+    use crate::resolve::ResolveOptions;
     use crate::types::PackageName;
     use reqwest::Client;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_builder() {
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = PackageDb::builder(cache_dir.path())
+            .index_url(Url::parse("https://pypi.org/simple/").unwrap())
+            .index_urls([Url::parse("file:///srv/index/").unwrap()])
+            .find_links([cache_dir.path().join("wheels")])
+            .user_agent("rip-test")
+            .timeout(std::time::Duration::from_secs(5))
+            .proxy_config(ProxyConfig::default().with_proxy("http://proxy:3128".parse().unwrap()))
+            .keyring_provider(KeyringProvider::Subprocess)
+            .build()
+            .unwrap();
+        assert_eq!(
+            package_db
+                .index_urls()
+                .iter()
+                .map(Url::as_str)
+                .collect::<Vec<_>>(),
+            ["https://pypi.org/simple/", "file:///srv/index/"]
+        );
+        assert_eq!(package_db.find_links(), [cache_dir.path().join("wheels")]);
+        assert_eq!(package_db.cache_dir(), cache_dir.path());
+
+        // A preconfigured client cannot be combined with the options of the client
+        let result = PackageDb::builder(cache_dir.path())
+            .client(Client::new().into())
+            .timeout(std::time::Duration::from_secs(5))
+            .build();
+        assert!(matches!(
+            result,
+            Err(PackageDbBuildError::ConflictingClientOption("timeout"))
+        ));
+        assert!(PackageDb::builder(cache_dir.path())
+            .client(Client::new().into())
+            .build()
+            .is_ok());
+
+        let result = PackageDb::builder(cache_dir.path())
+            .index_url(Url::parse("ftp://example.com/simple/").unwrap())
+            .build();
+        assert!(matches!(
+            result,
+            Err(PackageDbBuildError::UnsupportedIndexUrl(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_available_packages() {
         let cache_dir = TempDir::new().unwrap();
@@ -957,7 +1707,7 @@ mod test {
         // Get the first wheel artifact
         let artifact_info = artifacts
             .iter()
-            .flat_map(|(_, artifacts)| artifacts.into_iter().cloned())
+            .flat_map(|(_, artifacts)| artifacts.iter().cloned())
             .collect::<Vec<_>>();
 
         let (_artifact, _metadata) = package_db
@@ -967,6 +1717,114 @@ mod test {
             .unwrap();
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_clear_memory_cache() {
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(
+                ClientWithMiddleware::from(Client::new()),
+                &[Url::parse("https://pypi.org/simple/").unwrap()],
+                cache_dir.path(),
+            )
+            .unwrap(),
+        );
+
+        // The wheel builder shares the package database, just like during a resolve.
+        let env_markers = Arc::new(Pep508EnvMakers::from_env().await.unwrap().0);
+        let wheel_builder = WheelBuilder::new(
+            package_db.clone(),
+            env_markers,
+            None,
+            ResolveOptions::default(),
+            HashMap::default(),
+        )
+        .unwrap();
+
+        let name = "miniblack".parse::<PackageName>().unwrap();
+        let url = Url::from_file_path(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels/miniblack-23.1.0-py3-none-any.whl")
+                .canonicalize()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let before = package_db
+            .get_artifact_by_direct_url(name.clone(), url.clone(), &wheel_builder)
+            .await
+            .unwrap();
+        assert!(!package_db.artifacts.lock().is_empty());
+
+        package_db.clear_memory_cache();
+        assert!(package_db.artifacts.lock().is_empty());
+
+        // Fetching again repopulates the in-memory cache with a fresh, but identical, entry.
+        let after = package_db
+            .get_artifact_by_direct_url(name, url.clone(), &wheel_builder)
+            .await
+            .unwrap();
+        assert!(!package_db.artifacts.lock().is_empty());
+        assert!(!Arc::ptr_eq(&before, &after));
+
+        let before = &before.get(&PypiVersion::Url(url.clone())).unwrap()[0];
+        let after = &after.get(&PypiVersion::Url(url)).unwrap()[0];
+        assert_eq!(before.filename, after.filename);
+        assert_eq!(before.hashes, after.hashes);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_direct_url_fetched_once() {
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(
+                ClientWithMiddleware::from(Client::new()),
+                &[Url::parse("https://pypi.org/simple/").unwrap()],
+                cache_dir.path(),
+            )
+            .unwrap(),
+        );
+        let wheel_builder = WheelBuilder::new(
+            package_db.clone(),
+            Arc::new(Pep508EnvMakers::from_env().await.unwrap().0),
+            None,
+            ResolveOptions::default(),
+            HashMap::default(),
+        )
+        .unwrap();
+
+        let wheels = TempDir::new().unwrap();
+        let wheel = wheels.path().join("miniblack-23.1.0-py3-none-any.whl");
+        fs_err::copy(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels/miniblack-23.1.0-py3-none-any.whl"),
+            &wheel,
+        )
+        .unwrap();
+        let url = Url::from_file_path(&wheel).unwrap();
+        let name = "miniblack".parse::<PackageName>().unwrap();
+
+        let first = package_db
+            .get_artifact_by_direct_url(name.clone(), url.clone(), &wheel_builder)
+            .await
+            .unwrap();
+
+        // A second requirement on the same url is served without reading the wheel again
+        fs_err::remove_file(&wheel).unwrap();
+        let second = package_db
+            .get_artifact_by_direct_url(name.clone(), url, &wheel_builder)
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Another url of the same package is fetched separately
+        let other =
+            Url::from_file_path(wheels.path().join("miniblack-23.2.0-py3-none-any.whl")).unwrap();
+        assert!(package_db
+            .get_artifact_by_direct_url(name, other, &wheel_builder)
+            .await
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_pep658() {
         let cache_dir = TempDir::new().unwrap();
@@ -993,6 +1851,797 @@ mod test {
 
         let (_artifact, _metadata) = package_db.get_pep658_metadata(artifact_info).await.unwrap();
     }
+
+    /// Serves `bytes` over http on a random local port and returns the url to request them from.
+    /// `headers` are added to the response as is.
+    async fn serve_bytes(bytes: Vec<u8>, filename: &str, headers: &'static str) -> Url {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!(
+            "http://{}/files/{filename}",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0; 4096];
+                let _ = stream.read(&mut buf).await;
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n{headers}\r\n",
+                    bytes.len()
+                );
+                let _ = stream.write_all(header.as_bytes()).await;
+                let _ = stream.write_all(&bytes).await;
+            }
+        });
+        url
+    }
+
+    /// Creates a `.tar.gz` source distribution that only contains the given `PKG-INFO`.
+    fn sdist_with_pkg_info(pkg_info: &str) -> Vec<u8> {
+        sdist_with_files("reliable-1.0", &[("PKG-INFO", pkg_info)])
+    }
+
+    /// Creates a `.tar.gz` source distribution that contains the given files in the directory
+    /// `dir`.
+    fn sdist_with_files(dir: &str, files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, format!("{dir}/{path}"), contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    async fn sdist_metadata(
+        bytes: Vec<u8>,
+        filename: &str,
+        skip_unreliable_sdists: bool,
+    ) -> miette::Result<Option<WheelCoreMetadata>> {
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(
+                ClientWithMiddleware::from(Client::new()),
+                &[Url::parse("https://pypi.org/simple/").unwrap()],
+                cache_dir.path(),
+            )
+            .unwrap(),
+        );
+        let wheel_builder = WheelBuilder::new(
+            package_db.clone(),
+            Arc::new(Pep508EnvMakers::from_env().await.unwrap().0),
+            None,
+            ResolveOptions {
+                skip_unreliable_sdists,
+                ..Default::default()
+            },
+            HashMap::default(),
+        )
+        .unwrap();
+
+        let url = serve_bytes(bytes, filename, "").await;
+        let name: NormalizedPackageName = filename.split('-').next().unwrap().parse().unwrap();
+        let artifact_info = [ArtifactInfo {
+            filename: ArtifactName::from_filename(filename, &name).unwrap(),
+            url,
+            hashes: None,
+            requires_python: None,
+            dist_info_metadata: Default::default(),
+            yanked: Default::default(),
+            index_url: None,
+        }];
+        let metadata = package_db
+            .get_metadata(&artifact_info, Some(&wheel_builder))
+            .await?;
+        Ok(metadata.map(|(_, metadata)| metadata))
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_direct_url_artifact_name() {
+        let name: NormalizedPackageName = "flask".parse().unwrap();
+        let artifact_name = |url: &str| direct_url_artifact_name(&Url::parse(url).unwrap(), &name);
+
+        let wheel =
+            artifact_name("https://example.com/packages/Flask-3.0.0-py3-none-any.whl#sha256=abc")
+                .unwrap();
+        let Some(ArtifactName::Wheel(wheel)) = wheel else {
+            panic!("expected a wheel, got {wheel:?}");
+        };
+        assert_eq!(wheel.distribution, "Flask".parse().unwrap());
+        assert_eq!(wheel.version, "3.0.0".parse().unwrap());
+
+        let sdist = artifact_name("https://example.com/flask-3.0.0%2Blocal.tar.gz").unwrap();
+        assert_eq!(sdist.unwrap().to_string(), "flask-3.0.0+local.tar.gz");
+
+        // Archives that do not follow the sdist naming convention are named by their metadata
+        assert!(
+            artifact_name("https://github.com/pallets/flask/archive/refs/tags/3.0.0.zip")
+                .unwrap()
+                .is_none()
+        );
+        assert!(artifact_name("https://example.com/v3.0.0.tar.gz")
+            .unwrap()
+            .is_none());
+
+        // The filename has to belong to the requested package
+        let err = artifact_name("https://example.com/django-5.0-py3-none-any.whl").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the url 'https://example.com/django-5.0-py3-none-any.whl' does not refer to a wheel or source distribution of 'flask'"
+        );
+        assert!(artifact_name("https://example.com/django-5.0.tar.gz").is_err());
+
+        // Urls that do not end in a filename are rejected
+        for url in [
+            "https://example.com/packages/flask/",
+            "https://example.com",
+            "https://example.com/packages/flask",
+            "https://example.com/flask-3.0.0.exe",
+        ] {
+            assert!(artifact_name(url).is_err(), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_content_type() {
+        let html = r#"<!DOCTYPE html>
+            <html>
+              <body>
+                <a href="foo-1.0-py3-none-any.whl">foo-1.0-py3-none-any.whl</a>
+                <a href="foo-1.1.tar.gz">foo-1.1.tar.gz</a>
+              </body>
+            </html>"#;
+        let cache_dir = TempDir::new().unwrap();
+
+        // Valid html that is served as plain text is still parsed
+        let url = serve_bytes(html.into(), "", "content-type: text/plain\r\n").await;
+        let package_db = PackageDb::new(
+            Client::new().into(),
+            &[url.join("../simple/").unwrap()],
+            cache_dir.path(),
+        )
+        .unwrap();
+        let artifacts = package_db
+            .available_artifacts("foo".parse::<PackageName>().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            artifacts
+                .values()
+                .flatten()
+                .map(|a| a.filename.to_string())
+                .collect::<Vec<_>>(),
+            ["foo-1.1.tar.gz", "foo-1.0-py3-none-any.whl"]
+        );
+
+        // Anything else is still an error
+        let url = serve_bytes(
+            br#"{"files": []}"#.to_vec(),
+            "",
+            "content-type: application/json\r\n",
+        )
+        .await;
+        let package_db = PackageDb::new(
+            Client::new().into(),
+            &[url.join("../simple/").unwrap()],
+            cache_dir.path(),
+        )
+        .unwrap();
+        let err = package_db
+            .available_artifacts("foo".parse::<PackageName>().unwrap())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("but got application/json"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_index_url_of_artifacts() {
+        // Each index has a different version of the package
+        let mut index_urls = Vec::new();
+        for version in ["1.0", "2.0"] {
+            let html = format!(r#"<a href="/files/foo-{version}.tar.gz">foo-{version}.tar.gz</a>"#);
+            let url = serve_bytes(html.into(), "", "content-type: text/html\r\n").await;
+            index_urls.push(url.join("../simple/").unwrap());
+        }
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = PackageDb::new(Client::new().into(), &index_urls, cache_dir.path())
+            .unwrap()
+            .with_find_links(
+                [Path::new(env!("CARGO_MANIFEST_DIR")).join("../../test-data/sdists")],
+            );
+
+        let sources = |name: &str| {
+            let package_db = &package_db;
+            let name = name.parse::<PackageName>().unwrap();
+            async move {
+                package_db
+                    .available_artifacts(name)
+                    .await
+                    .unwrap()
+                    .values()
+                    .flatten()
+                    .map(|a| (a.filename.to_string(), a.index_url.clone()))
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(
+            sources("foo").await,
+            [
+                (String::from("foo-2.0.tar.gz"), Some(index_urls[1].clone())),
+                (String::from("foo-1.0.tar.gz"), Some(index_urls[0].clone())),
+            ]
+        );
+
+        // Artifacts from the find-links directories do not come from an index
+        assert!(sources("rich")
+            .await
+            .contains(&(String::from("rich-13.6.0.tar.gz"), None)));
+    }
+
+    #[tokio::test]
+    async fn test_refresh() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Like a stale proxy, the server confirms every cached page although a new version was
+        // published after the first request
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let index_url = Url::parse(&format!(
+            "http://{}/simple/",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        tokio::spawn(async move {
+            let mut pages = [
+                r#"<a href="/files/foo-1.0.tar.gz">foo-1.0.tar.gz</a>"#,
+                r#"<a href="/files/foo-1.0.tar.gz">foo-1.0.tar.gz</a><a href="/files/foo-2.0.tar.gz">foo-2.0.tar.gz</a>"#,
+            ]
+            .into_iter();
+            let mut page = pages.next().unwrap();
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0; 4096];
+                let len = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..len]).to_lowercase();
+                let response = if request.contains("if-none-match") {
+                    String::from("HTTP/1.1 304 Not Modified\r\netag: \"v1\"\r\ncache-control: no-cache\r\nconnection: close\r\n\r\n")
+                } else {
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: {}\r\netag: \"v1\"\r\ncache-control: no-cache\r\nconnection: close\r\n\r\n{page}",
+                        page.len()
+                    );
+                    page = pages.next().unwrap_or(page);
+                    response
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let cache_dir = TempDir::new().unwrap();
+        let versions = |refresh: bool| {
+            let package_db =
+                PackageDb::new(Client::new().into(), &[index_url.clone()], cache_dir.path())
+                    .unwrap()
+                    .with_refresh(refresh);
+            async move {
+                package_db
+                    .available_artifacts("foo".parse::<PackageName>().unwrap())
+                    .await
+                    .unwrap()
+                    .keys()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(versions(false).await, ["1.0"]);
+        assert_eq!(versions(false).await, ["1.0"]);
+
+        // Refreshing downloads the page again, which also replaces the cached page
+        assert_eq!(versions(true).await, ["2.0", "1.0"]);
+        assert_eq!(versions(false).await, ["2.0", "1.0"]);
+    }
+
+    /// Records the download events
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<(u64, Option<u64>)>>, Mutex<Vec<Url>>);
+
+    impl ProgressObserver for Recorder {
+        fn on_download_progress(&self, _url: &Url, bytes: u64, total: Option<u64>) {
+            self.0.lock().push((bytes, total));
+        }
+
+        fn on_download_finished(&self, url: &Url) {
+            self.1.lock().push(url.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_progress() {
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(
+                ClientWithMiddleware::from(Client::new()),
+                &[Url::parse("https://pypi.org/simple/").unwrap()],
+                cache_dir.path(),
+            )
+            .unwrap(),
+        );
+        let recorder = Arc::new(Recorder::default());
+        let wheel_builder = WheelBuilder::new(
+            package_db.clone(),
+            Arc::new(Pep508EnvMakers::from_env().await.unwrap().0),
+            None,
+            ResolveOptions {
+                progress_observer: Some(recorder.clone()),
+                ..Default::default()
+            },
+            HashMap::default(),
+        )
+        .unwrap();
+
+        let filename = "miniblack-23.1.0-py3-none-any.whl";
+        let wheel = fs_err::read(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels")
+                .join(filename),
+        )
+        .unwrap();
+        let size = wheel.len() as u64;
+        let url = serve_bytes(wheel, filename, "cache-control: max-age=3600\r\n").await;
+        let artifact_info = ArtifactInfo {
+            filename: ArtifactName::from_filename(filename, &"miniblack".parse().unwrap()).unwrap(),
+            url: url.clone(),
+            hashes: None,
+            requires_python: None,
+            dist_info_metadata: Default::default(),
+            yanked: Default::default(),
+            index_url: None,
+        };
+        package_db
+            .get_wheel(&artifact_info, Some(&wheel_builder))
+            .await
+            .unwrap();
+
+        // The progress increases up to the size of the wheel
+        let progress = recorder.0.lock().clone();
+        assert!(!progress.is_empty());
+        assert!(progress.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(progress.last(), Some(&(size, Some(size))));
+        assert_eq!(*recorder.1.lock(), [url]);
+
+        // Reading the wheel from the cache is not a download
+        package_db
+            .get_wheel(&artifact_info, Some(&wheel_builder))
+            .await
+            .unwrap();
+        assert_eq!(recorder.0.lock().len(), progress.len());
+        assert_eq!(recorder.1.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_without_content_length() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let filename = "miniblack-23.1.0-py3-none-any.whl";
+        let wheel = fs_err::read(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels")
+                .join(filename),
+        )
+        .unwrap();
+        let size = wheel.len() as u64;
+
+        // Serve the wheel in chunks without a Content-Length header
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!(
+            "http://{}/files/{filename}",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0; 4096];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\ncache-control: max-age=3600\r\nconnection: close\r\n\r\n")
+                    .await;
+                for chunk in wheel.chunks(4096) {
+                    let _ = stream
+                        .write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
+                        .await;
+                    let _ = stream.write_all(chunk).await;
+                    let _ = stream.write_all(b"\r\n").await;
+                }
+                let _ = stream.write_all(b"0\r\n\r\n").await;
+            }
+        });
+
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(
+                ClientWithMiddleware::from(Client::new()),
+                &[Url::parse("https://pypi.org/simple/").unwrap()],
+                cache_dir.path(),
+            )
+            .unwrap(),
+        );
+        let recorder = Arc::new(Recorder::default());
+        let wheel_builder = WheelBuilder::new(
+            package_db.clone(),
+            Arc::new(Pep508EnvMakers::from_env().await.unwrap().0),
+            None,
+            ResolveOptions {
+                progress_observer: Some(recorder.clone()),
+                ..Default::default()
+            },
+            HashMap::default(),
+        )
+        .unwrap();
+        let artifact_info = ArtifactInfo {
+            filename: ArtifactName::from_filename(filename, &"miniblack".parse().unwrap()).unwrap(),
+            url: url.clone(),
+            hashes: None,
+            requires_python: None,
+            dist_info_metadata: Default::default(),
+            yanked: Default::default(),
+            index_url: None,
+        };
+        // The metadata cannot be read sparsely without knowing the size, so the whole wheel is
+        // downloaded instead
+        let (_, metadata) = package_db
+            .get_metadata(&[artifact_info.clone()], Some(&wheel_builder))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.name.as_str(), "miniblack");
+        let wheel = package_db
+            .get_wheel(&artifact_info, Some(&wheel_builder))
+            .await
+            .unwrap();
+        let (_, metadata) = wheel.metadata().unwrap();
+        assert_eq!(metadata.name.as_str(), "miniblack");
+
+        // The progress is reported without a total
+        let progress = recorder.0.lock().clone();
+        assert!(progress.iter().all(|(_, total)| total.is_none()));
+        assert_eq!(progress.last(), Some(&(size, None)));
+        assert_eq!(*recorder.1.lock(), [url]);
+    }
+
+    #[tokio::test]
+    async fn test_gzip_content_encoding() {
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = PackageDb::new(
+            ClientWithMiddleware::from(Client::new()),
+            &[Url::parse("https://pypi.org/simple/").unwrap()],
+            cache_dir.path(),
+        )
+        .unwrap();
+        let artifact_info = |filename: &str, url: Url| {
+            let name: NormalizedPackageName = filename.split('-').next().unwrap().parse().unwrap();
+            ArtifactInfo {
+                filename: ArtifactName::from_filename(filename, &name).unwrap(),
+                url,
+                hashes: None,
+                requires_python: None,
+                dist_info_metadata: Default::default(),
+                yanked: Default::default(),
+                index_url: None,
+            }
+        };
+
+        // A wheel that is compressed once more for the transfer
+        let filename = "miniblack-23.1.0-py3-none-any.whl";
+        let wheel = fs_err::read(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels")
+                .join(filename),
+        )
+        .unwrap();
+        let url = serve_bytes(gzip(&wheel), filename, "content-encoding: gzip\r\n").await;
+        let wheel = package_db
+            .get_wheel(&artifact_info(filename, url), None)
+            .await
+            .unwrap();
+        let (_, metadata) = wheel.metadata().unwrap();
+        assert_eq!(metadata.name.as_str(), "miniblack");
+
+        // A source distribution that is sent as is but labeled with the gzip content encoding
+        let filename = "reliable-1.0.tar.gz";
+        let sdist = sdist_with_pkg_info("Metadata-Version: 2.2\nName: reliable\nVersion: 1.0\n");
+        let url = serve_bytes(sdist, filename, "content-encoding: gzip\r\n").await;
+        let sdist = package_db
+            .get_artifact_with_cache::<SDist>(
+                &artifact_info(filename, url),
+                CacheMode::NoStore,
+                None,
+            )
+            .await
+            .unwrap();
+        let metadata = sdist.pep643_metadata().unwrap().unwrap().1;
+        assert_eq!(metadata.name.as_str(), "reliable");
+    }
+
+    #[tokio::test]
+    async fn test_artifact_hash_verification() {
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = PackageDb::new(
+            ClientWithMiddleware::from(Client::new()),
+            &[Url::parse("https://pypi.org/simple/").unwrap()],
+            cache_dir.path(),
+        )
+        .unwrap();
+
+        let filename = "miniblack-23.1.0-py3-none-any.whl";
+        let wheel = fs_err::read(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels")
+                .join(filename),
+        )
+        .unwrap();
+        let sha256 = compute_bytes_digest::<Sha256>(&wheel);
+        let url = serve_bytes(wheel, filename, "cache-control: max-age=3600\r\n").await;
+        let artifact_info = |sha256| ArtifactInfo {
+            filename: ArtifactName::from_filename(filename, &"miniblack".parse().unwrap()).unwrap(),
+            url: url.clone(),
+            hashes: Some(ArtifactHashes {
+                sha256: Some(sha256),
+                ..Default::default()
+            }),
+            requires_python: None,
+            dist_info_metadata: Default::default(),
+            yanked: Default::default(),
+            index_url: None,
+        };
+
+        // The hash matches, the second time the artifact is read from the http cache and the
+        // checksum is stored.
+        for _ in 0..2 {
+            package_db
+                .get_wheel(&artifact_info(sha256), None)
+                .await
+                .unwrap();
+        }
+        assert!(fs_err::read_dir(cache_dir.path().join("checksums"))
+            .unwrap()
+            .next()
+            .is_some());
+
+        // The hash does not match
+        let err = package_db
+            .get_wheel(&artifact_info(rattler_digest::Sha256Hash::default()), None)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("but the index reported"), "{err}");
+    }
+
+    /// Returns the paths of all files in `dir` and its subdirectories.
+    fn files_in(dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for entry in fs_err::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(files_in(&path));
+            } else {
+                files.push(path);
+            }
+        }
+        files
+    }
+
+    #[tokio::test]
+    async fn test_recover_from_corrupt_cache() {
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = PackageDb::new(
+            ClientWithMiddleware::from(Client::new()),
+            &[Url::parse("https://pypi.org/simple/").unwrap()],
+            cache_dir.path(),
+        )
+        .unwrap();
+
+        let filename = "miniblack-23.1.0-py3-none-any.whl";
+        let wheel = fs_err::read(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels")
+                .join(filename),
+        )
+        .unwrap();
+        let artifact_info = ArtifactInfo {
+            filename: ArtifactName::from_filename(filename, &"miniblack".parse().unwrap()).unwrap(),
+            hashes: Some(ArtifactHashes {
+                sha256: Some(compute_bytes_digest::<Sha256>(&wheel)),
+                ..Default::default()
+            }),
+            url: serve_bytes(wheel, filename, "cache-control: max-age=3600\r\n").await,
+            requires_python: None,
+            dist_info_metadata: Default::default(),
+            yanked: Default::default(),
+            index_url: None,
+        };
+        package_db.get_wheel(&artifact_info, None).await.unwrap();
+
+        // Locks are stored next to the entries
+        let entries = files_in(&cache_dir.path().join("http-v2"))
+            .into_iter()
+            .filter(|path| path.extension().map_or(true, |ext| ext != "lock"))
+            .collect::<Vec<_>>();
+        let [entry] = entries.as_slice() else {
+            panic!("expected a single cache entry, found {entries:?}");
+        };
+
+        // A truncated entry is detected by its size. The entry is written again, with a new
+        // response time but with the same body.
+        let contents = fs_err::read(entry).unwrap();
+        let body = &contents[contents.len() - 1000..];
+        fs_err::write(entry, &contents[..contents.len() / 2]).unwrap();
+        package_db.get_wheel(&artifact_info, None).await.unwrap();
+        assert!(fs_err::read(entry).unwrap().ends_with(body));
+
+        // A damaged entry of the same size is detected by its hash
+        let mut damaged = contents.clone();
+        let last = damaged.len() - 100;
+        damaged[last] ^= 0xff;
+        fs_err::write(entry, &damaged).unwrap();
+        // The stored checksum would hide the damage if the modification time did not change
+        fs_err::remove_dir_all(cache_dir.path().join("checksums")).unwrap();
+        package_db.get_wheel(&artifact_info, None).await.unwrap();
+        assert!(fs_err::read(entry).unwrap().ends_with(body));
+
+        // Corrupt metadata is discarded and obtained from the wheel again
+        package_db.clear_memory_cache();
+        package_db
+            .put_metadata_in_cache(&artifact_info, b"\xff not metadata")
+            .await
+            .unwrap();
+        let (_, metadata) = package_db
+            .get_metadata(&[artifact_info.clone()], None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.name.as_str(), "miniblack");
+    }
+
+    fn rich_sdist() -> Vec<u8> {
+        fs_err::read(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../test-data/sdists/rich-13.6.0.tar.gz"),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sdist_metadata_without_building() {
+        // Reliable metadata is used without building the source distribution
+        let sdist = sdist_with_pkg_info(
+            "Metadata-Version: 2.2\nName: reliable\nVersion: 1.0\nRequires-Dist: rich\n",
+        );
+        let metadata = sdist_metadata(sdist, "reliable-1.0.tar.gz", true)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.requires_dist[0].name, "rich");
+
+        // rich only provides metadata version 2.1 so it has to be built
+        let err = sdist_metadata(rich_sdist(), "rich-13.6.0.tar.gz", true)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("is not reliable"), "{err}");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sdist_metadata_by_building() {
+        // The PKG-INFO does not implement PEP 643, the dependencies are only known after building
+        let sdist = sdist_with_files(
+            "unreliable-1.0",
+            &[
+                (
+                    "PKG-INFO",
+                    "Metadata-Version: 2.1\nName: unreliable\nVersion: 1.0\n",
+                ),
+                (
+                    "pyproject.toml",
+                    r#"
+[build-system]
+requires = []
+build-backend = "metadata_backend"
+backend-path = ["."]
+"#,
+                ),
+                (
+                    "metadata_backend.py",
+                    r#"
+import pathlib
+
+def get_requires_for_build_wheel(config_settings=None):
+    return []
+
+def prepare_metadata_for_build_wheel(metadata_directory, config_settings=None):
+    dist_info = pathlib.Path(metadata_directory, "unreliable-1.0.dist-info")
+    dist_info.mkdir()
+    (dist_info / "METADATA").write_text(
+        "Metadata-Version: 2.1\nName: unreliable\nVersion: 1.0\nRequires-Dist: pygments\n"
+    )
+    return dist_info.name
+"#,
+                ),
+            ],
+        );
+        let metadata = sdist_metadata(sdist, "unreliable-1.0.tar.gz", false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.name.as_str(), "unreliable");
+        assert!(metadata
+            .requires_dist
+            .iter()
+            .any(|req| req.name == "pygments"));
+    }
+
+    #[tokio::test]
+    async fn test_direct_url_archive_named_by_metadata() {
+        let pkg_info = "Metadata-Version: 2.2\nName: reliable\nVersion: 1.0\n";
+        let url = serve_bytes(sdist_with_pkg_info(pkg_info), "v1.0.tar.gz", "").await;
+        let cache_dir = TempDir::new().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(
+                ClientWithMiddleware::from(Client::new()),
+                &[Url::parse("https://pypi.org/simple/").unwrap()],
+                cache_dir.path(),
+            )
+            .unwrap(),
+        );
+        let wheel_builder = WheelBuilder::new(
+            package_db.clone(),
+            Arc::new(Pep508EnvMakers::from_env().await.unwrap().0),
+            None,
+            ResolveOptions::default(),
+            HashMap::default(),
+        )
+        .unwrap();
+
+        let artifact = package_db
+            .get_direct_http_url_artifact(
+                "reliable".parse::<NormalizedPackageName>().unwrap(),
+                url.clone(),
+                &wheel_builder,
+            )
+            .await
+            .unwrap();
+        let (_, artifacts) = artifact.first().unwrap();
+        assert_eq!(artifacts[0].filename.to_string(), "reliable-1.0.tar.gz");
+
+        let err = package_db
+            .get_direct_http_url_artifact(
+                "flask".parse::<NormalizedPackageName>().unwrap(),
+                url.clone(),
+                &wheel_builder,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "the url '{url}' refers to a source distribution of 'reliable' instead of 'flask'"
+            )
+        );
+    }
 }
 
 #[derive(Debug, Diagnostic)]
@@ -0,0 +1,234 @@
+//! Configuration of the HTTP proxy that is used to reach package indexes.
+//!
+//! reqwest can pick up the proxy environment variables by itself, but it offers no way to combine
+//! those with an explicitly configured proxy. [`ProxyConfig`] handles both and decides per request
+//! whether a proxy is used.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use url::Url;
+
+/// Determines which proxy, if any, is used to reach a certain url.
+///
+/// By default the `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY` environment variables
+/// (or their lowercase variants) are respected, an explicit proxy overrides the first three.
+/// Urls that do not use `http` or `https` (e.g. a `file://` index) never use a proxy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxyConfig {
+    http_proxy: Option<Url>,
+    https_proxy: Option<Url>,
+    no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Reads the proxy configuration from the environment variables of the current process.
+    pub fn from_env() -> Self {
+        Self::from_env_vars(|var| std::env::var(var).ok())
+    }
+
+    /// Reads the proxy configuration using `env_var` to look up environment variables.
+    fn from_env_vars(env_var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| {
+            env_var(name)
+                .or_else(|| env_var(&name.to_lowercase()))
+                .filter(|value| !value.trim().is_empty())
+        };
+        let proxy_url = |name: &str| {
+            var(name).and_then(|value| match Url::parse(&value) {
+                Ok(url) => Some(url),
+                Err(err) => {
+                    tracing::warn!("ignoring invalid proxy url in {name}: {err}");
+                    None
+                }
+            })
+        };
+
+        let all_proxy = proxy_url("ALL_PROXY");
+        Self {
+            http_proxy: proxy_url("HTTP_PROXY").or_else(|| all_proxy.clone()),
+            https_proxy: proxy_url("HTTPS_PROXY").or(all_proxy),
+            no_proxy: var("NO_PROXY")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|host| !host.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Use `proxy` for all `http` and `https` requests.
+    pub fn with_proxy(self, proxy: Url) -> Self {
+        Self {
+            http_proxy: Some(proxy.clone()),
+            https_proxy: Some(proxy),
+            ..self
+        }
+    }
+
+    /// Never use a proxy for the given hosts. Entries have the same format as the `NO_PROXY`
+    /// environment variable: a domain (which also matches its subdomains), an ip address or `*`
+    /// to disable the proxy altogether.
+    pub fn with_no_proxy(mut self, hosts: impl IntoIterator<Item = String>) -> Self {
+        self.no_proxy.extend(hosts);
+        self
+    }
+
+    /// Returns the proxy that should be used for a request to `url`, or `None` if the request
+    /// should be made directly.
+    pub fn proxy_for(&self, url: &Url) -> Option<&Url> {
+        let proxy = match url.scheme() {
+            "http" => self.http_proxy.as_ref(),
+            "https" => self.https_proxy.as_ref(),
+            _ => None,
+        }?;
+
+        let host = url.host_str()?;
+        if self.bypasses(host) {
+            None
+        } else {
+            Some(proxy)
+        }
+    }
+
+    /// Returns true if `host` matches one of the `NO_PROXY` entries.
+    fn bypasses(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let host_ip = host.parse::<IpAddr>().ok();
+        self.no_proxy.iter().any(|entry| {
+            if entry == "*" {
+                return true;
+            }
+
+            // Ip addresses have to match exactly
+            if let Ok(ip) = entry.parse::<IpAddr>() {
+                return host_ip == Some(ip);
+            }
+
+            // Domains also match their subdomains, a leading dot is optional
+            let entry = entry.trim_start_matches('.');
+            host.eq_ignore_ascii_case(entry)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", entry.to_ascii_lowercase()))
+        })
+    }
+
+    /// Configures the proxy on a [`reqwest::ClientBuilder`]. This replaces reqwest's own detection
+    /// of the proxy environment variables.
+    pub fn apply(self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if self.http_proxy.is_none() && self.https_proxy.is_none() {
+            return builder.no_proxy();
+        }
+
+        let config = Arc::new(self);
+        builder.proxy(reqwest::Proxy::custom(move |url| {
+            config.proxy_for(url).cloned()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn config(vars: &[(&str, &str)]) -> ProxyConfig {
+        ProxyConfig::from_env_vars(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    fn proxy_for(config: &ProxyConfig, url: &str) -> Option<String> {
+        config
+            .proxy_for(&Url::parse(url).unwrap())
+            .map(|url| url.to_string())
+    }
+
+    #[test]
+    fn test_proxy_from_env() {
+        let config = config(&[
+            ("HTTPS_PROXY", "http://secure-proxy:3128"),
+            ("http_proxy", "http://proxy:3128"),
+            ("NO_PROXY", "internal.example.com, .corp,10.0.0.1"),
+        ]);
+
+        assert_eq!(
+            proxy_for(&config, "https://pypi.org/simple/").as_deref(),
+            Some("http://secure-proxy:3128/")
+        );
+        assert_eq!(
+            proxy_for(&config, "http://pypi.org/simple/").as_deref(),
+            Some("http://proxy:3128/")
+        );
+
+        // Hosts listed in NO_PROXY, including their subdomains, bypass the proxy
+        assert_eq!(proxy_for(&config, "https://internal.example.com/"), None);
+        assert_eq!(
+            proxy_for(&config, "https://pypi.internal.example.com/"),
+            None
+        );
+        assert_eq!(proxy_for(&config, "https://index.corp/simple/"), None);
+        assert_eq!(proxy_for(&config, "http://10.0.0.1:8080/simple/"), None);
+        assert!(proxy_for(&config, "https://example.com/").is_some());
+        assert!(proxy_for(&config, "https://notcorp/").is_some());
+
+        // Local indexes never use a proxy
+        assert_eq!(proxy_for(&config, "file:///srv/index/simple/"), None);
+    }
+
+    #[test]
+    fn test_explicit_proxy() {
+        let config = config(&[("HTTP_PROXY", "http://proxy:3128"), ("NO_PROXY", "*")]);
+        assert_eq!(proxy_for(&config, "http://pypi.org/"), None);
+
+        let config = ProxyConfig::default()
+            .with_proxy(Url::parse("http://explicit:8080").unwrap())
+            .with_no_proxy(["localhost".to_string()]);
+        assert_eq!(
+            proxy_for(&config, "https://pypi.org/").as_deref(),
+            Some("http://explicit:8080/")
+        );
+        assert_eq!(proxy_for(&config, "http://localhost:8000/"), None);
+    }
+
+    #[tokio::test]
+    async fn test_client_uses_proxy() {
+        // A fake proxy that records the request line and returns an empty response
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        let client = ProxyConfig::default()
+            .with_proxy(proxy)
+            .apply(reqwest::Client::builder())
+            .build()
+            .unwrap();
+        let response = client
+            .get("http://pypi.invalid/simple/")
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+
+        let request = server.await.unwrap();
+        assert!(
+            request.starts_with("GET http://pypi.invalid/simple/ HTTP/1.1"),
+            "{request}"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Limits on the rate of requests and on the bandwidth that is used to download from indexes.
+//!
+//! Both limits are implemented with a token bucket. A request (or a chunk of a response body)
+//! takes tokens from the bucket, which is refilled at a constant rate. If the bucket does not
+//! contain enough tokens, the request waits until it has been refilled.
+
+use futures::StreamExt;
+use parking_lot::Mutex;
+use reqwest::{Request, Response, ResponseBuilderExt};
+use reqwest_middleware::{Middleware, Next};
+use std::num::{NonZeroU32, NonZeroU64};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use task_local_extensions::Extensions;
+
+/// Limits on the requests that are made to indexes. Nothing is limited by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    requests_per_second: Option<NonZeroU32>,
+    bytes_per_second: Option<NonZeroU64>,
+}
+
+impl RateLimit {
+    /// Spaces the requests out evenly so that at most `requests_per_second` requests are started
+    /// per second. Requests that are served from the cache do not count.
+    pub fn with_requests_per_second(self, requests_per_second: NonZeroU32) -> Self {
+        Self {
+            requests_per_second: Some(requests_per_second),
+            ..self
+        }
+    }
+
+    /// Limits the combined download speed of all responses to `bytes_per_second`.
+    pub fn with_bytes_per_second(self, bytes_per_second: NonZeroU64) -> Self {
+        Self {
+            bytes_per_second: Some(bytes_per_second),
+            ..self
+        }
+    }
+
+    /// Returns true if neither the requests nor the bandwidth are limited.
+    pub fn is_unlimited(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// A token bucket that holds at most `capacity` tokens and is refilled with `rate` tokens per
+/// second.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+
+    /// The number of available tokens and when they were counted. The number is negative if
+    /// tokens were taken in advance by callers that are still waiting.
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, capacity: f64) -> Self {
+        Self {
+            rate,
+            capacity,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Takes `amount` tokens from the bucket and waits until they would have been available.
+    /// Taking the tokens immediately ensures that concurrent callers are served in order.
+    async fn acquire(&self, amount: f64) {
+        let wait = {
+            let mut state = self.state.lock();
+            let (tokens, counted_at) = &mut *state;
+            let now = Instant::now();
+            *tokens = (*tokens + now.duration_since(*counted_at).as_secs_f64() * self.rate)
+                .min(self.capacity)
+                - amount;
+            *counted_at = now;
+            if *tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-*tokens / self.rate)
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// A [`Middleware`] that enforces a [`RateLimit`] on all requests of a client.
+pub struct RateLimitMiddleware {
+    /// Holds at most a single request so requests are evenly spaced
+    requests: Option<TokenBucket>,
+
+    /// Holds the bytes of one second so short bursts are allowed
+    bytes: Option<Arc<TokenBucket>>,
+}
+
+impl RateLimitMiddleware {
+    /// Constructs a new middleware that enforces `limit`.
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            requests: limit
+                .requests_per_second
+                .map(|rate| TokenBucket::new(f64::from(rate.get()), 1.0)),
+            bytes: limit.bytes_per_second.map(|rate| {
+                let rate = rate.get() as f64;
+                Arc::new(TokenBucket::new(rate, rate))
+            }),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RateLimitMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if let Some(requests) = &self.requests {
+            requests.acquire(1.0).await;
+        }
+        let response = next.run(req, extensions).await?;
+
+        let Some(bytes) = self.bytes.clone() else {
+            return Ok(response);
+        };
+
+        // Replace the body with one that waits for the bandwidth to become available for every
+        // chunk that is received
+        let mut builder = http::Response::builder()
+            .status(response.status())
+            .version(response.version())
+            .url(response.url().clone());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = response.headers().clone();
+        }
+        let body = response.bytes_stream().then(move |chunk| {
+            let bytes = bytes.clone();
+            async move {
+                if let Ok(chunk) = &chunk {
+                    bytes.acquire(chunk.len() as f64).await;
+                }
+                chunk
+            }
+        });
+        let response = builder
+            .body(reqwest::Body::wrap_stream(body))
+            .expect("the parts of a valid response form a valid response");
+        Ok(Response::from(response))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use reqwest_middleware::ClientBuilder;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use url::Url;
+
+    /// Serves `body` to every request on a random local port.
+    async fn serve(body: Vec<u8>) -> Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let body = body.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0; 4096];
+                    let _ = stream.read(&mut buf).await;
+                    let header = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    let _ = stream.write_all(header.as_bytes()).await;
+                    let _ = stream.write_all(&body).await;
+                });
+            }
+        });
+        url
+    }
+
+    fn client(limit: RateLimit) -> reqwest_middleware::ClientWithMiddleware {
+        ClientBuilder::new(reqwest::Client::new())
+            .with(RateLimitMiddleware::new(limit))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_requests_per_second() {
+        let url = serve(b"ok".to_vec()).await;
+        let client =
+            client(RateLimit::default().with_requests_per_second(NonZeroU32::new(20).unwrap()));
+
+        // The first request is made immediately, the others are spaced 50ms apart
+        let start = Instant::now();
+        let requests = (0..5).map(|_| async {
+            let response = client.get(url.clone()).send().await.unwrap();
+            assert_eq!(response.text().await.unwrap(), "ok");
+        });
+        futures::future::join_all(requests).await;
+        assert!(
+            start.elapsed() >= Duration::from_millis(200),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bytes_per_second() {
+        let body = (0..30_000u32).map(|i| i as u8).collect::<Vec<_>>();
+        let url = serve(body.clone()).await;
+        let client =
+            client(RateLimit::default().with_bytes_per_second(NonZeroU64::new(20_000).unwrap()));
+
+        // The first 20KB are available immediately, the remaining 10KB take half a second. The
+        // response is otherwise unchanged.
+        let start = Instant::now();
+        let response = client.get(url.clone()).send().await.unwrap();
+        assert_eq!(response.url(), &url);
+        assert_eq!(
+            response.headers()[http::header::CONTENT_LENGTH],
+            body.len().to_string()
+        );
+        assert_eq!(response.bytes().await.unwrap(), body);
+        assert!(
+            start.elapsed() >= Duration::from_millis(500),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_unlimited_by_default() {
+        assert!(RateLimit::default().is_unlimited());
+        assert!(!RateLimit::default()
+            .with_requests_per_second(NonZeroU32::new(1).unwrap())
+            .is_unlimited());
+    }
+}
@@ -0,0 +1,282 @@
+//! Configuration of the TLS certificates that are used to reach package indexes.
+//!
+//! Indexes that are hosted internally often use certificates that are signed by a private
+//! certificate authority, or require clients to authenticate with a certificate of their own.
+
+use miette::Diagnostic;
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+use std::path::Path;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// The certificates that are used to establish TLS connections, in addition to the root
+/// certificates that are trusted by default.
+///
+/// Like pip, [`TlsConfig::from_env`] respects the `REQUESTS_CA_BUNDLE` and `SSL_CERT_FILE`
+/// environment variables, in that order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsConfig {
+    ca_certs: Vec<PathBuf>,
+    client_cert: Option<(PathBuf, PathBuf)>,
+}
+
+/// An error that can occur when applying a [`TlsConfig`].
+#[derive(Debug, Error, Diagnostic)]
+pub enum TlsConfigError {
+    /// A certificate or key file could not be read.
+    #[error("failed to read '{0}'")]
+    Read(PathBuf, #[source] std::io::Error),
+
+    /// A certificate bundle does not contain any PEM encoded certificate.
+    #[error("'{0}' does not contain any PEM encoded certificate")]
+    NoCertificates(PathBuf),
+
+    /// A certificate in a bundle could not be parsed.
+    #[error("'{0}' contains an invalid certificate")]
+    InvalidCertificate(PathBuf, #[source] reqwest::Error),
+
+    /// The client certificate or its key could not be parsed.
+    #[error("invalid client certificate '{0}' or key '{1}'")]
+    #[diagnostic(help("the key must be a PEM encoded PKCS#8 private key"))]
+    InvalidClientCert(PathBuf, PathBuf, #[source] reqwest::Error),
+
+    /// Certificates can only be configured if a TLS backend is enabled.
+    #[error("certificates cannot be configured without a TLS backend")]
+    NoTlsBackend,
+}
+
+impl TlsConfig {
+    /// Reads the certificate bundle from the environment variables of the current process.
+    pub fn from_env() -> Self {
+        Self::from_env_vars(|var| std::env::var(var).ok())
+    }
+
+    /// Reads the certificate bundle using `env_var` to look up environment variables.
+    fn from_env_vars(env_var: impl Fn(&str) -> Option<String>) -> Self {
+        let ca_bundle = ["REQUESTS_CA_BUNDLE", "SSL_CERT_FILE"]
+            .into_iter()
+            .filter_map(env_var)
+            .find(|value| !value.trim().is_empty());
+        Self {
+            ca_certs: ca_bundle.map(PathBuf::from).into_iter().collect(),
+            client_cert: None,
+        }
+    }
+
+    /// Also trust the certificates in the PEM encoded bundle at `path`.
+    pub fn with_ca_cert(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_certs.push(path.into());
+        self
+    }
+
+    /// Authenticate with the PEM encoded client certificate at `cert` and its PKCS#8 private key
+    /// at `key`. Both may point to the same file.
+    pub fn with_client_cert(self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        Self {
+            client_cert: Some((cert.into(), key.into())),
+            ..self
+        }
+    }
+
+    /// Configures the certificates on a [`reqwest::ClientBuilder`]. The files are read and parsed
+    /// immediately so that invalid files are reported before any request is made.
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    pub fn apply(
+        &self,
+        mut builder: reqwest::ClientBuilder,
+    ) -> Result<reqwest::ClientBuilder, TlsConfigError> {
+        for path in &self.ca_certs {
+            for cert in read_certificates(path)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if let Some((cert, key)) = &self.client_cert {
+            builder = builder.identity(read_identity(cert, key)?);
+        }
+
+        Ok(builder)
+    }
+
+    /// Without a TLS backend no certificates can be configured.
+    #[cfg(not(any(feature = "native-tls", feature = "rustls-tls")))]
+    pub fn apply(
+        &self,
+        builder: reqwest::ClientBuilder,
+    ) -> Result<reqwest::ClientBuilder, TlsConfigError> {
+        if self == &Self::default() {
+            Ok(builder)
+        } else {
+            Err(TlsConfigError::NoTlsBackend)
+        }
+    }
+}
+
+/// Reads all certificates from the PEM encoded bundle at `path`.
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+fn read_certificates(path: &Path) -> Result<Vec<reqwest::Certificate>, TlsConfigError> {
+    let bundle = read(path)?;
+    let bundle = String::from_utf8_lossy(&bundle);
+    let certs = pem_blocks(&bundle, "CERTIFICATE")
+        .map(|block| {
+            reqwest::Certificate::from_pem(block.as_bytes())
+                .map_err(|err| TlsConfigError::InvalidCertificate(path.to_path_buf(), err))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(TlsConfigError::NoCertificates(path.to_path_buf()));
+    }
+    Ok(certs)
+}
+
+/// Reads a client certificate and its private key. reqwest uses native-tls if it is enabled,
+/// which expects them separately, rustls expects a single PEM file.
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+fn read_identity(cert_path: &Path, key_path: &Path) -> Result<reqwest::Identity, TlsConfigError> {
+    let cert = read(cert_path)?;
+    let key = read(key_path)?;
+
+    #[cfg(feature = "native-tls")]
+    let identity = {
+        // Only pass the relevant blocks, the certificate and key may be stored in a single file
+        let cert = String::from_utf8_lossy(&cert);
+        let key = String::from_utf8_lossy(&key);
+        let chain = pem_blocks(&cert, "CERTIFICATE")
+            .collect::<Vec<_>>()
+            .join("\n");
+        let key = pem_blocks(&key, "PRIVATE KEY").next().unwrap_or(&key);
+        reqwest::Identity::from_pkcs8_pem(chain.as_bytes(), key.as_bytes())
+    };
+    #[cfg(not(feature = "native-tls"))]
+    let identity = reqwest::Identity::from_pem(&[cert, key].join(&b'\n'));
+
+    identity.map_err(|err| {
+        TlsConfigError::InvalidClientCert(cert_path.to_path_buf(), key_path.to_path_buf(), err)
+    })
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+fn read(path: &Path) -> Result<Vec<u8>, TlsConfigError> {
+    fs_err::read(path).map_err(|err| TlsConfigError::Read(path.to_path_buf(), err))
+}
+
+/// Returns the PEM blocks with the given label, including their begin and end lines.
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+fn pem_blocks<'a>(pem: &'a str, label: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    let begin = format!("-----BEGIN {label}-----");
+    let end = format!("-----END {label}-----");
+    let mut rest = pem;
+    std::iter::from_fn(move || {
+        let start = rest.find(&begin)?;
+        let len = rest[start..].find(&end)? + end.len();
+        let block = &rest[start..start + len];
+        rest = &rest[start + len..];
+        Some(block)
+    })
+}
+
+#[cfg(all(test, any(feature = "native-tls", feature = "rustls-tls")))]
+mod test {
+    use super::*;
+
+    fn cert_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test-data/certs")
+            .join(name)
+    }
+
+    #[test]
+    fn test_ca_bundle_from_env() {
+        let config = TlsConfig::from_env_vars(|name| match name {
+            "REQUESTS_CA_BUNDLE" => Some("/etc/requests.pem".to_string()),
+            "SSL_CERT_FILE" => Some("/etc/ssl.pem".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.ca_certs, [PathBuf::from("/etc/requests.pem")]);
+
+        let config = TlsConfig::from_env_vars(|name| match name {
+            "REQUESTS_CA_BUNDLE" => Some(" ".to_string()),
+            "SSL_CERT_FILE" => Some("/etc/ssl.pem".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.ca_certs, [PathBuf::from("/etc/ssl.pem")]);
+
+        assert_eq!(TlsConfig::from_env_vars(|_| None), TlsConfig::default());
+    }
+
+    #[test]
+    fn test_client_with_custom_ca_bundle() {
+        // A bundle with several certificates and text in between
+        let dir = tempfile::tempdir().unwrap();
+        let ca = fs_err::read_to_string(cert_path("ca.pem")).unwrap();
+        let client = fs_err::read_to_string(cert_path("client.pem")).unwrap();
+        let bundle = dir.path().join("bundle.pem");
+        fs_err::write(&bundle, format!("# Test CA\n{ca}\n# Client\n{client}")).unwrap();
+        assert_eq!(read_certificates(&bundle).unwrap().len(), 2);
+
+        let config = TlsConfig::default()
+            .with_ca_cert(&bundle)
+            .with_client_cert(cert_path("client.pem"), cert_path("client-key.pem"));
+        config
+            .apply(reqwest::Client::builder())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // The client certificate and its key can also be stored in a single file
+        let key = fs_err::read_to_string(cert_path("client-key.pem")).unwrap();
+        let combined = dir.path().join("client.pem");
+        fs_err::write(&combined, format!("{client}{key}")).unwrap();
+        TlsConfig::default()
+            .with_client_cert(&combined, &combined)
+            .apply(reqwest::Client::builder())
+            .unwrap()
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_invalid_certificates() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.pem");
+        let err = TlsConfig::default()
+            .with_ca_cert(&missing)
+            .apply(reqwest::Client::builder())
+            .unwrap_err();
+        assert!(matches!(err, TlsConfigError::Read(path, _) if path == missing));
+
+        let empty = dir.path().join("empty.pem");
+        fs_err::write(&empty, "not a certificate").unwrap();
+        let err = TlsConfig::default()
+            .with_ca_cert(&empty)
+            .apply(reqwest::Client::builder())
+            .unwrap_err();
+        assert!(matches!(err, TlsConfigError::NoCertificates(path) if path == empty));
+
+        let corrupt = dir.path().join("corrupt.pem");
+        fs_err::write(
+            &corrupt,
+            "-----BEGIN CERTIFICATE-----\nbm90IGEgY2VydGlmaWNhdGU=\n-----END CERTIFICATE-----\n",
+        )
+        .unwrap();
+        let result = TlsConfig::default()
+            .with_ca_cert(&corrupt)
+            .apply(reqwest::Client::builder())
+            .and_then(|builder| {
+                builder
+                    .build()
+                    .map_err(|err| TlsConfigError::InvalidCertificate(corrupt.clone(), err))
+            });
+        assert!(matches!(
+            result,
+            Err(TlsConfigError::InvalidCertificate(..))
+        ));
+
+        // The key does not belong to a certificate
+        let err = TlsConfig::default()
+            .with_client_cert(cert_path("client-key.pem"), cert_path("client-key.pem"))
+            .apply(reqwest::Client::builder())
+            .unwrap_err();
+        assert!(matches!(err, TlsConfigError::InvalidClientCert(..)));
+    }
+}
@@ -0,0 +1,224 @@
+//! Verifies that the requirements of the distributions that are installed in an environment are
+//! satisfied by the other installed distributions, similar to `pip check`.
+
+use crate::artifacts::wheel::InstallPaths;
+use crate::python_env::{find_distributions_in_venv, Distribution, FindDistributionError};
+use crate::types::{
+    NormalizedPackageName, PackageName, VersionSpecifierExt, WheelCoreMetaDataError,
+    WheelCoreMetadata,
+};
+use fs_err as fs;
+use pep440_rs::Version;
+use pep508_rs::{MarkerEnvironment, Requirement, VersionOrUrl};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A requirement of an installed distribution that is not satisfied by the environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvironmentProblem {
+    /// The required package is not installed at all.
+    Missing {
+        /// The distribution that has the requirement
+        name: NormalizedPackageName,
+        /// The version of the distribution that has the requirement
+        version: Version,
+        /// The requirement that is not satisfied
+        requirement: Requirement,
+    },
+
+    /// The required package is installed, but the installed version does not match.
+    Conflict {
+        /// The distribution that has the requirement
+        name: NormalizedPackageName,
+        /// The version of the distribution that has the requirement
+        version: Version,
+        /// The requirement that is not satisfied
+        requirement: Requirement,
+        /// The version of the required package that is installed
+        installed: Version,
+    },
+}
+
+impl Display for EnvironmentProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnvironmentProblem::Missing {
+                name,
+                version,
+                requirement,
+            } => write!(
+                f,
+                "{name} {version} requires {}, which is not installed",
+                requirement_without_marker(requirement)
+            ),
+            EnvironmentProblem::Conflict {
+                name,
+                version,
+                requirement,
+                installed,
+            } => write!(
+                f,
+                "{name} {version} requires {}, but {installed} is installed",
+                requirement_without_marker(requirement)
+            ),
+        }
+    }
+}
+
+/// An error that can occur when running [`check_environment`].
+#[derive(Debug, Error)]
+pub enum CheckEnvironmentError {
+    /// The installed distributions could not be located
+    #[error(transparent)]
+    FindDistributions(#[from] FindDistributionError),
+
+    /// The METADATA file of an installed distribution could not be read
+    #[error("failed to read '{0}'")]
+    FailedToReadMetadata(PathBuf, #[source] std::io::Error),
+
+    /// The METADATA file of an installed distribution could not be parsed
+    #[error("failed to parse '{0}'")]
+    FailedToParseMetadata(PathBuf, #[source] Box<WheelCoreMetaDataError>),
+}
+
+/// Checks that the requirements of all distributions that are installed in the virtualenv rooted
+/// at `root` are satisfied by the other installed distributions. Requirements are evaluated for
+/// the given environment, requirements that are only added by an extra are not checked.
+///
+/// Returns the problems that were found, sorted by the name of the distribution that has the
+/// requirement. An empty list means the environment is consistent.
+pub fn check_environment(
+    root: &Path,
+    paths: &InstallPaths,
+    env: &MarkerEnvironment,
+) -> Result<Vec<EnvironmentProblem>, CheckEnvironmentError> {
+    let mut distributions = find_distributions_in_venv(root, paths)?;
+    distributions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let installed: HashMap<&NormalizedPackageName, &Distribution> = distributions
+        .iter()
+        .map(|distribution| (&distribution.name, distribution))
+        .collect();
+
+    let mut problems = Vec::new();
+    for distribution in &distributions {
+        let metadata_path = root.join(&distribution.dist_info).join("METADATA");
+        let bytes = fs::read(&metadata_path)
+            .map_err(|e| CheckEnvironmentError::FailedToReadMetadata(metadata_path.clone(), e))?;
+        let metadata = WheelCoreMetadata::try_from(bytes.as_slice()).map_err(|e| {
+            CheckEnvironmentError::FailedToParseMetadata(metadata_path, Box::new(e))
+        })?;
+
+        for requirement in metadata.requires_dist {
+            if !requirement
+                .marker
+                .as_ref()
+                .map_or(true, |marker| marker.evaluate(env, &[]))
+            {
+                continue;
+            }
+
+            // Requirements with a name that cannot be parsed cannot be installed either
+            let Ok(required) = PackageName::from_str(&requirement.name) else {
+                continue;
+            };
+            let Some(dependency) = installed.get(&NormalizedPackageName::from(required)) else {
+                problems.push(EnvironmentProblem::Missing {
+                    name: distribution.name.clone(),
+                    version: distribution.version.clone(),
+                    requirement,
+                });
+                continue;
+            };
+
+            // Pre-releases are accepted if they are installed, only the version is checked
+            if let Some(VersionOrUrl::VersionSpecifier(specifiers)) = &requirement.version_or_url {
+                if !specifiers.satisfied_by(&dependency.version) {
+                    problems.push(EnvironmentProblem::Conflict {
+                        name: distribution.name.clone(),
+                        version: distribution.version.clone(),
+                        installed: dependency.version.clone(),
+                        requirement,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Formats a requirement without its environment marker, the marker already applies to the
+/// environment that was checked.
+fn requirement_without_marker(requirement: &Requirement) -> String {
+    Requirement {
+        marker: None,
+        ..requirement.clone()
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::python_env::{Pep508EnvMakers, PythonInterpreterVersion};
+
+    fn install(site_packages: &Path, name: &str, version: &str, requires: &[&str]) {
+        let dist_info = site_packages.join(format!("{name}-{version}.dist-info"));
+        fs::create_dir_all(&dist_info).unwrap();
+        let mut metadata = format!("Metadata-Version: 2.1\nName: {name}\nVersion: {version}\n");
+        for requirement in requires {
+            metadata.push_str(&format!("Requires-Dist: {requirement}\n"));
+        }
+        fs::write(dist_info.join("METADATA"), metadata).unwrap();
+    }
+
+    #[test]
+    fn test_check_environment() {
+        let venv = tempfile::tempdir().unwrap();
+        let install_paths = InstallPaths::for_venv((3, 11, 0), false);
+        let site_packages = venv.path().join(install_paths.site_packages());
+
+        install(
+            &site_packages,
+            "flask",
+            "3.0.0",
+            &[
+                "Werkzeug>=3.0.0",
+                "Jinja2>=3.1.2",
+                "click>=8.1.3",
+                "importlib-metadata>=3.6.0; python_version < '3.10'",
+                "asgiref>=3.2; extra == 'async'",
+            ],
+        );
+        install(&site_packages, "werkzeug", "3.0.1", &["MarkupSafe>=2.1.1"]);
+        install(&site_packages, "MarkupSafe", "2.1.3", &[]);
+        install(&site_packages, "jinja2", "3.0.3", &["MarkupSafe>=2.0"]);
+
+        let env = Pep508EnvMakers::for_target_platform(
+            "linux-x86_64".parse().unwrap(),
+            &PythonInterpreterVersion::from((3, 11, 0)),
+        )
+        .0;
+        let problems = check_environment(venv.path(), &install_paths, &env).unwrap();
+        assert_eq!(
+            problems.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "flask 3.0.0 requires Jinja2 >=3.1.2, but 3.0.3 is installed",
+                "flask 3.0.0 requires click >=8.1.3, which is not installed",
+            ]
+        );
+
+        // Installing the missing dependency and upgrading the conflicting one fixes the
+        // environment
+        fs::remove_dir_all(site_packages.join("jinja2-3.0.3.dist-info")).unwrap();
+        install(&site_packages, "jinja2", "3.1.2", &["MarkupSafe>=2.0"]);
+        install(&site_packages, "click", "8.1.7", &[]);
+        assert!(check_environment(venv.path(), &install_paths, &env)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use super::Pep508EnvMakers;
+use super::{Pep508EnvMakers, MARKER_VARIABLES};
 use crate::python_env::{system_python_executable, FindPythonError};
 use std::io;
 use std::io::ErrorKind;
@@ -19,6 +19,9 @@ pub enum FromPythonError {
 
     #[error("execution failed with exit code {0}")]
     FailedToRun(ExitStatus),
+
+    #[error("the python interpreter did not report the '{0}' environment marker")]
+    MissingMarker(&'static str),
 }
 
 impl Pep508EnvMakers {
@@ -55,9 +58,17 @@ impl Pep508EnvMakers {
             return Err(FromPythonError::FailedToRun(output.status));
         }
 
-        // Convert the JSON
+        // Convert the JSON, all the variables defined by PEP 508 must be present
         let stdout = String::from_utf8_lossy(&output.stdout);
-        Ok(serde_json::from_str(stdout.trim())?)
+        let markers: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(stdout.trim())?;
+        if let Some(missing) = MARKER_VARIABLES
+            .iter()
+            .find(|variable| !markers.contains_key(**variable))
+        {
+            return Err(FromPythonError::MissingMarker(missing));
+        }
+        Ok(serde_json::from_value(serde_json::Value::Object(markers))?)
     }
 }
 
@@ -2,6 +2,83 @@ use serde::{Deserialize, Serialize};
 use std::ops::Deref;
 
 mod from_env;
+mod target;
+
+pub use target::{ParseTargetPlatformError, TargetArch, TargetOs, TargetPlatform};
+
+/// The names of the environment marker variables defined by PEP 508, except for `extra`. These
+/// are exactly the variables that are determined for a Python interpreter in [`Pep508EnvMakers`].
+pub const MARKER_VARIABLES: &[&str] = &[
+    "implementation_name",
+    "implementation_version",
+    "os_name",
+    "platform_machine",
+    "platform_python_implementation",
+    "platform_release",
+    "platform_system",
+    "platform_version",
+    "python_full_version",
+    "python_version",
+    "sys_platform",
+];
+
+/// Returns true if `name` is a marker variable defined by PEP 508, including `extra`.
+pub fn is_marker_variable(name: &str) -> bool {
+    name == "extra" || MARKER_VARIABLES.contains(&name)
+}
+
+/// Returns the variables referenced in the environment markers of the requirement `requirement`
+/// that are not defined by PEP 508. The requirement does not have to be parseable, this is
+/// useful to explain why a requirement was rejected.
+pub fn unknown_marker_variables(requirement: &str) -> Vec<String> {
+    let Some((_, markers)) = requirement.split_once(';') else {
+        return Vec::new();
+    };
+
+    let mut unknown = Vec::new();
+    let mut chars = markers.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // Skip over quoted strings
+            '"' | '\'' => {
+                for next in chars.by_ref() {
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut identifier = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next.is_ascii_alphanumeric() || next == '_' || next == '.' {
+                        identifier.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let is_keyword = matches!(identifier.as_str(), "and" | "or" | "not" | "in");
+                if !is_keyword && !is_marker_variable(&identifier) && !is_legacy(&identifier) {
+                    unknown.push(identifier);
+                }
+            }
+            _ => {}
+        }
+    }
+    unknown
+}
+
+/// Returns true for the dotted variable names of PEP 345 that are still accepted by most tools.
+fn is_legacy(name: &str) -> bool {
+    matches!(
+        name,
+        "os.name"
+            | "sys.platform"
+            | "platform.version"
+            | "platform.machine"
+            | "platform.python_implementation"
+    )
+}
 
 /// Describes the environment markers that can be used in dependency specifications to enable or
 /// disable certain dependencies based on runtime environment.
@@ -31,3 +108,25 @@ impl Deref for Pep508EnvMakers {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unknown_marker_variables() {
+        assert_eq!(
+            unknown_marker_variables(
+                "foo>=1.0; python_verison >= '3.8' and sys_platform == \"linux\""
+            ),
+            vec!["python_verison"]
+        );
+        assert_eq!(
+            unknown_marker_variables(
+                "foo; (os.name == 'nt' or extra == 'test') and platform_machine not in 'x86_64 and'"
+            ),
+            Vec::<String>::new()
+        );
+        assert!(unknown_marker_variables("foo>=1.0").is_empty());
+    }
+}
@@ -0,0 +1,294 @@
+use super::Pep508EnvMakers;
+use crate::python_env::PythonInterpreterVersion;
+use pep508_rs::{MarkerEnvironment, StringVersion};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An operating system for which environment markers can be determined without running Python.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetOs {
+    /// Linux
+    Linux,
+    /// macOS
+    MacOs,
+    /// Windows
+    Windows,
+}
+
+/// A processor architecture for which environment markers can be determined without running
+/// Python.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetArch {
+    /// 64-bit x86, also known as `amd64`
+    X86_64,
+    /// 64-bit ARM, also known as `arm64`
+    Aarch64,
+}
+
+/// The combination of an operating system and an architecture to resolve for, e.g. to resolve
+/// an environment for another machine than the current one.
+///
+/// Parses from strings like `linux-x86_64`, `macos-arm64` or `windows-amd64`. The common aliases
+/// of the architectures are accepted for every operating system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TargetPlatform {
+    /// The operating system
+    pub os: TargetOs,
+    /// The processor architecture
+    pub arch: TargetArch,
+}
+
+impl TargetPlatform {
+    /// Returns the value of the `platform_machine` marker. The same hardware is reported
+    /// differently depending on the operating system, e.g. 64-bit ARM is `aarch64` on Linux but
+    /// `arm64` on macOS, and Windows uses uppercase names.
+    pub fn platform_machine(&self) -> &'static str {
+        match (self.os, self.arch) {
+            (TargetOs::Linux, TargetArch::X86_64) => "x86_64",
+            (TargetOs::Linux, TargetArch::Aarch64) => "aarch64",
+            (TargetOs::MacOs, TargetArch::X86_64) => "x86_64",
+            (TargetOs::MacOs, TargetArch::Aarch64) => "arm64",
+            (TargetOs::Windows, TargetArch::X86_64) => "AMD64",
+            (TargetOs::Windows, TargetArch::Aarch64) => "ARM64",
+        }
+    }
+
+    /// Returns the platform tags of the wheels that can be installed on this platform, from the
+    /// most to the least specific, e.g. to construct [`crate::python_env::WheelTags`] for it.
+    ///
+    /// The exact version of the operating system is not known, so on Linux glibc 2.28 is assumed
+    /// and on macOS version 14.
+    pub fn platform_tags(&self) -> Vec<String> {
+        match (self.os, self.arch) {
+            (TargetOs::Linux, arch) => {
+                let machine = self.platform_machine();
+                let oldest_glibc = if arch == TargetArch::X86_64 { 5 } else { 17 };
+                let mut tags = Vec::new();
+                for minor in (oldest_glibc..=28).rev() {
+                    tags.push(format!("manylinux_2_{minor}_{machine}"));
+                    // The legacy aliases of PEP 513, 571 and 599
+                    match minor {
+                        17 => tags.push(format!("manylinux2014_{machine}")),
+                        12 => tags.push(format!("manylinux2010_{machine}")),
+                        5 => tags.push(format!("manylinux1_{machine}")),
+                        _ => {}
+                    }
+                }
+                tags.push(format!("linux_{machine}"));
+                tags
+            }
+            (TargetOs::MacOs, TargetArch::Aarch64) => (11..=14)
+                .rev()
+                .flat_map(|major| {
+                    [
+                        format!("macosx_{major}_0_arm64"),
+                        format!("macosx_{major}_0_universal2"),
+                    ]
+                })
+                .collect(),
+            (TargetOs::MacOs, TargetArch::X86_64) => (11..=14)
+                .rev()
+                .map(|major| (major, 0))
+                .chain((9..=16).rev().map(|minor| (10, minor)))
+                .flat_map(|(major, minor)| {
+                    [
+                        format!("macosx_{major}_{minor}_x86_64"),
+                        format!("macosx_{major}_{minor}_intel"),
+                        format!("macosx_{major}_{minor}_universal2"),
+                    ]
+                })
+                .collect(),
+            (TargetOs::Windows, TargetArch::X86_64) => vec![String::from("win_amd64")],
+            (TargetOs::Windows, TargetArch::Aarch64) => vec![String::from("win_arm64")],
+        }
+    }
+}
+
+/// An error that can occur when parsing a [`TargetPlatform`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ParseTargetPlatformError {
+    /// The platform is not of the form `<os>-<arch>`
+    #[error("invalid platform '{0}', expected something like 'linux-x86_64' or 'macos-arm64'")]
+    InvalidFormat(String),
+
+    /// The operating system is not supported
+    #[error("unknown operating system '{0}', expected one of 'linux', 'macos' or 'windows'")]
+    UnknownOs(String),
+
+    /// The architecture is not supported
+    #[error("unknown architecture '{0}', expected one of 'x86_64', 'amd64', 'aarch64' or 'arm64'")]
+    UnknownArch(String),
+}
+
+impl FromStr for TargetPlatform {
+    type Err = ParseTargetPlatformError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((os, arch)) = s.split_once('-') else {
+            return Err(ParseTargetPlatformError::InvalidFormat(s.to_owned()));
+        };
+        let os = match os.to_ascii_lowercase().as_str() {
+            "linux" => TargetOs::Linux,
+            "macos" | "osx" | "darwin" => TargetOs::MacOs,
+            "windows" | "win" => TargetOs::Windows,
+            _ => return Err(ParseTargetPlatformError::UnknownOs(os.to_owned())),
+        };
+        let arch = match arch.to_ascii_lowercase().as_str() {
+            "x86_64" | "amd64" | "x64" => TargetArch::X86_64,
+            "aarch64" | "arm64" => TargetArch::Aarch64,
+            _ => return Err(ParseTargetPlatformError::UnknownArch(arch.to_owned())),
+        };
+        Ok(Self { os, arch })
+    }
+}
+
+impl Display for TargetPlatform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let os = match self.os {
+            TargetOs::Linux => "linux",
+            TargetOs::MacOs => "macos",
+            TargetOs::Windows => "windows",
+        };
+        write!(f, "{os}-{}", self.platform_machine().to_ascii_lowercase())
+    }
+}
+
+impl Pep508EnvMakers {
+    /// Constructs the environment markers of CPython `python_version` running on `platform`,
+    /// without running Python.
+    ///
+    /// The `platform_release` and `platform_version` markers depend on the exact version of the
+    /// operating system and are left empty.
+    pub fn for_target_platform(
+        platform: TargetPlatform,
+        python_version: &PythonInterpreterVersion,
+    ) -> Self {
+        let (os_name, sys_platform, platform_system) = match platform.os {
+            TargetOs::Linux => ("posix", "linux", "Linux"),
+            TargetOs::MacOs => ("posix", "darwin", "Darwin"),
+            TargetOs::Windows => ("nt", "win32", "Windows"),
+        };
+        let PythonInterpreterVersion {
+            major,
+            minor,
+            patch,
+        } = python_version;
+        let full_version = StringVersion::from_str(&format!("{major}.{minor}.{patch}"))
+            .expect("a version of three numbers is always valid");
+
+        Self(MarkerEnvironment {
+            implementation_name: "cpython".to_owned(),
+            implementation_version: full_version.clone(),
+            os_name: os_name.to_owned(),
+            platform_machine: platform.platform_machine().to_owned(),
+            platform_python_implementation: "CPython".to_owned(),
+            platform_release: String::new(),
+            platform_system: platform_system.to_owned(),
+            platform_version: String::new(),
+            python_full_version: full_version,
+            python_version: StringVersion::from_str(&format!("{major}.{minor}"))
+                .expect("a version of two numbers is always valid"),
+            sys_platform: sys_platform.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn markers(platform: &str) -> Pep508EnvMakers {
+        Pep508EnvMakers::for_target_platform(
+            platform.parse().unwrap(),
+            &PythonInterpreterVersion::from((3, 11, 4)),
+        )
+    }
+
+    #[test]
+    fn test_platform_machine() {
+        for (platforms, expected) in [
+            (&["linux-x86_64", "linux-amd64"][..], "x86_64"),
+            (&["linux-aarch64", "linux-arm64"][..], "aarch64"),
+            (&["macos-x86_64", "osx-amd64"][..], "x86_64"),
+            (&["macos-arm64", "darwin-aarch64"][..], "arm64"),
+            (&["windows-amd64", "win-x86_64"][..], "AMD64"),
+            (&["windows-arm64", "windows-aarch64"][..], "ARM64"),
+        ] {
+            for platform in platforms {
+                assert_eq!(markers(platform).platform_machine, expected, "{platform}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_target_markers() {
+        let env = markers("macos-arm64");
+        assert_eq!(env.sys_platform, "darwin");
+        assert_eq!(env.platform_system, "Darwin");
+        assert_eq!(env.os_name, "posix");
+        assert_eq!(env.python_version.string, "3.11");
+        assert_eq!(env.python_full_version.string, "3.11.4");
+
+        let env = markers("windows-amd64");
+        assert_eq!(env.sys_platform, "win32");
+        assert_eq!(env.os_name, "nt");
+
+        // Markers that depend on the convention of the operating system select the right
+        // dependencies
+        let requirement = pep508_rs::Requirement::from_str(
+            "foo; platform_machine == 'arm64' and sys_platform == 'darwin'",
+        )
+        .unwrap();
+        let marker = requirement.marker.unwrap();
+        assert!(marker.evaluate(&markers("macos-aarch64"), &[]));
+        assert!(!marker.evaluate(&markers("linux-aarch64"), &[]));
+    }
+
+    #[test]
+    fn test_platform_tags() {
+        let tags = |platform: &str| platform.parse::<TargetPlatform>().unwrap().platform_tags();
+
+        let linux = tags("linux-x86_64");
+        assert_eq!(linux.first().unwrap(), "manylinux_2_28_x86_64");
+        assert!(linux.contains(&String::from("manylinux2014_x86_64")));
+        assert!(linux.contains(&String::from("manylinux1_x86_64")));
+        assert_eq!(linux.last().unwrap(), "linux_x86_64");
+        assert!(!tags("linux-aarch64").contains(&String::from("manylinux1_aarch64")));
+
+        let macos = tags("macos-arm64");
+        assert_eq!(macos.first().unwrap(), "macosx_14_0_arm64");
+        assert!(macos.contains(&String::from("macosx_11_0_universal2")));
+        assert!(!macos.iter().any(|tag| tag.starts_with("macosx_10")));
+        assert!(tags("macos-x86_64").contains(&String::from("macosx_10_9_x86_64")));
+
+        assert_eq!(tags("windows-amd64"), ["win_amd64"]);
+    }
+
+    #[test]
+    fn test_parse_display() {
+        for platform in [
+            "linux-x86_64",
+            "linux-aarch64",
+            "macos-arm64",
+            "windows-amd64",
+        ] {
+            assert_eq!(
+                platform.parse::<TargetPlatform>().unwrap().to_string(),
+                platform
+            );
+        }
+        assert_eq!(
+            "linux".parse::<TargetPlatform>(),
+            Err(ParseTargetPlatformError::InvalidFormat("linux".to_owned()))
+        );
+        assert_eq!(
+            "beos-x86_64".parse::<TargetPlatform>(),
+            Err(ParseTargetPlatformError::UnknownOs("beos".to_owned()))
+        );
+        assert_eq!(
+            "linux-riscv64".parse::<TargetPlatform>(),
+            Err(ParseTargetPlatformError::UnknownArch("riscv64".to_owned()))
+        );
+    }
+}
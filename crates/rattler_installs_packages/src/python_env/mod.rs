@@ -14,11 +14,17 @@ mod venv;
 
 mod byte_code_compiler;
 
-pub use tags::{WheelTag, WheelTags};
+mod check;
+
+pub use tags::{CPythonAbi, WheelTag, WheelTags};
 
 pub use byte_code_compiler::{ByteCodeCompiler, CompilationError, SpawnCompilerError};
+pub use check::{check_environment, CheckEnvironmentError, EnvironmentProblem};
 pub use distribution_finder::{find_distributions_in_venv, Distribution, FindDistributionError};
-pub use env_markers::Pep508EnvMakers;
+pub use env_markers::{
+    is_marker_variable, unknown_marker_variables, ParseTargetPlatformError, Pep508EnvMakers,
+    TargetArch, TargetOs, TargetPlatform, MARKER_VARIABLES,
+};
 pub(crate) use system_python::{system_python_executable, FindPythonError};
 pub use system_python::{ParsePythonInterpreterVersionError, PythonInterpreterVersion};
 pub use uninstall::{uninstall_distribution, UninstallDistributionError};
@@ -1,5 +1,7 @@
 use itertools::Itertools;
 use once_cell::sync::OnceCell;
+use std::env;
+use std::ffi::OsString;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -10,45 +12,103 @@ use thiserror::Error;
 pub enum FindPythonError {
     #[error("could not find python executable")]
     NotFound,
+    #[error("the environment variable {var} is set to '{}' but {reason}", path.display())]
+    InvalidEnvVar {
+        var: &'static str,
+        path: PathBuf,
+        reason: String,
+    },
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 }
 
+/// Environment variables that can be used to explicitly specify the python interpreter, in order
+/// of precedence.
+const PYTHON_ENV_VARS: [&str; 2] = ["RIP_PYTHON", "PYTHON"];
+
 /// Return cached python executable.
 /// Try to find the python executable in the current environment.
 /// Using sys.executable aproach will return original interpretator path
 /// and not the shim in case of using which.
+///
+/// If the `RIP_PYTHON` or `PYTHON` environment variable is set, that interpreter is used instead
+/// of searching the `PATH`.
 pub fn system_python_executable() -> Result<&'static PathBuf, FindPythonError> {
     static SYSTEM_PYTHON_EXECUTABLE: OnceCell<PathBuf> = OnceCell::new();
-    SYSTEM_PYTHON_EXECUTABLE.get_or_try_init(|| {
-        // When installed with homebrew on macOS, the python3 executable is called `python3` instead
-        // Also on some ubuntu installs this is the case
-        // For windows it should just be python
-        let output = match std::process::Command::new("python3")
-            .arg("-c")
-            .arg("import sys; print(sys.executable, end='')")
-            .output()
-            .or_else(|_| {
-                std::process::Command::new("python")
-                    .arg("-c")
-                    .arg("import sys; print(sys.executable, end='')")
-                    .output()
-            }) {
-            Err(e) if e.kind() == ErrorKind::NotFound => return Err(FindPythonError::NotFound),
-            Err(e) => return Err(FindPythonError::IoError(e)),
-            Ok(output) => output,
-        };
+    SYSTEM_PYTHON_EXECUTABLE.get_or_try_init(|| find_python_executable(|var| env::var_os(var)))
+}
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let python_path = PathBuf::from_str(&stdout).unwrap();
+/// Finds the python executable, `env_var` is used to look up the environment variables.
+fn find_python_executable(
+    env_var: impl Fn(&str) -> Option<OsString>,
+) -> Result<PathBuf, FindPythonError> {
+    for var in PYTHON_ENV_VARS {
+        if let Some(value) = env_var(var).filter(|value| !value.is_empty()) {
+            return python_from_env_var(var, PathBuf::from(value));
+        }
+    }
 
+    // When installed with homebrew on macOS, the python3 executable is called `python3` instead
+    // Also on some ubuntu installs this is the case
+    // For windows it should just be python
+    match sys_executable(Path::new("python3")).or_else(|_| sys_executable(Path::new("python"))) {
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(FindPythonError::NotFound),
+        Err(e) => Err(FindPythonError::IoError(e)),
         // sys.executable can return empty string or python's None
-        if !python_path.exists() {
-            return Err(FindPythonError::NotFound);
+        Ok(None) => Err(FindPythonError::NotFound),
+        Ok(Some(python_path)) => Ok(python_path),
+    }
+}
+
+/// Validates the python interpreter specified through the environment variable `var`.
+fn python_from_env_var(var: &'static str, python: PathBuf) -> Result<PathBuf, FindPythonError> {
+    let invalid = |reason: &str| FindPythonError::InvalidEnvVar {
+        var,
+        path: python.clone(),
+        reason: reason.to_owned(),
+    };
+
+    // A bare name like `python3.11` is looked up in the `PATH` when it is executed
+    if python.components().count() > 1 || python.is_absolute() {
+        if !python.is_file() {
+            return Err(invalid("the file does not exist"));
         }
+        if !is_executable(&python) {
+            return Err(invalid("the file is not executable"));
+        }
+    }
+
+    match sys_executable(&python) {
+        Ok(Some(python_path)) => Ok(python_path),
+        Ok(None) => Err(invalid("it is not a python interpreter")),
+        Err(e) => Err(invalid(&format!("it could not be executed: {e}"))),
+    }
+}
 
-        Ok(python_path)
-    })
+/// Runs the python interpreter to determine `sys.executable`. Returns `None` if the interpreter
+/// did not report an existing executable.
+fn sys_executable(python: &Path) -> std::io::Result<Option<PathBuf>> {
+    let output = std::process::Command::new(python)
+        .arg("-c")
+        .arg("import sys; print(sys.executable, end='')")
+        .output()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let python_path = PathBuf::from_str(&stdout).unwrap();
+    Ok(Some(python_path).filter(|path| output.status.success() && path.exists()))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
 }
 
 /// Errors that can occur while trying to parse the python version
@@ -146,7 +206,68 @@ impl PythonInterpreterVersion {
 
 #[cfg(test)]
 mod tests {
+    use super::{find_python_executable, system_python_executable, FindPythonError};
     use crate::python_env::PythonInterpreterVersion;
+    use std::ffi::OsString;
+    use std::path::Path;
+
+    fn env_with(vars: &[(&str, &Path)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars = vars
+            .iter()
+            .map(|(var, value)| (var.to_string(), value.as_os_str().to_owned()))
+            .collect::<Vec<_>>();
+        move |var| {
+            vars.iter()
+                .find(|(name, _)| name == var)
+                .map(|(_, value)| value.clone())
+        }
+    }
+
+    #[test]
+    pub fn python_from_env_var() {
+        let python = system_python_executable().unwrap();
+
+        // Without any variable the PATH is searched
+        assert_eq!(&find_python_executable(env_with(&[])).unwrap(), python);
+
+        // A valid interpreter is used
+        assert_eq!(
+            &find_python_executable(env_with(&[("PYTHON", python)])).unwrap(),
+            python
+        );
+
+        // RIP_PYTHON takes precedence over PYTHON
+        let missing = Path::new("/does/not/exist/python");
+        let err = find_python_executable(env_with(&[("PYTHON", python), ("RIP_PYTHON", missing)]))
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                FindPythonError::InvalidEnvVar {
+                    var: "RIP_PYTHON",
+                    ..
+                }
+            ),
+            "{err}"
+        );
+        assert!(err.to_string().contains("RIP_PYTHON"), "{err}");
+
+        // A file that is not a python interpreter
+        let dir = tempfile::tempdir().unwrap();
+        let not_python = dir.path().join("python");
+        fs_err::write(&not_python, "not python").unwrap();
+        let err = find_python_executable(env_with(&[("RIP_PYTHON", &not_python)])).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                FindPythonError::InvalidEnvVar {
+                    var: "RIP_PYTHON",
+                    ..
+                }
+            ),
+            "{err}"
+        );
+    }
 
     #[test]
     pub fn parse_python_version() {
@@ -4,6 +4,7 @@
 
 mod from_env;
 
+use crate::python_env::PythonInterpreterVersion;
 use indexmap::IndexSet;
 use itertools::Itertools;
 use serde_with::{DeserializeFromStr, SerializeDisplay};
@@ -84,6 +85,46 @@ impl Display for WheelTag {
     }
 }
 
+/// The ABI of a CPython interpreter, this determines which extension modules it can load.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CPythonAbi {
+    /// The regular build with the global interpreter lock, e.g. `cp313`.
+    #[default]
+    Gil,
+
+    /// A free-threaded build (`--disable-gil`), e.g. `cp313t`. Free-threaded interpreters cannot
+    /// load extension modules that are built for the regular or the stable (`abi3`) ABI.
+    FreeThreaded,
+}
+
+impl CPythonAbi {
+    /// Returns the ABI tag of an interpreter with the given version, e.g. `cp313t`.
+    pub fn tag(&self, version: &PythonInterpreterVersion) -> String {
+        match self {
+            CPythonAbi::Gil => format!("cp{}{}", version.major, version.minor),
+            CPythonAbi::FreeThreaded => format!("cp{}{}t", version.major, version.minor),
+        }
+    }
+
+    /// Determines the ABI of the CPython interpreter that supports the given tags, e.g. the tags
+    /// of [`WheelTags::from_env`]. Returns `None` if the tags are not those of CPython.
+    ///
+    /// Like `packaging.tags`, the ABI is taken from the most specific interpreter specific tag: a
+    /// `t` in its abiflags indicates a free-threaded build, e.g. `cp313t` or `cp313td`.
+    pub fn from_tags(tags: &WheelTags) -> Option<Self> {
+        let abi = tags
+            .tags()
+            .filter(|tag| tag.interpreter.starts_with("cp"))
+            .find_map(|tag| tag.abi.strip_prefix("cp"))?;
+        let abiflags = abi.trim_start_matches(|c: char| c.is_ascii_digit());
+        Some(if abiflags.contains('t') {
+            CPythonAbi::FreeThreaded
+        } else {
+            CPythonAbi::Gil
+        })
+    }
+}
+
 /// Contains an ordered set of platform tags with which compatibility of wheels can be determined.
 #[derive(Debug, Clone)]
 pub struct WheelTags {
@@ -91,6 +132,63 @@ pub struct WheelTags {
 }
 
 impl WheelTags {
+    /// Returns the tags that are supported by a CPython interpreter with the given version and
+    /// ABI on the given platforms, from the most to the least specific. This mirrors
+    /// `packaging.tags.sys_tags` for CPython 3.8 and newer, which makes it possible to determine
+    /// compatible wheels for an interpreter that is not available on this machine.
+    ///
+    /// `platforms` are platform tags like `manylinux_2_17_x86_64`, ordered from the most to the
+    /// least specific.
+    pub fn cpython(
+        version: &PythonInterpreterVersion,
+        abi: CPythonAbi,
+        platforms: &[&str],
+    ) -> Self {
+        let (major, minor) = (version.major, version.minor);
+        let interpreter = format!("cp{major}{minor}");
+        let tag = |interpreter: &str, abi: &str, platform: &str| WheelTag {
+            interpreter: interpreter.to_owned(),
+            abi: abi.to_owned(),
+            platform: platform.to_owned(),
+        };
+
+        // The interpreter specific tags
+        let mut abis = vec![abi.tag(version)];
+        let supports_abi3 = abi != CPythonAbi::FreeThreaded;
+        if supports_abi3 {
+            abis.push(String::from("abi3"));
+        }
+        abis.push(String::from("none"));
+        let mut tags = abis
+            .iter()
+            .flat_map(|abi| platforms.iter().map(|p| tag(&interpreter, abi, p)))
+            .collect::<IndexSet<_>>();
+        if supports_abi3 {
+            for older_minor in (2..minor).rev() {
+                tags.extend(
+                    platforms
+                        .iter()
+                        .map(|p| tag(&format!("cp{major}{older_minor}"), "abi3", p)),
+                );
+            }
+        }
+
+        // The tags of pure Python wheels, e.g. `py313`, `py3`, `py312`, ..., `py30`
+        let py_versions = std::iter::once(format!("py{major}{minor}"))
+            .chain(std::iter::once(format!("py{major}")))
+            .chain((0..minor).rev().map(|m| format!("py{major}{m}")))
+            .collect::<Vec<_>>();
+        for py_version in &py_versions {
+            tags.extend(platforms.iter().map(|p| tag(py_version, "none", p)));
+        }
+        tags.insert(tag(&interpreter, "none", "any"));
+        for py_version in &py_versions {
+            tags.insert(tag(py_version, "none", "any"));
+        }
+
+        Self { tags }
+    }
+
     /// Returns an iterator over the supported tags.
     pub fn tags(&self) -> impl Iterator<Item = &'_ WheelTag> + '_ {
         self.tags.iter()
@@ -105,6 +203,27 @@ impl WheelTags {
         self.tags.get_index_of(tag).map(|score| -(score as i32))
     }
 
+    /// Reorders the tags so that the tags of the given platforms, e.g. `manylinux_2_28_x86_64`, are
+    /// preferred over all other tags, in the order in which the platforms are given. The order of
+    /// the tags of the same platform, and of the platforms that are not given, is unchanged.
+    ///
+    /// This overrides the default preference for the most specific tag when multiple wheels of a
+    /// version are compatible, see [`Self::compatibility`]. Platforms that are not compatible are
+    /// ignored, so this never makes more wheels compatible.
+    pub fn with_platform_priority<S: AsRef<str>>(self, platforms: &[S]) -> Self {
+        let priority = |tag: &WheelTag| {
+            platforms
+                .iter()
+                .position(|platform| platform.as_ref() == tag.platform)
+                .unwrap_or(platforms.len())
+        };
+        let mut tags = self.tags.into_iter().collect::<Vec<_>>();
+        tags.sort_by_key(priority);
+        Self {
+            tags: tags.into_iter().collect(),
+        }
+    }
+
     /// Returns if the specified tag is compatible with this set.
     pub fn is_compatible(&self, tag: &WheelTag) -> bool {
         self.tags.contains(tag)
@@ -130,4 +249,105 @@ mod test {
         assert_eq!(tag.abi, "none");
         assert_eq!(tag.platform, "any");
     }
+
+    #[test]
+    fn test_cpython_free_threaded() {
+        let version = PythonInterpreterVersion::new(3, 13, 0);
+        let platforms = ["manylinux_2_17_x86_64", "linux_x86_64"];
+        let standard = WheelTags::cpython(&version, CPythonAbi::Gil, &platforms);
+        let free_threaded = WheelTags::cpython(&version, CPythonAbi::FreeThreaded, &platforms);
+        let is_compatible =
+            |tags: &WheelTags, tag: &str| tags.is_compatible(&WheelTag::from_str(tag).unwrap());
+
+        // A free-threaded interpreter only loads free-threaded extension modules
+        assert!(is_compatible(
+            &free_threaded,
+            "cp313-cp313t-manylinux_2_17_x86_64"
+        ));
+        assert!(!is_compatible(
+            &free_threaded,
+            "cp313-cp313-manylinux_2_17_x86_64"
+        ));
+        assert!(!is_compatible(
+            &free_threaded,
+            "cp313-abi3-manylinux_2_17_x86_64"
+        ));
+        assert!(!is_compatible(
+            &free_threaded,
+            "cp38-abi3-manylinux_2_17_x86_64"
+        ));
+
+        // And vice versa
+        assert!(is_compatible(
+            &standard,
+            "cp313-cp313-manylinux_2_17_x86_64"
+        ));
+        assert!(!is_compatible(
+            &standard,
+            "cp313-cp313t-manylinux_2_17_x86_64"
+        ));
+        assert!(is_compatible(&standard, "cp38-abi3-manylinux_2_17_x86_64"));
+
+        // Both load pure Python wheels and wheels without an abi
+        for tags in [&standard, &free_threaded] {
+            assert!(is_compatible(tags, "py3-none-any"));
+            assert!(is_compatible(tags, "cp313-none-linux_x86_64"));
+            assert!(is_compatible(tags, "py310-none-any"));
+            assert!(!is_compatible(tags, "cp314-none-any"));
+        }
+
+        // The most specific tag comes first
+        assert_eq!(
+            free_threaded.tags().next().unwrap().to_string(),
+            "cp313-cp313t-manylinux_2_17_x86_64"
+        );
+        assert_eq!(
+            standard
+                .tags()
+                .take(3)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "cp313-cp313-manylinux_2_17_x86_64",
+                "cp313-cp313-linux_x86_64",
+                "cp313-abi3-manylinux_2_17_x86_64"
+            ]
+        );
+        assert_eq!(standard.tags().last().unwrap().to_string(), "py30-none-any");
+    }
+
+    #[test]
+    fn test_cpython_abi_from_tags() {
+        let version = PythonInterpreterVersion::new(3, 13, 0);
+        let platforms = ["manylinux_2_17_x86_64", "linux_x86_64"];
+        for abi in [CPythonAbi::Gil, CPythonAbi::FreeThreaded] {
+            let tags = WheelTags::cpython(&version, abi, &platforms);
+            assert_eq!(CPythonAbi::from_tags(&tags), Some(abi));
+        }
+
+        // The tags of a free-threaded debug build as reported by `packaging.tags`
+        let debug = WheelTags {
+            tags: [
+                "cp313-cp313td-linux_x86_64",
+                "cp313-none-linux_x86_64",
+                "py3-none-any",
+            ]
+            .into_iter()
+            .map(|tag| WheelTag::from_str(tag).unwrap())
+            .collect(),
+        };
+        assert_eq!(
+            CPythonAbi::from_tags(&debug),
+            Some(CPythonAbi::FreeThreaded)
+        );
+
+        // Other interpreters have no CPython ABI
+        let pypy = WheelTags {
+            tags: ["pp310-pypy310_pp73-linux_x86_64", "py3-none-any"]
+                .into_iter()
+                .map(|tag| WheelTag::from_str(tag).unwrap())
+                .collect(),
+        };
+        assert_eq!(CPythonAbi::from_tags(&pypy), None);
+    }
 }
@@ -9,6 +9,7 @@ use crate::python_env::{
     PythonInterpreterVersion,
 };
 use fs_err as fs;
+use itertools::Itertools;
 use std::ffi::OsStr;
 use std::fmt::Debug;
 use std::io::ErrorKind;
@@ -70,6 +71,10 @@ pub enum VEnvError {
     ParsePythonInterpreterVersionError(#[from] ParsePythonInterpreterVersionError),
     #[error(transparent)]
     FailedToCreate(#[from] std::io::Error),
+    #[error("{0} is not a virtual environment, it does not contain a pyvenv.cfg")]
+    NotAVenv(PathBuf),
+    #[error("the virtual environment {0} does not contain a site-packages directory at {1}")]
+    MissingSitePackages(PathBuf, PathBuf),
 }
 
 /// Represents a virtual environment in which wheels can be installed
@@ -130,14 +135,53 @@ impl VEnv {
 
     /// Path to python executable in venv
     pub fn python_executable(&self) -> PathBuf {
-        let executable = if self.install_paths.is_windows() {
-            "python.exe"
+        Self::venv_python_executable(&self.location, self.install_paths.is_windows())
+    }
+
+    fn venv_python_executable(venv_dir: &Path, windows: bool) -> PathBuf {
+        if windows {
+            venv_dir.join("Scripts").join("python.exe")
         } else {
-            "python"
+            venv_dir.join("bin").join("python")
+        }
+    }
+
+    /// Open an existing virtual environment, e.g. one that was created with `python -m venv`,
+    /// for the platform we are running on
+    pub fn open(venv_dir: &Path) -> Result<VEnv, VEnvError> {
+        Self::open_custom(venv_dir, cfg!(windows))
+    }
+
+    /// Open an existing virtual environment, allows specifying if this is a windows venv.
+    ///
+    /// The location of the site-packages directory depends on the Python version of the
+    /// environment. It is read from the `pyvenv.cfg` of the environment, or determined by running
+    /// the interpreter of the environment if the file does not record it.
+    pub fn open_custom(venv_dir: &Path, windows: bool) -> Result<VEnv, VEnvError> {
+        let pyvenv_cfg = match fs::read_to_string(venv_dir.join("pyvenv.cfg")) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Err(VEnvError::NotAVenv(venv_dir.to_path_buf()))
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let python_version = match pyvenv_cfg_version(&pyvenv_cfg) {
+            Some(version) => version,
+            None => PythonInterpreterVersion::from_path(&Self::venv_python_executable(
+                venv_dir, windows,
+            ))?,
         };
-        self.location
-            .join(self.install_paths.scripts())
-            .join(executable)
+
+        let install_paths = InstallPaths::for_venv(python_version, windows);
+        let site_packages = venv_dir.join(install_paths.site_packages());
+        if !site_packages.is_dir() {
+            return Err(VEnvError::MissingSitePackages(
+                venv_dir.to_path_buf(),
+                site_packages,
+            ));
+        }
+
+        Ok(VEnv::new(venv_dir.to_path_buf(), install_paths))
     }
 
     /// Create a virtual environment at specified directory
@@ -338,10 +382,31 @@ prompt = {}"#,
     }
 }
 
+/// Returns the Python version that is recorded in the contents of a `pyvenv.cfg`. The `venv`
+/// module writes it as `version = 3.11.4`, `virtualenv` as `version_info = 3.11.4.final.0`.
+fn pyvenv_cfg_version(contents: &str) -> Option<PythonInterpreterVersion> {
+    contents.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        if !matches!(key.trim(), "version" | "version_info") {
+            return None;
+        }
+        let (major, minor, patch) = value
+            .trim()
+            .split('.')
+            .take(3)
+            .map(|part| part.parse().ok())
+            .collect::<Option<Vec<u32>>>()?
+            .into_iter()
+            .collect_tuple()?;
+        Some(PythonInterpreterVersion::new(major, minor, patch))
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use super::VEnv;
-    use crate::python_env::PythonLocation;
+    use super::{pyvenv_cfg_version, VEnv, VEnvError};
+    use crate::artifacts::wheel::UnpackWheelOptions;
+    use crate::python_env::{PythonInterpreterVersion, PythonLocation};
     use crate::types::NormalizedPackageName;
     use std::env;
     use std::path::Path;
@@ -378,6 +443,66 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_open_existing_venv() {
+        let venv_dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            VEnv::open_custom(venv_dir.path(), false),
+            Err(VEnvError::NotAVenv(_))
+        ));
+
+        // A venv created by another tool, the Python version in pyvenv.cfg determines the layout
+        fs_err::write(
+            venv_dir.path().join("pyvenv.cfg"),
+            "home = /usr/bin\ninclude-system-site-packages = false\nversion_info = 3.11.4.final.0\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            VEnv::open_custom(venv_dir.path(), false),
+            Err(VEnvError::MissingSitePackages(..))
+        ));
+        let site_packages = venv_dir.path().join("lib/python3.11/site-packages");
+        fs_err::create_dir_all(&site_packages).unwrap();
+
+        let venv = VEnv::open_custom(venv_dir.path(), false).unwrap();
+        let wheel = crate::artifacts::Wheel::from_path(
+            &Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels/wordle_python-2.3.32-py3-none-any.whl"),
+            &NormalizedPackageName::from_str("wordle_python").unwrap(),
+        )
+        .unwrap();
+        venv.install_wheel(
+            &wheel,
+            &UnpackWheelOptions {
+                installer: Some(String::from("rip")),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(site_packages.join("wordle/wordle.py").is_file());
+        let dist_info = site_packages.join("wordle_python-2.3.32.dist-info");
+        assert!(dist_info.join("RECORD").is_file());
+        assert_eq!(
+            fs_err::read_to_string(dist_info.join("INSTALLER")).unwrap(),
+            "rip\n"
+        );
+    }
+
+    #[test]
+    fn test_pyvenv_cfg_version() {
+        assert_eq!(
+            pyvenv_cfg_version("home = /usr/bin\nversion = 3.12.1\nprompt = venv"),
+            Some(PythonInterpreterVersion::new(3, 12, 1))
+        );
+        assert_eq!(
+            pyvenv_cfg_version("version_info = 3.9.18.final.0"),
+            Some(PythonInterpreterVersion::new(3, 9, 18))
+        );
+        assert_eq!(pyvenv_cfg_version("version = 3.13.0rc1"), None);
+        assert_eq!(pyvenv_cfg_version("home = /usr/bin"), None);
+    }
+
     #[test]
     pub fn test_python_set_env_prefix() {
         let venv_dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,184 @@
+use super::{resolve, PinnedPackage, PypiVersion, ResolutionError, ResolveOptions};
+use crate::artifacts::SDist;
+use crate::index::PackageDb;
+use crate::python_env::WheelTags;
+use crate::types::NormalizedPackageName;
+use crate::wheel_builder::BuildEnvironment;
+use pep508_rs::{MarkerEnvironment, Requirement};
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The build-system requirements of a package that was resolved to a source distribution,
+/// together with the packages that are needed to satisfy them. See
+/// [`resolve_build_dependencies`].
+#[serde_as]
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildDependencies {
+    /// The name of the package that is built from source
+    pub name: NormalizedPackageName,
+
+    /// The version of the package that is built from source
+    #[serde_as(as = "DisplayFromStr")]
+    pub version: PypiVersion,
+
+    /// The `build-system.requires` of the source distribution
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    pub requires: Vec<Requirement>,
+
+    /// The resolved build environment, sorted by name
+    pub packages: Vec<PinnedPackage>,
+}
+
+/// Resolves the build-system requirements of every package in `packages` that would be installed
+/// from a source distribution, i.e. of which the preferred artifact is an sdist.
+///
+/// The build requirements of each package are resolved separately because every source
+/// distribution is built in its own isolated environment. Source distributions without a
+/// `pyproject.toml` or without a build backend require the legacy setuptools backend.
+pub async fn resolve_build_dependencies(
+    package_db: Arc<PackageDb>,
+    packages: &[PinnedPackage],
+    env_markers: Arc<MarkerEnvironment>,
+    compatible_tags: Option<Arc<WheelTags>>,
+    options: ResolveOptions,
+) -> Result<Vec<BuildDependencies>, ResolutionError> {
+    let mut result = Vec::new();
+    for package in packages {
+        let Some(artifact_info) = package.artifacts.first().filter(|a| a.is::<SDist>()) else {
+            continue;
+        };
+        let sdist = package_db.get_sdist(artifact_info).await?;
+        let requires = BuildEnvironment::build_system(&sdist).requires;
+
+        let build_packages = resolve(
+            package_db.clone(),
+            &requires,
+            env_markers.clone(),
+            compatible_tags.clone(),
+            HashMap::default(),
+            HashMap::default(),
+            options.clone(),
+            HashMap::default(),
+        )
+        .await?;
+
+        result.push(BuildDependencies {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            requires,
+            packages: build_packages,
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::python_env::{Pep508EnvMakers, WheelTag};
+    use crate::resolve::test_support::{pkg_info, write_sdist, write_wheel};
+    use itertools::Itertools;
+    use reqwest::Client;
+    use std::str::FromStr;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolve_build_dependencies() {
+        let find_links = tempfile::tempdir().unwrap();
+        write_sdist(
+            find_links.path(),
+            "foo",
+            "1.0",
+            &[
+                ("PKG-INFO", &pkg_info("foo", "1.0")),
+                (
+                    "pyproject.toml",
+                    "[build-system]\nrequires = [\"flit_core>=3.2\"]\nbuild-backend = \"flit_core.buildapi\"\n",
+                ),
+            ],
+        );
+        write_sdist(
+            find_links.path(),
+            "legacy",
+            "2.0",
+            &[("PKG-INFO", &pkg_info("legacy", "2.0"))],
+        );
+        write_wheel(find_links.path(), "bar", "1.0", &[]);
+        write_wheel(find_links.path(), "flit_core", "3.1", &[]);
+        write_wheel(find_links.path(), "flit_core", "3.9", &[]);
+        write_wheel(find_links.path(), "setuptools", "69.0", &[]);
+        write_wheel(find_links.path(), "wheel", "0.42", &[]);
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(Client::new().into(), &[], cache_dir.path())
+                .unwrap()
+                .with_find_links([find_links.path().to_path_buf()]),
+        );
+        let env_markers = Arc::new(Pep508EnvMakers::from_env().await.unwrap().0);
+        let tags = Arc::new(
+            [WheelTag::from_str("py3-none-any").unwrap()]
+                .into_iter()
+                .collect::<WheelTags>(),
+        );
+        let requirements = ["foo", "legacy", "bar"]
+            .map(|r| Requirement::from_str(r).unwrap())
+            .to_vec();
+
+        let packages = resolve(
+            package_db.clone(),
+            &requirements,
+            env_markers.clone(),
+            Some(tags.clone()),
+            HashMap::default(),
+            HashMap::default(),
+            ResolveOptions::default(),
+            HashMap::default(),
+        )
+        .await
+        .unwrap();
+        let build_dependencies = resolve_build_dependencies(
+            package_db,
+            &packages,
+            env_markers,
+            Some(tags),
+            ResolveOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        // Only the packages that are built from source have build dependencies, these are not
+        // part of the runtime resolution
+        let summary = build_dependencies
+            .iter()
+            .map(|build| {
+                (
+                    build.name.as_str().to_owned(),
+                    build.requires.iter().map(|r| r.to_string()).collect_vec(),
+                    build
+                        .packages
+                        .iter()
+                        .map(|p| format!("{}=={}", p.name.as_str(), p.version))
+                        .collect_vec(),
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "foo".to_owned(),
+                    vec!["flit_core >=3.2".to_owned()],
+                    vec!["flit-core==3.9".to_owned()]
+                ),
+                (
+                    "legacy".to_owned(),
+                    vec!["setuptools".to_owned(), "wheel".to_owned()],
+                    vec!["setuptools==69.0".to_owned(), "wheel==0.42".to_owned()]
+                ),
+            ]
+        );
+        assert!(packages.iter().all(|p| p.name.as_str() != "flit-core"));
+    }
+}
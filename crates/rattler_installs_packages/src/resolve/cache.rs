@@ -0,0 +1,214 @@
+use super::{PinnedPackage, PypiVersion};
+use crate::index::PackageDb;
+use crate::python_env::WheelTags;
+use crate::types::{ArtifactInfo, Extra, NormalizedPackageName};
+use fs_err as fs;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use pep508_rs::{MarkerEnvironment, Requirement};
+use rattler_digest::{compute_bytes_digest, Sha256};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use url::Url;
+
+/// Stores the result of previous resolutions on disk so that resolving the exact same input again
+/// does not require running the solver.
+///
+/// A cached resolution is keyed by everything that influences the result: the requirements, the
+/// index urls, the environment markers, the compatible wheel tags and the resolution options. On
+/// top of that the cached result is only used if the artifacts that are available in the index
+/// for each of the resolved packages did not change since the resolution was cached. Checking
+/// this only requires the (http cached) project pages and not the metadata of any package.
+#[derive(Debug, Clone)]
+pub struct ResolutionCache {
+    dir: PathBuf,
+}
+
+/// Identifies the inputs of a resolution, this is a hash of everything that influences the
+/// result.
+pub(crate) struct CacheKey(String);
+
+/// The inputs of a resolution that are hashed to obtain a [`CacheKey`].
+#[derive(Serialize)]
+struct CacheKeyInputs<'a> {
+    requirements: Vec<String>,
+    index_urls: &'a [Url],
+    markers: &'a MarkerEnvironment,
+    compatible_tags: Option<Vec<String>>,
+    options: Vec<String>,
+    env_variables: BTreeMap<&'a String, &'a String>,
+}
+
+impl CacheKey {
+    /// Constructs the key of a resolution. `options` contains a string representation of each
+    /// of the options that influence the resolution.
+    pub fn new(
+        package_db: &PackageDb,
+        requirements: &[&Requirement],
+        markers: &MarkerEnvironment,
+        compatible_tags: Option<&WheelTags>,
+        options: Vec<String>,
+        env_variables: &HashMap<String, String>,
+    ) -> Self {
+        let inputs = CacheKeyInputs {
+            requirements: requirements
+                .iter()
+                .map(|r| r.to_string())
+                .sorted()
+                .collect(),
+            index_urls: package_db.index_urls(),
+            markers,
+            compatible_tags: compatible_tags
+                .map(|tags| tags.tags().map(|t| t.to_string()).collect()),
+            options,
+            env_variables: env_variables.iter().collect(),
+        };
+        let bytes = serde_json::to_vec(&inputs).expect("serializing the cache key cannot fail");
+        Self(format!("{:x}", compute_bytes_digest::<Sha256>(bytes)))
+    }
+}
+
+/// A package of a cached resolution.
+#[derive(Serialize, Deserialize)]
+struct CachedPackage {
+    name: NormalizedPackageName,
+    version: PypiVersion,
+    extras: Vec<Extra>,
+
+    /// The urls of the selected artifacts, the artifacts themselves are taken from the index
+    artifacts: Vec<Url>,
+    dependencies: Vec<NormalizedPackageName>,
+
+    /// A hash of the artifacts that were available in the index for this package when it was
+    /// resolved.
+    index_fingerprint: String,
+}
+
+impl ResolutionCache {
+    /// Constructs a cache that stores the resolutions in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the cached resolution for `key`, if there is one and the index has not changed for
+    /// any of the packages in it.
+    pub(crate) async fn load(
+        &self,
+        key: &CacheKey,
+        package_db: &PackageDb,
+    ) -> Option<Vec<PinnedPackage>> {
+        let path = self.path(key);
+        let cached: Vec<CachedPackage> = match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(cached) => cached,
+                Err(err) => {
+                    tracing::warn!(
+                        "ignoring invalid cached resolution {}: {err}",
+                        path.display()
+                    );
+                    return None;
+                }
+            },
+            Err(_) => return None,
+        };
+
+        let mut packages = Vec::with_capacity(cached.len());
+        for package in cached {
+            let available = package_db
+                .available_artifacts(package.name.clone())
+                .await
+                .ok()?;
+            if index_fingerprint(&available) != package.index_fingerprint {
+                tracing::info!(
+                    "cached resolution is outdated because the index changed for '{}'",
+                    package.name
+                );
+                return None;
+            }
+
+            let artifacts = available
+                .get(&package.version)
+                .into_iter()
+                .flatten()
+                .filter(|a| package.artifacts.contains(&a.url))
+                .cloned()
+                .collect();
+            packages.push(PinnedPackage {
+                name: package.name,
+                version: package.version,
+                extras: package.extras.into_iter().collect(),
+                artifacts,
+                dependencies: package.dependencies.into_iter().collect(),
+            });
+        }
+
+        tracing::info!("using cached resolution {}", path.display());
+        Some(packages)
+    }
+
+    /// Stores the result of a resolution.
+    pub(crate) async fn store(
+        &self,
+        key: &CacheKey,
+        package_db: &PackageDb,
+        packages: &[PinnedPackage],
+    ) -> miette::Result<()> {
+        let mut cached = Vec::with_capacity(packages.len());
+        for package in packages {
+            let Ok(available) = package_db.available_artifacts(package.name.clone()).await else {
+                // Without knowing the state of the index the resolution cannot be validated later
+                return Ok(());
+            };
+            cached.push(CachedPackage {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                extras: package.extras.iter().cloned().collect(),
+                artifacts: package.artifacts.iter().map(|a| a.url.clone()).collect(),
+                dependencies: package.dependencies.iter().cloned().collect(),
+                index_fingerprint: index_fingerprint(&available),
+            });
+        }
+
+        cached.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let path = self.path(key);
+        write_atomic(&path, &serde_json::to_vec(&cached).expect("cannot fail"))
+            .map_err(|err| miette::miette!("failed to write {}: {err}", path.display()))
+    }
+
+    fn path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.json", key.0))
+    }
+}
+
+/// Computes a hash of the artifacts that are available in the index for a package.
+fn index_fingerprint(artifacts: &IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>) -> String {
+    let entries = artifacts
+        .values()
+        .flatten()
+        .map(|a| {
+            format!(
+                "{} {} {}",
+                a.filename,
+                a.url,
+                if a.yanked.yanked { "yanked" } else { "" }
+            )
+        })
+        .sorted()
+        .join("\n");
+    format!("{:x}", compute_bytes_digest::<Sha256>(entries.as_bytes()))
+}
+
+/// Writes a file by first writing to a temporary file in the same directory, this ensures that
+/// concurrent readers never see a partially written file.
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let dir = path.parent().expect("the cache path has a parent");
+    fs::create_dir_all(dir)?;
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(bytes)?;
+    file.persist(path)?;
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use super::PypiVersion;
+use crate::types::{ArtifactInfo, NormalizedPackageName};
+use resolvo::SolvableId;
+use std::sync::Arc;
+
+/// A candidate version of a package that the solver can select.
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate<'a> {
+    /// The version of the candidate
+    pub version: &'a PypiVersion,
+
+    /// The artifacts that are available for this version and that passed all filters, e.g. the
+    /// compatible wheels and the source distributions if those are allowed.
+    pub artifacts: &'a [Arc<ArtifactInfo>],
+
+    pub(crate) solvable: SolvableId,
+}
+
+/// Determines the order in which the solver tries the candidate versions of a package, see
+/// [`super::ResolveOptions::candidate_sorter`]. The solver selects the first candidate that
+/// does not lead to a conflict.
+///
+/// The candidates are passed in the default order: versions from highest to lowest, with the
+/// artifact type that is preferred by [`super::ResolveOptions::sdist_resolution`] first. A sorter
+/// that does not care about some candidates can use a stable sort to keep their relative order.
+///
+/// ```
+/// # use rattler_installs_packages::resolve::{Candidate, CandidateSorter};
+/// # use rattler_installs_packages::types::NormalizedPackageName;
+/// /// Prefers versions for which a wheel is available
+/// struct PreferWheels;
+///
+/// impl CandidateSorter for PreferWheels {
+///     fn sort(&self, _package: &NormalizedPackageName, candidates: &mut [Candidate<'_>]) {
+///         candidates.sort_by_key(|c| !c.artifacts.iter().any(|a| a.filename.as_wheel().is_some()));
+///     }
+/// }
+/// ```
+pub trait CandidateSorter: Send + Sync {
+    /// Reorders the candidates of `package` from the most to the least preferred.
+    fn sort(&self, package: &NormalizedPackageName, candidates: &mut [Candidate<'_>]);
+}
@@ -0,0 +1,74 @@
+use super::{resolve, ResolutionError, ResolveOptions};
+use crate::index::PackageDb;
+use crate::python_env::WheelTags;
+use pep508_rs::{MarkerEnvironment, Requirement};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Finds a minimal subset of `requirements` that cannot be resolved together, which is usually
+/// far easier to act on than the explanation of a large failing resolution. Returns `None` if the
+/// requirements can be resolved.
+///
+/// The set is minimal in the sense that the remaining requirements can be resolved as soon as any
+/// one of them is removed, there might be other, smaller sets. It is found by removing the
+/// requirements one by one and keeping those without which the resolution succeeds, so this
+/// resolves the requirements once for every requirement. Errors that do not mean that the
+/// requirements conflict, e.g. network failures, are returned as is.
+pub async fn minimal_conflict_set(
+    package_db: Arc<PackageDb>,
+    requirements: &[Requirement],
+    env_markers: Arc<MarkerEnvironment>,
+    compatible_tags: Option<Arc<WheelTags>>,
+    options: ResolveOptions,
+) -> Result<Option<Vec<Requirement>>, ResolutionError> {
+    // Only the outcome of each resolution is of interest
+    let options = ResolveOptions {
+        skipped_artifacts: None,
+        summary: None,
+        progress_observer: None,
+        ..options
+    };
+    let conflicts = |requirements: Vec<Requirement>| {
+        let package_db = package_db.clone();
+        let env_markers = env_markers.clone();
+        let compatible_tags = compatible_tags.clone();
+        let options = options.clone();
+        async move {
+            let result = resolve(
+                package_db,
+                &requirements,
+                env_markers,
+                compatible_tags,
+                HashMap::default(),
+                HashMap::default(),
+                options,
+                HashMap::default(),
+            )
+            .await;
+            match result {
+                Ok(_) => Ok(false),
+                Err(ResolutionError::NoCandidates { .. } | ResolutionError::Conflict { .. }) => {
+                    Ok(true)
+                }
+                Err(err) => Err(err),
+            }
+        }
+    };
+
+    if !conflicts(requirements.to_vec()).await? {
+        return Ok(None);
+    }
+
+    let mut conflict_set = requirements.to_vec();
+    let mut index = 0;
+    while index < conflict_set.len() {
+        let mut without = conflict_set.clone();
+        without.remove(index);
+        if conflicts(without.clone()).await? {
+            conflict_set = without;
+        } else {
+            index += 1;
+        }
+    }
+    Ok(Some(conflict_set))
+}
@@ -3,13 +3,15 @@ use super::SDistResolution;
 use crate::artifacts::SDist;
 use crate::artifacts::Wheel;
 use crate::index::PackageDb;
-use crate::python_env::WheelTags;
-use crate::resolve::{PinnedPackage, ResolveOptions};
+use crate::python_env::{unknown_marker_variables, WheelTags};
+use crate::resolve::{Candidate, PinnedPackage, ResolveOptions, SkipReason};
 use crate::types::{
     Artifact, ArtifactInfo, ArtifactName, Extra, NormalizedPackageName, PackageName,
+    VersionSpecifierExt,
 };
-use crate::wheel_builder::WheelBuilder;
+use crate::wheel_builder::{SDistBuildError, WheelBuilder};
 use elsa::FrozenMap;
+use indexmap::IndexMap;
 use itertools::Itertools;
 use miette::{Diagnostic, IntoDiagnostic, MietteDiagnostic};
 use parking_lot::Mutex;
@@ -17,17 +19,18 @@ use pep440_rs::{Operator, Version, VersionSpecifier, VersionSpecifiers};
 use pep508_rs::{MarkerEnvironment, Requirement, VersionOrUrl};
 use resolvo::{
     Candidates, Dependencies, DependencyProvider, KnownDependencies, NameId, Pool, SolvableId,
-    SolverCache, VersionSet,
+    SolverCache, VersionSet, VersionSetId,
 };
 use serde::Deserialize;
 use serde::Serialize;
 use std::any::Any;
 use std::borrow::Borrow;
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::runtime::Handle;
 use tokio::task;
@@ -134,7 +137,7 @@ impl VersionSet for PypiVersionSet {
                     package_allows_prerelease,
                 },
             ) => {
-                spec.contains(version)
+                spec.satisfied_by(version)
                     // pre-releases are allowed only when the versionset allows them (jupyterlab==3.0.0a1)
                     // or there are no other versions available (foo-1.0.0a1, foo-1.0.0a2)
                     // or alternatively if the user has enabled all pre-releases or this specific (this is encoded in the allows_prerelease field)
@@ -199,20 +202,95 @@ impl Display for PypiPackageName {
     }
 }
 
+/// Versions are excluded for these reasons if none of their artifacts can be used with the target
+/// interpreter or platform, see [`ResolveOptions::strict`].
+const REQUIRES_PYTHON_EXCLUDED: &str =
+    "none of the artifacts support the version of the Python interpreter";
+const INCOMPATIBLE_EXCLUDED: &str =
+    "none of the artifacts are compatible with the Python interpreter or glibc version";
+const INCOMPATIBLE_WITHOUT_SDISTS_EXCLUDED: &str = "none of the artifacts are compatible with the Python interpreter or glibc version and there are no supported sdists";
+const PRERELEASE_EXCLUDED: &str = "pre-releases are not allowed";
+/// The reason of the index for yanking the version is added to this reason, if there is one.
+const YANKED_EXCLUDED: &str = "it is yanked";
+
+/// A package that provides another package through its `Provides-Dist` metadata, see
+/// [`ResolveOptions::honor_provides`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Provider {
+    /// The name of the providing package
+    pub name: NormalizedPackageName,
+
+    /// The version of the providing package
+    pub version: PypiVersion,
+
+    /// The version of the provided package
+    pub provided_version: PypiVersion,
+}
+
+/// The packages that provide each package.
+pub(crate) type Providers = HashMap<NormalizedPackageName, Vec<Provider>>;
+
 /// This is a [`DependencyProvider`] for PyPI packages
 pub(crate) struct PypiDependencyProvider {
     pub pool: Pool<PypiVersionSet, PypiPackageName>,
     package_db: Arc<PackageDb>,
     wheel_builder: Arc<WheelBuilder>,
-    markers: Arc<MarkerEnvironment>,
     compatible_tags: Option<Arc<WheelTags>>,
 
+    /// The environments for which requirements and `requires-python` are evaluated. This is
+    /// either the environment of the interpreter, one environment for each version of
+    /// [`ResolveOptions::python_range`] or one for each of [`ResolveOptions::interpreter_constraints`].
+    python_markers: Vec<MarkerEnvironment>,
+
     pub cached_artifacts: FrozenMap<SolvableId, Vec<Arc<ArtifactInfo>>>,
 
+    /// The names of the packages each solvable depends on, recorded when its dependencies are
+    /// requested by the solver.
+    pub cached_dependencies: FrozenMap<SolvableId, Vec<NormalizedPackageName>>,
+
+    /// The requirements of each solvable, recorded when its dependencies are requested by the
+    /// solver. These are used to explain why a resolution failed.
+    pub cached_requirements: Mutex<Vec<(SolvableId, VersionSetId)>>,
+
+    /// The errors that occurred when fetching the available artifacts of a package.
+    pub fetch_failures: Mutex<HashMap<NormalizedPackageName, miette::Report>>,
+
+    /// The versions that were excluded because none of their artifacts are compatible, together
+    /// with the reason. Only recorded if [`ResolveOptions::strict`] is set.
+    incompatible_versions: Mutex<HashMap<NormalizedPackageName, Vec<(PypiVersion, &'static str)>>>,
+
     favored_packages: HashMap<NormalizedPackageName, PinnedPackage>,
     locked_packages: HashMap<NormalizedPackageName, PinnedPackage>,
     pub name_to_url: FrozenMap<NormalizedPackageName, String>,
 
+    /// The `==` and `===` specifiers of the requirements of the user. Only the versions that
+    /// match all of them are considered, even if they fall outside of
+    /// [`ResolveOptions::only_newest`].
+    pinned_versions: HashMap<NormalizedPackageName, Vec<VersionSpecifier>>,
+
+    /// The versions of each package whose metadata was fetched, to enforce
+    /// [`ResolveOptions::max_versions_fetched`].
+    fetched_versions: Mutex<HashMap<NormalizedPackageName, HashSet<PypiVersion>>>,
+
+    /// The packages that are known to provide other packages. These are added as candidates of
+    /// the packages they provide.
+    providers: Providers,
+
+    /// The candidates that stand for a package that is provided by another package.
+    pub provided_solvables: Mutex<HashMap<SolvableId, Provider>>,
+
+    /// The newest candidate of every package, whose metadata is fetched to discover the packages
+    /// it provides. Only recorded if [`ResolveOptions::honor_provides`] is set.
+    newest_candidates: Mutex<HashMap<NormalizedPackageName, SolvableId>>,
+
+    /// The `Provides-Dist` of every package whose metadata was fetched. Only recorded if
+    /// [`ResolveOptions::honor_provides`] is set.
+    discovered_providers: Mutex<Providers>,
+
+    /// The first package of [`ResolveOptions::blocked_packages`] that the solver asked for. The
+    /// solver is cancelled once this is set.
+    pub blocked_package: Mutex<Option<NormalizedPackageName>>,
+
     options: ResolveOptions,
     should_cancel_with_value: Mutex<Option<MetadataError>>,
 }
@@ -229,6 +307,8 @@ impl PypiDependencyProvider {
         locked_packages: HashMap<NormalizedPackageName, PinnedPackage>,
         favored_packages: HashMap<NormalizedPackageName, PinnedPackage>,
         name_to_url: FrozenMap<NormalizedPackageName, String>,
+        pinned_versions: HashMap<NormalizedPackageName, Vec<VersionSpecifier>>,
+        providers: Providers,
         options: ResolveOptions,
         env_variables: HashMap<String, String>,
     ) -> miette::Result<Self> {
@@ -243,23 +323,111 @@ impl PypiDependencyProvider {
             .into_diagnostic()?,
         );
 
+        let python_markers = if !options.interpreter_constraints.is_empty() {
+            options
+                .interpreter_constraints
+                .iter()
+                .map(|interpreter| interpreter.marker_environment(&markers))
+                .collect()
+        } else {
+            match &options.python_range {
+                Some(range) => range.marker_environments(&markers),
+                None => vec![markers.as_ref().clone()],
+            }
+        };
+
         Ok(Self {
             pool,
             package_db,
             wheel_builder,
-            markers,
             compatible_tags,
+            python_markers,
             cached_artifacts: Default::default(),
+            cached_dependencies: Default::default(),
+            cached_requirements: Default::default(),
+            fetch_failures: Default::default(),
+            incompatible_versions: Default::default(),
             favored_packages,
             locked_packages,
             name_to_url,
+            pinned_versions,
+            fetched_versions: Default::default(),
+            providers,
+            provided_solvables: Default::default(),
+            newest_candidates: Default::default(),
+            discovered_providers: Default::default(),
+            blocked_package: Default::default(),
             options,
             should_cancel_with_value: Default::default(),
         })
     }
 
+    /// Fetches the metadata of the newest candidate of every package that the solver looked at
+    /// and returns the packages that provide other packages according to that metadata, together
+    /// with the providers that were discovered while solving.
+    pub fn discover_providers(&self) -> Providers {
+        let newest_candidates = self
+            .newest_candidates
+            .lock()
+            .values()
+            .copied()
+            .sorted()
+            .collect::<Vec<_>>();
+        for solvable_id in newest_candidates {
+            if self.cached_dependencies.get(&solvable_id).is_none() {
+                self.get_dependencies(solvable_id);
+            }
+        }
+        std::mem::take(&mut *self.discovered_providers.lock())
+    }
+
+    /// Records that an artifact was skipped if the user asked for it.
+    fn skip(&self, name: &NormalizedPackageName, artifact: &ArtifactInfo, reason: SkipReason) {
+        if let Some(summary) = &self.options.summary {
+            summary.skipped(artifact, &reason);
+        }
+        if let Some(skipped) = &self.options.skipped_artifacts {
+            skipped.push(name.clone(), artifact.filename.clone(), reason);
+        }
+    }
+
+    /// Returns the versions of a package that are considered if only the newest versions of each
+    /// package should be used, or `None` if all versions are considered.
+    fn newest_versions<'a>(
+        &self,
+        name: &NormalizedPackageName,
+        artifacts: &'a IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>,
+        allow_prerelease: bool,
+    ) -> Option<HashSet<&'a PypiVersion>> {
+        let only_newest = self.options.only_newest?;
+        let mut versions = artifacts
+            .iter()
+            .filter(|(version, _)| allow_prerelease || !version.any_prerelease())
+            .filter(|(_, artifacts)| artifacts.iter().any(|a| !a.yanked.yanked))
+            .map(|(version, _)| version)
+            .collect::<Vec<_>>();
+        if versions.len() > only_newest {
+            tracing::warn!(
+                "only considering the {only_newest} newest of {} versions of '{name}'",
+                versions.len()
+            );
+        }
+        versions.sort_unstable_by(|a, b| b.cmp(a));
+        versions.truncate(only_newest);
+
+        // Versions that are explicitly pinned are always considered
+        let pinned = self.pinned_versions.get(name).into_iter().flatten();
+        versions.extend(artifacts.keys().filter(|version| match version {
+            PypiVersion::Version { version, .. } => pinned.clone().any(|pin| pin.contains(version)),
+            PypiVersion::Url(_) => false,
+        }));
+
+        Some(versions.into_iter().collect())
+    }
+
     fn filter_candidates<'a, A: Borrow<ArtifactInfo>>(
         &self,
+        name: &NormalizedPackageName,
         artifacts: &'a [A],
     ) -> Result<Vec<&'a A>, &'static str> {
         // Filter only artifacts we can work with
@@ -270,10 +438,41 @@ impl PypiDependencyProvider {
 
         let mut artifacts = artifacts.iter().collect::<Vec<_>>();
         // Filter yanked artifacts
-        artifacts.retain(|a| !(*a).borrow().yanked.yanked);
+        artifacts.retain(|a| {
+            let ai = (*a).borrow();
+            if ai.yanked.yanked {
+                self.skip(name, ai, SkipReason::Yanked(ai.yanked.reason.clone()));
+            }
+            !ai.yanked.yanked
+        });
 
         if artifacts.is_empty() {
-            return Err("it is yanked");
+            return Err(YANKED_EXCLUDED);
+        }
+
+        // Filter artifacts that do not support the version of the python interpreter, or any of
+        // the versions in the requested range
+        artifacts.retain(|a| {
+            let ai = (*a).borrow();
+            match &ai.requires_python {
+                Some(requires_python)
+                    if !self.python_markers.iter().all(|markers| {
+                        requires_python.satisfied_by(&markers.python_full_version.version)
+                    }) =>
+                {
+                    self.skip(
+                        name,
+                        ai,
+                        SkipReason::RequiresPython(requires_python.clone()),
+                    );
+                    false
+                }
+                _ => true,
+            }
+        });
+
+        if artifacts.is_empty() {
+            return Err(REQUIRES_PYTHON_EXCLUDED);
         }
 
         // This should keep only the wheels
@@ -284,12 +483,20 @@ impl PypiDependencyProvider {
                 .filter(|a| (*a).borrow().is::<Wheel>())
                 .collect::<Vec<_>>();
 
-            if !self.options.sdist_resolution.allow_sdists() && wheels.is_empty() {
-                return Err("there are no wheels available");
+            if !self.options.sdist_resolution.allow_sdists() {
+                for a in artifacts.iter().filter(|a| !(**a).borrow().is::<Wheel>()) {
+                    self.skip(name, (*a).borrow(), SkipReason::NoWheel);
+                }
+                if wheels.is_empty() {
+                    return Err("there are no wheels available");
+                }
             }
 
             wheels
         } else {
+            for a in artifacts.iter().filter(|a| (**a).borrow().is::<Wheel>()) {
+                self.skip(name, (*a).borrow(), SkipReason::NoSDist);
+            }
             vec![]
         };
 
@@ -313,10 +520,15 @@ impl PypiDependencyProvider {
 
             sdists.retain(|a| {
                 let ai = (*a).borrow();
-                ai.filename
+                let supported = ai
+                    .filename
                     .as_sdist()
                     .is_some_and(|f| f.format.is_supported())
-                    || ai.filename.as_stree().is_some()
+                    || ai.filename.as_stree().is_some();
+                if !supported {
+                    self.skip(name, ai, SkipReason::UnsupportedFormat);
+                }
+                supported
             });
 
             if wheels.is_empty() && sdists.is_empty() {
@@ -328,15 +540,49 @@ impl PypiDependencyProvider {
             vec![]
         };
 
+        // Wheels of the same version that only differ in their build tag are rebuilds, PEP 427
+        // prefers the highest build tag. The sort is stable so the compatibility sort below only
+        // uses the build tag as a tie-breaker.
+        wheels.sort_by_cached_key(|a| {
+            Reverse(
+                (*a).borrow()
+                    .filename
+                    .as_wheel()
+                    .and_then(|wheel_name| wheel_name.build_tag().cloned()),
+            )
+        });
+
         // Filter based on compatibility
         if self.options.sdist_resolution.allow_wheels() {
+            if self.options.pure_python_only {
+                wheels.retain(|artifact| {
+                    let ai = (*artifact).borrow();
+                    let pure = ai.filename.as_wheel().is_some_and(|wheel_name| {
+                        wheel_name
+                            .all_tags_iter()
+                            .any(|t| t.abi == "none" && t.platform == "any")
+                    });
+                    if !pure {
+                        self.skip(name, ai, SkipReason::NotPurePython);
+                    }
+                    pure
+                });
+            }
+
             if let Some(compatible_tags) = &self.compatible_tags {
-                wheels.retain(|artifact| match &(*artifact).borrow().filename {
-                    ArtifactName::Wheel(wheel_name) => wheel_name
-                        .all_tags_iter()
-                        .any(|t| compatible_tags.is_compatible(&t)),
-                    ArtifactName::SDist(_) => false,
-                    ArtifactName::STree(_) => false,
+                wheels.retain(|artifact| {
+                    let ai = (*artifact).borrow();
+                    let compatible = match &ai.filename {
+                        ArtifactName::Wheel(wheel_name) => wheel_name
+                            .all_tags_iter()
+                            .any(|t| compatible_tags.is_compatible(&t)),
+                        ArtifactName::SDist(_) => false,
+                        ArtifactName::STree(_) => false,
+                    };
+                    if !compatible {
+                        self.skip(name, ai, SkipReason::IncompatiblePlatform);
+                    }
+                    compatible
                 });
 
                 // Sort the artifacts from most compatible to least compatible, this ensures that we
@@ -356,13 +602,11 @@ impl PypiDependencyProvider {
             }
 
             if !self.options.sdist_resolution.allow_sdists() && wheels.is_empty() {
-                return Err(
-                    "none of the artifacts are compatible with the Python interpreter or glibc version",
-                );
+                return Err(INCOMPATIBLE_EXCLUDED);
             }
 
             if wheels.is_empty() && sdists.is_empty() {
-                return Err("none of the artifacts are compatible with the Python interpreter or glibc version and there are no supported sdists");
+                return Err(INCOMPATIBLE_WITHOUT_SDISTS_EXCLUDED);
             }
         }
 
@@ -377,6 +621,64 @@ impl PypiDependencyProvider {
         Ok(artifacts)
     }
 
+    /// Returns the versions of a package that were excluded because none of their artifacts are
+    /// compatible, together with the reason. Locked and favored packages are selected
+    /// deliberately and never have such versions. Versions are only recorded if
+    /// [`ResolveOptions::strict`] is set.
+    pub fn incompatible_versions(
+        &self,
+        name: &NormalizedPackageName,
+    ) -> Vec<(PypiVersion, &'static str)> {
+        if self.locked_packages.contains_key(name) || self.favored_packages.contains_key(name) {
+            return Vec::new();
+        }
+        self.incompatible_versions
+            .lock()
+            .get(name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Records that the metadata of `version` of `name` is fetched. Returns an error if this
+    /// exceeds [`ResolveOptions::max_versions_fetched`]. The metadata of an extra is that of the
+    /// base package, so it only counts once.
+    fn record_fetched_version(
+        &self,
+        name: &NormalizedPackageName,
+        version: &PypiVersion,
+    ) -> Option<MetadataError> {
+        let budget = self.options.max_versions_fetched?;
+        let mut fetched_versions = self.fetched_versions.lock();
+        if fetched_versions
+            .get(name)
+            .is_some_and(|versions| versions.contains(version))
+        {
+            return None;
+        }
+
+        let fetched = fetched_versions.values().map(HashSet::len).sum::<usize>();
+        if fetched >= budget {
+            let packages = fetched_versions
+                .iter()
+                .map(|(name, versions)| (versions.len(), name))
+                .sorted_by(|(a_count, a_name), (b_count, b_name)| {
+                    b_count.cmp(a_count).then_with(|| a_name.cmp(b_name))
+                })
+                .take(5)
+                .format_with(", ", |(count, name), f| {
+                    f(&format_args!("{}: {count}", name.as_str()))
+                })
+                .to_string();
+            return Some(MetadataError::FetchBudgetExceeded { budget, packages });
+        }
+
+        fetched_versions
+            .entry(name.clone())
+            .or_default()
+            .insert(version.clone());
+        None
+    }
+
     fn solvable_has_artifact_type<S: Artifact>(&self, solvable_id: SolvableId) -> bool {
         self.cached_artifacts
             .get(&solvable_id)
@@ -386,17 +688,96 @@ impl PypiDependencyProvider {
     }
 }
 
+/// An error that occurred while obtaining the metadata of a package during resolution.
 #[derive(Debug, Error, Diagnostic, Clone)]
-pub(crate) enum MetadataError {
+pub enum MetadataError {
+    /// None of the artifacts of a package provided metadata
     #[error("Extraction of metadata in case of wheels or building in case of sdists returned no results for following artifacts:\n{0}")]
     NoMetadata(String),
 
+    /// Extracting the metadata of the artifacts of a package failed
     #[error("No metadata could be extracted for the following available artifacts:\n{artifacts}")]
     ExtractionFailure {
+        /// The artifacts for which extraction was attempted
         artifacts: String,
+
+        /// The errors that occurred
         #[related]
         errors: Vec<MietteDiagnostic>,
     },
+
+    /// A package only has source distributions and building them failed
+    #[error("{package} has no compatible wheel and building its source distribution '{filename}' failed, the build backend reported:\n{output}")]
+    #[diagnostic(help(
+        "install the packages required to build {package}, require a different version of it that has wheels, or pass --only-wheels to only consider versions with wheels"
+    ))]
+    SDistBuildFailure {
+        /// The name and version of the package
+        package: String,
+
+        /// The filename of the source distribution that failed to build
+        filename: String,
+
+        /// The last lines of the output of the build backend
+        output: String,
+    },
+
+    /// A requirement of a package uses a marker variable that is not defined by PEP 508
+    #[error("the requirement '{requirement}' of {package} uses the unknown environment marker variable '{variable}'")]
+    #[diagnostic(help(
+        "unknown marker variables are an error because strict marker checking is enabled"
+    ))]
+    UnknownMarkerVariable {
+        /// The package that has the requirement
+        package: String,
+
+        /// The requirement with the unknown variable
+        requirement: String,
+
+        /// The unknown variable
+        variable: String,
+    },
+
+    /// The metadata of more versions was needed than allowed by
+    /// [`ResolveOptions::max_versions_fetched`]
+    #[error("the resolution needs the metadata of more than {budget} package versions, the packages with the most fetched versions are: {packages}")]
+    #[diagnostic(help(
+        "constrain the versions of these packages in the requirements, or raise the budget if this many versions are expected"
+    ))]
+    FetchBudgetExceeded {
+        /// The maximum number of versions whose metadata may be fetched
+        budget: usize,
+
+        /// The packages for which the most versions were fetched, with their counts
+        packages: String,
+    },
+
+    /// The metadata of a package could not be obtained within
+    /// [`ResolveOptions::timeout_per_package`]
+    #[error("the metadata of {package} could not be obtained within {timeout:?}")]
+    #[diagnostic(help(
+        "slow packages are an error because strict mode is enabled, otherwise the version is excluded"
+    ))]
+    MetadataTimeout {
+        /// The name and version of the package
+        package: String,
+
+        /// The timeout that was exceeded
+        timeout: Duration,
+    },
+
+    /// A requirement of a package cannot be parsed
+    #[error("the requirement '{requirement}' of {package} cannot be parsed")]
+    #[diagnostic(help(
+        "invalid requirements are an error because strict mode is enabled, otherwise they are ignored"
+    ))]
+    InvalidRequirement {
+        /// The package that has the requirement
+        package: String,
+
+        /// The requirement that cannot be parsed
+        requirement: String,
+    },
 }
 
 impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDependencyProvider {
@@ -405,6 +786,10 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
     }
 
     fn should_cancel_with_value(&self) -> Option<Box<dyn Any>> {
+        if let Some(name) = self.blocked_package.lock().as_ref() {
+            return Some(Box::new(name.clone()));
+        }
+
         // Supply the error message
         self.should_cancel_with_value
             .lock()
@@ -457,13 +842,42 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
                     PypiVersion::Version { version: b, .. },
                 ) => b.cmp(a),
             }
-        })
+        });
+
+        // Let a custom sorter reorder the candidates, starting from the default order
+        if let Some(sorter) = &self.options.candidate_sorter {
+            let Some(&first) = solvables.first() else {
+                return;
+            };
+            let pool = solver.pool();
+            let name = pool.resolve_package_name(pool.resolve_solvable(first).name_id());
+            let mut candidates = solvables
+                .iter()
+                .map(|&solvable| Candidate {
+                    version: pool.resolve_solvable(solvable).inner(),
+                    artifacts: self.cached_artifacts.get(&solvable).unwrap_or_default(),
+                    solvable,
+                })
+                .collect::<Vec<_>>();
+            sorter.sort(name.base(), &mut candidates);
+            for (solvable, candidate) in solvables.iter_mut().zip(candidates) {
+                *solvable = candidate.solvable;
+            }
+        }
     }
 
     fn get_candidates(&self, name: NameId) -> Option<Candidates> {
         let package_name = self.pool.resolve_package_name(name);
         tracing::info!("collecting {}", package_name);
 
+        // A blocked package fails the resolution as soon as it is required
+        if self.options.blocked_packages.contains(package_name.base()) {
+            self.blocked_package
+                .lock()
+                .get_or_insert_with(|| package_name.base().clone());
+            return None;
+        }
+
         // check if we have URL variant for this name
         let url_version = self.name_to_url.get(package_name.base());
 
@@ -486,12 +900,26 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
             })
         };
 
+        // The packages that provide this package, a package that is provided does not have to
+        // exist on the index
+        let providers = match package_name {
+            PypiPackageName::Base(name) => self.providers.get(name),
+            PypiPackageName::Extra(..) => None,
+        };
+
         let artifacts = match result {
             Ok(artifacts) => artifacts,
+            Err(err) if providers.is_some() => {
+                tracing::debug!("failed to fetch artifacts of '{package_name}', only the packages that provide it are considered: {err:?}");
+                Default::default()
+            }
             Err(err) => {
                 tracing::error!(
                     "failed to fetch artifacts of '{package_name}': {err:?}, skipping.."
                 );
+                self.fetch_failures
+                    .lock()
+                    .insert(package_name.base().clone(), err);
                 return None;
             }
         };
@@ -514,7 +942,25 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
             PreReleaseResolution::Allow => true,
         };
 
+        let newest_versions = self.newest_versions(
+            package_name.base(),
+            &artifacts,
+            should_package_allow_prerelease,
+        );
+
+        let pins = self
+            .pinned_versions
+            .get(package_name.base())
+            .filter(|pins| !pins.is_empty());
+
         for (artifact_version, artifacts) in artifacts.iter() {
+            // Skip this version if it is too old to be considered
+            if let Some(newest_versions) = &newest_versions {
+                if !newest_versions.contains(artifact_version) {
+                    continue;
+                }
+            }
+
             // Skip this version if a locked or favored version exists for this version. It will be
             // added below.
             if locked_package.map(|p| &p.version) == Some(artifact_version)
@@ -537,13 +983,80 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
             let solvable_id = self.pool.intern_solvable(name, internable_version);
             candidates.candidates.push(solvable_id);
 
+            // A version that does not match the pins of the user can never be selected, so there
+            // is no need to look at its artifacts or metadata. It is still added as an excluded
+            // candidate to explain conflicts with other requirements on the package.
+            if let (Some(pins), PypiVersion::Version { version, .. }) = (pins, artifact_version) {
+                if !pins.iter().all(|pin| pin.contains(version)) {
+                    let reason = format!("the user requested {}", pins.iter().join(","));
+                    candidates
+                        .excluded
+                        .push((solvable_id, self.pool.intern_string(reason)));
+                    continue;
+                }
+            }
+
+            // If pre-releases are disallowed no requirement can select this version. Otherwise
+            // pre-releases remain candidates, a requirement can still explicitly ask for one.
+            if matches!(
+                self.options.pre_release_resolution,
+                PreReleaseResolution::Disallow
+            ) && artifact_version.any_prerelease()
+            {
+                for artifact in artifacts {
+                    self.skip(package_name.base(), artifact, SkipReason::PreRelease);
+                }
+                candidates
+                    .excluded
+                    .push((solvable_id, self.pool.intern_string(PRERELEASE_EXCLUDED)));
+                continue;
+            }
+
             // Determine the candidates
-            match self.filter_candidates(artifacts) {
+            match self.filter_candidates(package_name.base(), artifacts) {
                 Ok(artifacts) => {
+                    if let Some(summary) = &self.options.summary {
+                        for artifact in &artifacts {
+                            summary.considered(artifact);
+                        }
+                    }
                     self.cached_artifacts
                         .insert(solvable_id, artifacts.into_iter().cloned().collect());
+                    if self.options.honor_provides {
+                        if let PypiPackageName::Base(name) = package_name {
+                            let mut newest_candidates = self.newest_candidates.lock();
+                            let newest =
+                                newest_candidates.entry(name.clone()).or_insert(solvable_id);
+                            if self.pool.resolve_solvable(*newest).inner() < artifact_version {
+                                *newest = solvable_id;
+                            }
+                        }
+                    }
                 }
                 Err(reason) => {
+                    let incompatible = [
+                        REQUIRES_PYTHON_EXCLUDED,
+                        INCOMPATIBLE_EXCLUDED,
+                        INCOMPATIBLE_WITHOUT_SDISTS_EXCLUDED,
+                    ];
+                    if self.options.strict && incompatible.contains(&reason) {
+                        let version = self.pool.resolve_solvable(solvable_id).inner().clone();
+                        self.incompatible_versions
+                            .lock()
+                            .entry(package_name.base().clone())
+                            .or_default()
+                            .push((version, reason));
+                    }
+                    let yanked_reason = artifacts
+                        .iter()
+                        .filter_map(|a| a.yanked.reason.as_deref())
+                        .find(|reason| !reason.is_empty());
+                    let reason = match yanked_reason {
+                        Some(yanked_reason) if reason == YANKED_EXCLUDED => {
+                            format!("{reason} ({yanked_reason})")
+                        }
+                        _ => reason.to_owned(),
+                    };
                     candidates
                         .excluded
                         .push((solvable_id, self.pool.intern_string(reason)));
@@ -569,6 +1082,18 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
                 .insert(solvable_id, favored.artifacts.clone());
         }
 
+        // Add the packages that provide this package
+        for provider in providers.into_iter().flatten() {
+            let solvable_id = self
+                .pool
+                .intern_solvable(name, provider.provided_version.clone());
+            candidates.candidates.push(solvable_id);
+            self.cached_artifacts.insert(solvable_id, Vec::new());
+            self.provided_solvables
+                .lock()
+                .insert(solvable_id, provider.clone());
+        }
+
         Some(candidates)
     }
 
@@ -592,16 +1117,7 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
                 .pool
                 .lookup_package_name(&PypiPackageName::Base(package_name.clone()))
                 .expect("base package not found while resolving extra");
-            let specifiers = match package_version {
-                PypiVersion::Version { version, .. } => {
-                    VersionOrUrl::VersionSpecifier(VersionSpecifiers::from_iter([
-                        VersionSpecifier::new(Operator::ExactEqual, version.clone(), false)
-                            .expect("failed to construct equality version specifier"),
-                    ]))
-                }
-                PypiVersion::Url(url_version) => VersionOrUrl::Url(url_version.clone()),
-            };
-
+            let specifiers = exact_version(package_version);
             let version_set_id = self.pool.intern_version_set(
                 base_name_id,
                 PypiVersionSet::from_spec(Some(specifiers), &self.options.pre_release_resolution),
@@ -609,6 +1125,29 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
             dependencies.requirements.push(version_set_id);
         }
 
+        // A package that is provided by another package only requires that exact version of the
+        // providing package
+        let provider = self.provided_solvables.lock().get(&solvable_id).cloned();
+        if let Some(provider) = provider {
+            let provider_name_id = self
+                .pool
+                .intern_package_name(PypiPackageName::Base(provider.name.clone()));
+            let version_set_id = self.pool.intern_version_set(
+                provider_name_id,
+                PypiVersionSet::from_spec(
+                    Some(exact_version(&provider.version)),
+                    &self.options.pre_release_resolution,
+                ),
+            );
+            dependencies.requirements.push(version_set_id);
+            self.cached_dependencies
+                .insert(solvable_id, vec![provider.name]);
+            self.cached_requirements
+                .lock()
+                .push((solvable_id, version_set_id));
+            return Dependencies::Known(dependencies);
+        }
+
         // Retrieve the artifacts that are applicable for this version
         let artifacts = self
             .cached_artifacts
@@ -633,13 +1172,62 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
             return Dependencies::Unknown(error);
         }
 
+        if let Some(error) = self.record_fetched_version(package_name.base(), package_version) {
+            *self.should_cancel_with_value.lock() = Some(error);
+            return Dependencies::Unknown(self.pool.intern_string("".to_string()));
+        }
+
+        // The metadata of an extra is the metadata of the base package, which is only reported
+        // once
+        let progress = self
+            .options
+            .progress_observer
+            .as_ref()
+            .filter(|_| matches!(package_name, PypiPackageName::Base(_)));
+        if let Some(progress) = progress {
+            progress.on_package_started(package_name.base(), package_version);
+        }
+        if let Some(summary) = &self.options.summary {
+            summary.fetched(package_name.base(), package_version);
+        }
         let result = task::block_in_place(|| {
-            // First try getting wheels
-            Handle::current().block_on(
-                self.package_db
-                    .get_metadata(artifacts, Some(&self.wheel_builder)),
-            )
+            Handle::current().block_on(async {
+                // First try getting wheels
+                let get_metadata = self
+                    .package_db
+                    .get_metadata(artifacts, Some(&self.wheel_builder));
+                match self.options.timeout_per_package {
+                    Some(timeout) => tokio::time::timeout(timeout, get_metadata).await.ok(),
+                    None => Some(get_metadata.await),
+                }
+            })
         });
+        if let Some(progress) = progress {
+            progress.on_package_finished(package_name.base(), package_version);
+        }
+
+        // The metadata could not be obtained in time, exclude this version unless strict
+        let Some(result) = result else {
+            let timeout = self.options.timeout_per_package.unwrap_or_default();
+            if self.options.strict {
+                *self.should_cancel_with_value.lock() = Some(MetadataError::MetadataTimeout {
+                    package: format!("{} {}", package_name.base(), package_version),
+                    timeout,
+                });
+                return Dependencies::Unknown(self.pool.intern_string("".to_string()));
+            }
+            tracing::warn!(
+                "the metadata of {} {} could not be obtained within {timeout:?}, this version is excluded from the resolution",
+                package_name.base(),
+                package_version
+            );
+            for artifact in artifacts {
+                self.skip(package_name.base(), artifact, SkipReason::MetadataTimeout);
+            }
+            return Dependencies::Unknown(self.pool.intern_string(format!(
+                "the metadata could not be obtained within {timeout:?}"
+            )));
+        };
 
         let metadata = match result {
             // We have retrieved a value without error
@@ -661,6 +1249,18 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
             // Errors have occurred during metadata extraction
             // This is almost always an sdist build failure
             Err(e) => {
+                if let Some(build_error) = e.downcast_ref::<SDistBuildError>() {
+                    if !artifacts.iter().any(|a| a.is::<Wheel>()) {
+                        *self.should_cancel_with_value.lock() =
+                            Some(MetadataError::SDistBuildFailure {
+                                package: format!("{} {}", package_name.base(), package_version),
+                                filename: build_error.filename.clone(),
+                                output: output_tail(&build_error.output),
+                            });
+                        return Dependencies::Unknown(self.pool.intern_string("".to_string()));
+                    }
+                }
+
                 let formatted_artifacts = artifacts
                     .iter()
                     .format_with("\n", |a, f| f(&format_args!("\t- {}", a.filename)))
@@ -673,23 +1273,74 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
             }
         };
 
+        // Requirements with markers that are not defined by PEP 508 are ignored, unless the user
+        // asked for strict checking. In strict mode any requirement that cannot be parsed fails.
+        if self.options.strict_markers || self.options.strict {
+            for requirement in &metadata.invalid_requires_dist {
+                let error = if let Some(variable) =
+                    unknown_marker_variables(requirement).into_iter().next()
+                {
+                    MetadataError::UnknownMarkerVariable {
+                        package: format!("{package_name}={package_version}"),
+                        requirement: requirement.clone(),
+                        variable,
+                    }
+                } else if self.options.strict {
+                    MetadataError::InvalidRequirement {
+                        package: format!("{package_name}={package_version}"),
+                        requirement: requirement.clone(),
+                    }
+                } else {
+                    continue;
+                };
+                *self.should_cancel_with_value.lock() = Some(error);
+                return Dependencies::Unknown(self.pool.intern_string("".to_string()));
+            }
+        }
+
+        // Remember the packages that this package provides
+        if let (true, PypiPackageName::Base(name)) = (self.options.honor_provides, package_name) {
+            let mut discovered_providers = self.discovered_providers.lock();
+            for provides in &metadata.provides_dist {
+                let applies = provides.marker.as_ref().map_or(true, |marker| {
+                    self.python_markers
+                        .iter()
+                        .any(|environment| marker.evaluate(environment, &[]))
+                });
+                if !applies {
+                    continue;
+                }
+                let provided_version = match &provides.version {
+                    Some(version) => PypiVersion::Version {
+                        version: version.clone(),
+                        package_allows_prerelease: version.any_prerelease(),
+                    },
+                    None => package_version.clone(),
+                };
+                let provider = Provider {
+                    name: name.clone(),
+                    version: package_version.clone(),
+                    provided_version,
+                };
+                let providers = discovered_providers
+                    .entry(provides.name.clone().into())
+                    .or_default();
+                if !providers.contains(&provider) {
+                    providers.push(provider);
+                }
+            }
+        }
+
         // Add constraints that restrict that the extra packages are set to the same version.
         if let PypiPackageName::Base(package_name) = package_name {
-            // Add constraints on the extras of a package
-            for extra in metadata.extras {
+            // Add constraints on the extras of a package. The extras are sorted so they are
+            // interned in the same order on every run.
+            for extra in metadata.extras.into_iter().sorted() {
                 let extra_name_id = self
                     .pool
                     .intern_package_name(PypiPackageName::Extra(package_name.clone(), extra));
 
-                let specifiers = match package_version {
-                    PypiVersion::Version { version, .. } => {
-                        VersionOrUrl::VersionSpecifier(VersionSpecifiers::from_iter([
-                            VersionSpecifier::new(Operator::ExactEqual, version.clone(), false)
-                                .expect("failed to construct equality version specifier"),
-                        ]))
-                    }
-                    PypiVersion::Url(url_version) => VersionOrUrl::Url(url_version.clone()),
-                };
+                let specifiers = exact_version(package_version);
                 let version_set_id = self.pool.intern_version_set(
                     extra_name_id,
                     PypiVersionSet::from_spec(
@@ -707,13 +1358,36 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
             .map(|e| e.as_str())
             .collect::<Vec<_>>();
         for requirement in metadata.requires_dist {
-            // Evaluate environment markers
+            // Evaluate environment markers, with a range of Python versions the requirement is
+            // added if it applies to any of them. With interpreter constraints it must apply to
+            // all of the interpreters.
             if let Some(markers) = requirement.marker.as_ref() {
-                if !markers.evaluate(&self.markers, &extras) {
+                let applies =
+                    |environment: &MarkerEnvironment| markers.evaluate(environment, &extras);
+                let applies = if self.options.interpreter_constraints.is_empty() {
+                    self.python_markers.iter().any(applies)
+                } else {
+                    self.python_markers.iter().all(applies)
+                };
+                if !applies {
                     continue;
                 }
             }
 
+            // Drop the requirement if the user asked to ignore it
+            let ignored = self.options.ignored_dependencies.iter().any(|ignored| {
+                &ignored.package == package_name.base()
+                    && PackageName::from_str(&requirement.name)
+                        .is_ok_and(|name| NormalizedPackageName::from(name) == ignored.dependency)
+            });
+            if ignored {
+                tracing::warn!(
+                    "ignoring the requirement '{requirement}' of {} {package_version} as requested, the selected packages might not work together",
+                    package_name.base()
+                );
+                continue;
+            }
+
             // Add the dependency to the pool
             let Requirement {
                 name,
@@ -758,6 +1432,1239 @@ impl<'p> DependencyProvider<PypiVersionSet, PypiPackageName> for &'p PypiDepende
             }
         }
 
+        // Remember which packages this solvable depends on so we can construct the dependency
+        // graph of the solution later.
+        let dependency_names = dependencies
+            .requirements
+            .iter()
+            .map(|&version_set_id| {
+                self.pool
+                    .resolve_package_name(
+                        self.pool.resolve_version_set_package_name(version_set_id),
+                    )
+                    .base()
+                    .clone()
+            })
+            .filter(|name| name != package_name.base())
+            .unique()
+            .collect();
+        self.cached_dependencies
+            .insert(solvable_id, dependency_names);
+        self.cached_requirements.lock().extend(
+            dependencies
+                .requirements
+                .iter()
+                .map(|&version_set_id| (solvable_id, version_set_id)),
+        );
+
         Dependencies::Known(dependencies)
     }
 }
+
+/// Returns the specifier that only matches exactly `version`.
+fn exact_version(version: &PypiVersion) -> VersionOrUrl {
+    match version {
+        PypiVersion::Version { version, .. } => {
+            VersionOrUrl::VersionSpecifier(VersionSpecifiers::from_iter([VersionSpecifier::new(
+                Operator::ExactEqual,
+                version.clone(),
+                false,
+            )
+            .expect("failed to construct equality version specifier")]))
+        }
+        PypiVersion::Url(url_version) => VersionOrUrl::Url(url_version.clone()),
+    }
+}
+
+/// The number of lines of the build backend output that is shown when building a source
+/// distribution fails, the cause is usually at the end.
+const BUILD_OUTPUT_TAIL_LINES: usize = 20;
+
+/// Returns the last lines of the output of a build backend.
+fn output_tail(output: &str) -> String {
+    let lines = output.trim_end().lines().collect::<Vec<_>>();
+    let tail = &lines[lines.len().saturating_sub(BUILD_OUTPUT_TAIL_LINES)..];
+    let mut result = tail.join("\n");
+    if tail.len() < lines.len() {
+        result.insert_str(0, "...\n");
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::python_env::{Pep508EnvMakers, WheelTag};
+    use crate::resolve::{ResolutionSummary, SkippedArtifacts, SummaryCounts};
+    use crate::types::{ArtifactHashes, DistInfoMetadata, Yanked};
+    use indexmap::IndexMap;
+    use reqwest::Client;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    /// The ordered versions from the test suite of `packaging`, which is the reference
+    /// implementation of PEP 440. Every version is strictly smaller than the next one.
+    const PEP440_ORDERED_VERSIONS: [&str; 54] = [
+        // Implicit epoch of 0
+        "1.0.dev456",
+        "1.0a1",
+        "1.0a2.dev456",
+        "1.0a12.dev456",
+        "1.0a12",
+        "1.0b1.dev456",
+        "1.0b2",
+        "1.0b2.post345.dev456",
+        "1.0b2.post345",
+        "1.0b2-346",
+        "1.0c1.dev456",
+        "1.0c1",
+        "1.0rc2",
+        "1.0c3",
+        "1.0",
+        "1.0.post456.dev34",
+        "1.0.post456",
+        "1.1.dev1",
+        "1.2+123abc",
+        "1.2+123abc456",
+        "1.2+abc",
+        "1.2+abc123",
+        "1.2+abc123def",
+        "1.2+1234.abc",
+        "1.2+123456",
+        "1.2.r32+123456",
+        "1.2.rev33+123456",
+        // Explicit epoch of 1
+        "1!1.0.dev456",
+        "1!1.0a1",
+        "1!1.0a2.dev456",
+        "1!1.0a12.dev456",
+        "1!1.0a12",
+        "1!1.0b1.dev456",
+        "1!1.0b2",
+        "1!1.0b2.post345.dev456",
+        "1!1.0b2.post345",
+        "1!1.0b2-346",
+        "1!1.0c1.dev456",
+        "1!1.0c1",
+        "1!1.0rc2",
+        "1!1.0c3",
+        "1!1.0",
+        "1!1.0.post456.dev34",
+        "1!1.0.post456",
+        "1!1.1.dev1",
+        "1!1.2+123abc",
+        "1!1.2+123abc456",
+        "1!1.2+abc",
+        "1!1.2+abc123",
+        "1!1.2+abc123def",
+        "1!1.2+1234.abc",
+        "1!1.2+123456",
+        "1!1.2.r32+123456",
+        "1!1.2.rev33+123456",
+    ];
+
+    #[test]
+    fn test_pep440_ordering() {
+        let versions = PEP440_ORDERED_VERSIONS
+            .iter()
+            .map(|v| Version::from_str(v).unwrap_or_else(|e| panic!("{v}: {e}")))
+            .collect::<Vec<_>>();
+        for (a, b) in versions.iter().tuple_windows() {
+            assert!(a < b, "expected {a} < {b}");
+        }
+
+        // Sorting a shuffled list restores the reference order
+        let mut shuffled = versions.clone();
+        shuffled.reverse();
+        let (evens, odds): (Vec<_>, Vec<_>) = shuffled
+            .into_iter()
+            .enumerate()
+            .partition(|(idx, _)| idx % 2 == 0);
+        let mut shuffled = odds
+            .into_iter()
+            .chain(evens)
+            .map(|(_, v)| v)
+            .collect::<Vec<_>>();
+        shuffled.sort();
+        assert_eq!(shuffled, versions);
+
+        // Candidates are compared by their version
+        let mut candidates = versions
+            .iter()
+            .rev()
+            .map(|version| PypiVersion::Version {
+                version: version.clone(),
+                package_allows_prerelease: false,
+            })
+            .collect::<Vec<_>>();
+        candidates.sort();
+        assert!(candidates
+            .iter()
+            .map(ToString::to_string)
+            .eq(versions.iter().map(ToString::to_string)));
+
+        // Alternative spellings are equal to their normalized form
+        for (a, b) in [
+            ("1.0c1", "1.0rc1"),
+            ("1.0b2-346", "1.0b2.post346"),
+            ("1.2.r32", "1.2.post32"),
+            ("1.0", "1.0.0"),
+            ("0!1.0", "1.0"),
+            ("1.0-dev456", "1.0.dev456"),
+        ] {
+            assert_eq!(Version::from_str(a).unwrap(), Version::from_str(b).unwrap());
+        }
+    }
+
+    fn artifact(
+        name: &NormalizedPackageName,
+        filename: &str,
+        requires_python: Option<&str>,
+        yanked: Option<&str>,
+    ) -> Arc<ArtifactInfo> {
+        Arc::new(ArtifactInfo {
+            filename: ArtifactName::from_filename(filename, name).unwrap(),
+            url: format!("https://example.com/{filename}").parse().unwrap(),
+            hashes: None,
+            requires_python: requires_python.map(|s| s.parse().unwrap()),
+            dist_info_metadata: DistInfoMetadata::default(),
+            yanked: Yanked {
+                yanked: yanked.is_some(),
+                reason: yanked.map(ToOwned::to_owned),
+            },
+            index_url: None,
+        })
+    }
+
+    #[test]
+    fn test_wildcard_version_set() {
+        let version = |version: &str, package_allows_prerelease| PypiVersion::Version {
+            version: version.parse().unwrap(),
+            package_allows_prerelease,
+        };
+        let set = |spec: &str, option: &PreReleaseResolution| {
+            PypiVersionSet::from_spec(
+                Some(VersionOrUrl::VersionSpecifier(spec.parse().unwrap())),
+                option,
+            )
+        };
+        let default = PreReleaseResolution::AllowIfNoOtherVersionsOrEnabled {
+            allow_names: Vec::new(),
+        };
+
+        let equal = set("==1.2.*", &default);
+        assert!(equal.contains(&version("1.2.0", false)));
+        assert!(equal.contains(&version("1.2.9", false)));
+        assert!(!equal.contains(&version("1.3.0", false)));
+        assert!(!set("==2.1.*", &default).contains(&version("2", false)));
+
+        let not_equal = set("!=1.2.*", &default);
+        assert!(!not_equal.contains(&version("1.2.0", false)));
+        assert!(not_equal.contains(&version("1.3.0", false)));
+
+        // A wildcard does not enable pre-releases by itself
+        assert!(!equal.contains(&version("1.2.0rc1", false)));
+        assert!(!not_equal.contains(&version("1.3.0rc1", false)));
+        assert!(equal.contains(&version("1.2.0rc1", true)));
+        assert!(set("==1.2.*", &PreReleaseResolution::Allow).contains(&version("1.2.0rc1", false)));
+        assert!(!set("==1.2.*", &PreReleaseResolution::Allow).contains(&version("1.3.0rc1", false)));
+    }
+
+    /// Creates a provider for which the index only contains `versions` of the package `name`.
+    async fn provider(
+        cache_dir: &Path,
+        name: &NormalizedPackageName,
+        versions: IndexMap<PypiVersion, Vec<Arc<ArtifactInfo>>>,
+        pinned_versions: HashMap<NormalizedPackageName, Vec<VersionSpecifier>>,
+        options: ResolveOptions,
+    ) -> PypiDependencyProvider {
+        let package_db = Arc::new(
+            PackageDb::new(
+                Client::new().into(),
+                &[Url::parse("https://example.com/simple/").unwrap()],
+                cache_dir,
+            )
+            .unwrap(),
+        );
+        package_db.insert_artifacts(name.clone(), versions);
+
+        PypiDependencyProvider::new(
+            Pool::new(),
+            package_db,
+            Arc::new(Pep508EnvMakers::from_env().await.unwrap().0),
+            Some(Arc::new(
+                [
+                    WheelTag::from_str("cp311-cp311-manylinux_2_17_x86_64").unwrap(),
+                    WheelTag::from_str("py3-none-any").unwrap(),
+                ]
+                .into_iter()
+                .collect(),
+            )),
+            HashMap::default(),
+            HashMap::default(),
+            FrozenMap::default(),
+            pinned_versions,
+            Providers::default(),
+            options,
+            HashMap::default(),
+        )
+        .unwrap()
+    }
+
+    /// Collects the candidates of `foo` and returns the reasons of all skipped artifacts.
+    async fn skipped_artifacts(sdist_resolution: SDistResolution) -> Vec<(String, SkipReason)> {
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let mut versions = IndexMap::new();
+        let mut add = |version: &str, artifacts: Vec<Arc<ArtifactInfo>>| {
+            let version: Version = version.parse().unwrap();
+            versions.insert(
+                PypiVersion::Version {
+                    package_allows_prerelease: version.any_prerelease(),
+                    version,
+                },
+                artifacts,
+            );
+        };
+        add(
+            "1.0",
+            vec![
+                artifact(&name, "foo-1.0-py3-none-any.whl", None, Some("broken")),
+                artifact(&name, "foo-1.0.tar.gz", None, None),
+            ],
+        );
+        add(
+            "1.1",
+            vec![
+                artifact(&name, "foo-1.1-py3-none-any.whl", Some(">=99"), None),
+                artifact(&name, "foo-1.1-cp27-cp27m-win32.whl", None, None),
+                artifact(&name, "foo-1.1.tar.bz2", None, None),
+            ],
+        );
+        add(
+            "2.0a1",
+            vec![artifact(&name, "foo-2.0a1-py3-none-any.whl", None, None)],
+        );
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let skipped = SkippedArtifacts::default();
+        let provider = provider(
+            cache_dir.path(),
+            &name,
+            versions,
+            HashMap::default(),
+            ResolveOptions {
+                sdist_resolution,
+                pre_release_resolution: PreReleaseResolution::Disallow,
+                skipped_artifacts: Some(skipped.clone()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let name_id = provider
+            .pool
+            .intern_package_name(PypiPackageName::Base(name.clone()));
+        (&provider).get_candidates(name_id).unwrap();
+
+        let mut by_package = skipped.by_package();
+        assert_eq!(by_package.len(), 1);
+        by_package
+            .remove(&name)
+            .unwrap()
+            .into_iter()
+            .map(|(artifact, reason)| (artifact.to_string(), reason))
+            .sorted_by(|a, b| a.0.cmp(&b.0))
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_skipped_artifacts() {
+        assert_eq!(
+            skipped_artifacts(SDistResolution::Normal).await,
+            vec![
+                (
+                    "foo-1.0-py3-none-any.whl".to_string(),
+                    SkipReason::Yanked(Some("broken".to_string()))
+                ),
+                (
+                    "foo-1.1-cp27-cp27m-win32.whl".to_string(),
+                    SkipReason::IncompatiblePlatform
+                ),
+                (
+                    "foo-1.1-py3-none-any.whl".to_string(),
+                    SkipReason::RequiresPython(">=99".parse().unwrap())
+                ),
+                ("foo-1.1.tar.bz2".to_string(), SkipReason::UnsupportedFormat),
+                (
+                    "foo-2.0a1-py3-none-any.whl".to_string(),
+                    SkipReason::PreRelease
+                ),
+            ]
+        );
+
+        let only_wheels = skipped_artifacts(SDistResolution::OnlyWheels).await;
+        assert!(only_wheels.contains(&("foo-1.0.tar.gz".to_string(), SkipReason::NoWheel)));
+        assert!(only_wheels.contains(&("foo-1.1.tar.bz2".to_string(), SkipReason::NoWheel)));
+
+        let only_sdists = skipped_artifacts(SDistResolution::OnlySDists).await;
+        assert!(only_sdists.contains(&(
+            "foo-1.1-cp27-cp27m-win32.whl".to_string(),
+            SkipReason::NoSDist
+        )));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolution_summary() {
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let mut versions = IndexMap::new();
+        for (version, artifacts) in [
+            (
+                "1.0",
+                vec![
+                    artifact(&name, "foo-1.0-py3-none-any.whl", None, Some("broken")),
+                    artifact(&name, "foo-1.0.tar.gz", None, None),
+                ],
+            ),
+            (
+                "1.1",
+                vec![
+                    artifact(&name, "foo-1.1-py3-none-any.whl", Some(">=99"), None),
+                    artifact(&name, "foo-1.1-cp27-cp27m-win32.whl", None, None),
+                    artifact(&name, "foo-1.1.tar.bz2", None, None),
+                ],
+            ),
+            (
+                "1.2",
+                vec![
+                    artifact(&name, "foo-1.2-py3-none-any.whl", None, None),
+                    artifact(&name, "foo-1.2.tar.gz", None, None),
+                ],
+            ),
+            (
+                "2.0a1",
+                vec![artifact(&name, "foo-2.0a1-py3-none-any.whl", None, None)],
+            ),
+        ] {
+            let version: Version = version.parse().unwrap();
+            versions.insert(
+                PypiVersion::Version {
+                    package_allows_prerelease: version.any_prerelease(),
+                    version,
+                },
+                artifacts,
+            );
+        }
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let summary = ResolutionSummary::default();
+        let provider = provider(
+            cache_dir.path(),
+            &name,
+            versions,
+            HashMap::default(),
+            ResolveOptions {
+                pre_release_resolution: PreReleaseResolution::Disallow,
+                summary: Some(summary.clone()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        // The candidates of an extra are the same artifacts, they are only counted once
+        for package_name in [
+            PypiPackageName::Base(name.clone()),
+            PypiPackageName::Extra(name.clone(), "test".parse().unwrap()),
+        ] {
+            let name_id = provider.pool.intern_package_name(package_name);
+            (&provider).get_candidates(name_id).unwrap();
+        }
+
+        assert_eq!(
+            summary.counts(),
+            SummaryCounts {
+                wheels_considered: 1,
+                sdists_considered: 2,
+                skipped: BTreeMap::from([
+                    ("incompatible_platform", 1),
+                    ("prerelease", 1),
+                    ("requires_python", 1),
+                    ("unsupported_format", 1),
+                    ("yanked", 1),
+                ]),
+                versions_fetched: 0,
+            }
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_only_newest() {
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let mut versions = IndexMap::new();
+        for (version, yanked) in [
+            ("1.0", None),
+            ("1.1", None),
+            ("1.2", None),
+            ("2.0", Some("broken")),
+            ("2.1", None),
+            ("3.0b1", None),
+        ] {
+            let filename = format!("foo-{version}-py3-none-any.whl");
+            let version: Version = version.parse().unwrap();
+            versions.insert(
+                PypiVersion::Version {
+                    package_allows_prerelease: false,
+                    version,
+                },
+                vec![artifact(&name, &filename, None, yanked)],
+            );
+        }
+
+        let interned_versions = |provider: PypiDependencyProvider| {
+            let name_id = provider
+                .pool
+                .intern_package_name(PypiPackageName::Base(name.clone()));
+            (&provider)
+                .get_candidates(name_id)
+                .unwrap()
+                .candidates
+                .into_iter()
+                .map(|id| provider.pool.resolve_solvable(id).inner().to_string())
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+
+        // The yanked version and the pre-release do not count towards the limit
+        let options = ResolveOptions {
+            only_newest: Some(2),
+            pre_release_resolution: PreReleaseResolution::Disallow,
+            ..Default::default()
+        };
+        let cache_dir = tempfile::tempdir().unwrap();
+        let newest = provider(
+            cache_dir.path(),
+            &name,
+            versions.clone(),
+            HashMap::default(),
+            options.clone(),
+        )
+        .await;
+        assert_eq!(interned_versions(newest), vec!["1.2", "2.1"]);
+
+        // Explicitly pinned versions are always considered
+        let pinned = provider(
+            cache_dir.path(),
+            &name,
+            versions.clone(),
+            HashMap::from([(name.clone(), vec!["==1.0".parse().unwrap()])]),
+            options,
+        )
+        .await;
+        assert_eq!(interned_versions(pinned), vec!["1.0", "1.2", "2.1"]);
+
+        let all = provider(
+            cache_dir.path(),
+            &name,
+            versions,
+            HashMap::default(),
+            Default::default(),
+        )
+        .await;
+        assert_eq!(interned_versions(all).len(), 6);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_python_range() {
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let mut versions = IndexMap::new();
+        for (version, requires_python) in [("1.0", ">=3.8"), ("2.0", ">=3.12"), ("3.0", "<3.12")] {
+            let filename = format!("foo-{version}-py3-none-any.whl");
+            versions.insert(
+                PypiVersion::Version {
+                    version: version.parse().unwrap(),
+                    package_allows_prerelease: false,
+                },
+                vec![artifact(&name, &filename, Some(requires_python), None)],
+            );
+        }
+
+        // Only the versions that support all Python versions in the range are candidates
+        let cache_dir = tempfile::tempdir().unwrap();
+        let provider = provider(
+            cache_dir.path(),
+            &name,
+            versions,
+            HashMap::default(),
+            ResolveOptions {
+                python_range: Some("3.9..3.12".parse().unwrap()),
+                ..Default::default()
+            },
+        )
+        .await;
+        let name_id = provider
+            .pool
+            .intern_package_name(PypiPackageName::Base(name.clone()));
+        let candidates = (&provider).get_candidates(name_id).unwrap();
+        let excluded = candidates
+            .excluded
+            .iter()
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        let selectable = candidates
+            .candidates
+            .into_iter()
+            .filter(|id| !excluded.contains(id))
+            .map(|id| provider.pool.resolve_solvable(id).inner().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(selectable, vec!["1.0"]);
+
+        // Requirements are added if they apply to any of the Python versions
+        let metadata = "Metadata-Version: 2.1\nName: bar\nVersion: 1.0\n\
+            Requires-Dist: tomli; python_version < '3.11'\n\
+            Requires-Dist: exceptiongroup; python_version < '3.9'\n\
+            Requires-Dist: typing-extensions; python_version >= '3.12'\n";
+        let mut bar = artifact(
+            &"bar".parse().unwrap(),
+            "bar-1.0-py3-none-any.whl",
+            None,
+            None,
+        );
+        Arc::get_mut(&mut bar).unwrap().hashes = Some(ArtifactHashes {
+            sha256: Some(
+                rattler_digest::compute_bytes_digest::<rattler_digest::Sha256>(b"bar-1.0"),
+            ),
+            ..Default::default()
+        });
+        provider
+            .package_db
+            .put_metadata_in_cache(&bar, metadata.as_bytes())
+            .await
+            .unwrap();
+        provider.package_db.insert_artifacts(
+            "bar".parse().unwrap(),
+            IndexMap::from([(
+                PypiVersion::Version {
+                    version: "1.0".parse().unwrap(),
+                    package_allows_prerelease: false,
+                },
+                vec![bar],
+            )]),
+        );
+        let bar_id = provider
+            .pool
+            .intern_package_name(PypiPackageName::Base("bar".parse().unwrap()));
+        let solvable = (&provider).get_candidates(bar_id).unwrap().candidates[0];
+        let Dependencies::Known(dependencies) = (&provider).get_dependencies(solvable) else {
+            panic!("the dependencies of bar are known");
+        };
+        let dependency_names = dependencies
+            .requirements
+            .iter()
+            .map(|&id| {
+                let name_id = provider.pool.resolve_version_set_package_name(id);
+                provider.pool.resolve_package_name(name_id).to_string()
+            })
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(dependency_names, vec!["tomli", "typing-extensions"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_interpreter_constraints() {
+        let metadata = "Metadata-Version: 2.1\nName: bar\nVersion: 1.0\n\
+            Requires-Dist: attrs\n\
+            Requires-Dist: cffi; platform_python_implementation == 'CPython'\n\
+            Requires-Dist: tomli; python_version < '3.11'\n";
+        let mut bar = artifact(
+            &"bar".parse().unwrap(),
+            "bar-1.0-py3-none-any.whl",
+            Some(">=3.9"),
+            None,
+        );
+        Arc::get_mut(&mut bar).unwrap().hashes = Some(ArtifactHashes {
+            sha256: Some(
+                rattler_digest::compute_bytes_digest::<rattler_digest::Sha256>(b"bar-1.0"),
+            ),
+            ..Default::default()
+        });
+
+        let dependency_names = |interpreters: &'static [&'static str]| {
+            let bar = bar.clone();
+            async move {
+                let cache_dir = tempfile::tempdir().unwrap();
+                let name: NormalizedPackageName = "bar".parse().unwrap();
+                let provider = provider(
+                    cache_dir.path(),
+                    &name,
+                    IndexMap::from([(
+                        PypiVersion::Version {
+                            version: "1.0".parse().unwrap(),
+                            package_allows_prerelease: false,
+                        },
+                        vec![bar.clone()],
+                    )]),
+                    HashMap::default(),
+                    ResolveOptions {
+                        interpreter_constraints: interpreters
+                            .iter()
+                            .map(|i| i.parse().unwrap())
+                            .collect(),
+                        ..Default::default()
+                    },
+                )
+                .await;
+                provider
+                    .package_db
+                    .put_metadata_in_cache(&bar, metadata.as_bytes())
+                    .await
+                    .unwrap();
+                let bar_id = provider
+                    .pool
+                    .intern_package_name(PypiPackageName::Base(name));
+                let candidates = (&provider).get_candidates(bar_id).unwrap();
+                if !candidates.excluded.is_empty() {
+                    return None;
+                }
+                let Dependencies::Known(dependencies) =
+                    (&provider).get_dependencies(candidates.candidates[0])
+                else {
+                    panic!("the dependencies of bar are known");
+                };
+                Some(
+                    dependencies
+                        .requirements
+                        .iter()
+                        .map(|&id| {
+                            let name_id = provider.pool.resolve_version_set_package_name(id);
+                            provider.pool.resolve_package_name(name_id).to_string()
+                        })
+                        .sorted()
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+
+        // The markers are evaluated for every interpreter
+        assert_eq!(
+            dependency_names(&["cpython3.9", "cpython3.10"]).await,
+            Some(vec![
+                String::from("attrs"),
+                String::from("cffi"),
+                String::from("tomli")
+            ])
+        );
+
+        // The CPython-only dependency is excluded if PyPy is among the interpreters
+        assert_eq!(
+            dependency_names(&["cpython3.9", "cpython3.12", "pypy3.10"]).await,
+            Some(vec![String::from("attrs")])
+        );
+
+        // The package itself must support all interpreters
+        assert_eq!(dependency_names(&["cpython3.8", "pypy3.10"]).await, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_build_tags() {
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let find_links = tempfile::tempdir().unwrap();
+        for filename in [
+            "foo-1.0-1-py3-none-any.whl",
+            "foo-1.0-2-py3-none-any.whl",
+            "foo-1.0.0-py3-none-any.whl",
+            "foo-1.0-1-cp311-cp311-manylinux_2_17_x86_64.whl",
+            "foo-2.0-py3-none-any.whl",
+        ] {
+            fs_err::write(find_links.path().join(filename), "").unwrap();
+        }
+
+        // Rebuilds of a version are grouped together with the other artifacts of that version
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = PackageDb::new(Client::new().into(), &[], cache_dir.path())
+            .unwrap()
+            .with_find_links([find_links.path().to_path_buf()]);
+        let versions = package_db.available_artifacts(name.clone()).await.unwrap();
+        assert_eq!(versions.len(), 2);
+
+        let provider = provider(
+            cache_dir.path(),
+            &name,
+            versions.as_ref().clone(),
+            HashMap::default(),
+            ResolveOptions::default(),
+        )
+        .await;
+        let name_id = provider
+            .pool
+            .intern_package_name(PypiPackageName::Base(name.clone()));
+        let candidates = (&provider).get_candidates(name_id).unwrap().candidates;
+        let versions = candidates
+            .iter()
+            .map(|&id| provider.pool.resolve_solvable(id).inner().to_string())
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(versions, ["1.0", "2.0"]);
+
+        // The most compatible wheel comes first, the highest build tag breaks ties
+        let solvable = candidates
+            .into_iter()
+            .find(|&id| provider.pool.resolve_solvable(id).inner().to_string() == "1.0")
+            .unwrap();
+        let artifacts = provider
+            .cached_artifacts
+            .get(&solvable)
+            .unwrap()
+            .iter()
+            .map(|a| a.filename.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            artifacts,
+            [
+                "foo-1.0-1-cp311-cp311-manylinux_2_17_x86_64.whl",
+                "foo-1.0-2-py3-none-any.whl",
+                "foo-1.0-1-py3-none-any.whl",
+                "foo-1.0.0-py3-none-any.whl",
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pure_python_only() {
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let mut versions = IndexMap::new();
+        for (version, filenames) in [
+            (
+                "1.0",
+                &[
+                    "foo-1.0-cp311-cp311-manylinux_2_17_x86_64.whl",
+                    "foo-1.0-py3-none-any.whl",
+                ][..],
+            ),
+            (
+                "2.0",
+                &[
+                    "foo-2.0-cp311-cp311-manylinux_2_17_x86_64.whl",
+                    "foo-2.0.tar.gz",
+                ][..],
+            ),
+            (
+                "3.0",
+                &["foo-3.0-cp311-cp311-manylinux_2_17_x86_64.whl"][..],
+            ),
+        ] {
+            versions.insert(
+                PypiVersion::Version {
+                    package_allows_prerelease: false,
+                    version: version.parse().unwrap(),
+                },
+                filenames
+                    .iter()
+                    .map(|filename| artifact(&name, filename, None, None))
+                    .collect(),
+            );
+        }
+
+        let candidates = |pure_python_only: bool, sdist_resolution: SDistResolution| {
+            let versions = versions.clone();
+            let name = name.clone();
+            async move {
+                let cache_dir = tempfile::tempdir().unwrap();
+                let skipped = SkippedArtifacts::default();
+                let provider = provider(
+                    cache_dir.path(),
+                    &name,
+                    versions,
+                    HashMap::default(),
+                    ResolveOptions {
+                        pure_python_only,
+                        sdist_resolution,
+                        skipped_artifacts: Some(skipped.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await;
+                let name_id = provider
+                    .pool
+                    .intern_package_name(PypiPackageName::Base(name.clone()));
+                let candidates = (&provider)
+                    .get_candidates(name_id)
+                    .unwrap()
+                    .candidates
+                    .into_iter()
+                    // Versions without usable artifacts are excluded
+                    .filter_map(|id| {
+                        let artifacts = provider
+                            .cached_artifacts
+                            .get(&id)?
+                            .iter()
+                            .map(|a| a.filename.to_string())
+                            .collect::<Vec<_>>();
+                        Some((
+                            provider.pool.resolve_solvable(id).inner().to_string(),
+                            artifacts,
+                        ))
+                    })
+                    .sorted()
+                    .collect::<Vec<_>>();
+                let not_pure = skipped
+                    .by_package()
+                    .remove(&name)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|(_, reason)| reason == &SkipReason::NotPurePython)
+                    .count();
+                (candidates, not_pure)
+            }
+        };
+
+        // Compatible platform specific wheels are used by default
+        let (all, not_pure) = candidates(false, SDistResolution::Normal).await;
+        assert_eq!(
+            all.iter().map(|(v, _)| v.as_str()).collect::<Vec<_>>(),
+            ["1.0", "2.0", "3.0"]
+        );
+        assert_eq!(not_pure, 0);
+
+        // Platform specific wheels are excluded, 2.0 falls back to its sdist and 3.0 is skipped
+        let (pure, not_pure) = candidates(true, SDistResolution::Normal).await;
+        assert_eq!(
+            pure,
+            [
+                (
+                    "1.0".to_string(),
+                    vec!["foo-1.0-py3-none-any.whl".to_string()]
+                ),
+                ("2.0".to_string(), vec!["foo-2.0.tar.gz".to_string()]),
+            ]
+        );
+        assert_eq!(not_pure, 3);
+
+        // Without sdists only the version with a pure Python wheel remains
+        let (pure_wheels, _) = candidates(true, SDistResolution::OnlyWheels).await;
+        assert_eq!(
+            pure_wheels
+                .iter()
+                .map(|(v, _)| v.as_str())
+                .collect::<Vec<_>>(),
+            ["1.0"]
+        );
+    }
+
+    /// Returns the error that is reported for a package with the given `Requires-Dist`, if any.
+    async fn dependencies_with_requirement(
+        requires_dist: &str,
+        options: ResolveOptions,
+    ) -> Option<MetadataError> {
+        let name: NormalizedPackageName = "bar".parse().unwrap();
+        let mut wheel = artifact(&name, "bar-1.0-py3-none-any.whl", None, None);
+        Arc::get_mut(&mut wheel).unwrap().hashes = Some(ArtifactHashes {
+            sha256: Some(
+                rattler_digest::compute_bytes_digest::<rattler_digest::Sha256>(b"bar-1.0"),
+            ),
+            ..Default::default()
+        });
+        let versions = IndexMap::from([(
+            PypiVersion::Version {
+                version: "1.0".parse().unwrap(),
+                package_allows_prerelease: false,
+            },
+            vec![wheel.clone()],
+        )]);
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let provider = provider(
+            cache_dir.path(),
+            &name,
+            versions,
+            HashMap::default(),
+            options,
+        )
+        .await;
+        let metadata = format!(
+            "Metadata-Version: 2.1\nName: bar\nVersion: 1.0\nRequires-Dist: {requires_dist}\n"
+        );
+        provider
+            .package_db
+            .put_metadata_in_cache(&wheel, metadata.as_bytes())
+            .await
+            .unwrap();
+
+        let name_id = provider
+            .pool
+            .intern_package_name(PypiPackageName::Base(name.clone()));
+        let candidates = (&provider).get_candidates(name_id).unwrap();
+        let dependencies = (&provider).get_dependencies(candidates.candidates[0]);
+        let error = provider.should_cancel_with_value.lock().take();
+        assert_eq!(
+            error.is_none(),
+            matches!(dependencies, Dependencies::Known(_))
+        );
+        error
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_strict_markers() {
+        const BOGUS_MARKER: &str = "foo ; python_verison >= \"3.8\"";
+        assert!(
+            dependencies_with_requirement(BOGUS_MARKER, Default::default())
+                .await
+                .is_none()
+        );
+
+        let expected = "the requirement 'foo ; python_verison >= \"3.8\"' of bar=1.0 uses the unknown environment marker variable 'python_verison'";
+        for options in [
+            ResolveOptions {
+                strict_markers: true,
+                ..Default::default()
+            },
+            ResolveOptions {
+                strict: true,
+                ..Default::default()
+            },
+        ] {
+            let error = dependencies_with_requirement(BOGUS_MARKER, options)
+                .await
+                .unwrap();
+            assert_eq!(error.to_string(), expected);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_strict_invalid_requirement() {
+        const INVALID: &str = "foo (>=1.0";
+        let strict = || ResolveOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(dependencies_with_requirement(INVALID, Default::default())
+            .await
+            .is_none());
+        assert!(dependencies_with_requirement("foo>=1.0", strict())
+            .await
+            .is_none());
+
+        let error = dependencies_with_requirement(INVALID, strict())
+            .await
+            .unwrap();
+        assert!(matches!(error, MetadataError::InvalidRequirement { .. }));
+        assert_eq!(
+            error.to_string(),
+            "the requirement 'foo (>=1.0' of bar=1.0 cannot be parsed"
+        );
+
+        // Strict marker checking alone ignores requirements that cannot be parsed
+        let options = ResolveOptions {
+            strict_markers: true,
+            ..Default::default()
+        };
+        assert!(dependencies_with_requirement(INVALID, options)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_prerelease_skips() {
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let versions = ["1.0", "2.0a1"]
+            .into_iter()
+            .map(|version| {
+                let filename = format!("foo-{version}-py3-none-any.whl");
+                (
+                    PypiVersion::Version {
+                        version: version.parse().unwrap(),
+                        package_allows_prerelease: false,
+                    },
+                    vec![artifact(&name, &filename, None, None)],
+                )
+            })
+            .collect::<IndexMap<_, _>>();
+
+        // Returns the selectable candidates and the skipped artifacts
+        let candidates = |pre_release_resolution| {
+            let versions = versions.clone();
+            let name = name.clone();
+            async move {
+                let cache_dir = tempfile::tempdir().unwrap();
+                let skipped = SkippedArtifacts::default();
+                let provider = provider(
+                    cache_dir.path(),
+                    &name,
+                    versions,
+                    HashMap::default(),
+                    ResolveOptions {
+                        pre_release_resolution,
+                        skipped_artifacts: Some(skipped.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await;
+                let name_id = provider
+                    .pool
+                    .intern_package_name(PypiPackageName::Base(name.clone()));
+                let candidates = (&provider).get_candidates(name_id).unwrap();
+                let excluded = candidates
+                    .excluded
+                    .iter()
+                    .map(|(id, _)| *id)
+                    .collect::<Vec<_>>();
+                let selectable = candidates
+                    .candidates
+                    .into_iter()
+                    .filter(|id| !excluded.contains(id))
+                    .map(|id| provider.pool.resolve_solvable(id).inner().to_string())
+                    .collect::<Vec<_>>();
+                let skipped = skipped
+                    .by_package()
+                    .into_values()
+                    .flatten()
+                    .map(|(artifact, reason)| (artifact.to_string(), reason))
+                    .collect::<Vec<_>>();
+                (selectable, skipped)
+            }
+        };
+
+        // A requirement can still ask for the pre-release, so it is not skipped
+        let (selectable, skipped) = candidates(PreReleaseResolution::default()).await;
+        assert_eq!(selectable, vec!["1.0", "2.0a1"]);
+        assert_eq!(skipped, vec![]);
+
+        // If pre-releases are disallowed it can never be selected
+        let (selectable, skipped) = candidates(PreReleaseResolution::Disallow).await;
+        assert_eq!(selectable, vec!["1.0"]);
+        assert_eq!(
+            skipped,
+            vec![(
+                "foo-2.0a1-py3-none-any.whl".to_string(),
+                SkipReason::PreRelease
+            )]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_yanked_reason() {
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let versions = [("1.0", Some("security")), ("2.0", Some("")), ("3.0", None)]
+            .into_iter()
+            .map(|(version, yanked)| {
+                (
+                    PypiVersion::Version {
+                        version: version.parse().unwrap(),
+                        package_allows_prerelease: false,
+                    },
+                    vec![artifact(
+                        &name,
+                        &format!("foo-{version}-py3-none-any.whl"),
+                        None,
+                        yanked,
+                    )],
+                )
+            })
+            .collect();
+
+        // The reason of the index explains why a yanked version cannot be selected
+        let cache_dir = tempfile::tempdir().unwrap();
+        let provider = provider(
+            cache_dir.path(),
+            &name,
+            versions,
+            HashMap::default(),
+            ResolveOptions::default(),
+        )
+        .await;
+        let name_id = provider
+            .pool
+            .intern_package_name(PypiPackageName::Base(name.clone()));
+        let candidates = (&provider).get_candidates(name_id).unwrap();
+        let excluded = candidates
+            .excluded
+            .iter()
+            .map(|(id, reason)| {
+                (
+                    provider.pool.resolve_solvable(*id).inner().to_string(),
+                    provider.pool.resolve_string(*reason).to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            excluded,
+            vec![
+                ("1.0".to_string(), "it is yanked (security)".to_string()),
+                ("2.0".to_string(), YANKED_EXCLUDED.to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_requires_python() {
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let mut versions = IndexMap::new();
+        for (version, artifacts) in [
+            ("1.0", vec![("foo-1.0-py3-none-any.whl", None)]),
+            ("2.0", vec![("foo-2.0-py3-none-any.whl", Some(">=99"))]),
+            (
+                "3.0",
+                vec![
+                    ("foo-3.0-py3-none-any.whl", Some(">=99")),
+                    ("foo-3.0.tar.gz", None),
+                ],
+            ),
+        ] {
+            versions.insert(
+                PypiVersion::Version {
+                    version: version.parse().unwrap(),
+                    package_allows_prerelease: false,
+                },
+                artifacts
+                    .into_iter()
+                    .map(|(filename, requires_python)| {
+                        artifact(&name, filename, requires_python, None)
+                    })
+                    .collect(),
+            );
+        }
+
+        // Artifacts that do not support the interpreter are never used, even if skipped artifacts
+        // are not recorded
+        let cache_dir = tempfile::tempdir().unwrap();
+        let provider = provider(
+            cache_dir.path(),
+            &name,
+            versions,
+            HashMap::default(),
+            ResolveOptions::default(),
+        )
+        .await;
+        let name_id = provider
+            .pool
+            .intern_package_name(PypiPackageName::Base(name.clone()));
+        let candidates = (&provider).get_candidates(name_id).unwrap();
+        let excluded = candidates
+            .excluded
+            .iter()
+            .map(|(id, reason)| {
+                (
+                    provider.pool.resolve_solvable(*id).inner().to_string(),
+                    provider.pool.resolve_string(*reason).to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            excluded,
+            vec![("2.0".to_string(), REQUIRES_PYTHON_EXCLUDED.to_string())]
+        );
+
+        // Of the version that is still a candidate only the supported artifacts are used
+        let solvable_3 = candidates
+            .candidates
+            .into_iter()
+            .find(|id| provider.pool.resolve_solvable(*id).inner().to_string() == "3.0")
+            .unwrap();
+        let artifacts = provider
+            .cached_artifacts
+            .get(&solvable_3)
+            .unwrap()
+            .iter()
+            .map(|a| a.filename.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(artifacts, vec!["foo-3.0.tar.gz"]);
+    }
+}
@@ -0,0 +1,135 @@
+use super::dependency_provider::MetadataError;
+use super::{PypiVersion, PypiVersionSet};
+use crate::types::{Extra, NormalizedPackageName};
+use miette::Diagnostic;
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+/// An error that can occur when resolving a set of requirements with [`super::resolve`].
+///
+/// The [`Display`] implementation of each variant is meant to be shown to users, the fields allow
+/// handling each case programmatically.
+#[derive(Debug, Error, Diagnostic)]
+pub enum ResolutionError {
+    /// None of the available versions of a package satisfy a requirement. This is also the case
+    /// if the package does not exist at all.
+    #[error("{message}")]
+    NoCandidates {
+        /// The package for which no matching versions are available
+        name: NormalizedPackageName,
+
+        /// The requirement that could not be satisfied
+        requirement: Box<ConflictingRequirement>,
+
+        /// A user friendly explanation of the problem
+        message: String,
+    },
+
+    /// There are versions available for all requirements but they cannot be combined.
+    #[error("{message}")]
+    Conflict {
+        /// The requirements that are involved in the conflict. Requirements of the user come
+        /// first, the others are sorted by the package that has the requirement.
+        requirements: Vec<ConflictingRequirement>,
+
+        /// A user friendly explanation of the problem
+        message: String,
+    },
+
+    /// The available artifacts of a package could not be fetched from the index. Retrying might
+    /// help if this was caused by a network issue.
+    #[error("failed to fetch the available artifacts of '{name}': {error}")]
+    FetchFailed {
+        /// The package that could not be fetched
+        name: NormalizedPackageName,
+
+        /// The reason why fetching failed
+        error: miette::Report,
+    },
+
+    /// A newer version of a package was excluded because none of its artifacts are compatible
+    /// with the target interpreter or platform. Only reported if [`super::ResolveOptions::strict`]
+    /// is set, otherwise the older version is selected.
+    #[error(
+        "{name} {selected} was selected because the newer version {newer} is excluded: {reason}"
+    )]
+    #[diagnostic(help(
+        "older versions are only selected silently if strict mode is disabled, require {name}<={selected} to select it deliberately"
+    ))]
+    IncompatibleNewerVersion {
+        /// The package
+        name: NormalizedPackageName,
+
+        /// The version that was selected
+        selected: PypiVersion,
+
+        /// The newest version that was excluded
+        newer: PypiVersion,
+
+        /// Why the newer version was excluded
+        reason: String,
+    },
+
+    /// A package that is blocked by [`super::ResolveOptions::blocked_packages`] would be pulled
+    /// into the resolution.
+    #[error("{message}")]
+    BlockedPackage {
+        /// The blocked package
+        name: NormalizedPackageName,
+
+        /// The packages through which the blocked package is required, starting with the package
+        /// that requires it and ending with a package that was requested by the user. Empty if
+        /// the user requested the blocked package directly.
+        chain: Vec<(NormalizedPackageName, PypiVersion)>,
+
+        /// A user friendly explanation of the problem
+        message: String,
+    },
+
+    /// The metadata of a package could not be determined.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Metadata(#[from] MetadataError),
+
+    /// Any other error, e.g. the build environment of source distributions could not be set up.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Other(Box<dyn Diagnostic + Send + Sync>),
+}
+
+impl From<miette::Report> for ResolutionError {
+    fn from(report: miette::Report) -> Self {
+        ResolutionError::Other(report.into())
+    }
+}
+
+/// A requirement that is involved in a resolution failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictingRequirement {
+    /// The package (and version) that has the requirement, or `None` if the requirement was
+    /// requested by the user.
+    pub required_by: Option<(NormalizedPackageName, PypiVersion)>,
+
+    /// The name of the required package
+    pub name: NormalizedPackageName,
+
+    /// The extra of the required package, if any
+    pub extra: Option<Extra>,
+
+    /// The versions of the package that satisfy the requirement
+    pub specifiers: PypiVersionSet,
+}
+
+impl Display for ConflictingRequirement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.required_by {
+            Some((name, version)) => write!(f, "{name}={version} requires ")?,
+            None => write!(f, "the user requested ")?,
+        }
+        write!(f, "{}", self.name)?;
+        if let Some(extra) = &self.extra {
+            write!(f, "[{}]", extra.as_str())?;
+        }
+        write!(f, " {}", self.specifiers)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::types::{NormalizedPackageName, PackageName};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A dependency of a package that is ignored during resolution, written as `package: dependency`.
+///
+/// When passed in [`super::ResolveOptions::ignored_dependencies`] the requirements of `package` on
+/// `dependency` are dropped from its metadata. This is an escape hatch for packages whose
+/// metadata over-constrains a dependency by mistake.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IgnoredDependency {
+    /// The package whose requirement is ignored
+    pub package: NormalizedPackageName,
+
+    /// The package that is no longer required by [`Self::package`]
+    pub dependency: NormalizedPackageName,
+}
+
+/// An error that can occur when parsing an [`IgnoredDependency`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ParseIgnoredDependencyError {
+    /// The dependency is not of the form `package: dependency`
+    #[error("expected a dependency of the form 'package: dependency' but got '{0}'")]
+    InvalidFormat(String),
+
+    /// One of the names is not a valid package name
+    #[error("invalid package name '{0}'")]
+    InvalidPackageName(String),
+}
+
+impl FromStr for IgnoredDependency {
+    type Err = ParseIgnoredDependencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((package, dependency)) = s.split_once(':') else {
+            return Err(ParseIgnoredDependencyError::InvalidFormat(s.to_owned()));
+        };
+        let parse_name = |name: &str| {
+            PackageName::from_str(name.trim())
+                .map(NormalizedPackageName::from)
+                .map_err(|_| {
+                    ParseIgnoredDependencyError::InvalidPackageName(name.trim().to_owned())
+                })
+        };
+        Ok(Self {
+            package: parse_name(package)?,
+            dependency: parse_name(dependency)?,
+        })
+    }
+}
+
+impl Display for IgnoredDependency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.package, self.dependency)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_ignored_dependency() {
+        let ignored = IgnoredDependency::from_str("Foo_Bar: baz").unwrap();
+        assert_eq!(ignored.package.as_str(), "foo-bar");
+        assert_eq!(ignored.dependency.as_str(), "baz");
+        assert_eq!(ignored.to_string(), "foo-bar: baz");
+
+        assert_eq!(
+            IgnoredDependency::from_str("foo"),
+            Err(ParseIgnoredDependencyError::InvalidFormat(String::from(
+                "foo"
+            )))
+        );
+        assert_eq!(
+            IgnoredDependency::from_str("foo: ba r"),
+            Err(ParseIgnoredDependencyError::InvalidPackageName(
+                String::from("ba r")
+            ))
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use super::{PinnedPackage, PypiVersion};
+use crate::types::{ArtifactInfo, ArtifactName, NormalizedPackageName};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+/// A single step that has to be performed to install the result of a resolution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallAction {
+    /// Download an artifact from its url.
+    Download {
+        /// The name of the package the artifact belongs to
+        name: NormalizedPackageName,
+
+        /// The artifact to download, [`ArtifactInfo::url`] is the location to download it from.
+        artifact: Arc<ArtifactInfo>,
+    },
+
+    /// Build a wheel from a previously downloaded source distribution.
+    Build {
+        /// The name of the package that is built
+        name: NormalizedPackageName,
+
+        /// The source distribution to build the wheel from
+        sdist: Arc<ArtifactInfo>,
+    },
+
+    /// Install a wheel into the environment. If the package has no wheel available this is
+    /// preceded by a [`InstallAction::Build`] step and `artifact` refers to the source
+    /// distribution from which the wheel is built.
+    Install {
+        /// The name of the package to install
+        name: NormalizedPackageName,
+
+        /// The version of the package to install
+        version: PypiVersion,
+
+        /// The artifact that is installed
+        artifact: Arc<ArtifactInfo>,
+    },
+}
+
+impl InstallAction {
+    /// Returns the name of the package this action applies to.
+    pub fn name(&self) -> &NormalizedPackageName {
+        match self {
+            InstallAction::Download { name, .. }
+            | InstallAction::Build { name, .. }
+            | InstallAction::Install { name, .. } => name,
+        }
+    }
+}
+
+/// Turns the result of [`super::resolve`] into an ordered list of [`InstallAction`]s.
+///
+/// Packages are ordered topologically so that every package is installed after the packages it
+/// depends on. Packages that are independent of each other are ordered by name which makes the
+/// plan deterministic. If the dependencies contain a cycle, the cycle is broken by picking the
+/// package with the lowest name first.
+///
+/// For each package a wheel is preferred, the artifacts of a [`PinnedPackage`] are already ordered
+/// by compatibility. If there is no wheel the first source distribution is downloaded and built
+/// instead. Packages without any artifacts (e.g. because they were locked) are skipped.
+pub fn install_plan(packages: &[PinnedPackage]) -> Vec<InstallAction> {
+    let by_name: HashMap<&NormalizedPackageName, &PinnedPackage> =
+        packages.iter().map(|p| (&p.name, p)).collect();
+
+    // The number of dependencies of each package that have not been scheduled yet, and the reverse
+    // edges of the dependency graph. Dependencies outside of the solution are ignored.
+    let mut pending: HashMap<&NormalizedPackageName, usize> = HashMap::new();
+    let mut dependents: HashMap<&NormalizedPackageName, Vec<&NormalizedPackageName>> =
+        HashMap::new();
+    for package in packages {
+        let dependencies = package
+            .dependencies
+            .iter()
+            .filter_map(|name| by_name.get_key_value(name).map(|(name, _)| *name))
+            .filter(|&name| name != &package.name);
+        let mut count = 0;
+        for dependency in dependencies {
+            dependents
+                .entry(dependency)
+                .or_default()
+                .push(&package.name);
+            count += 1;
+        }
+        pending.insert(&package.name, count);
+    }
+
+    let mut ready: BTreeSet<&NormalizedPackageName> = pending
+        .iter()
+        .filter(|(_, &count)| count == 0)
+        .map(|(&name, _)| name)
+        .collect();
+    let mut ordered = Vec::with_capacity(packages.len());
+    while ordered.len() < packages.len() {
+        let next = match ready.pop_first() {
+            Some(name) => name,
+            // Everything that is left is part of a cycle
+            None => pending
+                .keys()
+                .copied()
+                .min()
+                .expect("there are packages left"),
+        };
+        pending.remove(next);
+        ordered.push(by_name[next]);
+
+        for dependent in dependents.get(next).into_iter().flatten() {
+            if let Some(count) = pending.get_mut(dependent) {
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+    }
+
+    ordered.into_iter().flat_map(package_actions).collect()
+}
+
+/// Returns the actions required to install a single package.
+fn package_actions(package: &PinnedPackage) -> Vec<InstallAction> {
+    let wheel = package
+        .artifacts
+        .iter()
+        .find(|a| matches!(a.filename, ArtifactName::Wheel(_)));
+    if let Some(wheel) = wheel {
+        return vec![
+            InstallAction::Download {
+                name: package.name.clone(),
+                artifact: wheel.clone(),
+            },
+            InstallAction::Install {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                artifact: wheel.clone(),
+            },
+        ];
+    }
+
+    let Some(sdist) = package.artifacts.first() else {
+        return Vec::new();
+    };
+    vec![
+        InstallAction::Download {
+            name: package.name.clone(),
+            artifact: sdist.clone(),
+        },
+        InstallAction::Build {
+            name: package.name.clone(),
+            sdist: sdist.clone(),
+        },
+        InstallAction::Install {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            artifact: sdist.clone(),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::PackageName;
+    use std::str::FromStr;
+
+    fn package(name: &str, filename: &str, dependencies: &[&str]) -> PinnedPackage {
+        let normalized: NormalizedPackageName = PackageName::from_str(name).unwrap().into();
+        let filename = ArtifactName::from_filename(filename, &normalized).unwrap();
+        PinnedPackage {
+            name: normalized,
+            version: filename.version(),
+            extras: Default::default(),
+            artifacts: vec![Arc::new(ArtifactInfo {
+                url: format!("https://example.com/{filename}").parse().unwrap(),
+                filename,
+                hashes: None,
+                requires_python: None,
+                dist_info_metadata: Default::default(),
+                yanked: Default::default(),
+                index_url: None,
+            })],
+            dependencies: dependencies
+                .iter()
+                .map(|name| PackageName::from_str(name).unwrap().into())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_install_plan_topological_order() {
+        // flask depends on jinja2 and werkzeug, jinja2 depends on markupsafe and werkzeug depends
+        // on markupsafe as well.
+        let packages = vec![
+            package(
+                "flask",
+                "flask-3.0.0-py3-none-any.whl",
+                &["jinja2", "werkzeug"],
+            ),
+            package("werkzeug", "werkzeug-3.0.1.tar.gz", &["markupsafe"]),
+            package(
+                "markupsafe",
+                "MarkupSafe-2.1.3-py3-none-any.whl",
+                &["not-in-solution"],
+            ),
+            package("jinja2", "Jinja2-3.1.2-py3-none-any.whl", &["markupsafe"]),
+        ];
+
+        let plan = install_plan(&packages);
+        let steps = plan
+            .iter()
+            .map(|action| match action {
+                InstallAction::Download { name, .. } => format!("download {}", name.as_str()),
+                InstallAction::Build { name, .. } => format!("build {}", name.as_str()),
+                InstallAction::Install { name, .. } => format!("install {}", name.as_str()),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            steps,
+            vec![
+                "download markupsafe",
+                "install markupsafe",
+                "download jinja2",
+                "install jinja2",
+                "download werkzeug",
+                "build werkzeug",
+                "install werkzeug",
+                "download flask",
+                "install flask",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_install_plan_breaks_cycles() {
+        let packages = vec![
+            package("b", "b-1.0-py3-none-any.whl", &["a"]),
+            package("a", "a-1.0-py3-none-any.whl", &["b"]),
+            package("c", "c-1.0-py3-none-any.whl", &["b"]),
+        ];
+
+        let order = install_plan(&packages)
+            .into_iter()
+            .filter(|action| matches!(action, InstallAction::Install { .. }))
+            .map(|action| action.name().as_str().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["a", "b", "c"]);
+    }
+}
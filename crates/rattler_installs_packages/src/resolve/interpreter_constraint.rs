@@ -0,0 +1,153 @@
+use pep508_rs::{MarkerEnvironment, StringVersion};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A Python implementation that an [`InterpreterConstraint`] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PythonImplementation {
+    /// The reference implementation
+    CPython,
+    /// The PyPy implementation
+    PyPy,
+}
+
+impl PythonImplementation {
+    /// The value of the `implementation_name` marker.
+    pub fn implementation_name(&self) -> &'static str {
+        match self {
+            PythonImplementation::CPython => "cpython",
+            PythonImplementation::PyPy => "pypy",
+        }
+    }
+
+    /// The value of the `platform_python_implementation` marker.
+    pub fn platform_python_implementation(&self) -> &'static str {
+        match self {
+            PythonImplementation::CPython => "CPython",
+            PythonImplementation::PyPy => "PyPy",
+        }
+    }
+}
+
+/// A Python interpreter that a resolution must support, e.g. `cpython3.9` or `pypy3.10`. A
+/// version without an implementation, e.g. `3.12`, refers to CPython.
+///
+/// When passed in [`super::ResolveOptions::interpreter_constraints`] a package is only selected
+/// if it is compatible with every one of the interpreters. This generalizes
+/// [`super::ResolveOptions::python_range`] to multiple implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InterpreterConstraint {
+    /// The implementation of the interpreter
+    pub implementation: PythonImplementation,
+
+    /// The major version of the Python language
+    pub major: u32,
+
+    /// The minor version of the Python language
+    pub minor: u32,
+}
+
+/// An error that can occur when parsing an [`InterpreterConstraint`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ParseInterpreterConstraintError {
+    /// The implementation is not known
+    #[error("unknown Python implementation '{0}', expected 'cpython' or 'pypy'")]
+    UnknownImplementation(String),
+
+    /// The version is not of the form `major.minor`
+    #[error("'{0}' is not a Python version of the form 'major.minor'")]
+    InvalidVersion(String),
+}
+
+impl InterpreterConstraint {
+    /// Returns the environment markers of the interpreter. Only the markers that describe the
+    /// interpreter differ from `markers`, the full version is the first release of the minor
+    /// version (e.g. `3.9.0`). The version of the implementation is not known, so it is the same
+    /// as the version of the language.
+    pub fn marker_environment(&self, markers: &MarkerEnvironment) -> MarkerEnvironment {
+        let full_version = StringVersion::from_str(&format!("{}.{}.0", self.major, self.minor))
+            .expect("a version of three numbers is always valid");
+        MarkerEnvironment {
+            implementation_name: self.implementation.implementation_name().to_owned(),
+            implementation_version: full_version.clone(),
+            platform_python_implementation: self
+                .implementation
+                .platform_python_implementation()
+                .to_owned(),
+            python_version: StringVersion::from_str(&format!("{}.{}", self.major, self.minor))
+                .expect("a version of two numbers is always valid"),
+            python_full_version: full_version,
+            ..markers.clone()
+        }
+    }
+}
+
+impl FromStr for InterpreterConstraint {
+    type Err = ParseInterpreterConstraintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let version_start = s.find(|c: char| c.is_ascii_digit()).unwrap_or(s.len());
+        let (implementation, version) = s.split_at(version_start);
+        let implementation = match implementation.to_ascii_lowercase().as_str() {
+            "" | "cpython" => PythonImplementation::CPython,
+            "pypy" => PythonImplementation::PyPy,
+            _ => {
+                return Err(ParseInterpreterConstraintError::UnknownImplementation(
+                    implementation.to_owned(),
+                ))
+            }
+        };
+
+        let invalid = || ParseInterpreterConstraintError::InvalidVersion(version.to_owned());
+        let (major, minor) = version.split_once('.').ok_or_else(invalid)?;
+        Ok(Self {
+            implementation,
+            major: major.parse().map_err(|_| invalid())?,
+            minor: minor.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl Display for InterpreterConstraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}.{}",
+            self.implementation.implementation_name(),
+            self.major,
+            self.minor
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_interpreter_constraint() {
+        for (input, expected) in [
+            ("cpython3.9", "cpython3.9"),
+            ("PyPy3.10", "pypy3.10"),
+            ("3.12", "cpython3.12"),
+        ] {
+            assert_eq!(
+                InterpreterConstraint::from_str(input).unwrap().to_string(),
+                expected
+            );
+        }
+
+        assert_eq!(
+            InterpreterConstraint::from_str("jython2.7"),
+            Err(ParseInterpreterConstraintError::UnknownImplementation(
+                "jython".into()
+            ))
+        );
+        assert_eq!(
+            InterpreterConstraint::from_str("pypy3"),
+            Err(ParseInterpreterConstraintError::InvalidVersion("3".into()))
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use super::PinnedPackage;
+use crate::types::{NormalizedPackageName, PackageName};
+use pep440_rs::Version;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The pinned versions of the packages in a lock file, e.g. the output of a previous resolution.
+///
+/// A lock file contains a single `name==version` pin per line. Empty lines and comments starting
+/// with `#` are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockedVersions(BTreeMap<NormalizedPackageName, String>);
+
+/// An error that can occur when parsing a lock file.
+#[derive(Debug, Error)]
+pub enum ParseLockError {
+    /// A line does not contain a `name==version` pin
+    #[error("line {0}: expected a pin like `name==version`, found '{1}'")]
+    InvalidPin(usize, String),
+
+    /// The name of a package is invalid
+    #[error("line {0}: invalid package name '{1}'")]
+    InvalidPackageName(usize, String),
+}
+
+impl FromStr for LockedVersions {
+    type Err = ParseLockError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut versions = BTreeMap::new();
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((name, version)) = line.split_once("==") else {
+                return Err(ParseLockError::InvalidPin(idx + 1, line.to_owned()));
+            };
+            let name = PackageName::from_str(name.trim())
+                .map_err(|_| ParseLockError::InvalidPackageName(idx + 1, name.to_owned()))?;
+            versions.insert(name.into(), version.trim().to_owned());
+        }
+        Ok(Self(versions))
+    }
+}
+
+impl LockedVersions {
+    /// Returns the locked version of a package.
+    pub fn get(&self, name: &NormalizedPackageName) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    /// Iterates over the locked packages, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&NormalizedPackageName, &str)> + '_ {
+        self.0
+            .iter()
+            .map(|(name, version)| (name, version.as_str()))
+    }
+}
+
+impl<'a> FromIterator<&'a PinnedPackage> for LockedVersions {
+    fn from_iter<T: IntoIterator<Item = &'a PinnedPackage>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|p| (p.name.clone(), p.version.to_string()))
+                .collect(),
+        )
+    }
+}
+
+/// A package that is present in only one of the two compared sets of packages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffEntry {
+    /// The name of the package
+    pub name: NormalizedPackageName,
+
+    /// The version of the package
+    pub version: String,
+}
+
+/// A package that is present in both compared sets of packages but with a different version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedEntry {
+    /// The name of the package
+    pub name: NormalizedPackageName,
+
+    /// The version in the old set of packages
+    pub old_version: String,
+
+    /// The version in the new set of packages
+    pub new_version: String,
+}
+
+/// The difference between two sets of locked packages. All entries are sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LockDiff {
+    /// Packages that are only present in the new set
+    pub added: Vec<DiffEntry>,
+
+    /// Packages that are only present in the old set
+    pub removed: Vec<DiffEntry>,
+
+    /// Packages whose version changed
+    pub changed: Vec<ChangedEntry>,
+}
+
+impl LockDiff {
+    /// Computes the changes required to go from `old` to `new`.
+    pub fn new(old: &LockedVersions, new: &LockedVersions) -> Self {
+        let mut diff = LockDiff::default();
+        for (name, old_version) in old.iter() {
+            match new.get(name) {
+                None => diff.removed.push(DiffEntry {
+                    name: name.clone(),
+                    version: old_version.to_owned(),
+                }),
+                Some(new_version) if !same_version(old_version, new_version) => {
+                    diff.changed.push(ChangedEntry {
+                        name: name.clone(),
+                        old_version: old_version.to_owned(),
+                        new_version: new_version.to_owned(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for (name, version) in new.iter() {
+            if old.get(name).is_none() {
+                diff.added.push(DiffEntry {
+                    name: name.clone(),
+                    version: version.to_owned(),
+                });
+            }
+        }
+        diff
+    }
+
+    /// Returns true if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Fails if there are any differences, e.g. to check that a lock file is still up to date
+    /// with the resolution of its requirements.
+    pub fn ensure_unchanged(self) -> Result<(), OutdatedLock> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(OutdatedLock { diff: self })
+        }
+    }
+}
+
+/// A lock file that differs from a new resolution of its requirements, see
+/// [`LockDiff::ensure_unchanged`].
+#[derive(Debug, Error)]
+#[error("the lock file is out of date:\n{}", .diff.to_string().trim_end())]
+pub struct OutdatedLock {
+    /// The changes between the lock file and the new resolution
+    pub diff: LockDiff,
+}
+
+impl Display for LockDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for entry in &self.added {
+            writeln!(f, "+ {} {}", entry.name, entry.version)?;
+        }
+        for entry in &self.removed {
+            writeln!(f, "- {} {}", entry.name, entry.version)?;
+        }
+        for entry in &self.changed {
+            writeln!(
+                f,
+                "~ {} {} -> {}",
+                entry.name, entry.old_version, entry.new_version
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two versions, `1.0` and `1.0.0` are considered to be the same version.
+fn same_version(a: &str, b: &str) -> bool {
+    match (Version::from_str(a), Version::from_str(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_lock() {
+        let lock = LockedVersions::from_str(
+            "# generated by rip\nFlask==3.0.0\n\njinja2 == 3.1.2  # a comment\n",
+        )
+        .unwrap();
+        assert_eq!(
+            lock.iter().collect::<Vec<_>>(),
+            vec![
+                (&"flask".parse().unwrap(), "3.0.0"),
+                (&"jinja2".parse().unwrap(), "3.1.2")
+            ]
+        );
+
+        assert!(matches!(
+            LockedVersions::from_str("flask==3.0.0\nflask>=2"),
+            Err(ParseLockError::InvalidPin(2, _))
+        ));
+    }
+
+    #[test]
+    fn test_lock_diff() {
+        let old =
+            LockedVersions::from_str("flask==2.3.0\nitsdangerous==2.1.2\njinja2==3.1.2").unwrap();
+        let new = LockedVersions::from_str("flask==3.0.0\nblinker==1.7.0\njinja2==3.1.2").unwrap();
+
+        let diff = LockDiff::new(&old, &new);
+        assert_eq!(
+            diff.added,
+            vec![DiffEntry {
+                name: "blinker".parse().unwrap(),
+                version: "1.7.0".to_string()
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            vec![DiffEntry {
+                name: "itsdangerous".parse().unwrap(),
+                version: "2.1.2".to_string()
+            }]
+        );
+        assert_eq!(
+            diff.changed,
+            vec![ChangedEntry {
+                name: "flask".parse().unwrap(),
+                old_version: "2.3.0".to_string(),
+                new_version: "3.0.0".to_string()
+            }]
+        );
+
+        assert_eq!(
+            diff.to_string(),
+            "+ blinker 1.7.0\n- itsdangerous 2.1.2\n~ flask 2.3.0 -> 3.0.0\n"
+        );
+
+        assert!(LockDiff::new(&old, &old).is_empty());
+        assert!(LockDiff::new(&old, &old).ensure_unchanged().is_ok());
+        assert!(LockDiff::new(
+            &LockedVersions::from_str("flask==3.0").unwrap(),
+            &LockedVersions::from_str("flask==3.0.0").unwrap()
+        )
+        .is_empty());
+    }
+    #[test]
+    fn test_frozen_lock() {
+        let lock = LockedVersions::from_str("flask==2.3.0\njinja2==3.1.2").unwrap();
+
+        // A lock that matches the resolution is up to date
+        let resolution = LockedVersions::from_str("flask==2.3.0\njinja2==3.1.2").unwrap();
+        assert!(LockDiff::new(&lock, &resolution).ensure_unchanged().is_ok());
+
+        // A stale lock is reported together with the changes
+        let resolution = LockedVersions::from_str("flask==3.0.0\njinja2==3.1.2").unwrap();
+        let err = LockDiff::new(&lock, &resolution)
+            .ensure_unchanged()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the lock file is out of date:\n~ flask 2.3.0 -> 3.0.0"
+        );
+    }
+}
@@ -8,11 +8,52 @@
 //! See the `rip_bin` crate for an example of how to use the [`resolve`] function in the: [RIP Repo](https://github.com/prefix-dev/rip)
 //!
 
+mod build_dependencies;
+mod cache;
+mod candidate_sorter;
+mod conflict_set;
 mod dependency_provider;
+mod error;
+mod ignored_dependency;
+mod install_plan;
+mod interpreter_constraint;
+mod lock;
+mod newest_compatible;
+mod preferences;
+mod progress;
+mod python_range;
+mod skipped;
 mod solve;
+mod summary;
+#[cfg(test)]
+pub(crate) mod test_support;
+mod universal;
+mod verify_lock;
 
-pub use dependency_provider::{PypiVersion, PypiVersionSet};
+pub use build_dependencies::{resolve_build_dependencies, BuildDependencies};
+pub use cache::ResolutionCache;
+pub use candidate_sorter::{Candidate, CandidateSorter};
+pub use conflict_set::minimal_conflict_set;
+pub use dependency_provider::{MetadataError, PypiVersion, PypiVersionSet};
+pub use error::{ConflictingRequirement, ResolutionError};
+pub use ignored_dependency::{IgnoredDependency, ParseIgnoredDependencyError};
+pub use install_plan::{install_plan, InstallAction};
+pub use interpreter_constraint::{
+    InterpreterConstraint, ParseInterpreterConstraintError, PythonImplementation,
+};
+pub use lock::{ChangedEntry, DiffEntry, LockDiff, LockedVersions, OutdatedLock, ParseLockError};
+pub use newest_compatible::newest_compatible_version;
+pub use preferences::{ParsePreferencesError, VersionPreferences};
+pub use progress::ProgressObserver;
+pub use python_range::{ParsePythonVersionRangeError, PythonVersionRange};
+pub use skipped::{SkipReason, SkippedArtifacts};
 pub use solve::{
     resolve, OnWheelBuildFailure, PinnedPackage, PreReleaseResolution, ResolveOptions,
     SDistResolution,
 };
+pub use summary::{ResolutionSummary, SummaryCounts};
+pub use universal::{
+    environment_markers, merge_resolutions, resolve_universal, ConditionalPackage,
+    UniversalEnvironment,
+};
+pub use verify_lock::{verify_lock, LockProblem};
@@ -0,0 +1,167 @@
+use super::PypiVersion;
+use crate::index::PackageDb;
+use crate::python_env::WheelTags;
+use crate::types::{ArtifactInfo, ArtifactName, PackageName, VersionSpecifierExt};
+use miette::IntoDiagnostic;
+use pep508_rs::{MarkerEnvironment, Requirement, VersionOrUrl};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Returns the newest version of the package of `requirement` that matches its specifiers and
+/// can be installed in the environment described by `env_markers` and `compatible_tags`, without
+/// resolving the dependencies of that version. Returns `None` if no version is compatible.
+///
+/// The versions are checked from the newest to the oldest and the search stops at the first
+/// compatible version. A version is compatible if it has an artifact that is not yanked, whose
+/// `Requires-Python` (as reported by the index) includes the Python version of `env_markers` and
+/// that is either a source distribution or a wheel with a compatible tag. The metadata of such a
+/// version is fetched to check the `Requires-Python` of the package itself, unless it can only be
+/// obtained by building a source distribution.
+///
+/// Pre-releases are only considered if `allow_prerelease` is set. The markers of `requirement`
+/// are not evaluated.
+pub async fn newest_compatible_version(
+    package_db: &PackageDb,
+    requirement: &Requirement,
+    env_markers: &MarkerEnvironment,
+    compatible_tags: Option<&WheelTags>,
+    allow_prerelease: bool,
+) -> miette::Result<Option<PypiVersion>> {
+    let name = PackageName::from_str(&requirement.name).into_diagnostic()?;
+    let python_version = &env_markers.python_full_version.version;
+
+    // The artifacts are sorted from the newest to the oldest version
+    let available = package_db.available_artifacts(name.clone()).await?;
+    for (version, artifacts) in available.iter() {
+        let PypiVersion::Version { version: v, .. } = version else {
+            continue;
+        };
+        let matches = match &requirement.version_or_url {
+            Some(VersionOrUrl::VersionSpecifier(specifiers)) => specifiers.satisfied_by(v),
+            Some(VersionOrUrl::Url(_)) => false,
+            None => true,
+        };
+        if !matches || (!allow_prerelease && v.any_prerelease()) {
+            continue;
+        }
+
+        let compatible = artifacts
+            .iter()
+            .filter(|a| !a.yanked.yanked)
+            .filter(|a| {
+                a.requires_python.as_ref().map_or(true, |requires_python| {
+                    requires_python.satisfied_by(python_version)
+                })
+            })
+            .filter(|a| is_installable(a, compatible_tags))
+            .cloned()
+            .collect::<Vec<Arc<ArtifactInfo>>>();
+        if compatible.is_empty() {
+            continue;
+        }
+
+        // The index does not always report the `Requires-Python` of an artifact
+        if let Some((artifact, metadata)) = package_db.get_metadata(&compatible, None).await? {
+            if let Some(requires_python) = &metadata.requires_python {
+                if !requires_python.satisfied_by(python_version) {
+                    tracing::debug!(
+                        "skipping {} {version} because {} requires python {requires_python}",
+                        name.as_str(),
+                        artifact.filename
+                    );
+                    continue;
+                }
+            }
+        }
+
+        return Ok(Some(version.clone()));
+    }
+
+    Ok(None)
+}
+
+/// Returns true if `artifact` is a source distribution in a supported format or a wheel that is
+/// compatible with `compatible_tags`. Every wheel is compatible if no tags are given.
+fn is_installable(artifact: &ArtifactInfo, compatible_tags: Option<&WheelTags>) -> bool {
+    match &artifact.filename {
+        ArtifactName::Wheel(wheel) => compatible_tags.map_or(true, |compatible_tags| {
+            wheel
+                .all_tags_iter()
+                .any(|tag| compatible_tags.is_compatible(&tag))
+        }),
+        ArtifactName::SDist(sdist) => sdist.format.is_supported(),
+        ArtifactName::STree(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::python_env::{CPythonAbi, Pep508EnvMakers, PythonInterpreterVersion};
+    use crate::resolve::test_support::{package_db_with, IndexArtifact};
+
+    async fn newest(
+        package_db: &PackageDb,
+        requirement: &str,
+        minor: u32,
+    ) -> miette::Result<Option<String>> {
+        let version = PythonInterpreterVersion::from((3, minor, 0));
+        let env = Pep508EnvMakers::for_target_platform("linux-x86_64".parse().unwrap(), &version);
+        let tags = WheelTags::cpython(&version, CPythonAbi::Gil, &["manylinux_2_17_x86_64"]);
+        newest_compatible_version(
+            package_db,
+            &Requirement::from_str(requirement).unwrap(),
+            &env.0,
+            Some(&tags),
+            false,
+        )
+        .await
+        .map(|version| version.map(|version| version.to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_newest_compatible_version() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let artifact = |filename: &str, requires_python: &str| {
+            IndexArtifact::new("foo", filename)
+                .with_metadata(&format!("Requires-Python: {requires_python}\n"))
+        };
+        let package_db = package_db_with(
+            cache_dir.path(),
+            [
+                artifact("foo-4.0a1-py3-none-any.whl", ">=3.7"),
+                artifact("foo-3.0-py3-none-any.whl", ">=3.9"),
+                artifact("foo-2.2-py3-none-any.whl", ">=3.7").requires_python(">=3.10"),
+                artifact("foo-2.1-cp38-cp38-win_amd64.whl", ">=3.7"),
+                artifact("foo-2.0-py3-none-any.whl", ">=3.7"),
+                // The metadata of this version cannot be fetched, so the search must stop before
+                IndexArtifact::new("foo", "foo-1.0-py3-none-any.whl"),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            newest(&package_db, "foo", 8).await.unwrap().as_deref(),
+            Some("2.0")
+        );
+        assert_eq!(
+            newest(&package_db, "foo", 9).await.unwrap().as_deref(),
+            Some("3.0")
+        );
+        assert_eq!(
+            newest(&package_db, "foo", 10).await.unwrap().as_deref(),
+            Some("3.0")
+        );
+        assert_eq!(
+            newest(&package_db, "foo<3,!=2.0", 11)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("2.2")
+        );
+        assert_eq!(newest(&package_db, "foo>4", 12).await.unwrap(), None);
+
+        // Checking the oldest version requires its metadata, which the other checks never fetched
+        assert!(newest(&package_db, "foo<2", 8).await.is_err());
+    }
+}
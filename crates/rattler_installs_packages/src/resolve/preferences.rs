@@ -0,0 +1,82 @@
+use super::{Candidate, CandidateSorter, LockedVersions, ParseLockError, PypiVersion};
+use crate::types::NormalizedPackageName;
+use pep440_rs::Version;
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Soft version preferences for some packages, e.g. read from a file with a `name==version` pin
+/// per line. Use it as [`super::ResolveOptions::candidate_sorter`] to try the preferred version
+/// of a package before any other version.
+///
+/// Unlike locked packages the preferences are only advisory: if the preferred version of a
+/// package conflicts with the other requirements another version is selected. This keeps
+/// resolutions stable when the requirements change slightly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionPreferences(HashMap<NormalizedPackageName, Version>);
+
+/// An error that can occur when parsing [`VersionPreferences`].
+#[derive(Debug, Error)]
+pub enum ParsePreferencesError {
+    /// The preferences are not a list of `name==version` pins
+    #[error(transparent)]
+    InvalidPin(#[from] ParseLockError),
+
+    /// The preferred version of a package is not a valid version
+    #[error("invalid preferred version '{1}' of {0}")]
+    InvalidVersion(NormalizedPackageName, String),
+}
+
+impl VersionPreferences {
+    /// Returns the preferred version of a package.
+    pub fn get(&self, name: &NormalizedPackageName) -> Option<&Version> {
+        self.0.get(name)
+    }
+
+    /// Adds the preferences of `other` for the packages that do not have a preferred version yet,
+    /// e.g. to fall back to the versions of a lock file.
+    pub fn merge(&mut self, other: VersionPreferences) {
+        for (name, version) in other.0 {
+            self.0.entry(name).or_insert(version);
+        }
+    }
+}
+
+impl TryFrom<&LockedVersions> for VersionPreferences {
+    type Error = ParsePreferencesError;
+
+    fn try_from(locked: &LockedVersions) -> Result<Self, Self::Error> {
+        locked
+            .iter()
+            .map(|(name, version)| {
+                Version::from_str(version)
+                    .map(|version| (name.clone(), version))
+                    .map_err(|_| {
+                        ParsePreferencesError::InvalidVersion(name.clone(), version.to_owned())
+                    })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl FromStr for VersionPreferences {
+    type Err = ParsePreferencesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(&LockedVersions::from_str(s)?)
+    }
+}
+
+impl CandidateSorter for VersionPreferences {
+    fn sort(&self, package: &NormalizedPackageName, candidates: &mut [Candidate<'_>]) {
+        let Some(preferred) = self.get(package) else {
+            return;
+        };
+
+        // Move the preferred version to the front, the others keep their order
+        candidates.sort_by_key(|candidate| {
+            !matches!(candidate.version, PypiVersion::Version { version, .. } if version == preferred)
+        });
+    }
+}
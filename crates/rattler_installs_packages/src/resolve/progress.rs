@@ -0,0 +1,40 @@
+use super::PypiVersion;
+use crate::types::NormalizedPackageName;
+use url::Url;
+
+/// Receives progress events during resolution, see [`super::ResolveOptions::progress_observer`].
+/// This allows embedders to report progress in any way they like, e.g. with progress bars in a
+/// terminal or in a graphical user interface.
+///
+/// The methods can be called concurrently from multiple threads and should return quickly, all
+/// of them do nothing by default.
+///
+/// ```
+/// # use rattler_installs_packages::resolve::{ProgressObserver, PypiVersion};
+/// # use rattler_installs_packages::types::NormalizedPackageName;
+/// /// Logs the packages for which the metadata is retrieved
+/// struct LogProgress;
+///
+/// impl ProgressObserver for LogProgress {
+///     fn on_package_started(&self, name: &NormalizedPackageName, version: &PypiVersion) {
+///         println!("retrieving the metadata of {name} {version}");
+///     }
+/// }
+/// ```
+pub trait ProgressObserver: Send + Sync {
+    /// Called when the resolver starts to retrieve the metadata of a version of a package. This
+    /// might involve downloading or building an artifact.
+    fn on_package_started(&self, _name: &NormalizedPackageName, _version: &PypiVersion) {}
+
+    /// Called when the metadata of a version of a package has been retrieved, or when retrieving
+    /// it failed.
+    fn on_package_finished(&self, _name: &NormalizedPackageName, _version: &PypiVersion) {}
+
+    /// Called while an artifact is downloaded from `url`. `bytes` is the number of bytes that
+    /// have been downloaded so far and `total` is the size of the artifact if the server reported
+    /// it. Artifacts that are read from the cache are not reported.
+    fn on_download_progress(&self, _url: &Url, _bytes: u64, _total: Option<u64>) {}
+
+    /// Called when the download of an artifact from `url` has finished, or when it failed.
+    fn on_download_finished(&self, _url: &Url) {}
+}
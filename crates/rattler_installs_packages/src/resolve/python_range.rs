@@ -0,0 +1,169 @@
+use pep440_rs::Version;
+use pep508_rs::{MarkerEnvironment, StringVersion};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An inclusive range of Python minor versions, e.g. `3.9..3.12`.
+///
+/// When passed as [`super::ResolveOptions::python_range`] a package is only selected if it is
+/// compatible with every Python version in the range. This is useful to resolve the dependencies
+/// of a library that supports multiple interpreters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PythonVersionRange {
+    major: u32,
+    min_minor: u32,
+    max_minor: u32,
+}
+
+/// An error that can occur when parsing a [`PythonVersionRange`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ParsePythonVersionRangeError {
+    /// The range is not of the form `<min>..<max>`
+    #[error("expected a range of the form '3.9..3.12' but got '{0}'")]
+    InvalidFormat(String),
+
+    /// One of the bounds is not a `major.minor` version
+    #[error("'{0}' is not a Python version of the form 'major.minor'")]
+    InvalidVersion(String),
+
+    /// The bounds do not have the same major version
+    #[error("the bounds of the range must have the same major version")]
+    DifferentMajorVersions,
+
+    /// The lower bound is higher than the upper bound
+    #[error("the lower bound {0} is higher than the upper bound {1}")]
+    Empty(String, String),
+}
+
+impl PythonVersionRange {
+    /// Returns the `major.minor` versions in the range, from the lowest to the highest.
+    pub fn versions(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (self.min_minor..=self.max_minor).map(|minor| (self.major, minor))
+    }
+
+    /// Returns the environment markers for each of the versions in the range. Only the Python
+    /// version markers differ from `markers`, the full version is the first release of each minor
+    /// version (e.g. `3.9.0`).
+    pub fn marker_environments(&self, markers: &MarkerEnvironment) -> Vec<MarkerEnvironment> {
+        self.versions()
+            .map(|(major, minor)| MarkerEnvironment {
+                python_version: StringVersion::from_str(&format!("{major}.{minor}"))
+                    .expect("a version of two numbers is always valid"),
+                python_full_version: StringVersion::from_str(&format!("{major}.{minor}.0"))
+                    .expect("a version of three numbers is always valid"),
+                ..markers.clone()
+            })
+            .collect()
+    }
+}
+
+impl FromStr for PythonVersionRange {
+    type Err = ParsePythonVersionRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((min, max)) = s.split_once("..") else {
+            return Err(ParsePythonVersionRangeError::InvalidFormat(s.to_owned()));
+        };
+        let (min_major, min_minor) = parse_minor_version(min.trim())?;
+        let (max_major, max_minor) = parse_minor_version(max.trim())?;
+        if min_major != max_major {
+            return Err(ParsePythonVersionRangeError::DifferentMajorVersions);
+        }
+        if min_minor > max_minor {
+            return Err(ParsePythonVersionRangeError::Empty(
+                min.trim().to_owned(),
+                max.trim().to_owned(),
+            ));
+        }
+        Ok(Self {
+            major: min_major,
+            min_minor,
+            max_minor,
+        })
+    }
+}
+
+impl Display for PythonVersionRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{major}.{}..{major}.{}",
+            self.min_minor,
+            self.max_minor,
+            major = self.major
+        )
+    }
+}
+
+/// Parses a version of the form `major.minor`.
+fn parse_minor_version(s: &str) -> Result<(u32, u32), ParsePythonVersionRangeError> {
+    let invalid = || ParsePythonVersionRangeError::InvalidVersion(s.to_owned());
+    let version = Version::from_str(s).map_err(|_| invalid())?;
+    match version.release[..] {
+        [major, minor]
+            if version.epoch == 0
+                && version.pre.is_none()
+                && version.post.is_none()
+                && version.dev.is_none()
+                && version.local.is_none() =>
+        {
+            Ok((
+                major.try_into().map_err(|_| invalid())?,
+                minor.try_into().map_err(|_| invalid())?,
+            ))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_python_range() {
+        let range = PythonVersionRange::from_str("3.9..3.12").unwrap();
+        assert_eq!(
+            range.versions().collect::<Vec<_>>(),
+            [(3, 9), (3, 10), (3, 11), (3, 12)]
+        );
+        assert_eq!(range.to_string(), "3.9..3.12");
+        assert_eq!(
+            PythonVersionRange::from_str("3.11..3.11")
+                .unwrap()
+                .versions()
+                .count(),
+            1
+        );
+
+        for (input, expected) in [
+            (
+                "3.9",
+                ParsePythonVersionRangeError::InvalidFormat("3.9".into()),
+            ),
+            (
+                "3.9..3.12.1",
+                ParsePythonVersionRangeError::InvalidVersion("3.12.1".into()),
+            ),
+            (
+                "3..3.12",
+                ParsePythonVersionRangeError::InvalidVersion("3".into()),
+            ),
+            (
+                "2.7..3.12",
+                ParsePythonVersionRangeError::DifferentMajorVersions,
+            ),
+            (
+                "3.12..3.9",
+                ParsePythonVersionRangeError::Empty("3.12".into(), "3.9".into()),
+            ),
+        ] {
+            assert_eq!(
+                PythonVersionRange::from_str(input).unwrap_err(),
+                expected,
+                "{input}"
+            );
+        }
+    }
+}
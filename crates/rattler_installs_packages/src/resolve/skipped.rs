@@ -0,0 +1,143 @@
+use crate::types::{ArtifactName, NormalizedPackageName};
+use itertools::Itertools;
+use parking_lot::Mutex;
+use pep440_rs::VersionSpecifiers;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// The reason why an artifact was not considered during resolution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The artifact was yanked from the index
+    Yanked(Option<String>),
+
+    /// The artifact is a source distribution but only wheels are allowed
+    NoWheel,
+
+    /// The artifact is a wheel but only source distributions are allowed
+    NoSDist,
+
+    /// The artifact belongs to a pre-release version and pre-releases are not allowed at all, see
+    /// [`crate::resolve::PreReleaseResolution::Disallow`]. Pre-releases that can still be selected
+    /// because a requirement explicitly asks for one are not skipped.
+    PreRelease,
+
+    /// The wheel is not compatible with the platform or the Python interpreter
+    IncompatiblePlatform,
+
+    /// The wheel is specific to a platform or abi but only pure Python wheels are allowed
+    NotPurePython,
+
+    /// The `requires-python` of the artifact does not match the Python interpreter
+    RequiresPython(VersionSpecifiers),
+
+    /// The archive format of the source distribution is not supported
+    UnsupportedFormat,
+
+    /// The metadata of the version could not be obtained within
+    /// [`super::ResolveOptions::timeout_per_package`]
+    MetadataTimeout,
+}
+
+impl SkipReason {
+    /// A short identifier of the kind of reason, without its details, e.g. `requires_python`.
+    pub fn category(&self) -> &'static str {
+        match self {
+            SkipReason::Yanked(_) => "yanked",
+            SkipReason::NoWheel => "no_wheel",
+            SkipReason::NoSDist => "no_sdist",
+            SkipReason::PreRelease => "prerelease",
+            SkipReason::IncompatiblePlatform => "incompatible_platform",
+            SkipReason::NotPurePython => "not_pure_python",
+            SkipReason::RequiresPython(_) => "requires_python",
+            SkipReason::UnsupportedFormat => "unsupported_format",
+            SkipReason::MetadataTimeout => "metadata_timeout",
+        }
+    }
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::Yanked(Some(reason)) => write!(f, "yanked ({reason})"),
+            SkipReason::Yanked(None) => write!(f, "yanked"),
+            SkipReason::NoWheel => write!(f, "no wheel (source distributions are not allowed)"),
+            SkipReason::NoSDist => write!(f, "no sdist (wheels are not allowed)"),
+            SkipReason::PreRelease => write!(f, "prerelease"),
+            SkipReason::IncompatiblePlatform => write!(f, "incompatible platform"),
+            SkipReason::NotPurePython => write!(f, "not pure python"),
+            SkipReason::RequiresPython(specifiers) => {
+                write!(f, "requires-python mismatch ({specifiers})")
+            }
+            SkipReason::UnsupportedFormat => write!(f, "unsupported sdist format"),
+            SkipReason::MetadataTimeout => write!(f, "metadata timed out"),
+        }
+    }
+}
+
+/// The skipped artifacts of a single package together with the reason why they were skipped.
+type SkippedPackageArtifacts = Vec<(ArtifactName, SkipReason)>;
+
+/// Collects the artifacts that were skipped during resolution together with the reason why they
+/// were skipped.
+///
+/// This is a cheaply cloneable handle, pass a clone of it to
+/// [`super::ResolveOptions::skipped_artifacts`] and inspect it after the resolution finished.
+#[derive(Debug, Clone, Default)]
+pub struct SkippedArtifacts {
+    skipped: Arc<Mutex<BTreeMap<NormalizedPackageName, SkippedPackageArtifacts>>>,
+}
+
+impl SkippedArtifacts {
+    /// Records that an artifact of the given package was skipped.
+    pub fn push(&self, name: NormalizedPackageName, artifact: ArtifactName, reason: SkipReason) {
+        let mut skipped = self.skipped.lock();
+        let entries = skipped.entry(name).or_default();
+        if !entries.iter().any(|(a, r)| a == &artifact && r == &reason) {
+            entries.push((artifact, reason));
+        }
+    }
+
+    /// Returns true if no artifacts have been skipped.
+    pub fn is_empty(&self) -> bool {
+        self.skipped.lock().is_empty()
+    }
+
+    /// Returns the artifacts that were skipped because they were yanked, together with the reason
+    /// that the index gave for yanking them, grouped by package name.
+    pub fn yanked(&self) -> BTreeMap<NormalizedPackageName, Vec<(ArtifactName, Option<String>)>> {
+        self.skipped
+            .lock()
+            .iter()
+            .filter_map(|(name, artifacts)| {
+                let yanked = artifacts
+                    .iter()
+                    .filter_map(|(artifact, reason)| match reason {
+                        SkipReason::Yanked(reason) => Some((artifact.clone(), reason.clone())),
+                        _ => None,
+                    })
+                    .sorted_by(|a, b| a.0.cmp(&b.0))
+                    .collect::<Vec<_>>();
+                (!yanked.is_empty()).then(|| (name.clone(), yanked))
+            })
+            .collect()
+    }
+
+    /// Returns all skipped artifacts grouped by package name.
+    pub fn by_package(&self) -> BTreeMap<NormalizedPackageName, SkippedPackageArtifacts> {
+        self.skipped.lock().clone()
+    }
+}
+
+impl Display for SkippedArtifacts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (name, artifacts) in self.skipped.lock().iter() {
+            writeln!(f, "{}:", name.as_str())?;
+            for (artifact, reason) in artifacts.iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+                writeln!(f, "  - {artifact}: {reason}")?;
+            }
+        }
+        Ok(())
+    }
+}
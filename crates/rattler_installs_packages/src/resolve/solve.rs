@@ -1,40 +1,62 @@
-use super::dependency_provider::{PypiPackageName, PypiVersionSet};
+use super::cache::CacheKey;
+use super::dependency_provider::{MetadataError, Providers, PypiPackageName, PypiVersionSet};
 use crate::index::PackageDb;
 use crate::python_env::{PythonLocation, WheelTags};
 use crate::resolve::dependency_provider::PypiDependencyProvider;
-use crate::resolve::PypiVersion;
+use crate::resolve::{
+    CandidateSorter, ConflictingRequirement, IgnoredDependency, InterpreterConstraint,
+    ProgressObserver, PypiVersion, PythonVersionRange, ResolutionCache, ResolutionError,
+    ResolutionSummary, SkippedArtifacts,
+};
 use crate::types::PackageName;
 use crate::{types::ArtifactInfo, types::Extra, types::NormalizedPackageName};
 use elsa::FrozenMap;
+use itertools::Itertools;
+use pep440_rs::{Operator, VersionSpecifier};
 use pep508_rs::{MarkerEnvironment, Requirement, VersionOrUrl};
-use resolvo::{DefaultSolvableDisplay, Pool, Solver, UnsolvableOrCancelled};
-use std::collections::HashMap;
+use resolvo::problem::Problem;
+use resolvo::{
+    DefaultSolvableDisplay, Pool, SolvableId, Solver, UnsolvableOrCancelled, VersionSet,
+    VersionSetId,
+};
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::str::FromStr;
 
-use std::collections::HashSet;
-use std::ops::Deref;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Represents a single locked down distribution (python package) after calling [`resolve`].
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// The extras and dependencies are ordered sets, so they are iterated and serialized in the same
+/// order on every run. When serialized, the artifacts are omitted.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct PinnedPackage {
     /// The name of the package
     pub name: NormalizedPackageName,
 
     /// The selected version
+    #[serde_as(as = "DisplayFromStr")]
     pub version: PypiVersion,
 
     /// The possible direct URL for it
     // pub url: Option<Url>,
 
     /// The extras that where selected either by the user or as part of the resolution.
-    pub extras: HashSet<Extra>,
+    pub extras: BTreeSet<Extra>,
 
     /// The applicable artifacts for this package. These have been ordered by compatibility if
     /// `compatible_tags` have been provided to the solver.
     ///
     /// This list may be empty if the package was locked or favored.
+    #[serde(skip)]
     pub artifacts: Vec<Arc<ArtifactInfo>>,
+
+    /// The names of the packages in the solution that this package depends on, including the
+    /// dependencies of its selected extras.
+    pub dependencies: BTreeSet<NormalizedPackageName>,
 }
 
 /// Defines how to handle sdists during resolution.
@@ -239,6 +261,136 @@ pub struct ResolveOptions {
     /// Defines whether pre-releases are allowed to be selected during resolution. By default
     /// pre-releases are not allowed (only if there are no other versions available for a given dependency).
     pub pre_release_resolution: PreReleaseResolution,
+
+    /// If set, every artifact that is not considered during resolution is recorded here together
+    /// with the reason why it was skipped.
+    pub skipped_artifacts: Option<SkippedArtifacts>,
+
+    /// If set, the number of artifacts that were considered or skipped and the number of versions
+    /// whose metadata was retrieved are counted here.
+    pub summary: Option<ResolutionSummary>,
+
+    /// If true, source distributions without reliable metadata are not built to determine their
+    /// dependencies and cannot be selected. The `PKG-INFO` of a source distribution is only
+    /// reliable if it implements PEP 643 (metadata version 2.2 or higher), otherwise the actual
+    /// dependencies are only known after running the build backend.
+    ///
+    /// Building is expensive and executes arbitrary code, but most source distributions on PyPI
+    /// do not have reliable metadata yet, so they are built by default.
+    pub skip_unreliable_sdists: bool,
+
+    /// If true, the build backend of a source distribution is never run, neither to determine
+    /// its metadata nor to build a wheel from it. Source distributions with reliable metadata (PEP 643) can still be selected, but
+    /// resolution fails if the dependencies of a package can only be determined by building it.
+    pub no_build: bool,
+
+    /// If set, only the metadata of the `N` highest versions of each package is considered.
+    /// Pre-release versions that are not allowed and versions that are completely yanked do not
+    /// count towards this limit. Versions that are pinned exactly by one of the requirements
+    /// passed to [`resolve`] are always considered.
+    ///
+    /// This greatly reduces the amount of metadata that has to be fetched for packages with many
+    /// releases, at the cost of possibly not finding a solution even if one exists.
+    pub only_newest: Option<usize>,
+
+    /// If set, the resolution fails once it needs the metadata of more than `N` package versions
+    /// in total, reporting the packages for which the most versions were fetched. Unlike
+    /// [`Self::only_newest`] this does not change which versions are considered, it bounds the
+    /// work of a resolution that explores far more of the index than expected, e.g. because a
+    /// requirement is unconstrained.
+    pub max_versions_fetched: Option<usize>,
+
+    /// If set, obtaining the metadata of a single version of a package, including downloading and
+    /// building it, may take at most this long. A version that exceeds it is excluded from the
+    /// resolution with a warning and recorded in [`Self::skipped_artifacts`], so one slow package
+    /// cannot stall the whole resolution. The resolution fails instead if [`Self::strict`] is
+    /// set.
+    ///
+    /// This bounds the work per package, unlike the timeout of the http client which bounds
+    /// every single request.
+    pub timeout_per_package: Option<Duration>,
+
+    /// If true, resolution fails when a `Requires-Dist` of a package refers to an environment
+    /// marker variable that is not defined by PEP 508. By default such requirements are ignored
+    /// with a warning.
+    pub strict_markers: bool,
+
+    /// If true, situations in which a resolution silently ignores or works around a problem fail
+    /// instead, so that nothing surprising is resolved. These are:
+    ///
+    /// - a `Requires-Dist` of a package that uses an unknown environment marker variable, see
+    ///   [`Self::strict_markers`], or that cannot be parsed at all. Otherwise the requirement is
+    ///   ignored with a warning.
+    /// - a source distribution whose declared dependencies (in its `PKG-INFO` or `pyproject.toml`)
+    ///   differ from the dependencies of the built package, see
+    ///   [`crate::wheel_builder::MetadataMismatch`]. Otherwise the built metadata is used with a
+    ///   warning.
+    /// - an older version of a package that is selected because none of the artifacts of a newer
+    ///   version are compatible with the interpreter or platform, see
+    ///   [`super::ResolutionError::IncompatibleNewerVersion`]. Otherwise the older version is
+    ///   selected silently.
+    /// - a version of a package whose metadata could not be obtained within
+    ///   [`Self::timeout_per_package`], see [`MetadataError::MetadataTimeout`]. Otherwise the
+    ///   version is excluded with a warning.
+    ///
+    /// Yanked versions are never selected, so they need no strict handling.
+    pub strict: bool,
+
+    /// If true, only pure Python wheels (wheels with the `none` abi and the `any` platform tag) are
+    /// considered, even if a platform specific wheel is compatible. Versions without a pure Python
+    /// wheel fall back to their source distributions if those are allowed.
+    pub pure_python_only: bool,
+
+    /// Customizes the order in which the candidate versions of a package are tried. By default
+    /// the highest version is preferred. Resolutions with a custom sorter are never cached.
+    pub candidate_sorter: Option<Arc<dyn CandidateSorter>>,
+
+    /// If set, a package is only selected if it is compatible with every Python version in the
+    /// range: the `requires-python` of its artifacts must include all of them and the
+    /// requirements of each version are combined. This only affects the metadata that is
+    /// evaluated, wheels are still filtered by the compatible tags of the interpreter.
+    pub python_range: Option<PythonVersionRange>,
+
+    /// If not empty, a package is only selected if it is compatible with every one of these
+    /// interpreters, which can differ in their implementation and Python version. Unlike with
+    /// [`Self::python_range`] a requirement of a package is only added if its marker holds for all
+    /// of the interpreters, so dependencies that are specific to some of the interpreters (e.g.
+    /// CPython-only dependencies) are excluded. Wheels are still filtered by the compatible tags
+    /// of the interpreter. This takes precedence over [`Self::python_range`].
+    pub interpreter_constraints: Vec<InterpreterConstraint>,
+
+    /// Receives progress events while the metadata of packages is retrieved and artifacts are
+    /// downloaded. This is also used by the [`crate::wheel_builder::WheelBuilder`] that is
+    /// constructed with these options.
+    pub progress_observer: Option<Arc<dyn ProgressObserver>>,
+
+    /// If set, the result of the resolution is cached and a previous result for the exact same
+    /// input is reused as long as the index did not change. No caching is performed if locked or
+    /// favored packages are passed, if a requirement refers to a url or if
+    /// [`Self::skipped_artifacts`], [`Self::summary`] or [`Self::timeout_per_package`] is set.
+    pub resolution_cache: Option<ResolutionCache>,
+
+    /// If true, a requirement on a package can also be satisfied by a package that declares that
+    /// it provides it with a `Provides-Dist` field, e.g. a package that bundles another package.
+    /// The provided package does not have to exist on the index.
+    ///
+    /// The packages that provide other packages are only known once their metadata has been
+    /// fetched. If a resolution fails, the metadata of the newest version of every package that
+    /// was considered is fetched and the resolution is retried if new providers were found. A
+    /// package that is never considered is therefore never used as a provider, so the providing
+    /// package has to be required somewhere.
+    pub honor_provides: bool,
+
+    /// Requirements of packages that are dropped from their metadata, see [`IgnoredDependency`].
+    /// A warning is emitted for every requirement that is ignored.
+    pub ignored_dependencies: Vec<IgnoredDependency>,
+
+    /// Packages that may never be part of the resolution, e.g. because they are known to be
+    /// malicious or are forbidden by policy. The resolution fails with
+    /// [`ResolutionError::BlockedPackage`] as soon as one of them is required, even if the
+    /// requirement could also be satisfied without it, so that the package that pulls it in can be
+    /// dealt with.
+    pub blocked_packages: Vec<NormalizedPackageName>,
 }
 
 /// Resolves an environment that contains the given requirements and all dependencies of those
@@ -251,6 +403,21 @@ pub struct ResolveOptions {
 /// If `compatible_tags` is defined then the available artifacts of a distribution are filtered to
 /// include only artifacts that are compatible with the specified tags. If `None` is passed, the
 /// artifacts are not filtered at all
+///
+/// The resolved packages are sorted by their normalized name, so the result does not depend on
+/// the order in which the solver selected them.
+///
+/// Identical input always yields an identical resolution. Everything that is handed to the solver
+/// is either ordered by the input (the requirements and the `Requires-Dist` of each package) or
+/// sorted (the candidates and the extras of a package), so the solver interns and visits packages
+/// in the same order on every run. Unordered maps are only used for lookups. The extras and the
+/// dependencies of a [`PinnedPackage`] are ordered sets for the same reason.
+///
+/// There is no separate stage that fetches the metadata of the whole dependency graph before
+/// solving. The available artifacts and the metadata of a package are only retrieved when the
+/// solver first asks for the candidates or the dependencies of that package, so solving starts
+/// right away and only the metadata of the versions that the solver actually considers is kept in
+/// memory.
 // TODO: refactor this into an input type of sorts later
 #[allow(clippy::too_many_arguments)]
 pub async fn resolve(
@@ -262,28 +429,125 @@ pub async fn resolve(
     favored_packages: HashMap<NormalizedPackageName, PinnedPackage>,
     options: ResolveOptions,
     env_variables: HashMap<String, String>,
-) -> miette::Result<Vec<PinnedPackage>> {
+) -> Result<Vec<PinnedPackage>, ResolutionError> {
+    let requirements = requirements.into_iter().collect::<Vec<_>>();
+
+    // Use a previous resolution of the same input if possible. Resolutions that depend on state
+    // outside of the index or that should report what happened during solving are not cached.
+    let use_cache = locked_packages.is_empty()
+        && favored_packages.is_empty()
+        && options.skipped_artifacts.is_none()
+        && options.summary.is_none()
+        && options.candidate_sorter.is_none()
+        && options.timeout_per_package.is_none()
+        && !requirements
+            .iter()
+            .any(|r| matches!(r.version_or_url, Some(VersionOrUrl::Url(_))));
+    let cache = match &options.resolution_cache {
+        Some(cache) if use_cache => Some((
+            cache.clone(),
+            CacheKey::new(
+                &package_db,
+                &requirements,
+                &env_markers,
+                compatible_tags.as_deref(),
+                cache_options(&options),
+                &env_variables,
+            ),
+        )),
+        _ => None,
+    };
+    if let Some((cache, key)) = &cache {
+        if let Some(packages) = cache.load(key, &package_db).await {
+            return Ok(packages);
+        }
+    }
+
+    // The packages that provide other packages are only known once their metadata has been
+    // fetched, so the resolution is retried when it fails and new providers were discovered
+    let mut providers = Providers::default();
+    let packages = loop {
+        let mut discovered = Providers::default();
+        let result = solve_requirements(
+            package_db.clone(),
+            &requirements,
+            env_markers.clone(),
+            compatible_tags.clone(),
+            locked_packages.clone(),
+            favored_packages.clone(),
+            &providers,
+            &mut discovered,
+            options.clone(),
+            env_variables.clone(),
+        );
+
+        let mut new_providers = false;
+        for (name, discovered) in discovered {
+            let known = providers.entry(name).or_default();
+            for provider in discovered {
+                if !known.contains(&provider) {
+                    known.push(provider);
+                    new_providers = true;
+                }
+            }
+        }
+        match result {
+            Err(_) if new_providers => continue,
+            result => break result?,
+        }
+    };
+
+    if let Some((cache, key)) = &cache {
+        if let Err(err) = cache.store(key, &package_db, &packages).await {
+            tracing::warn!("failed to cache the resolution: {err}");
+        }
+    }
+
+    Ok(packages)
+}
+
+/// Solves the requirements once, see [`resolve`]. The packages in `providers` are added as
+/// candidates of the packages they provide. If solving fails and
+/// [`ResolveOptions::honor_provides`] is set, the packages that provide other packages according
+/// to the metadata of the newest versions that were considered are stored in `discovered`.
+#[allow(clippy::too_many_arguments, clippy::result_large_err)]
+fn solve_requirements(
+    package_db: Arc<PackageDb>,
+    requirements: &[&Requirement],
+    env_markers: Arc<MarkerEnvironment>,
+    compatible_tags: Option<Arc<WheelTags>>,
+    locked_packages: HashMap<NormalizedPackageName, PinnedPackage>,
+    favored_packages: HashMap<NormalizedPackageName, PinnedPackage>,
+    providers: &Providers,
+    discovered: &mut Providers,
+    options: ResolveOptions,
+    env_variables: HashMap<String, String>,
+) -> Result<Vec<PinnedPackage>, ResolutionError> {
     // Construct the pool
     let pool = Pool::new();
 
     // Construct HashMap of Name to URL
     let name_to_url: FrozenMap<NormalizedPackageName, String> = FrozenMap::default();
 
+    // The `==` and `===` specifiers of the requirements, only matching versions are considered
+    let mut pinned_versions: HashMap<NormalizedPackageName, Vec<VersionSpecifier>> = HashMap::new();
+
     // Construct the root requirements from the requirements requested by the user.
-    let requirements = requirements.into_iter();
-    let requirement_count = requirements.size_hint();
-    let mut root_requirements =
-        Vec::with_capacity(requirement_count.1.unwrap_or(requirement_count.0));
+    let mut root_requirements = Vec::with_capacity(requirements.len());
 
     for Requirement {
         name,
         version_or_url,
         extras,
         ..
-    } in requirements
+    } in requirements.iter().copied()
     {
-        let name = PackageName::from_str(name).expect("invalid package name");
-        let pypi_name = PypiPackageName::Base(name.clone().into());
+        // Intern the normalized name, like the names of the dependencies of a package, so a
+        // requirement on `Flask` and a dependency on `flask` refer to the same package
+        let name: NormalizedPackageName = PackageName::from_str(name)
+            .expect("invalid package name")
+            .into();
+        let pypi_name = PypiPackageName::Base(name.clone());
         let dependency_package_name = pool.intern_package_name(pypi_name.clone());
         let version_set_id = pool.intern_version_set(
             dependency_package_name,
@@ -291,14 +555,30 @@ pub async fn resolve(
         );
         root_requirements.push(version_set_id);
 
-        if let Some(VersionOrUrl::Url(url)) = version_or_url {
-            name_to_url.insert(pypi_name.base().clone(), url.clone().as_str().to_owned());
+        match version_or_url {
+            Some(VersionOrUrl::Url(url)) => {
+                name_to_url.insert(pypi_name.base().clone(), url.clone().as_str().to_owned());
+            }
+            Some(VersionOrUrl::VersionSpecifier(specifiers)) => {
+                pinned_versions
+                    .entry(pypi_name.base().clone())
+                    .or_default()
+                    .extend(
+                        specifiers
+                            .iter()
+                            .filter(|s| {
+                                matches!(s.operator(), Operator::Equal | Operator::ExactEqual)
+                            })
+                            .cloned(),
+                    );
+            }
+            None => {}
         }
 
         for extra in extras.iter().flatten() {
             let extra: Extra = extra.parse().expect("invalid extra");
-            let dependency_package_name = pool
-                .intern_package_name(PypiPackageName::Extra(name.clone().into(), extra.clone()));
+            let dependency_package_name =
+                pool.intern_package_name(PypiPackageName::Extra(name.clone(), extra.clone()));
             let version_set_id = pool.intern_version_set(
                 dependency_package_name,
                 PypiVersionSet::from_spec(version_or_url.clone(), &options.pre_release_resolution),
@@ -308,41 +588,72 @@ pub async fn resolve(
     }
 
     // Construct the provider
+    let honor_provides = options.honor_provides;
     let provider = PypiDependencyProvider::new(
         pool,
-        package_db,
+        package_db.clone(),
         env_markers,
         compatible_tags,
         locked_packages,
         favored_packages,
         name_to_url,
+        pinned_versions,
+        providers.clone(),
         options,
         env_variables,
     )?;
 
     // Invoke the solver to get a solution to the requirements
     let mut solver = Solver::new(&provider);
-    let solvables = match solver.solve(root_requirements) {
+    let solvables = match solver.solve(root_requirements.clone()) {
         Ok(solvables) => solvables,
         Err(e) => {
+            // The solver either gave up on the blocked package or was cancelled by it
+            if let Some(name) = provider.blocked_package.lock().take() {
+                return Err(blocked_package_error(
+                    name,
+                    solver.pool(),
+                    &provider,
+                    &root_requirements,
+                ));
+            }
             return match e {
-                UnsolvableOrCancelled::Unsolvable(problem) => Err(miette::miette!(
-                    "{}",
-                    problem
-                        .display_user_friendly(&solver, &DefaultSolvableDisplay)
-                        .to_string()
-                        .trim()
-                )),
+                UnsolvableOrCancelled::Unsolvable(problem) => {
+                    if honor_provides {
+                        *discovered = provider.discover_providers();
+                    }
+                    Err(unsolvable_error(
+                        &problem,
+                        &solver,
+                        &provider,
+                        &root_requirements,
+                    ))
+                }
                 UnsolvableOrCancelled::Cancelled(e) => {
-                    let e = e.downcast::<crate::resolve::dependency_provider::MetadataError>().expect("invalid cancellation error message, expected a MetadataError, this indicates an error in the code");
-                    let report = e.deref().clone().into();
-                    Err(report)
+                    let e = e.downcast::<MetadataError>().expect("invalid cancellation error message, expected a MetadataError, this indicates an error in the code");
+                    Err(ResolutionError::Metadata(*e))
                 }
             };
         }
     };
-    let mut result: HashMap<NormalizedPackageName, PinnedPackage> = HashMap::new();
-    for solvable_id in solvables {
+    // A package that is provided by another package is not part of the result, the packages that
+    // depend on it depend on the providing package instead
+    let provided_solvables = provider.provided_solvables.lock();
+    let provided: HashMap<NormalizedPackageName, NormalizedPackageName> = solvables
+        .iter()
+        .filter_map(|solvable_id| {
+            let provider = provided_solvables.get(solvable_id)?;
+            let solvable = solver.pool().resolve_solvable(*solvable_id);
+            let name = solver.pool().resolve_package_name(solvable.name_id());
+            Some((name.base().clone(), provider.name.clone()))
+        })
+        .collect();
+
+    let mut result: BTreeMap<NormalizedPackageName, PinnedPackage> = BTreeMap::new();
+    for &solvable_id in &solvables {
+        if provided_solvables.contains_key(&solvable_id) {
+            continue;
+        }
         let pool = solver.pool();
         let solvable = pool.resolve_solvable(solvable_id);
         let name = pool.resolve_package_name(solvable.name_id());
@@ -362,16 +673,1996 @@ pub async fn resolve(
                     .flatten()
                     .cloned()
                     .collect(),
+                dependencies: Default::default(),
             });
 
         // Add the extra if selected
         if let PypiPackageName::Extra(_, extra) = name {
             entry.extras.insert(extra.clone());
         }
+
+        // Record the dependencies of this solvable (or of the extra)
+        entry.dependencies.extend(
+            provider
+                .cached_dependencies
+                .get(&solvable_id)
+                .into_iter()
+                .flatten()
+                .map(|name| provided.get(name).unwrap_or(name).clone()),
+        );
+    }
+
+    // In strict mode an older version may not be selected because a newer version that satisfies
+    // the requirements on the package is incompatible
+    let pool = solver.pool();
+    let requirements = root_requirements
+        .iter()
+        .map(|&version_set_id| (None, version_set_id))
+        .chain(
+            provider
+                .cached_requirements
+                .lock()
+                .iter()
+                .filter(|(solvable_id, _)| solvables.contains(solvable_id))
+                .map(|&(solvable_id, version_set_id)| (Some(solvable_id), version_set_id)),
+        )
+        .collect::<Vec<_>>();
+    for package in result.values() {
+        let satisfies_requirements = |version: &PypiVersion| {
+            requirements.iter().all(|&(required_by, version_set_id)| {
+                // The extras of a package require the package itself at the selected version
+                let required_by_itself = required_by.is_some_and(|solvable_id| {
+                    let solvable = pool.resolve_solvable(solvable_id);
+                    pool.resolve_package_name(solvable.name_id()).base() == &package.name
+                });
+                let name = pool
+                    .resolve_package_name(pool.resolve_version_set_package_name(version_set_id));
+                required_by_itself
+                    || name.base() != &package.name
+                    || pool.resolve_version_set(version_set_id).contains(version)
+            })
+        };
+        let newer = provider
+            .incompatible_versions(&package.name)
+            .into_iter()
+            .filter(|(version, _)| version > &package.version && satisfies_requirements(version))
+            .max_by(|(a, _), (b, _)| a.cmp(b));
+        if let Some((newer, reason)) = newer {
+            return Err(ResolutionError::IncompatibleNewerVersion {
+                name: package.name.clone(),
+                selected: package.version.clone(),
+                newer,
+                reason: reason.to_string(),
+            });
+        }
     }
 
     Ok(result.into_values().collect())
 }
 
+/// Returns the error for a package that is blocked by [`ResolveOptions::blocked_packages`]. Like
+/// `rip --why`, it explains through which packages the blocked package is required, following the
+/// shortest chain of requirements that the solver looked at back to a requirement of the user.
+fn blocked_package_error(
+    name: NormalizedPackageName,
+    pool: &Pool<PypiVersionSet, PypiPackageName>,
+    provider: &PypiDependencyProvider,
+    root_requirements: &[VersionSetId],
+) -> ResolutionError {
+    let required_name = |version_set_id: VersionSetId| {
+        pool.resolve_package_name(pool.resolve_version_set_package_name(version_set_id))
+            .base()
+            .clone()
+    };
+    let roots = root_requirements
+        .iter()
+        .map(|&version_set_id| required_name(version_set_id))
+        .collect::<HashSet<_>>();
+
+    // The solvables that require each package, in the order in which they were looked at
+    let mut required_by: HashMap<NormalizedPackageName, Vec<SolvableId>> = HashMap::new();
+    for &(solvable_id, version_set_id) in provider.cached_requirements.lock().iter() {
+        let solvable = pool.resolve_solvable(solvable_id);
+        let dependency = required_name(version_set_id);
+        if pool.resolve_package_name(solvable.name_id()).base() != &dependency {
+            required_by.entry(dependency).or_default().push(solvable_id);
+        }
+    }
+
+    // Search breadth first from the blocked package towards the requirements of the user, for
+    // every package remember the solvable through which it requires the previous package
+    let mut towards_blocked: HashMap<NormalizedPackageName, (SolvableId, NormalizedPackageName)> =
+        HashMap::new();
+    let mut queue = VecDeque::from([name.clone()]);
+    let mut visited = HashSet::from([name.clone()]);
+    let mut furthest = name.clone();
+    while let Some(package) = queue.pop_front() {
+        furthest = package.clone();
+        if roots.contains(&package) {
+            break;
+        }
+        for &solvable_id in required_by.get(&package).into_iter().flatten() {
+            let solvable = pool.resolve_solvable(solvable_id);
+            let dependent = pool.resolve_package_name(solvable.name_id()).base();
+            if visited.insert(dependent.clone()) {
+                towards_blocked.insert(dependent.clone(), (solvable_id, package.clone()));
+                queue.push_back(dependent.clone());
+            }
+        }
+    }
+
+    let mut chain = Vec::new();
+    let mut package = furthest;
+    while let Some((solvable_id, dependency)) = towards_blocked.remove(&package) {
+        let version = pool.resolve_solvable(solvable_id).inner().clone();
+        chain.push((package, version));
+        package = dependency;
+    }
+    chain.reverse();
+
+    let message = if chain.is_empty() {
+        format!("{name} is blocked but it was requested")
+    } else {
+        let mut message = format!("{name} is blocked but it is required by:\n{name}");
+        for (depth, (dependent, version)) in chain.iter().enumerate() {
+            message.push_str(&format!(
+                "\n{}└── {dependent} {version}",
+                "    ".repeat(depth)
+            ));
+        }
+        message
+    };
+    ResolutionError::BlockedPackage {
+        name,
+        chain,
+        message,
+    }
+}
+
+/// Converts the problem reported by the solver into a [`ResolutionError`] that describes the
+/// requirements that are involved.
+fn unsolvable_error(
+    problem: &Problem,
+    solver: &Solver<PypiVersionSet, PypiPackageName, &PypiDependencyProvider>,
+    provider: &PypiDependencyProvider,
+    root_requirements: &[VersionSetId],
+) -> ResolutionError {
+    let message = problem
+        .display_user_friendly(solver, &DefaultSolvableDisplay)
+        .to_string()
+        .trim()
+        .to_owned();
+    let pool = solver.pool();
+
+    // The structure of the problem is not exposed by the solver (resolvo 0.3.0 can only write
+    // the conflict graph for pools with `String` package names), so the requirements that are
+    // mentioned in the explanation are matched with the requirements that were passed to it.
+    let lines = problem_requirements(&message);
+    if lines.is_empty() {
+        // The wording of the explanation is not recognized, the message is still complete
+        tracing::warn!("could not determine the requirements that are involved in the conflict");
+        return ResolutionError::Conflict {
+            requirements: Vec::new(),
+            message,
+        };
+    }
+
+    let requirements = root_requirements
+        .iter()
+        .map(|&version_set_id| (None, version_set_id))
+        .chain(
+            provider
+                .cached_requirements
+                .lock()
+                .iter()
+                .map(|&(solvable_id, version_set_id)| (Some(solvable_id), version_set_id)),
+        )
+        .collect::<Vec<_>>();
+
+    let mut involved = Vec::new();
+    let mut missing = Vec::new();
+    for line in lines {
+        for &(required_by, version_set_id) in &requirements {
+            let name =
+                pool.resolve_package_name(pool.resolve_version_set_package_name(version_set_id));
+            let version_set = pool.resolve_version_set(version_set_id);
+            if name.to_string() != line.name || version_set.to_string() != line.specifiers {
+                continue;
+            }
+
+            let required_by = match (required_by, &line.required_by) {
+                (None, None) => None,
+                (Some(solvable_id), Some((parent_name, parent_versions))) => {
+                    let solvable = pool.resolve_solvable(solvable_id);
+                    let solvable_name = pool.resolve_package_name(solvable.name_id());
+                    if &solvable_name.to_string() != parent_name
+                        || !parent_versions.contains(&solvable.inner().to_string())
+                    {
+                        continue;
+                    }
+                    Some((solvable_name.base().clone(), solvable.inner().clone()))
+                }
+                _ => continue,
+            };
+
+            let requirement = ConflictingRequirement {
+                required_by,
+                name: name.base().clone(),
+                extra: name.extra().cloned(),
+                specifiers: version_set.clone(),
+            };
+            if line.missing && !missing.contains(&requirement) {
+                missing.push(requirement.clone());
+            }
+            if !involved.contains(&requirement) {
+                involved.push(requirement);
+            }
+        }
+    }
+
+    // Requirements of the user come first
+    let sort_key = |requirement: &ConflictingRequirement| {
+        (
+            requirement.required_by.clone(),
+            requirement.name.clone(),
+            requirement.extra.clone(),
+        )
+    };
+    involved.sort_by_key(sort_key);
+    missing.sort_by_key(sort_key);
+
+    match missing.into_iter().next() {
+        Some(requirement) => {
+            let name = requirement.name.clone();
+            match provider.fetch_failures.lock().remove(&name) {
+                Some(error) => ResolutionError::FetchFailed { name, error },
+                None => ResolutionError::NoCandidates {
+                    name,
+                    requirement: Box::new(requirement),
+                    message,
+                },
+            }
+        }
+        None => ResolutionError::Conflict {
+            requirements: involved,
+            message,
+        },
+    }
+}
+
+/// A requirement that is mentioned in the explanation of an unsolvable problem.
+#[derive(Debug, PartialEq, Eq)]
+struct ProblemRequirement {
+    /// The name and the versions of the package that has the requirement, or `None` for a
+    /// requirement of the user.
+    required_by: Option<(String, Vec<String>)>,
+    name: String,
+    specifiers: String,
+    /// True if there are no candidates for the requirement
+    missing: bool,
+}
+
+/// Extracts the requirements from the explanation of an unsolvable problem as produced by
+/// [`Problem::display_user_friendly`]. The wording is that of resolvo 0.3.0, which is why the
+/// dependency is pinned to that exact version.
+///
+/// The explanation is a tree in which every line is indented by four spaces per level and starts
+/// with `|-- `. Requirements are written as `<name> <specifiers>` followed by a fixed phrase, the
+/// candidates that have a requirement are written as `<name> <versions> would require` with the
+/// versions separated by ` | `.
+fn problem_requirements(message: &str) -> Vec<ProblemRequirement> {
+    // The phrases that follow a requirement and whether they indicate that there are no
+    // candidates. Some phrases end with another one, so the longest ones have to come first.
+    const REQUIREMENT_SUFFIXES: [(&str, bool); 5] = [
+        (", for which no candidates were found.", true),
+        (
+            ", which can be installed with any of the following options:",
+            false,
+        ),
+        (
+            ", which cannot be installed because there are no viable options:",
+            false,
+        ),
+        (
+            " can be installed with any of the following options:",
+            false,
+        ),
+        (
+            " cannot be installed because there are no viable options:",
+            false,
+        ),
+    ];
+
+    // The candidates that have a requirement, indexed by depth
+    let mut parents: Vec<Option<(String, Vec<String>)>> = Vec::new();
+    let mut requirements = Vec::new();
+    for line in message.lines() {
+        let content = line.trim_start_matches(' ');
+        let depth = (line.len() - content.len()) / 4;
+        let content = content.strip_prefix("|-- ").unwrap_or(content);
+        parents.truncate(depth);
+
+        if let Some(candidate) = content.strip_suffix(" would require") {
+            let Some((name, versions)) = candidate.split_once(' ') else {
+                continue;
+            };
+            parents.resize(depth, None);
+            parents.push(Some((
+                name.to_owned(),
+                versions.split(" | ").map(ToOwned::to_owned).collect(),
+            )));
+            continue;
+        }
+
+        let requirement = content
+            .strip_prefix("No candidates were found for ")
+            .and_then(|r| r.strip_suffix('.'))
+            .map(|r| (r, true))
+            .or_else(|| {
+                REQUIREMENT_SUFFIXES
+                    .iter()
+                    .find_map(|(suffix, missing)| Some((content.strip_suffix(suffix)?, *missing)))
+            });
+        let Some((Some((name, specifiers)), missing)) =
+            requirement.map(|(r, missing)| (r.split_once(' '), missing))
+        else {
+            continue;
+        };
+
+        let required_by = depth
+            .checked_sub(1)
+            .and_then(|parent| parents.get(parent).cloned().flatten());
+        requirements.push(ProblemRequirement {
+            required_by,
+            name: name.to_owned(),
+            specifiers: specifiers.to_owned(),
+            missing,
+        });
+    }
+    requirements
+}
+
+/// Returns a representation of the options that influence the result of a resolution, this is
+/// part of the key of a cached resolution.
+///
+/// The options are destructured without a rest pattern, so a new option does not compile until
+/// it is decided here whether it is part of the key.
+fn cache_options(options: &ResolveOptions) -> Vec<String> {
+    let ResolveOptions {
+        sdist_resolution,
+        python_location,
+        pre_release_resolution,
+        skip_unreliable_sdists,
+        no_build,
+        only_newest,
+        strict_markers,
+        strict,
+        pure_python_only,
+        python_range,
+        interpreter_constraints,
+        honor_provides,
+        ignored_dependencies,
+        blocked_packages,
+        // These only determine what happens to build environments, how failures and progress
+        // are reported or what is recorded during solving
+        clean_env: _,
+        on_wheel_build_failure: _,
+        skipped_artifacts: _,
+        summary: _,
+        max_versions_fetched: _,
+        progress_observer: _,
+        resolution_cache: _,
+        // Resolutions that use these are never cached
+        timeout_per_package: _,
+        candidate_sorter: _,
+    } = options;
+    vec![
+        format!("sdist_resolution={sdist_resolution:?}"),
+        format!("python_location={python_location:?}"),
+        format!("pre_release_resolution={pre_release_resolution:?}"),
+        format!("skip_unreliable_sdists={skip_unreliable_sdists}"),
+        format!("no_build={no_build}"),
+        format!("only_newest={only_newest:?}"),
+        format!("strict_markers={strict_markers}"),
+        format!("strict={strict}"),
+        format!("pure_python_only={pure_python_only}"),
+        format!("python_range={python_range:?}"),
+        format!(
+            "interpreter_constraints={}",
+            interpreter_constraints.iter().join(",")
+        ),
+        format!("honor_provides={honor_provides}"),
+        format!(
+            "ignored_dependencies={}",
+            ignored_dependencies.iter().join(",")
+        ),
+        format!(
+            "blocked_packages={}",
+            blocked_packages.iter().sorted().join(",")
+        ),
+    ]
+}
+
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+    use crate::python_env::{CPythonAbi, Pep508EnvMakers, PythonInterpreterVersion, WheelTag};
+    use crate::resolve::test_support::{
+        package_db, package_db_with, write_sdist, write_wheel, IndexArtifact,
+    };
+    use crate::resolve::{Candidate, LockedVersions, SkipReason, VersionPreferences};
+    use crate::types::{ArtifactHashes, ArtifactName, DistInfoMetadata, Yanked};
+    use indexmap::IndexMap;
+    use itertools::Itertools;
+    use reqwest::Client;
+    use std::path::Path;
+    use url::Url;
+
+    async fn resolve_requirements(
+        package_db: Arc<PackageDb>,
+        requirements: &[&str],
+        options: ResolveOptions,
+    ) -> Result<Vec<PinnedPackage>, ResolutionError> {
+        let requirements = requirements
+            .iter()
+            .map(|r| Requirement::from_str(r).unwrap())
+            .collect::<Vec<_>>();
+        resolve(
+            package_db,
+            &requirements,
+            Arc::new(Pep508EnvMakers::from_env().await.unwrap().0),
+            Some(Arc::new(
+                [WheelTag::from_str("py3-none-any").unwrap()]
+                    .into_iter()
+                    .collect(),
+            )),
+            HashMap::default(),
+            HashMap::default(),
+            options,
+            HashMap::default(),
+        )
+        .await
+    }
+
+    async fn resolve_with_cache(
+        package_db: Arc<PackageDb>,
+        resolution_cache: &ResolutionCache,
+    ) -> Vec<(String, String, Vec<Url>)> {
+        let options = ResolveOptions {
+            resolution_cache: Some(resolution_cache.clone()),
+            ..Default::default()
+        };
+        resolve_requirements(package_db, &["foo"], options)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| {
+                (
+                    p.name.to_string(),
+                    p.version.to_string(),
+                    p.artifacts.iter().map(|a| a.url.clone()).collect(),
+                )
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolution_cache() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let resolution_cache = ResolutionCache::new(cache_dir.path().join("resolutions"));
+        let expected = |bar: &str| {
+            ["bar", "foo"]
+                .into_iter()
+                .zip([bar, "1.0"])
+                .map(|(name, version)| {
+                    let url = format!("https://example.invalid/{name}-{version}-py3-none-any.whl");
+                    (
+                        name.to_string(),
+                        version.to_string(),
+                        vec![url.parse().unwrap()],
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let packages = [("foo", "1.0", "Requires-Dist: bar\n"), ("bar", "1.0", "")];
+        let package_db = package_db(cache_dir.path(), &packages).await;
+        let first = resolve_with_cache(package_db, &resolution_cache).await;
+        assert_eq!(first, expected("1.0"));
+
+        // The cached resolution is used without running the solver, which would fail because
+        // the changed metadata requires a package that does not exist. Only the index is checked.
+        let package_db = self::package_db(
+            cache_dir.path(),
+            &[("foo", "1.0", "Requires-Dist: missing\n"), packages[1]],
+        )
+        .await;
+        let second = resolve_with_cache(package_db, &resolution_cache).await;
+        assert_eq!(second, first);
+
+        // A new release of a resolved package invalidates the cached resolution
+        let package_db = self::package_db(
+            cache_dir.path(),
+            &[packages[0], packages[1], ("bar", "1.1", "")],
+        )
+        .await;
+        let third = resolve_with_cache(package_db, &resolution_cache).await;
+        assert_eq!(third, expected("1.1"));
+    }
+
+    /// Formats the requirements of a resolution error.
+    fn format_requirements(requirements: &[ConflictingRequirement]) -> Vec<String> {
+        requirements.iter().map(ToString::to_string).collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolution_is_deterministic() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                (
+                    "app",
+                    "1.0",
+                    "Provides-Extra: zeta\nProvides-Extra: alpha\nProvides-Extra: mid\n\
+                     Requires-Dist: lib[fast,extra-b,extra-a]\n\
+                     Requires-Dist: zlib; extra == 'zeta'\n\
+                     Requires-Dist: alib; extra == 'alpha'\n\
+                     Requires-Dist: mlib>=1.1; extra == 'mid'\n",
+                ),
+                (
+                    "lib",
+                    "1.0",
+                    "Provides-Extra: fast\nProvides-Extra: extra-b\nProvides-Extra: extra-a\n\
+                     Requires-Dist: mlib; extra == 'fast'\n",
+                ),
+                ("zlib", "1.0", "Requires-Dist: mlib<2\n"),
+                ("alib", "1.0", "Requires-Dist: mlib\n"),
+                ("mlib", "1.0", ""),
+                ("mlib", "1.1", ""),
+                ("mlib", "2.0", ""),
+            ],
+        )
+        .await;
+
+        // Every resolution has its own unordered maps with their own random iteration order
+        let mut outputs = Vec::new();
+        for _ in 0..5 {
+            let packages = resolve_requirements(
+                package_db.clone(),
+                &["app[zeta,alpha,mid]"],
+                Default::default(),
+            )
+            .await
+            .unwrap();
+            outputs.push(format!(
+                "{}\n{packages:?}",
+                serde_json::to_string(&packages).unwrap()
+            ));
+        }
+        assert!(outputs.iter().all_equal(), "{outputs:#?}");
+        assert!(
+            outputs[0].starts_with(
+                r#"[{"name":"alib","version":"1.0","extras":[],"dependencies":["mlib"]},{"name":"app","version":"1.0","extras":["alpha","mid","zeta"],"#
+            ),
+            "{}",
+            outputs[0]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolution_is_sorted_by_name() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                (
+                    "zeta",
+                    "1.0",
+                    "Requires-Dist: Mid_Package\nRequires-Dist: alpha\n",
+                ),
+                ("mid-package", "1.0", "Requires-Dist: beta\n"),
+                ("alpha", "1.0", ""),
+                ("beta", "1.0", ""),
+                ("Omega", "1.0", ""),
+            ],
+        )
+        .await;
+
+        for requirements in [&["zeta", "omega"][..], &["omega", "zeta", "beta"][..]] {
+            let packages =
+                resolve_requirements(package_db.clone(), requirements, Default::default())
+                    .await
+                    .unwrap();
+            let names = packages
+                .iter()
+                .map(|p| p.name.as_str().to_owned())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["alpha", "beta", "mid-package", "omega", "zeta"]);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_custom_candidate_sorter() {
+        /// Prefers the lowest version of every package
+        struct LowestFirst;
+
+        impl CandidateSorter for LowestFirst {
+            fn sort(&self, _package: &NormalizedPackageName, candidates: &mut [Candidate<'_>]) {
+                candidates.reverse();
+            }
+        }
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("foo", "1.0", "Requires-Dist: bar>=1.5\n"),
+                ("foo", "2.0", "Requires-Dist: bar\n"),
+                ("foo", "3.0", "Requires-Dist: bar\n"),
+                ("bar", "1.0", ""),
+                ("bar", "2.0", ""),
+            ],
+        )
+        .await;
+        let versions = |packages: Vec<PinnedPackage>| {
+            packages
+                .into_iter()
+                .map(|p| format!("{}=={}", p.name.as_str(), p.version))
+                .collect::<Vec<_>>()
+        };
+
+        let packages = resolve_requirements(package_db.clone(), &["foo"], Default::default())
+            .await
+            .unwrap();
+        assert_eq!(versions(packages), ["bar==2.0", "foo==3.0"]);
+
+        let options = ResolveOptions {
+            candidate_sorter: Some(Arc::new(LowestFirst)),
+            ..Default::default()
+        };
+        let packages = resolve_requirements(package_db, &["foo"], options)
+            .await
+            .unwrap();
+        assert_eq!(versions(packages), ["bar==2.0", "foo==1.0"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_version_preferences() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("django", "4.1", ""),
+                ("django", "4.2", ""),
+                ("django", "5.0", ""),
+                ("app", "1.0", "Requires-Dist: django>=5\n"),
+            ],
+        )
+        .await;
+        let options = || ResolveOptions {
+            candidate_sorter: Some(Arc::new(
+                VersionPreferences::from_str("Django==4.2.0\n").unwrap(),
+            )),
+            ..Default::default()
+        };
+        let versions = |packages: Vec<PinnedPackage>| {
+            packages
+                .into_iter()
+                .map(|p| format!("{}=={}", p.name.as_str(), p.version))
+                .collect::<Vec<_>>()
+        };
+
+        // The preferred version is selected although a newer version is available
+        let packages = resolve_requirements(package_db.clone(), &["django"], options())
+            .await
+            .unwrap();
+        assert_eq!(versions(packages), ["django==4.2"]);
+
+        // The preference is ignored if it does not satisfy the requirements
+        let packages = resolve_requirements(package_db, &["django", "app"], options())
+            .await
+            .unwrap();
+        assert_eq!(versions(packages), ["app==1.0", "django==5.0"]);
+    }
+
+    /// Records the package events
+    #[derive(Default)]
+    struct Recorder(parking_lot::Mutex<Vec<String>>);
+
+    impl ProgressObserver for Recorder {
+        fn on_package_started(&self, name: &NormalizedPackageName, version: &PypiVersion) {
+            self.0.lock().push(format!("started {name} {version}"));
+        }
+
+        fn on_package_finished(&self, name: &NormalizedPackageName, version: &PypiVersion) {
+            self.0.lock().push(format!("finished {name} {version}"));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_progress_observer() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                (
+                    "foo",
+                    "1.0",
+                    "Provides-Extra: x\nRequires-Dist: bar\nRequires-Dist: baz; extra == 'x'\n",
+                ),
+                ("bar", "1.0", ""),
+                ("baz", "1.0", ""),
+            ],
+        )
+        .await;
+        let recorder = Arc::new(Recorder::default());
+        let options = ResolveOptions {
+            progress_observer: Some(recorder.clone()),
+            ..Default::default()
+        };
+        resolve_requirements(package_db, &["foo[x]"], options)
+            .await
+            .unwrap();
+
+        // Every package is reported once, the extra of foo is not reported separately
+        let events = recorder.0.lock().clone();
+        for pair in events.chunks(2) {
+            assert_eq!(pair[0].replace("started", "finished"), pair[1]);
+        }
+        assert_eq!(
+            events.into_iter().sorted().collect::<Vec<_>>(),
+            [
+                "finished bar 1.0",
+                "finished baz 1.0",
+                "finished foo 1.0",
+                "started bar 1.0",
+                "started baz 1.0",
+                "started foo 1.0",
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_metadata_is_fetched_lazily() {
+        // Only the newest version of each package has metadata, fetching the metadata of any
+        // other version fails
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db_with(
+            cache_dir.path(),
+            (1..10)
+                .map(|major| IndexArtifact::new("foo", &format!("foo-{major}.0-py3-none-any.whl")))
+                .chain([
+                    IndexArtifact::wheel("foo", "10.0", "Requires-Dist: bar\n"),
+                    IndexArtifact::new("bar", "bar-1.0-py3-none-any.whl"),
+                    IndexArtifact::wheel("bar", "2.0", "Requires-Dist: baz\n"),
+                    IndexArtifact::wheel("baz", "1.0", ""),
+                    IndexArtifact::new("unused", "unused-1.0-py3-none-any.whl"),
+                ]),
+        )
+        .await;
+
+        // The metadata is requested while solving, only for the versions that are selected
+        let recorder = Arc::new(Recorder::default());
+        let options = ResolveOptions {
+            progress_observer: Some(recorder.clone()),
+            ..Default::default()
+        };
+        let packages = resolve_requirements(package_db, &["foo"], options)
+            .await
+            .unwrap();
+        assert_eq!(
+            packages
+                .iter()
+                .map(|p| format!("{}=={}", p.name, p.version))
+                .sorted()
+                .collect::<Vec<_>>(),
+            ["bar==2.0", "baz==1.0", "foo==10.0"]
+        );
+        let started = recorder
+            .0
+            .lock()
+            .iter()
+            .filter(|event| event.starts_with("started"))
+            .cloned()
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            started,
+            ["started bar 2.0", "started baz 1.0", "started foo 10.0"]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_yanked_pin() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db_with(
+            cache_dir.path(),
+            [
+                IndexArtifact::wheel("click", "8.1.7", "").yanked("broken release"),
+                IndexArtifact::wheel("click", "8.1.6", ""),
+            ],
+        )
+        .await;
+
+        // The reason of the index is reported without recording the skipped artifacts
+        let err = resolve_requirements(package_db, &["click==8.1.7"], Default::default())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("it is yanked (broken release)"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_exact_pin() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("foo", "1.0", "Requires-Dist: bar\n"),
+                ("foo", "2.0", "Requires-Dist: bar\n"),
+                ("foo", "3.0a1", ""),
+                ("bar", "1.0", ""),
+                ("app", "1.0", "Requires-Dist: foo>=2\n"),
+            ],
+        )
+        .await;
+
+        // Only the metadata of the pinned version is requested and the artifacts of the other
+        // versions are not even looked at, so the pre-release is not skipped either
+        let recorder = Arc::new(Recorder::default());
+        let skipped = SkippedArtifacts::default();
+        let options = ResolveOptions {
+            progress_observer: Some(recorder.clone()),
+            skipped_artifacts: Some(skipped.clone()),
+            ..Default::default()
+        };
+        let packages = resolve_requirements(package_db.clone(), &["foo==1.0"], options)
+            .await
+            .unwrap();
+        assert_eq!(
+            packages
+                .iter()
+                .map(|p| format!("{}=={}", p.name, p.version))
+                .sorted()
+                .collect::<Vec<_>>(),
+            ["bar==1.0", "foo==1.0"]
+        );
+        let started = recorder
+            .0
+            .lock()
+            .iter()
+            .filter(|event| event.starts_with("started"))
+            .cloned()
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(started, ["started bar 1.0", "started foo 1.0"]);
+        assert!(skipped.is_empty());
+
+        // A pinned version that does not exist is still an error
+        let err = resolve_requirements(package_db.clone(), &["foo==1.5"], Default::default())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, ResolutionError::NoCandidates { name, .. } if name.as_str() == "foo"),
+            "{err:?}"
+        );
+
+        // The versions that are not pinned still explain conflicts
+        let err = resolve_requirements(package_db, &["foo==1.0", "app"], Default::default())
+            .await
+            .unwrap_err();
+        let ResolutionError::Conflict { requirements, .. } = &err else {
+            panic!("expected a conflict, got {err:?}");
+        };
+        assert_eq!(
+            format_requirements(requirements),
+            ["the user requested app *", "app=1.0 requires foo >=2",]
+        );
+        assert!(err
+            .to_string()
+            .contains("foo 2.0 is excluded because the user requested ==1.0"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolution_error_no_candidates() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("foo", "1.0", "Requires-Dist: bar>=2\n"),
+                ("bar", "1.0", ""),
+            ],
+        )
+        .await;
+
+        // A requirement of the user without matching versions
+        let err = resolve_requirements(package_db.clone(), &["foo>=2"], Default::default())
+            .await
+            .unwrap_err();
+        let ResolutionError::NoCandidates {
+            name, requirement, ..
+        } = &err
+        else {
+            panic!("expected no candidates, got {err:?}");
+        };
+        assert_eq!(name.as_str(), "foo");
+        assert_eq!(requirement.to_string(), "the user requested foo >=2");
+        assert!(err
+            .to_string()
+            .contains("No candidates were found for foo >=2"));
+
+        // A requirement of a dependency without matching versions
+        let err = resolve_requirements(package_db, &["foo"], Default::default())
+            .await
+            .unwrap_err();
+        let ResolutionError::NoCandidates {
+            name, requirement, ..
+        } = &err
+        else {
+            panic!("expected no candidates, got {err:?}");
+        };
+        assert_eq!(name.as_str(), "bar");
+        assert_eq!(requirement.to_string(), "foo=1.0 requires bar >=2");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolution_error_conflict() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("foo", "1.0", "Requires-Dist: bar<2\n"),
+                ("foo", "1.1", "Requires-Dist: bar<2\n"),
+                ("bar", "1.0", ""),
+                ("bar", "2.0", ""),
+                ("baz", "1.0", ""),
+            ],
+        )
+        .await;
+
+        let err = resolve_requirements(package_db, &["foo", "bar>=2", "baz"], Default::default())
+            .await
+            .unwrap_err();
+        let ResolutionError::Conflict { requirements, .. } = &err else {
+            panic!("expected a conflict, got {err:?}");
+        };
+        assert_eq!(
+            format_requirements(requirements),
+            [
+                "the user requested bar >=2",
+                "the user requested foo *",
+                "foo=1.0 requires bar <2",
+                "foo=1.1 requires bar <2",
+            ]
+        );
+        assert!(err
+            .to_string()
+            .starts_with("The following packages are incompatible"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolution_error_fetch_failed() {
+        // Nothing listens on the discard port, so fetching from this index fails
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(
+                Client::new().into(),
+                &[Url::parse("http://127.0.0.1:9/simple/").unwrap()],
+                cache_dir.path(),
+            )
+            .unwrap(),
+        );
+
+        let err = resolve_requirements(package_db, &["foo"], Default::default())
+            .await
+            .unwrap_err();
+        let ResolutionError::FetchFailed { name, .. } = &err else {
+            panic!("expected a fetch failure, got {err:?}");
+        };
+        assert_eq!(name.as_str(), "foo");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolution_error_metadata() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[(
+                "foo",
+                "1.0",
+                "Requires-Dist: bar ; python_verison >= \"3.8\"\n",
+            )],
+        )
+        .await;
+
+        let options = ResolveOptions {
+            strict_markers: true,
+            ..Default::default()
+        };
+        let err = resolve_requirements(package_db, &["foo"], options)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ResolutionError::Metadata(MetadataError::UnknownMarkerVariable { .. })
+            ),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_max_versions_fetched() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        // Only the oldest version of foo has dependencies that can be satisfied
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("foo", "3.0", "Requires-Dist: bar>=5\n"),
+                ("foo", "2.0", "Requires-Dist: bar>=5\n"),
+                ("foo", "1.0", "Requires-Dist: bar\n"),
+                ("bar", "1.0", ""),
+            ],
+        )
+        .await;
+
+        let options = |budget| ResolveOptions {
+            max_versions_fetched: Some(budget),
+            ..Default::default()
+        };
+        let err = resolve_requirements(package_db.clone(), &["foo"], options(2))
+            .await
+            .unwrap_err();
+        let ResolutionError::Metadata(MetadataError::FetchBudgetExceeded { budget, packages }) =
+            &err
+        else {
+            panic!("expected the fetch budget to be exceeded, got {err:?}");
+        };
+        assert_eq!(*budget, 2);
+        assert_eq!(packages, "foo: 2");
+        assert!(err.to_string().contains("more than 2 package versions"));
+
+        let packages = resolve_requirements(package_db, &["foo"], options(4))
+            .await
+            .unwrap();
+        assert_eq!(
+            packages
+                .iter()
+                .map(|p| (p.name.as_str(), p.version.to_string()))
+                .collect::<Vec<_>>(),
+            [("bar", String::from("1.0")), ("foo", String::from("1.0"))]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_names_differing_by_normalization() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("flask", "3.0.0", ""),
+                ("flask-login", "0.6.3", "Requires-Dist: flask>=1.0.4\n"),
+            ],
+        )
+        .await;
+
+        let packages = resolve_requirements(
+            package_db,
+            &["Flask[Async]==3.0.0", "Flask_Login"],
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            packages
+                .iter()
+                .map(|p| (p.name.as_str(), p.version.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                ("flask", String::from("3.0.0")),
+                ("flask-login", String::from("0.6.3"))
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_platform_priority() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(
+                Client::new().into(),
+                &[Url::parse("https://example.com/simple/").unwrap()],
+                cache_dir.path(),
+            )
+            .unwrap(),
+        );
+
+        // Two wheels of the same version that are both compatible
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let mut artifacts = Vec::new();
+        for filename in [
+            "foo-1.0-cp311-cp311-manylinux_2_28_x86_64.whl",
+            "foo-1.0-cp311-cp311-manylinux_2_17_x86_64.manylinux2014_x86_64.whl",
+        ] {
+            let artifact = Arc::new(ArtifactInfo {
+                filename: ArtifactName::from_filename(filename, &name).unwrap(),
+                url: format!("https://example.com/{filename}").parse().unwrap(),
+                hashes: Some(ArtifactHashes {
+                    sha256: Some(
+                        rattler_digest::compute_bytes_digest::<rattler_digest::Sha256>(
+                            filename.as_bytes(),
+                        ),
+                    ),
+                    ..Default::default()
+                }),
+                requires_python: None,
+                dist_info_metadata: DistInfoMetadata::default(),
+                yanked: Yanked::default(),
+                index_url: None,
+            });
+            package_db
+                .put_metadata_in_cache(
+                    &artifact,
+                    b"Metadata-Version: 2.1\nName: foo\nVersion: 1.0\n",
+                )
+                .await
+                .unwrap();
+            artifacts.push(artifact);
+        }
+        package_db.insert_artifacts(
+            name,
+            [(
+                PypiVersion::Version {
+                    version: "1.0".parse().unwrap(),
+                    package_allows_prerelease: false,
+                },
+                artifacts,
+            )]
+            .into_iter()
+            .collect(),
+        );
+
+        let version = PythonInterpreterVersion::new(3, 11, 0);
+        let env_markers =
+            Pep508EnvMakers::for_target_platform("linux-x86_64".parse().unwrap(), &version).0;
+        let tags = WheelTags::cpython(
+            &version,
+            CPythonAbi::Gil,
+            &[
+                "manylinux_2_28_x86_64",
+                "manylinux_2_17_x86_64",
+                "manylinux2014_x86_64",
+            ],
+        );
+        let selected = |tags: WheelTags| {
+            let package_db = package_db.clone();
+            let env_markers = env_markers.clone();
+            async move {
+                let packages = resolve(
+                    package_db,
+                    &[Requirement::from_str("foo").unwrap()],
+                    Arc::new(env_markers),
+                    Some(Arc::new(tags)),
+                    HashMap::default(),
+                    HashMap::default(),
+                    Default::default(),
+                    HashMap::default(),
+                )
+                .await
+                .unwrap();
+                packages[0].artifacts[0].filename.to_string()
+            }
+        };
+
+        // By default the most specific platform is preferred
+        assert_eq!(
+            selected(tags.clone()).await,
+            "foo-1.0-cp311-cp311-manylinux_2_28_x86_64.whl"
+        );
+        assert_eq!(
+            selected(tags.with_platform_priority(&["manylinux2014_x86_64"])).await,
+            "foo-1.0-cp311-cp311-manylinux_2_17_x86_64.manylinux2014_x86_64.whl"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_timeout_per_package() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(cache_dir.path(), &[("foo", "1.0", "")]).await;
+
+        // The newest version is served by a server that accepts connections but never responds
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let name: NormalizedPackageName = "foo".parse().unwrap();
+        let filename = "foo-2.0-py3-none-any.whl";
+        let slow = Arc::new(ArtifactInfo {
+            filename: ArtifactName::from_filename(filename, &name).unwrap(),
+            url: format!("http://{}/{filename}", listener.local_addr().unwrap())
+                .parse()
+                .unwrap(),
+            hashes: None,
+            requires_python: None,
+            dist_info_metadata: DistInfoMetadata::default(),
+            yanked: Yanked::default(),
+            index_url: None,
+        });
+        let mut versions = IndexMap::new();
+        versions.insert(
+            PypiVersion::Version {
+                version: "2.0".parse().unwrap(),
+                package_allows_prerelease: false,
+            },
+            vec![slow],
+        );
+        versions.extend(
+            package_db
+                .available_artifacts(name.clone())
+                .await
+                .unwrap()
+                .iter()
+                .map(|(version, artifacts)| (version.clone(), artifacts.clone())),
+        );
+        package_db.clear_memory_cache();
+        package_db.insert_artifacts(name, versions);
+
+        // The slow version is excluded and reported
+        let skipped = SkippedArtifacts::default();
+        let options = |strict| ResolveOptions {
+            timeout_per_package: Some(Duration::from_millis(200)),
+            skipped_artifacts: Some(skipped.clone()),
+            strict,
+            ..Default::default()
+        };
+        let packages = resolve_requirements(package_db.clone(), &["foo"], options(false))
+            .await
+            .unwrap();
+        assert_eq!(packages[0].version.to_string(), "1.0");
+        assert_eq!(
+            skipped
+                .by_package()
+                .into_values()
+                .flatten()
+                .map(|(artifact, reason)| (artifact.to_string(), reason))
+                .collect::<Vec<_>>(),
+            [(filename.to_string(), SkipReason::MetadataTimeout)]
+        );
+
+        let err = resolve_requirements(package_db, &["foo"], options(true))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                ResolutionError::Metadata(MetadataError::MetadataTimeout { package, .. })
+                    if package == "foo 2.0"
+            ),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolve_from_find_links_without_index() {
+        let find_links = tempfile::tempdir().unwrap();
+        write_wheel(find_links.path(), "foo", "1.0", &["bar>=2"]);
+        write_wheel(find_links.path(), "bar", "1.0", &[]);
+        write_wheel(find_links.path(), "bar", "2.0", &[]);
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(Client::new().into(), &[], cache_dir.path())
+                .unwrap()
+                .with_find_links([find_links.path().to_path_buf()]),
+        );
+
+        let resolved = resolve_requirements(package_db.clone(), &["foo"], Default::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| (p.name.to_string(), p.version.to_string()))
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            [
+                ("bar".to_string(), "2.0".to_string()),
+                ("foo".to_string(), "1.0".to_string())
+            ]
+        );
+
+        // A package that is not in the find-links directory cannot be found anywhere
+        let err = resolve_requirements(package_db, &["baz"], Default::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no index is configured"), "{err}");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_strict_incompatible_newer_version() {
+        let find_links = tempfile::tempdir().unwrap();
+        write_wheel(find_links.path(), "foo", "1.0", &[]);
+        write_wheel(find_links.path(), "foo", "2.0", &[]);
+        fs_err::rename(
+            find_links.path().join("foo-2.0-py3-none-any.whl"),
+            find_links.path().join("foo-2.0-cp27-cp27m-win32.whl"),
+        )
+        .unwrap();
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(Client::new().into(), &[], cache_dir.path())
+                .unwrap()
+                .with_find_links([find_links.path().to_path_buf()]),
+        );
+
+        // Normally the older version is selected silently
+        let resolved = resolve_requirements(package_db.clone(), &["foo"], Default::default())
+            .await
+            .unwrap();
+        assert_eq!(resolved[0].version.to_string(), "1.0");
+
+        let strict = ResolveOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let err = resolve_requirements(package_db.clone(), &["foo"], strict.clone())
+            .await
+            .unwrap_err();
+        let ResolutionError::IncompatibleNewerVersion {
+            name,
+            selected,
+            newer,
+            ..
+        } = &err
+        else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(
+            (name.as_str(), selected.to_string(), newer.to_string()),
+            ("foo", "1.0".to_string(), "2.0".to_string())
+        );
+
+        // Unless the older version is requested deliberately
+        let resolved = resolve_requirements(package_db, &["foo<2"], strict)
+            .await
+            .unwrap();
+        assert_eq!(resolved[0].version.to_string(), "1.0");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_download_resolution() {
+        let find_links = tempfile::tempdir().unwrap();
+        write_wheel(find_links.path(), "foo", "1.0", &["bar"]);
+        write_wheel(find_links.path(), "bar", "1.0", &[]);
+        write_wheel(find_links.path(), "bar", "2.0", &[]);
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(Client::new().into(), &[], cache_dir.path())
+                .unwrap()
+                .with_find_links([find_links.path().to_path_buf()]),
+        );
+        let resolved = resolve_requirements(package_db.clone(), &["foo"], Default::default())
+            .await
+            .unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let dest = dest.path().join("mirror");
+        for package in &resolved {
+            let artifact = package.artifacts.first().unwrap();
+            package_db
+                .download_artifact(artifact, &dest, None)
+                .await
+                .unwrap();
+        }
+
+        let downloaded = fs_err::read_dir(&dest)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            downloaded,
+            ["bar-2.0-py3-none-any.whl", "foo-1.0-py3-none-any.whl"]
+        );
+        for filename in &downloaded {
+            assert_eq!(
+                fs_err::read(dest.join(filename)).unwrap(),
+                fs_err::read(find_links.path().join(filename)).unwrap()
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_no_build() {
+        let find_links = tempfile::tempdir().unwrap();
+        let marker = find_links.path().join("backend-was-run");
+        let backend = format!(
+            r#"
+import pathlib
+
+def get_requires_for_build_wheel(config_settings=None):
+    pathlib.Path({marker:?}).touch()
+    return []
+
+def prepare_metadata_for_build_wheel(metadata_directory, config_settings=None):
+    pathlib.Path({marker:?}).touch()
+    raise RuntimeError("the backend should not run")
+"#,
+            marker = marker.display().to_string()
+        );
+        write_sdist(
+            find_links.path(),
+            "needs-build",
+            "1.0",
+            &[
+                (
+                    "PKG-INFO",
+                    "Metadata-Version: 2.1\nName: needs-build\nVersion: 1.0\n",
+                ),
+                (
+                    "pyproject.toml",
+                    r#"
+[build-system]
+requires = []
+build-backend = "marker_backend"
+backend-path = ["."]
+"#,
+                ),
+                ("marker_backend.py", &backend),
+            ],
+        );
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(Client::new().into(), &[], cache_dir.path())
+                .unwrap()
+                .with_find_links([find_links.path().to_path_buf()]),
+        );
+
+        // Forbidding builds takes precedence over building unreliable source distributions
+        let options = ResolveOptions {
+            no_build: true,
+            ..Default::default()
+        };
+        let err = resolve_requirements(package_db, &["needs-build"], options)
+            .await
+            .unwrap_err();
+        let message = format!("{:?}", miette::Report::new(err));
+        assert!(
+            message.contains("building source distributions is not allowed"),
+            "{message}"
+        );
+        assert!(!marker.exists(), "the build backend was run");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sdist_build_failure() {
+        let find_links = tempfile::tempdir().unwrap();
+        write_sdist(
+            find_links.path(),
+            "failing",
+            "1.0",
+            &[
+                (
+                    "PKG-INFO",
+                    "Metadata-Version: 2.1\nName: failing\nVersion: 1.0\n",
+                ),
+                (
+                    "pyproject.toml",
+                    r#"
+[build-system]
+requires = []
+build-backend = "failing_backend"
+backend-path = ["."]
+"#,
+                ),
+                (
+                    "failing_backend.py",
+                    r#"
+import sys
+
+def get_requires_for_build_wheel(config_settings=None):
+    return []
+
+def prepare_metadata_for_build_wheel(metadata_directory, config_settings=None):
+    print("error: could not find the foo compiler", file=sys.stderr)
+    sys.exit(1)
+"#,
+                ),
+            ],
+        );
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(Client::new().into(), &[], cache_dir.path())
+                .unwrap()
+                .with_find_links([find_links.path().to_path_buf()]),
+        );
+        let options = ResolveOptions::default();
+
+        let err = resolve_requirements(package_db, &["failing"], options)
+            .await
+            .unwrap_err();
+        let ResolutionError::Metadata(MetadataError::SDistBuildFailure {
+            package,
+            filename,
+            output,
+        }) = &err
+        else {
+            panic!("expected a build failure, got {err:?}");
+        };
+        assert_eq!(package, "failing 1.0");
+        assert_eq!(filename, "failing-1.0.tar.gz");
+        assert!(
+            output.contains("could not find the foo compiler"),
+            "{output}"
+        );
+        let message = err.to_string();
+        assert!(
+            message.contains("failing 1.0 has no compatible wheel"),
+            "{message}"
+        );
+        assert!(
+            message.contains("could not find the foo compiler"),
+            "{message}"
+        );
+    }
+
+    /// Creates a git repository with a single commit that contains `files`.
+    fn git_repository(dir: &Path, files: &[(&str, &str)]) {
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=rip", "-c", "user.email=rip@example.com"])
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        git(&["init", "-q"]);
+        for (path, contents) in files {
+            fs_err::write(dir.join(path), contents).unwrap();
+        }
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "initial commit"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resolve_git_requirement() {
+        let repository = tempfile::tempdir().unwrap();
+        git_repository(
+            repository.path(),
+            &[(
+                "pyproject.toml",
+                r#"
+[project]
+name = "foo"
+version = "0.1.0"
+dependencies = ["bar>=2"]
+
+[project.optional-dependencies]
+extra = ["baz"]
+
+[build-system]
+requires = ["does-not-exist"]
+build-backend = "does_not_exist"
+"#,
+            )],
+        );
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[("bar", "1.0", ""), ("bar", "2.0", ""), ("baz", "1.0", "")],
+        )
+        .await;
+        let url = Url::from_directory_path(repository.path()).unwrap();
+        let url = format!("git+file://{}", url.path());
+
+        // The static metadata of the repository is used, its build backend does not exist
+        let resolved = resolve_requirements(
+            package_db,
+            &[&format!("foo[extra] @ {url}")],
+            Default::default(),
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|p| (p.name.to_string(), p.version.to_string()))
+        .sorted()
+        .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            [
+                ("bar".to_string(), "2.0".to_string()),
+                ("baz".to_string(), "1.0".to_string()),
+                ("foo".to_string(), url),
+            ]
+        );
+
+        // The repository is cloned into the cache
+        assert!(cache_dir.path().join("git").exists());
+    }
+
+    #[test]
+    fn test_serialize_pinned_package() {
+        let package = PinnedPackage {
+            name: "flask".parse().unwrap(),
+            version: PypiVersion::Version {
+                version: "3.0.0".parse().unwrap(),
+                package_allows_prerelease: false,
+            },
+            extras: ["dotenv", "async"]
+                .into_iter()
+                .map(|e| e.parse().unwrap())
+                .collect(),
+            artifacts: Vec::new(),
+            dependencies: ["werkzeug", "asgiref", "python-dotenv"]
+                .into_iter()
+                .map(|name| name.parse().unwrap())
+                .collect(),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&package).unwrap(),
+            serde_json::json!({
+                "name": "flask",
+                "version": "3.0.0",
+                "extras": ["async", "dotenv"],
+                "dependencies": ["asgiref", "python-dotenv", "werkzeug"],
+            })
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_honor_provides() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("a", "2.0", "Provides-Dist: b (1.5)\n"),
+                ("c", "1.0", "Requires-Dist: a\nRequires-Dist: b>=1\n"),
+            ],
+        )
+        .await;
+        // There is no package `b` on the index
+        package_db.insert_artifacts(
+            "b".parse::<NormalizedPackageName>().unwrap(),
+            IndexMap::new(),
+        );
+
+        // The requirement on `b` is satisfied by `a`
+        let packages = resolve_requirements(
+            package_db.clone(),
+            &["c"],
+            ResolveOptions {
+                honor_provides: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let packages = packages
+            .iter()
+            .map(|p| {
+                (
+                    p.name.as_str(),
+                    p.version.to_string(),
+                    p.dependencies.iter().map(|d| d.as_str()).collect_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            packages,
+            [
+                ("a", String::from("2.0"), vec![]),
+                ("c", String::from("1.0"), vec!["a"]),
+            ]
+        );
+
+        // The version that is provided must satisfy the requirement
+        let result = resolve_requirements(
+            package_db.clone(),
+            &["c", "b>=2"],
+            ResolveOptions {
+                honor_provides: true,
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(result.is_err());
+
+        // Without the option `b` cannot be found
+        let result = resolve_requirements(package_db, &["c"], ResolveOptions::default()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ignored_dependencies() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("foo", "1.0", "Requires-Dist: bar<1\nRequires-Dist: baz\n"),
+                ("bar", "1.0", ""),
+                ("baz", "1.0", "Requires-Dist: bar\n"),
+            ],
+        )
+        .await;
+
+        // The requirement of foo on bar cannot be satisfied
+        assert!(
+            resolve_requirements(package_db.clone(), &["foo"], ResolveOptions::default())
+                .await
+                .is_err()
+        );
+
+        // Only the edge from foo to bar is dropped, baz still requires bar
+        let packages = resolve_requirements(
+            package_db,
+            &["foo"],
+            ResolveOptions {
+                ignored_dependencies: vec!["Foo: bar".parse().unwrap()],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let packages = packages
+            .iter()
+            .map(|p| {
+                (
+                    p.name.as_str(),
+                    p.dependencies.iter().map(|d| d.as_str()).collect_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            packages,
+            [("bar", vec![]), ("baz", vec!["bar"]), ("foo", vec!["baz"])]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_minimal_conflict_set() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("a", "1.0", "Requires-Dist: x<2\n"),
+                ("b", "1.0", "Requires-Dist: x\n"),
+                ("c", "1.0", "Requires-Dist: y\n"),
+                ("d", "1.0", ""),
+                ("x", "1.0", ""),
+                ("x", "2.0", ""),
+                ("y", "1.0", "Requires-Dist: x>=2\n"),
+            ],
+        )
+        .await;
+        let conflict_set = |requirements: &[&str]| {
+            let package_db = package_db.clone();
+            let requirements = requirements
+                .iter()
+                .map(|r| Requirement::from_str(r).unwrap())
+                .collect::<Vec<_>>();
+            async move {
+                crate::resolve::minimal_conflict_set(
+                    package_db,
+                    &requirements,
+                    Arc::new(Pep508EnvMakers::from_env().await.unwrap().0),
+                    Some(Arc::new(
+                        [WheelTag::from_str("py3-none-any").unwrap()]
+                            .into_iter()
+                            .collect(),
+                    )),
+                    ResolveOptions::default(),
+                )
+                .await
+                .unwrap()
+                .map(|set| set.iter().map(|r| r.to_string()).collect::<Vec<_>>())
+            }
+        };
+
+        // Only a and c conflict, through the dependency of c on y
+        assert_eq!(
+            conflict_set(&["b", "a", "d", "c"]).await,
+            Some(vec![String::from("a"), String::from("c")])
+        );
+        assert_eq!(
+            conflict_set(&["x>=2", "d", "a"]).await,
+            Some(vec![String::from("x >=2"), String::from("a")])
+        );
+        assert_eq!(conflict_set(&["a", "b", "d"]).await, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_blocked_packages() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("app", "1.0", "Requires-Dist: lib\nRequires-Dist: other\n"),
+                ("lib", "2.0", "Requires-Dist: Evil-Pkg>=1\n"),
+                ("other", "1.0", ""),
+                ("evil-pkg", "1.0", ""),
+            ],
+        )
+        .await;
+        let blocked = ResolveOptions {
+            blocked_packages: vec!["evil_pkg".parse::<PackageName>().unwrap().into()],
+            ..Default::default()
+        };
+
+        // The blocked transitive dependency aborts the resolution, reporting how it is required
+        let err = resolve_requirements(package_db.clone(), &["app"], blocked.clone())
+            .await
+            .unwrap_err();
+        let ResolutionError::BlockedPackage { name, chain, .. } = &err else {
+            panic!("expected the package to be blocked, got {err:?}");
+        };
+        assert_eq!(name.as_str(), "evil-pkg");
+        assert_eq!(
+            chain
+                .iter()
+                .map(|(name, version)| format!("{} {version}", name.as_str()))
+                .collect_vec(),
+            ["lib 2.0", "app 1.0"]
+        );
+        assert_eq!(
+            err.to_string(),
+            "evil-pkg is blocked but it is required by:\nevil-pkg\n└── lib 2.0\n    └── app 1.0"
+        );
+
+        // A blocked package that is requested directly
+        let err = resolve_requirements(package_db.clone(), &["evil-pkg"], blocked.clone())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, ResolutionError::BlockedPackage { chain, .. } if chain.is_empty()),
+            "{err:?}"
+        );
+
+        // Packages that do not depend on the blocked package are not affected
+        assert!(resolve_requirements(package_db, &["other"], blocked)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_constraint_from_lock() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db(
+            cache_dir.path(),
+            &[
+                ("flask", "2.0", ""),
+                ("flask", "3.0", ""),
+                ("jinja2", "3.0", ""),
+                ("jinja2", "3.1", ""),
+                ("requests", "2.30", ""),
+                ("requests", "2.31", ""),
+                ("sphinx", "7.0", "Requires-Dist: jinja2>=3.1\n"),
+            ],
+        )
+        .await;
+        let versions = |packages: Vec<PinnedPackage>| {
+            packages
+                .into_iter()
+                .map(|p| format!("{}=={}", p.name.as_str(), p.version))
+                .collect::<Vec<_>>()
+        };
+
+        // The lock of a project, the preferences of the user take precedence over it
+        let lock = LockedVersions::from_str("flask==2.0\njinja2==3.0\nrequests==2.30\n").unwrap();
+        let mut preferences = VersionPreferences::from_str("requests==2.31\n").unwrap();
+        preferences.merge(VersionPreferences::try_from(&lock).unwrap());
+        let options = ResolveOptions {
+            candidate_sorter: Some(Arc::new(preferences)),
+            ..Default::default()
+        };
+
+        // Adding sphinx only moves jinja2, which conflicts with it, the other packages stay at
+        // their locked or preferred versions
+        let packages = resolve_requirements(
+            package_db.clone(),
+            &["flask", "jinja2", "requests", "sphinx"],
+            options,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            versions(packages),
+            ["flask==2.0", "jinja2==3.1", "requests==2.31", "sphinx==7.0"]
+        );
+
+        // Without the lock the newest versions are selected
+        let packages = resolve_requirements(
+            package_db,
+            &["flask", "jinja2", "requests", "sphinx"],
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            versions(packages),
+            ["flask==3.0", "jinja2==3.1", "requests==2.31", "sphinx==7.0"]
+        );
+    }
+
+    /// The explanations are the ones that resolvo 0.3.0 produces for the problems in
+    /// `test_resolution_error_conflict` and `test_resolution_error_no_candidates`, if the wording
+    /// of the solver changes these tests fail before the conflicts silently lose their
+    /// requirements.
+    #[test]
+    fn test_problem_requirements() {
+        let message = "\
+The following packages are incompatible
+|-- bar >=2 can be installed with any of the following options:
+    |-- bar 2.0
+|-- foo * cannot be installed because there are no viable options:
+    |-- foo 1.0 | 1.1 would require
+        |-- bar <2, which cannot be installed because there are no viable options:
+            |-- bar 1.0, which conflicts with the versions reported above.
+";
+        let foo = Some(("foo".to_owned(), vec!["1.0".to_owned(), "1.1".to_owned()]));
+        assert_eq!(
+            problem_requirements(message),
+            [
+                ProblemRequirement {
+                    required_by: None,
+                    name: "bar".to_owned(),
+                    specifiers: ">=2".to_owned(),
+                    missing: false,
+                },
+                ProblemRequirement {
+                    required_by: None,
+                    name: "foo".to_owned(),
+                    specifiers: "*".to_owned(),
+                    missing: false,
+                },
+                ProblemRequirement {
+                    required_by: foo,
+                    name: "bar".to_owned(),
+                    specifiers: "<2".to_owned(),
+                    missing: false,
+                },
+            ]
+        );
+
+        let message = "\
+foo * cannot be installed because there are no viable options:
+|-- foo 1.0 would require
+    |-- bar >=2, for which no candidates were found.
+";
+        assert_eq!(
+            problem_requirements(message),
+            [
+                ProblemRequirement {
+                    required_by: None,
+                    name: "foo".to_owned(),
+                    specifiers: "*".to_owned(),
+                    missing: false,
+                },
+                ProblemRequirement {
+                    required_by: Some(("foo".to_owned(), vec!["1.0".to_owned()])),
+                    name: "bar".to_owned(),
+                    specifiers: ">=2".to_owned(),
+                    missing: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_problem_requirements_unrecognized() {
+        let message = "\
+Cannot satisfy the requirements
+- foo requires bar <2
+- bar 2.0 was requested
+";
+        assert!(problem_requirements(message).is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+use super::{PypiVersion, SkipReason};
+use crate::artifacts::{SDist, Wheel};
+use crate::types::{ArtifactInfo, ArtifactName, NormalizedPackageName};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::Arc;
+
+/// Counts the artifacts that were considered or skipped during a resolution and the versions
+/// whose metadata was retrieved. This is a lightweight health check of a resolution, see
+/// [`SkippedArtifacts`](super::SkippedArtifacts) for the details of each skipped artifact.
+///
+/// This is a cheaply cloneable handle, pass a clone of it to [`super::ResolveOptions::summary`]
+/// and inspect it with [`Self::counts`] after the resolution finished. Every artifact and version
+/// is only counted once, even if the solver looks at it multiple times.
+#[derive(Debug, Clone, Default)]
+pub struct ResolutionSummary {
+    inner: Arc<Mutex<SummaryEntries>>,
+}
+
+#[derive(Debug, Default)]
+struct SummaryEntries {
+    wheels: BTreeSet<ArtifactName>,
+    sdists: BTreeSet<ArtifactName>,
+    skipped: BTreeSet<(ArtifactName, &'static str)>,
+    fetched: HashSet<(NormalizedPackageName, PypiVersion)>,
+}
+
+/// The counts of a [`ResolutionSummary`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SummaryCounts {
+    /// The number of wheels that could be selected
+    pub wheels_considered: usize,
+
+    /// The number of source distributions that could be selected
+    pub sdists_considered: usize,
+
+    /// The number of skipped artifacts per [`SkipReason::category`]
+    pub skipped: BTreeMap<&'static str, usize>,
+
+    /// The number of package versions whose metadata was retrieved
+    pub versions_fetched: usize,
+}
+
+impl ResolutionSummary {
+    /// Records that an artifact could be selected.
+    pub(crate) fn considered(&self, artifact: &ArtifactInfo) {
+        let mut inner = self.inner.lock();
+        if artifact.is::<Wheel>() {
+            inner.wheels.insert(artifact.filename.clone());
+        } else if artifact.is::<SDist>() {
+            inner.sdists.insert(artifact.filename.clone());
+        }
+    }
+
+    /// Records that an artifact was skipped.
+    pub(crate) fn skipped(&self, artifact: &ArtifactInfo, reason: &SkipReason) {
+        self.inner
+            .lock()
+            .skipped
+            .insert((artifact.filename.clone(), reason.category()));
+    }
+
+    /// Records that the metadata of a version of a package was retrieved.
+    pub(crate) fn fetched(&self, name: &NormalizedPackageName, version: &PypiVersion) {
+        self.inner
+            .lock()
+            .fetched
+            .insert((name.clone(), version.clone()));
+    }
+
+    /// Returns the counts that were recorded so far.
+    pub fn counts(&self) -> SummaryCounts {
+        let inner = self.inner.lock();
+        let mut skipped = BTreeMap::new();
+        for (_, category) in &inner.skipped {
+            *skipped.entry(*category).or_default() += 1;
+        }
+        SummaryCounts {
+            wheels_considered: inner.wheels.len(),
+            sdists_considered: inner.sdists.len(),
+            skipped,
+            versions_fetched: inner.fetched.len(),
+        }
+    }
+}
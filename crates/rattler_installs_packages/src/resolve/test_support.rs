@@ -0,0 +1,198 @@
+//! Helpers that are shared by the tests of the resolver and the wheel builder.
+
+use crate::index::PackageDb;
+use crate::types::{
+    ArtifactHashes, ArtifactInfo, ArtifactName, DistInfoMetadata, NormalizedPackageName, Yanked,
+};
+use indexmap::IndexMap;
+use reqwest::Client;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use url::Url;
+
+/// An artifact in the index of the package database of [`package_db_with`].
+pub(crate) struct IndexArtifact {
+    name: String,
+    filename: String,
+    requires_python: Option<String>,
+    yanked: Option<String>,
+    metadata: Option<String>,
+}
+
+impl IndexArtifact {
+    /// An artifact of `name` with the given filename whose metadata is not cached, fetching it
+    /// fails.
+    pub fn new(name: &str, filename: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            filename: filename.to_owned(),
+            requires_python: None,
+            yanked: None,
+            metadata: None,
+        }
+    }
+
+    /// A pure Python wheel whose metadata contains the given fields after the name and the
+    /// version, e.g. `Requires-Dist: bar\n`.
+    pub fn wheel(name: &str, version: &str, fields: &str) -> Self {
+        Self::new(name, &format!("{name}-{version}-py3-none-any.whl")).with_metadata(fields)
+    }
+
+    /// Caches metadata that contains the given fields after the name and the version.
+    pub fn with_metadata(self, fields: &str) -> Self {
+        Self {
+            metadata: Some(fields.to_owned()),
+            ..self
+        }
+    }
+
+    /// Sets the `requires-python` that the index reports for the artifact.
+    pub fn requires_python(self, specifiers: &str) -> Self {
+        Self {
+            requires_python: Some(specifiers.to_owned()),
+            ..self
+        }
+    }
+
+    /// Marks the artifact as yanked from the index.
+    pub fn yanked(self, reason: &str) -> Self {
+        Self {
+            yanked: Some(reason.to_owned()),
+            ..self
+        }
+    }
+}
+
+/// Creates a package database that contains the given `(name, version, requires_dist)`
+/// wheels and has their metadata cached.
+pub(crate) async fn package_db(
+    cache_dir: &Path,
+    packages: &[(&str, &str, &str)],
+) -> Arc<PackageDb> {
+    package_db_with(
+        cache_dir,
+        packages.iter().map(|(name, version, requires_dist)| {
+            IndexArtifact::wheel(name, version, requires_dist)
+        }),
+    )
+    .await
+}
+
+/// Creates a package database whose index contains the given artifacts, in the given order. The
+/// index cannot be reached, only the metadata that is cached can be used.
+pub(crate) async fn package_db_with(
+    cache_dir: &Path,
+    artifacts: impl IntoIterator<Item = IndexArtifact>,
+) -> Arc<PackageDb> {
+    let package_db = Arc::new(
+        PackageDb::new(
+            Client::new().into(),
+            &[Url::parse("https://example.invalid/simple/").unwrap()],
+            cache_dir,
+        )
+        .unwrap(),
+    );
+
+    let mut index: IndexMap<NormalizedPackageName, IndexMap<_, Vec<_>>> = IndexMap::new();
+    for artifact in artifacts {
+        let name: NormalizedPackageName = artifact.name.parse().unwrap();
+        let filename = ArtifactName::from_filename(&artifact.filename, &name).unwrap();
+        let version = filename.version();
+        let info = Arc::new(ArtifactInfo {
+            filename,
+            url: format!("https://example.invalid/{}", artifact.filename)
+                .parse()
+                .unwrap(),
+            hashes: Some(ArtifactHashes {
+                sha256: Some(
+                    rattler_digest::compute_bytes_digest::<rattler_digest::Sha256>(
+                        artifact.filename.as_bytes(),
+                    ),
+                ),
+                ..Default::default()
+            }),
+            requires_python: artifact
+                .requires_python
+                .map(|specifiers| specifiers.parse().unwrap()),
+            dist_info_metadata: DistInfoMetadata::default(),
+            yanked: Yanked {
+                yanked: artifact.yanked.is_some(),
+                reason: artifact.yanked,
+            },
+            index_url: None,
+        });
+        if let Some(fields) = artifact.metadata {
+            let metadata = format!(
+                "Metadata-Version: 2.1\nName: {}\nVersion: {version}\n{fields}",
+                artifact.name
+            );
+            package_db
+                .put_metadata_in_cache(&info, metadata.as_bytes())
+                .await
+                .unwrap();
+        }
+        index
+            .entry(name)
+            .or_default()
+            .entry(version)
+            .or_default()
+            .push(info);
+    }
+    for (name, versions) in index {
+        package_db.insert_artifacts(name, versions);
+    }
+    package_db
+}
+
+/// Returns reliable (PEP 643) core metadata for the `PKG-INFO` of a source distribution.
+pub(crate) fn pkg_info(name: &str, version: &str) -> String {
+    format!("Metadata-Version: 2.2\nName: {name}\nVersion: {version}\n")
+}
+
+/// Writes a `.tar.gz` source distribution that contains `files` to `dir`. The files are placed in
+/// the `{name}-{version}` directory of the archive.
+pub(crate) fn write_sdist(dir: &Path, name: &str, version: &str, files: &[(&str, &str)]) {
+    let file = fs_err::File::create(dir.join(format!("{name}-{version}.tar.gz"))).unwrap();
+    let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(file, Default::default()));
+    for (path, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive
+            .append_data(
+                &mut header,
+                format!("{name}-{version}/{path}"),
+                contents.as_bytes(),
+            )
+            .unwrap();
+    }
+    archive.into_inner().unwrap().finish().unwrap();
+}
+
+/// Writes a minimal pure Python wheel with the given `Requires-Dist` entries to `dir`.
+pub(crate) fn write_wheel(dir: &Path, name: &str, version: &str, requires_dist: &[&str]) {
+    use zip::write::FileOptions;
+
+    let file =
+        fs_err::File::create(dir.join(format!("{name}-{version}-py3-none-any.whl"))).unwrap();
+    let mut archive = zip::ZipWriter::new(file);
+    let dist_info = format!("{name}-{version}.dist-info");
+    let mut metadata = format!("Metadata-Version: 2.1\nName: {name}\nVersion: {version}\n");
+    for requirement in requires_dist {
+        metadata.push_str(&format!("Requires-Dist: {requirement}\n"));
+    }
+    for (path, contents) in [
+        (format!("{dist_info}/METADATA"), metadata),
+        (
+            format!("{dist_info}/WHEEL"),
+            "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n".to_string(),
+        ),
+        (format!("{dist_info}/RECORD"), String::new()),
+    ] {
+        archive.start_file(path, FileOptions::default()).unwrap();
+        archive.write_all(contents.as_bytes()).unwrap();
+    }
+    archive.finish().unwrap();
+}
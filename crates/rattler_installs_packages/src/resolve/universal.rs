@@ -0,0 +1,302 @@
+use super::{resolve, PinnedPackage, PypiVersion, ResolutionError, ResolveOptions};
+use crate::index::PackageDb;
+use crate::python_env::WheelTags;
+use crate::types::{Extra, NormalizedPackageName};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use pep508_rs::{MarkerEnvironment, MarkerTree, Requirement};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// An environment for which [`resolve_universal`] resolves the requirements.
+#[derive(Debug, Clone)]
+pub struct UniversalEnvironment {
+    /// The environment markers of the environment
+    pub env_markers: Arc<MarkerEnvironment>,
+
+    /// The tags of the wheels that can be installed in the environment
+    pub compatible_tags: Option<Arc<WheelTags>>,
+}
+
+/// A package of a universal resolution together with the environments that need it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionalPackage {
+    /// The resolved package. The artifacts are those of all the environments that need it.
+    pub package: PinnedPackage,
+
+    /// The marker that holds in exactly the environments that need this package, or `None` if
+    /// all environments need it
+    pub marker: Option<MarkerTree>,
+}
+
+/// Resolves `requirements` into a single set of packages that is valid in every one of
+/// `environments`, e.g. to create a lock file that can be installed on multiple platforms.
+///
+/// Every environment is resolved separately, which means that a dependency that is gated by a
+/// marker is only part of the resolutions of the environments in which the marker holds, and
+/// that a different version of a package may be selected in each environment. The resolutions
+/// are combined by adding a marker to every package that is not needed by all environments, see
+/// [`merge_resolutions`]. An installer selects the packages of its environment by evaluating the
+/// markers.
+pub async fn resolve_universal(
+    package_db: Arc<PackageDb>,
+    requirements: &[Requirement],
+    environments: &[UniversalEnvironment],
+    options: ResolveOptions,
+) -> Result<Vec<ConditionalPackage>, ResolutionError> {
+    let mut resolutions = Vec::with_capacity(environments.len());
+    for environment in environments {
+        let packages = resolve(
+            package_db.clone(),
+            requirements,
+            environment.env_markers.clone(),
+            environment.compatible_tags.clone(),
+            HashMap::default(),
+            HashMap::default(),
+            options.clone(),
+            HashMap::default(),
+        )
+        .await?;
+        resolutions.push(packages);
+    }
+
+    let markers = environment_markers(environments.iter().map(|e| e.env_markers.as_ref()));
+    Ok(merge_resolutions(&markers, resolutions))
+}
+
+/// The markers that distinguish environments from each other, in the order in which they are
+/// added to the marker of an environment.
+const DISTINGUISHING_MARKERS: [&str; 4] = [
+    "sys_platform",
+    "platform_machine",
+    "implementation_name",
+    "python_version",
+];
+
+/// Returns a marker for each of `environments` that only holds in that environment and in other
+/// environments that cannot be distinguished from it. The markers compare the platform, the
+/// implementation and the Python version, but only those that differ between the environments.
+/// Returns `None` for every environment if they are all the same.
+pub fn environment_markers<'a>(
+    environments: impl IntoIterator<Item = &'a MarkerEnvironment>,
+) -> Vec<Option<MarkerTree>> {
+    let values = environments
+        .into_iter()
+        .map(|env| {
+            [
+                env.sys_platform.clone(),
+                env.platform_machine.clone(),
+                env.implementation_name.clone(),
+                env.python_version.string.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let distinguishing = (0..DISTINGUISHING_MARKERS.len())
+        .filter(|&i| values.iter().map(|v| &v[i]).unique().count() > 1)
+        .collect::<Vec<_>>();
+    if distinguishing.is_empty() {
+        return vec![None; values.len()];
+    }
+
+    values
+        .iter()
+        .map(|values| {
+            let marker = distinguishing
+                .iter()
+                .map(|&i| format!("{} == '{}'", DISTINGUISHING_MARKERS[i], values[i]))
+                .join(" and ");
+            Some(MarkerTree::from_str(&marker).expect("the marker of an environment is valid"))
+        })
+        .collect()
+}
+
+/// Identifies a resolved package by its name, its version and its sorted extras.
+type PackageKey = (NormalizedPackageName, PypiVersion, Vec<Extra>);
+
+/// Combines the resolutions of multiple environments, where `resolutions[i]` is the resolution
+/// of the environment with marker `markers[i]`, as returned by [`environment_markers`].
+///
+/// A package with the same version and extras in all resolutions is unconditional. Otherwise
+/// its marker is the disjunction of the markers of the environments that need it. The packages
+/// are sorted by name and version.
+pub fn merge_resolutions(
+    markers: &[Option<MarkerTree>],
+    resolutions: Vec<Vec<PinnedPackage>>,
+) -> Vec<ConditionalPackage> {
+    let mut merged: IndexMap<PackageKey, (PinnedPackage, Vec<usize>)> = IndexMap::new();
+    for (index, packages) in resolutions.iter().enumerate() {
+        for package in packages {
+            let key = (
+                package.name.clone(),
+                package.version.clone(),
+                package.extras.iter().cloned().collect(),
+            );
+            match merged.get_mut(&key) {
+                Some((existing, environments)) => {
+                    // Every environment might need a different artifact of the same version
+                    for artifact in &package.artifacts {
+                        if !existing
+                            .artifacts
+                            .iter()
+                            .any(|a| a.filename == artifact.filename)
+                        {
+                            existing.artifacts.push(artifact.clone());
+                        }
+                    }
+                    existing
+                        .dependencies
+                        .extend(package.dependencies.iter().cloned());
+                    environments.push(index);
+                }
+                None => {
+                    merged.insert(key, (package.clone(), vec![index]));
+                }
+            }
+        }
+    }
+
+    merged
+        .into_iter()
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, (package, environments))| {
+            let marker = if environments.len() == resolutions.len() {
+                None
+            } else {
+                let mut markers = environments
+                    .iter()
+                    .filter_map(|&i| markers.get(i).cloned().flatten())
+                    .collect::<Vec<_>>();
+                match markers.len() {
+                    0 => None,
+                    1 => markers.pop(),
+                    _ => Some(MarkerTree::Or(markers)),
+                }
+            };
+            ConditionalPackage { package, marker }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::python_env::{Pep508EnvMakers, PythonInterpreterVersion};
+
+    fn env(platform: &str, minor: u32) -> MarkerEnvironment {
+        Pep508EnvMakers::for_target_platform(
+            platform.parse().unwrap(),
+            &PythonInterpreterVersion::from((3, minor, 0)),
+        )
+        .0
+    }
+
+    fn package(name: &str, version: &str) -> PinnedPackage {
+        PinnedPackage {
+            name: name.parse().unwrap(),
+            version: PypiVersion::Version {
+                version: version.parse().unwrap(),
+                package_allows_prerelease: false,
+            },
+            extras: Default::default(),
+            artifacts: Vec::new(),
+            dependencies: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_environment_markers() {
+        let markers = |envs: &[MarkerEnvironment]| {
+            environment_markers(envs)
+                .into_iter()
+                .map(|m| m.map(|m| m.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            markers(&[env("linux-x86_64", 11), env("windows-amd64", 11)]),
+            [
+                Some(String::from(
+                    "sys_platform == 'linux' and platform_machine == 'x86_64'"
+                )),
+                Some(String::from(
+                    "sys_platform == 'win32' and platform_machine == 'AMD64'"
+                )),
+            ]
+        );
+        assert_eq!(
+            markers(&[env("linux-x86_64", 9), env("linux-x86_64", 12)]),
+            [
+                Some(String::from("python_version == '3.9'")),
+                Some(String::from("python_version == '3.12'")),
+            ]
+        );
+        assert_eq!(
+            markers(&[env("linux-x86_64", 9), env("linux-x86_64", 9)]),
+            [None, None]
+        );
+    }
+
+    #[test]
+    fn test_merge_resolutions() {
+        let linux = env("linux-x86_64", 10);
+        let windows = env("windows-amd64", 10);
+        let markers = environment_markers([&linux, &windows]);
+
+        // `colorama` is a marker-gated dependency that is only needed on Windows and `tomli`
+        // resolved to a different version on each platform
+        let mut click = package("click", "8.1.7");
+        click.dependencies = ["colorama".parse().unwrap()].into_iter().collect();
+        let merged = merge_resolutions(
+            &markers,
+            vec![
+                vec![package("click", "8.1.7"), package("tomli", "2.0.1")],
+                vec![
+                    click,
+                    package("colorama", "0.4.6"),
+                    package("tomli", "1.2.3"),
+                ],
+            ],
+        );
+
+        let merged = merged
+            .iter()
+            .map(|p| {
+                (
+                    p.package.name.as_str(),
+                    p.package.version.to_string(),
+                    p.marker.as_ref().map(|m| m.to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+        let linux_marker = markers[0].as_ref().map(|m| m.to_string());
+        let windows_marker = markers[1].as_ref().map(|m| m.to_string());
+        assert_eq!(
+            merged,
+            [
+                ("click", String::from("8.1.7"), None),
+                ("colorama", String::from("0.4.6"), windows_marker.clone()),
+                ("tomli", String::from("1.2.3"), windows_marker),
+                ("tomli", String::from("2.0.1"), linux_marker),
+            ]
+        );
+
+        // Every marker selects the right packages
+        let merged = merge_resolutions(
+            &markers,
+            vec![
+                vec![package("tomli", "2.0.1")],
+                vec![package("colorama", "0.4.6"), package("tomli", "2.0.1")],
+            ],
+        );
+        let selected = |env: &MarkerEnvironment| {
+            merged
+                .iter()
+                .filter(|p| p.marker.as_ref().map_or(true, |m| m.evaluate(env, &[])))
+                .map(|p| p.package.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(selected(&linux), ["tomli"]);
+        assert_eq!(selected(&windows), ["colorama", "tomli"]);
+    }
+}
@@ -0,0 +1,301 @@
+use super::{LockedVersions, PypiVersion, ResolutionError, ResolveOptions};
+use crate::index::PackageDb;
+use crate::python_env::WheelTags;
+use crate::types::{NormalizedPackageName, PackageName, VersionSpecifierExt};
+use crate::wheel_builder::WheelBuilder;
+use miette::IntoDiagnostic;
+use pep440_rs::Version;
+use pep508_rs::{MarkerEnvironment, Requirement, VersionOrUrl};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A pin of a lock file that is no longer valid, see [`verify_lock`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockProblem {
+    /// The locked version is not available on the index (anymore).
+    NotFound {
+        /// The name of the locked package
+        name: NormalizedPackageName,
+        /// The locked version
+        version: String,
+    },
+
+    /// All artifacts of the locked version have been yanked.
+    Yanked {
+        /// The name of the locked package
+        name: NormalizedPackageName,
+        /// The locked version
+        version: String,
+        /// The reason for yanking, if the index provided one
+        reason: Option<String>,
+    },
+
+    /// A requirement of a locked package is not locked.
+    Missing {
+        /// The name of the locked package that has the requirement
+        name: NormalizedPackageName,
+        /// The locked version of the package that has the requirement
+        version: String,
+        /// The requirement that is not locked
+        requirement: Requirement,
+    },
+
+    /// A requirement of a locked package is locked at a version that does not satisfy it.
+    Conflict {
+        /// The name of the locked package that has the requirement
+        name: NormalizedPackageName,
+        /// The locked version of the package that has the requirement
+        version: String,
+        /// The requirement that is not satisfied
+        requirement: Requirement,
+        /// The locked version of the required package
+        locked: String,
+    },
+}
+
+impl Display for LockProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockProblem::NotFound { name, version } => {
+                write!(f, "{name} {version} is not available on the index")
+            }
+            LockProblem::Yanked {
+                name,
+                version,
+                reason,
+            } => {
+                write!(f, "{name} {version} has been yanked")?;
+                if let Some(reason) = reason {
+                    write!(f, " (reason: {reason})")?;
+                }
+                Ok(())
+            }
+            LockProblem::Missing {
+                name,
+                version,
+                requirement,
+            } => write!(
+                f,
+                "{name} {version} requires {}, which is not locked",
+                requirement_without_marker(requirement)
+            ),
+            LockProblem::Conflict {
+                name,
+                version,
+                requirement,
+                locked,
+            } => write!(
+                f,
+                "{name} {version} requires {}, but {locked} is locked",
+                requirement_without_marker(requirement)
+            ),
+        }
+    }
+}
+
+/// Verifies that the pins of a lock file still form a valid environment without resolving again:
+/// every locked version must still be available on the index and not be yanked, and the
+/// requirements of every locked package must be satisfied by the other locked packages.
+///
+/// Requirements are evaluated for `env_markers`, requirements that are only added by an extra are
+/// not checked because a lock file does not record extras. The metadata of the locked packages is
+/// obtained in the same way as during [`super::resolve`], which might involve building source
+/// distributions.
+///
+/// Returns the problems that were found, sorted by the name of the locked package. An empty list
+/// means the lock file is still valid.
+pub async fn verify_lock(
+    package_db: Arc<PackageDb>,
+    lock: &LockedVersions,
+    env_markers: Arc<MarkerEnvironment>,
+    compatible_tags: Option<Arc<WheelTags>>,
+    options: ResolveOptions,
+) -> Result<Vec<LockProblem>, ResolutionError> {
+    let wheel_builder = WheelBuilder::new(
+        package_db.clone(),
+        env_markers.clone(),
+        compatible_tags,
+        options,
+        HashMap::default(),
+    )
+    .into_diagnostic()?;
+
+    let mut problems = Vec::new();
+    for (name, locked_version) in lock.iter() {
+        let not_found = || LockProblem::NotFound {
+            name: name.clone(),
+            version: locked_version.to_owned(),
+        };
+        let Ok(version) = Version::from_str(locked_version) else {
+            problems.push(not_found());
+            continue;
+        };
+
+        let available = package_db
+            .available_artifacts(name.clone())
+            .await
+            .map_err(|error| ResolutionError::FetchFailed {
+                name: name.clone(),
+                error,
+            })?;
+        let Some(all_artifacts) = available.iter().find_map(|(v, artifacts)| match v {
+            PypiVersion::Version { version: v, .. } if *v == version => Some(artifacts),
+            _ => None,
+        }) else {
+            problems.push(not_found());
+            continue;
+        };
+
+        let artifacts = all_artifacts
+            .iter()
+            .filter(|a| !a.yanked.yanked)
+            .cloned()
+            .collect::<Vec<_>>();
+        if artifacts.is_empty() {
+            problems.push(LockProblem::Yanked {
+                name: name.clone(),
+                version: locked_version.to_owned(),
+                reason: all_artifacts.iter().find_map(|a| a.yanked.reason.clone()),
+            });
+            continue;
+        }
+
+        let Some((_, metadata)) = package_db
+            .get_metadata(&artifacts, Some(&wheel_builder))
+            .await?
+        else {
+            return Err(miette::miette!(
+                "no metadata could be obtained for {name} {locked_version}"
+            )
+            .into());
+        };
+
+        for requirement in metadata.requires_dist {
+            if !requirement
+                .marker
+                .as_ref()
+                .map_or(true, |marker| marker.evaluate(&env_markers, &[]))
+            {
+                continue;
+            }
+
+            // Requirements with a name that cannot be parsed cannot be locked either
+            let Ok(required) = PackageName::from_str(&requirement.name) else {
+                continue;
+            };
+            let Some(dependency_version) = lock.get(&NormalizedPackageName::from(required)) else {
+                problems.push(LockProblem::Missing {
+                    name: name.clone(),
+                    version: locked_version.to_owned(),
+                    requirement,
+                });
+                continue;
+            };
+
+            // Pre-releases are accepted if they are locked, only the version is checked
+            if let (Some(VersionOrUrl::VersionSpecifier(specifiers)), Ok(parsed_version)) = (
+                &requirement.version_or_url,
+                Version::from_str(dependency_version),
+            ) {
+                if !specifiers.satisfied_by(&parsed_version) {
+                    problems.push(LockProblem::Conflict {
+                        name: name.clone(),
+                        version: locked_version.to_owned(),
+                        locked: dependency_version.to_owned(),
+                        requirement,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Formats a requirement without its environment marker, the marker already applies to the
+/// environment that was verified.
+fn requirement_without_marker(requirement: &Requirement) -> String {
+    Requirement {
+        marker: None,
+        ..requirement.clone()
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::python_env::{Pep508EnvMakers, PythonInterpreterVersion};
+    use crate::resolve::test_support::{package_db_with, IndexArtifact};
+
+    async fn verify(package_db: Arc<PackageDb>, lock: &str) -> Vec<String> {
+        let env = Pep508EnvMakers::for_target_platform(
+            "linux-x86_64".parse().unwrap(),
+            &PythonInterpreterVersion::from((3, 11, 0)),
+        )
+        .0;
+        verify_lock(
+            package_db,
+            &LockedVersions::from_str(lock).unwrap(),
+            Arc::new(env),
+            None,
+            ResolveOptions::default(),
+        )
+        .await
+        .unwrap()
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn test_verify_lock() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = package_db_with(
+            cache_dir.path(),
+            [
+                IndexArtifact::wheel(
+                    "flask",
+                    "3.0.0",
+                    "Requires-Dist: jinja2>=3.1.2\nRequires-Dist: click>=8.1.3\nRequires-Dist: asgiref>=3.2; extra == 'async'\n",
+                ),
+                IndexArtifact::wheel("jinja2", "3.0.3", ""),
+                IndexArtifact::wheel("jinja2", "3.1.2", ""),
+                IndexArtifact::wheel("click", "8.1.7", "").yanked("broken release"),
+                IndexArtifact::wheel("click", "8.1.6", ""),
+                IndexArtifact::wheel("werkzeug", "3.0.0", ""),
+            ],
+        )
+        .await;
+
+        // A closed set of available pins
+        assert!(verify(
+            package_db.clone(),
+            "flask==3.0.0\njinja2==3.1.2\nclick==8.1.6\n"
+        )
+        .await
+        .is_empty());
+
+        // The locked version of click has been yanked since the lock file was created
+        assert_eq!(
+            verify(
+                package_db.clone(),
+                "flask==3.0.0\njinja2==3.1.2\nclick==8.1.7\n"
+            )
+            .await,
+            ["click 8.1.7 has been yanked (reason: broken release)"]
+        );
+
+        // Deleted versions, missing and conflicting pins are reported as well
+        assert_eq!(
+            verify(package_db, "flask==3.0.0\njinja2==3.0.3\nwerkzeug==3.0.1\n").await,
+            [
+                "flask 3.0.0 requires jinja2 >=3.1.2, but 3.0.3 is locked",
+                "flask 3.0.0 requires click >=8.1.3, which is not locked",
+                "werkzeug 3.0.1 is not available on the index",
+            ]
+        );
+    }
+}
@@ -127,6 +127,32 @@ pub struct WheelFilename {
 }
 
 impl WheelFilename {
+    /// Returns the normalized name of the distribution.
+    pub fn distribution(&self) -> NormalizedPackageName {
+        self.distribution.clone().into()
+    }
+
+    /// Returns the version of the distribution.
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Returns the optional build tag of the wheel.
+    pub fn build_tag(&self) -> Option<&BuildTag> {
+        self.build_tag.as_ref()
+    }
+
+    /// Returns the expanded `(python, abi, platform)` tag triples of this wheel name. A wheel name
+    /// can contain compressed tag sets (e.g. `py2.py3-none-any`), these are expanded into every
+    /// combination.
+    pub fn tags(&self) -> impl Iterator<Item = (&str, &str, &str)> + '_ {
+        self.py_tags
+            .iter()
+            .cartesian_product(self.abi_tags.iter())
+            .cartesian_product(self.arch_tags.iter())
+            .map(|((py, abi), arch)| (py.as_str(), abi.as_str(), arch.as_str()))
+    }
+
     /// Creates a set of all tags that are contained in this wheel name.
     pub fn all_tags(&self) -> HashSet<WheelTag> {
         HashSet::from_iter(self.all_tags_iter())
@@ -136,15 +162,11 @@ impl WheelFilename {
     /// might be duplicates in the iterator. Use [`Self::all_tags`] if you want a unique set of
     /// tags.
     pub fn all_tags_iter(&self) -> impl Iterator<Item = WheelTag> + '_ {
-        self.py_tags
-            .iter()
-            .cartesian_product(self.abi_tags.iter())
-            .cartesian_product(self.arch_tags.iter())
-            .map(|((py, abi), arch)| WheelTag {
-                interpreter: py.clone(),
-                abi: abi.clone(),
-                platform: arch.clone(),
-            })
+        self.tags().map(|(py, abi, arch)| WheelTag {
+            interpreter: py.to_owned(),
+            abi: abi.to_owned(),
+            platform: arch.to_owned(),
+        })
     }
 }
 
@@ -349,46 +371,76 @@ impl SDistFilename {
         s: &str,
         normalized_package_name: &NormalizedPackageName,
     ) -> Result<Self, ParseArtifactNameError> {
-        let (package_name, rest) = split_into_filename_rest(s, normalized_package_name).ok_or(
-            ParseArtifactNameError::PackageNameNotFound(
+        // Determine the package format, some projects upload archives with an upper case
+        // extension
+        let (stem, format) = split_sdist_extension(s)
+            .ok_or_else(|| ParseArtifactNameError::InvalidExtension(s.to_string()))?;
+
+        // The name and the version are both allowed to contain dashes (e.g. `my-pkg-1.2.3-rc1`).
+        // Use the known package name as an anchor: every dash after which the prefix matches the
+        // package name is a candidate split and the rest should be the version. Prefer the first
+        // split for which the version can be parsed.
+        let mut first_error = None;
+        for (package_name, version) in filename_splits(stem, normalized_package_name) {
+            match Version::from_str(version) {
+                Ok(version) => {
+                    let distribution = PackageName::from_str(package_name)
+                        .map_err(ParseArtifactNameError::InvalidPackageName)?;
+                    return Ok(SDistFilename {
+                        distribution,
+                        version,
+                        format,
+                    });
+                }
+                Err(e) => {
+                    first_error.get_or_insert_with(|| e.to_string());
+                }
+            }
+        }
+
+        Err(match first_error {
+            Some(error) => ParseArtifactNameError::InvalidVersion(error),
+            None => ParseArtifactNameError::PackageNameNotFound(
                 normalized_package_name.clone(),
                 s.to_string(),
             ),
-        )?;
-
-        // Determine the package format
-        let (version, format) = if let Some(rest) = rest.strip_suffix(".zip") {
-            (rest, SDistFormat::Zip)
-        } else if let Some(rest) = rest.strip_suffix(".tar.gz") {
-            (rest, SDistFormat::TarGz)
-        } else if let Some(rest) = rest.strip_suffix(".tar.bz2") {
-            (rest, SDistFormat::TarBz2)
-        } else if let Some(rest) = rest.strip_suffix(".tar.xz") {
-            (rest, SDistFormat::TarXz)
-        } else if let Some(rest) = rest.strip_suffix(".tar.Z") {
-            (rest, SDistFormat::TarZ)
-        } else if let Some(rest) = rest.strip_suffix(".tar") {
-            (rest, SDistFormat::Tar)
-        } else {
-            return Err(ParseArtifactNameError::InvalidExtension(rest.to_string()));
-        };
-
-        // Parse the package name
-        let distribution = PackageName::from_str(package_name)
-            .map_err(ParseArtifactNameError::InvalidPackageName)?;
-
-        // Parse the version
-        let version = Version::from_str(version)
-            .map_err(|e| ParseArtifactNameError::InvalidVersion(e.to_string()))?;
-
-        Ok(SDistFilename {
-            distribution,
-            version,
-            format,
         })
     }
 }
 
+/// Splits the extension of an sdist from a filename, the extension is matched case-insensitively.
+pub(crate) fn split_sdist_extension(s: &str) -> Option<(&str, SDistFormat)> {
+    [
+        (".zip", SDistFormat::Zip),
+        (".tar.gz", SDistFormat::TarGz),
+        (".tar.bz2", SDistFormat::TarBz2),
+        (".tar.xz", SDistFormat::TarXz),
+        (".tar.z", SDistFormat::TarZ),
+        (".tar", SDistFormat::Tar),
+    ]
+    .into_iter()
+    .find_map(|(extension, format)| {
+        let split = s.len().checked_sub(extension.len())?;
+        let suffix = s.get(split..)?;
+        suffix
+            .eq_ignore_ascii_case(extension)
+            .then_some((&s[..split], format))
+    })
+}
+
+/// Returns all the ways `s` can be split on a `-` such that the part before the dash is the
+/// normalized package name, from the shortest name to the longest.
+fn filename_splits<'a: 'b, 'b>(
+    s: &'a str,
+    normalized_package_name: &'b NormalizedPackageName,
+) -> impl Iterator<Item = (&'a str, &'a str)> + 'b {
+    s.match_indices('-').filter_map(move |(idx, _)| {
+        let (name, rest) = (&s[..idx], &s[idx + 1..]);
+        let parsed = name.parse::<NormalizedPackageName>().ok()?;
+        (parsed == *normalized_package_name).then_some((name, rest))
+    })
+}
+
 /// Split the filename into a filename and the rest of the path
 /// by matching it with the normalized package name.
 /// Split on the `-` and check if the first part is the normalized package name.
@@ -400,18 +452,7 @@ fn split_into_filename_rest<'a>(
     s: &'a str,
     normalized_package_name: &NormalizedPackageName,
 ) -> Option<(&'a str, &'a str)> {
-    for (idx, char) in s.char_indices() {
-        if char == '-' {
-            let (name, rest) = (&s[..idx], &s[idx + 1..]);
-            let parsed = name.parse::<NormalizedPackageName>();
-            if let Ok(parsed) = parsed {
-                if parsed == *normalized_package_name {
-                    return Some((name, rest));
-                }
-            }
-        }
-    }
-    None
+    filename_splits(s, normalized_package_name).next()
 }
 
 impl WheelFilename {
@@ -579,6 +620,68 @@ mod test {
         assert_eq!(sn.to_string(), "trio-three-0.19a0.tar.gz");
     }
 
+    #[test]
+    fn test_sdist_tricky_names() {
+        for (filename, name, version, format) in [
+            (
+                "my-pkg-1.2.3-rc1.tar.gz",
+                "my-pkg",
+                "1.2.3rc1",
+                SDistFormat::TarGz,
+            ),
+            (
+                "my_pkg-1.2.3_rc1.tar.gz",
+                "my-pkg",
+                "1.2.3rc1",
+                SDistFormat::TarGz,
+            ),
+            ("My.Pkg-1.0.tar.gz", "my-pkg", "1.0", SDistFormat::TarGz),
+            ("pkg2-2.0.tar.gz", "pkg2", "2.0", SDistFormat::TarGz),
+            ("py-1-2-3.0.zip", "py-1-2", "3.0", SDistFormat::Zip),
+            (
+                "foo-1.0.0-1.tar.gz",
+                "foo",
+                "1.0.0.post1",
+                SDistFormat::TarGz,
+            ),
+            (
+                "foo-1.0+local.tar.gz",
+                "foo",
+                "1.0+local",
+                SDistFormat::TarGz,
+            ),
+            ("foo-v1.0.tar.bz2", "foo", "1.0", SDistFormat::TarBz2),
+            ("foo-1!2.0.tar.xz", "foo", "1!2.0", SDistFormat::TarXz),
+            ("FOO-1.0.TAR.GZ", "foo", "1.0", SDistFormat::TarGz),
+            ("foo-1.0.tar.Z", "foo", "1.0", SDistFormat::TarZ),
+            ("foo-1.0.Zip", "foo", "1.0", SDistFormat::Zip),
+        ] {
+            let sn = SDistFilename::from_filename(filename, &name.parse().unwrap())
+                .unwrap_or_else(|e| panic!("{filename}: {e}"));
+            assert_eq!(
+                NormalizedPackageName::from(sn.distribution),
+                name.parse().unwrap(),
+                "{filename}"
+            );
+            assert_eq!(sn.version, version.parse().unwrap(), "{filename}");
+            assert_eq!(sn.format, format, "{filename}");
+        }
+
+        // The name is a prefix of the filename but the rest is not a version
+        assert!(matches!(
+            SDistFilename::from_filename("foo-bar-1.0.tar.gz", &"foo".parse().unwrap()),
+            Err(ParseArtifactNameError::InvalidVersion(_))
+        ));
+        assert!(matches!(
+            SDistFilename::from_filename("foobar-1.0.tar.gz", &"foo".parse().unwrap()),
+            Err(ParseArtifactNameError::PackageNameNotFound(..))
+        ));
+        assert!(matches!(
+            SDistFilename::from_filename("foo-1.0.rar", &"foo".parse().unwrap()),
+            Err(ParseArtifactNameError::InvalidExtension(_))
+        ));
+    }
+
     #[test]
     fn test_many_linux() {
         let n = WheelFilename::from_filename(
@@ -630,4 +733,29 @@ mod test {
 
         assert_eq!(n.to_string(), "foo.bar-0.1b3-1local-py2.py3-none-any.whl");
     }
+
+    #[test]
+    fn test_wheel_name_accessors() {
+        let n = WheelFilename::from_filename(
+            "Foo_Bar-1.0-2-cp311.pp310-cp311.none-manylinux_2_17_x86_64.manylinux2014_x86_64.whl",
+            &"foo-bar".parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(n.distribution(), "foo-bar".parse().unwrap());
+        assert_eq!(n.version(), &"1.0".parse().unwrap());
+        assert_eq!(n.build_tag().map(ToString::to_string).as_deref(), Some("2"));
+        assert_eq!(
+            n.tags().collect::<Vec<_>>(),
+            vec![
+                ("cp311", "cp311", "manylinux_2_17_x86_64"),
+                ("cp311", "cp311", "manylinux2014_x86_64"),
+                ("cp311", "none", "manylinux_2_17_x86_64"),
+                ("cp311", "none", "manylinux2014_x86_64"),
+                ("pp310", "cp311", "manylinux_2_17_x86_64"),
+                ("pp310", "cp311", "manylinux2014_x86_64"),
+                ("pp310", "none", "manylinux_2_17_x86_64"),
+                ("pp310", "none", "manylinux2014_x86_64"),
+            ]
+        );
+    }
 }
@@ -8,7 +8,9 @@ use crate::{
 };
 use once_cell::sync::Lazy;
 use pep440_rs::Pep440Error;
-use pep508_rs::Requirement;
+use pep508_rs::{MarkerEnvironment, MarkerTree, Requirement};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 use std::{collections::HashSet, str::FromStr};
 use thiserror::Error;
 
@@ -25,10 +27,208 @@ pub struct WheelCoreMetadata {
     /// Requirements for this distribution
     /// Matches the Requires-Dist field
     pub requires_dist: Vec<Requirement>,
+    /// The Requires-Dist entries that could not be parsed and are therefore ignored
+    pub invalid_requires_dist: Vec<String>,
     /// Python requirement
     pub requires_python: Option<VersionSpecifiers>,
     /// Extras provided by this distribution
     pub extras: HashSet<Extra>,
+    /// The other distributions that this distribution contains
+    /// Matches the Provides-Dist field
+    pub provides_dist: Vec<ProvidesDist>,
+    /// The authors of the distribution
+    /// Matches the Author-email field
+    pub author_email: Vec<EmailAddress>,
+    /// The maintainers of the distribution
+    /// Matches the Maintainer-email field
+    pub maintainer_email: Vec<EmailAddress>,
+    /// The long description of the distribution, from the message body or the Description field
+    pub description: Option<String>,
+    /// The markup of the description, e.g. `text/markdown; charset=UTF-8`
+    /// Matches the Description-Content-Type field
+    pub description_content_type: Option<String>,
+}
+
+impl WheelCoreMetadata {
+    /// Returns the extras that are declared by the distribution together with the requirements
+    /// that each extra adds in the given environment. A requirement is added by an extra if its
+    /// marker only applies when that extra is requested.
+    pub fn extra_requirements(
+        &self,
+        env: &MarkerEnvironment,
+    ) -> BTreeMap<Extra, Vec<&Requirement>> {
+        self.extras
+            .iter()
+            .map(|extra| {
+                let requirements = self
+                    .requires_dist
+                    .iter()
+                    .filter(|requirement| {
+                        requirement.marker.as_ref().is_some_and(|marker| {
+                            marker.evaluate(env, &[extra.as_str()]) && !marker.evaluate(env, &[])
+                        })
+                    })
+                    .collect();
+                (extra.clone(), requirements)
+            })
+            .collect()
+    }
+}
+
+/// A distribution that is contained in another distribution, as declared by a `Provides-Dist`
+/// field, e.g. `OtherProject (1.0); python_version < '3.12'`. A requirement on the provided
+/// distribution can be satisfied by the distribution that declares it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvidesDist {
+    /// The name of the provided distribution
+    pub name: PackageName,
+    /// The version of the provided distribution, if it differs from the version of the
+    /// distribution that provides it
+    pub version: Option<Version>,
+    /// The environments in which the distribution is provided, `None` if it is always provided
+    pub marker: Option<MarkerTree>,
+}
+
+impl FromStr for ProvidesDist {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (head, marker) = match s.split_once(';') {
+            Some((head, marker)) => (head, Some(marker.trim())),
+            None => (s, None),
+        };
+        let marker = marker
+            .map(|marker| MarkerTree::from_str(marker).map_err(|e| e.to_string()))
+            .transpose()?;
+
+        let (name, version) = match head.split_once('(') {
+            Some((name, version)) => {
+                let version = version
+                    .trim()
+                    .strip_suffix(')')
+                    .ok_or_else(|| String::from("missing closing parenthesis"))?;
+                let version = Version::from_str(version.trim())?;
+                (name, Some(version))
+            }
+            None => (head, None),
+        };
+        let name = PackageName::from_str(name.trim()).map_err(|e| e.to_string())?;
+
+        Ok(ProvidesDist {
+            name,
+            version,
+            marker,
+        })
+    }
+}
+
+/// An email address with an optional display name as found in the `Author-email` and
+/// `Maintainer-email` fields, e.g. `Jane Doe <jane@example.com>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailAddress {
+    /// The display name, e.g. `Jane Doe`
+    pub name: Option<String>,
+    /// The email address, e.g. `jane@example.com`
+    pub email: String,
+}
+
+impl EmailAddress {
+    /// Parses a comma separated list of RFC822 addresses. Display names may be quoted, in which
+    /// case they can contain commas. Entries that do not contain an email address are skipped.
+    pub fn parse_list(s: &str) -> Vec<EmailAddress> {
+        let mut entries = Vec::new();
+        let mut current = String::new();
+        let mut in_quotes = false;
+        let mut in_brackets = false;
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if in_quotes => {
+                    current.push(c);
+                    current.extend(chars.next());
+                    continue;
+                }
+                '"' => in_quotes = !in_quotes,
+                '<' if !in_quotes => in_brackets = true,
+                '>' if !in_quotes => in_brackets = false,
+                ',' if !in_quotes && !in_brackets => {
+                    entries.push(std::mem::take(&mut current));
+                    continue;
+                }
+                _ => {}
+            }
+            current.push(c);
+        }
+        entries.push(current);
+
+        entries
+            .iter()
+            .filter_map(|entry| {
+                let address = Self::parse_single(entry.trim());
+                if address.is_none() && !entry.trim().is_empty() {
+                    tracing::debug!("ignoring invalid email address '{}'", entry.trim());
+                }
+                address
+            })
+            .collect()
+    }
+
+    /// Parses a single address: `Name <email>`, `email (Name)` or just `email`.
+    fn parse_single(entry: &str) -> Option<EmailAddress> {
+        let (name, email) = if let Some(rest) = entry.strip_suffix('>') {
+            let (name, email) = rest.rsplit_once('<')?;
+            (Some(name), email)
+        } else if let Some(rest) = entry.strip_suffix(')') {
+            let (email, name) = rest.split_once('(')?;
+            (Some(name), email)
+        } else {
+            (None, entry)
+        };
+
+        let email = email.trim();
+        if !email.contains('@') || email.contains(char::is_whitespace) {
+            return None;
+        }
+
+        let name = name.map(unquote).filter(|name| !name.is_empty());
+        Some(EmailAddress {
+            name,
+            email: email.to_owned(),
+        })
+    }
+}
+
+/// Removes the quotes around a display name and resolves escaped characters.
+fn unquote(name: &str) -> String {
+    let name = name.trim();
+    let Some(name) = name.strip_prefix('"').and_then(|n| n.strip_suffix('"')) else {
+        return name.to_owned();
+    };
+    let mut result = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            result.extend(chars.next());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+impl Display for EmailAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) if name.contains(|c: char| c == ',' || c == '"') => write!(
+                f,
+                "\"{}\" <{}>",
+                name.replace('\\', "\\\\").replace('"', "\\\""),
+                self.email
+            ),
+            Some(name) => write!(f, "{} <{}>", name, self.email),
+            None => write!(f, "{}", self.email),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -49,6 +249,17 @@ impl MetadataVersion {
         }
         true
     }
+
+    /// Returns true if metadata of this version can declare dependencies. `Requires-Dist` was
+    /// introduced in version 1.2 (PEP 345), so older metadata cannot be compared with the
+    /// dependencies of a built package.
+    pub fn declares_dependencies(&self) -> bool {
+        static VERSION_1_2: Lazy<MetadataVersion> = Lazy::new(|| {
+            MetadataVersion(Version::from_str("1.2").expect("cannot parse 1.2 version string"))
+        });
+
+        self >= &VERSION_1_2
+    }
 }
 
 #[derive(Debug, Error)]
@@ -91,11 +302,28 @@ impl TryFrom<&[u8]> for WheelCoreMetadata {
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
         let (name, version, metadata_version, mut parsed) = parse_common(value)?;
 
+        // Since metadata version 2.1 the description is the message body, older metadata has a
+        // Description field instead
+        let description_field = parsed
+            .maybe_take("Description")
+            .map_err(|_| WheelCoreMetaDataError::DuplicateKey(String::from("Description")))?;
+        let description = parsed
+            .body
+            .take()
+            .filter(|body| !body.trim().is_empty())
+            .or_else(|| description_field.as_deref().map(unfold_description));
+        let description_content_type =
+            parsed.maybe_take("Description-Content-Type").map_err(|_| {
+                WheelCoreMetaDataError::DuplicateKey(String::from("Description-Content-Type"))
+            })?;
+
         let mut requires_dist = Vec::new();
+        let mut invalid_requires_dist = Vec::new();
         for req_str in parsed.take_all("Requires-Dist").into_iter() {
             match req_str.parse() {
                 Err(e) => {
-                    tracing::warn!("ignoring Requires-Dist: {req_str}, failed to parse: {e}")
+                    tracing::warn!("ignoring Requires-Dist: {req_str}, failed to parse: {e}");
+                    invalid_requires_dist.push(req_str);
                 }
                 Ok(req) => requires_dist.push(req),
             }
@@ -118,17 +346,59 @@ impl TryFrom<&[u8]> for WheelCoreMetadata {
             );
         }
 
+        let mut provides_dist = Vec::new();
+        for provides in parsed.take_all("Provides-Dist").into_iter() {
+            match provides.parse() {
+                Err(e) => {
+                    tracing::warn!("ignoring Provides-Dist: {provides}, failed to parse: {e}")
+                }
+                Ok(provides) => provides_dist.push(provides),
+            }
+        }
+
+        let author_email = parsed
+            .take_all("Author-email")
+            .iter()
+            .flat_map(|value| EmailAddress::parse_list(value))
+            .collect();
+        let maintainer_email = parsed
+            .take_all("Maintainer-email")
+            .iter()
+            .flat_map(|value| EmailAddress::parse_list(value))
+            .collect();
+
         Ok(WheelCoreMetadata {
             name,
             version,
             metadata_version,
             requires_dist,
+            invalid_requires_dist,
             requires_python,
             extras,
+            provides_dist,
+            author_email,
+            maintainer_email,
+            description,
+            description_content_type,
         })
     }
 }
 
+/// Removes the indentation of the continuation lines of a multi-line Description field. Tools
+/// indent these lines with 8 spaces, or with 7 spaces and a `|` to preserve leading whitespace.
+fn unfold_description(value: &str) -> String {
+    let mut lines = value.lines();
+    let first = lines.next().unwrap_or_default();
+    std::iter::once(first)
+        .chain(lines.map(|line| {
+            line.strip_prefix("       |")
+                .or_else(|| line.strip_prefix("        "))
+                .unwrap_or_else(|| line.trim_start())
+        }))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn parse_common(
     input: &[u8],
 ) -> Result<(PackageName, Version, MetadataVersion, RFC822ish), WheelCoreMetaDataError> {
@@ -155,12 +425,21 @@ fn parse_common(
     // pip does), and new metadata releases are so rare and so
     // much-discussed beforehand that if a tool's authors don't know
     // about it it's because the tool is abandoned anyway.
-    let metadata_version = parsed
-        .take("Metadata-Version")
-        .map_err(|_| WheelCoreMetaDataError::MissingKey(String::from("Metadata-Version")))?;
-    let metadata_version: Version = metadata_version
-        .parse()
-        .map_err(WheelCoreMetaDataError::InvalidMetadataVersion)?;
+    //
+    // Some ancient PKG-INFO files do not have a Metadata-Version at all. Those are treated as
+    // version 1.0, which means that they are never considered reliable (PEP 643).
+    let metadata_version: Version = match parsed
+        .maybe_take("Metadata-Version")
+        .map_err(|_| WheelCoreMetaDataError::DuplicateKey(String::from("Metadata-Version")))?
+    {
+        Some(metadata_version) => metadata_version
+            .parse()
+            .map_err(WheelCoreMetaDataError::InvalidMetadataVersion)?,
+        None => {
+            tracing::debug!("metadata without a Metadata-Version, assuming version 1.0");
+            Version::from_str("1.0").expect("cannot parse 1.0 version string")
+        }
+    };
     if metadata_version >= *NEXT_MAJOR_METADATA_VERSION {
         return Err(WheelCoreMetaDataError::UnsupportedVersion(metadata_version));
     }
@@ -181,3 +460,245 @@ fn parse_common(
         parsed,
     ))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn address(name: Option<&str>, email: &str) -> EmailAddress {
+        EmailAddress {
+            name: name.map(ToOwned::to_owned),
+            email: email.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_parse_description() {
+        let metadata = WheelCoreMetadata::try_from(
+            b"Metadata-Version: 2.1
+Name: foo
+Version: 1.0
+Description-Content-Type: text/markdown; charset=UTF-8
+
+# foo
+
+A package that does things:
+
+    foo.bar()
+"
+            .as_slice(),
+        )
+        .unwrap();
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("# foo\n\nA package that does things:\n\n    foo.bar()\n")
+        );
+        assert_eq!(
+            metadata.description_content_type.as_deref(),
+            Some("text/markdown; charset=UTF-8")
+        );
+
+        // Older metadata has a folded Description field instead of a body
+        let metadata = WheelCoreMetadata::try_from(
+            b"Metadata-Version: 1.1
+Name: foo
+Version: 1.0
+Description: First line
+        second line
+       |    indented line
+Platform: UNKNOWN
+"
+            .as_slice(),
+        )
+        .unwrap();
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("First line\nsecond line\n    indented line")
+        );
+        assert_eq!(metadata.description_content_type, None);
+
+        let metadata = WheelCoreMetadata::try_from(
+            b"Metadata-Version: 2.1\nName: foo\nVersion: 1.0\n".as_slice(),
+        )
+        .unwrap();
+        assert_eq!(metadata.description, None);
+    }
+
+    #[test]
+    fn test_parse_author_email() {
+        let metadata = WheelCoreMetadata::try_from(
+            b"Metadata-Version: 2.1
+Name: foo
+Version: 1.0
+Author-email: Jane Doe <jane@example.com>, bob@example.com
+Maintainer-email: \"Doe, John\" <john@example.com>, UNKNOWN
+"
+            .as_slice(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            metadata.author_email,
+            vec![
+                address(Some("Jane Doe"), "jane@example.com"),
+                address(None, "bob@example.com")
+            ]
+        );
+        assert_eq!(
+            metadata.maintainer_email,
+            vec![address(Some("Doe, John"), "john@example.com")]
+        );
+    }
+
+    #[test]
+    fn test_parse_provides_dist() {
+        let metadata = WheelCoreMetadata::try_from(
+            b"Metadata-Version: 2.1
+Name: foo
+Version: 1.0
+Provides-Dist: bar
+Provides-Dist: Baz (2.0); python_version < '3.12'
+Provides-Dist: qux (
+"
+            .as_slice(),
+        )
+        .unwrap();
+
+        let provides = metadata
+            .provides_dist
+            .iter()
+            .map(|p| {
+                (
+                    p.name.as_str(),
+                    p.version.as_ref().map(ToString::to_string),
+                    p.marker.as_ref().map(ToString::to_string),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            provides,
+            [
+                ("bar", None, None),
+                (
+                    "baz",
+                    Some(String::from("2.0")),
+                    Some(String::from("python_version < '3.12'"))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_legacy_metadata() {
+        // Metadata-Version 1.0 (PEP 241) only has a few fields
+        let metadata = WheelCoreMetadata::try_from(
+            b"Metadata-Version: 1.0
+Name: legacy
+Version: 0.1
+Summary: A package from the days of distutils
+Platform: UNKNOWN
+"
+            .as_slice(),
+        )
+        .unwrap();
+        assert_eq!(metadata.name.as_str(), "legacy");
+        assert_eq!(
+            metadata.metadata_version.0,
+            Version::from_str("1.0").unwrap()
+        );
+        assert!(!metadata.metadata_version.implements_pep643());
+        assert!(!metadata.metadata_version.declares_dependencies());
+        assert!(metadata.requires_dist.is_empty());
+
+        // Metadata without a version is treated as 1.0
+        let metadata =
+            WheelCoreMetadata::try_from(b"Name: legacy\nVersion: 0.1\n".as_slice()).unwrap();
+        assert_eq!(
+            metadata.metadata_version.0,
+            Version::from_str("1.0").unwrap()
+        );
+
+        // The name and version are still required
+        assert!(matches!(
+            WheelCoreMetadata::try_from(b"Metadata-Version: 1.0\nName: legacy\n".as_slice()),
+            Err(WheelCoreMetaDataError::MissingKey(key)) if key == "Version"
+        ));
+    }
+
+    #[test]
+    fn test_extra_requirements() {
+        let wheel = crate::artifacts::Wheel::from_path(
+            &std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../test-data/wheels/miniblack-23.1.0-py3-none-any.whl"),
+            &"miniblack".parse().unwrap(),
+        )
+        .unwrap();
+        let (_, metadata) = wheel.metadata().unwrap();
+        let env = MarkerEnvironment {
+            implementation_name: "cpython".to_string(),
+            implementation_version: "3.7.0".parse().unwrap(),
+            os_name: "posix".to_string(),
+            platform_machine: "x86_64".to_string(),
+            platform_python_implementation: "CPython".to_string(),
+            platform_release: String::new(),
+            platform_system: "Linux".to_string(),
+            platform_version: String::new(),
+            python_full_version: "3.7.0".parse().unwrap(),
+            python_version: "3.7".parse().unwrap(),
+            sys_platform: "linux".to_string(),
+        };
+
+        let listing = metadata
+            .extra_requirements(&env)
+            .into_iter()
+            .map(|(extra, requirements)| {
+                (
+                    extra.as_str().to_owned(),
+                    requirements
+                        .iter()
+                        .map(|r| r.name.clone())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Requirements that only depend on the environment (e.g. `typed-ast` for Python 3.7) are
+        // not attributed to any extra
+        assert_eq!(
+            listing,
+            [
+                ("colorama".to_string(), vec!["colorama".to_string()]),
+                ("d".to_string(), vec!["aiohttp".to_string()]),
+                (
+                    "jupyter".to_string(),
+                    vec!["ipython".to_string(), "tokenize-rt".to_string()]
+                ),
+                ("uvloop".to_string(), vec!["uvloop".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_email_address_list() {
+        assert_eq!(
+            EmailAddress::parse_list(
+                r#""Jane \"JD\" Doe" <jane@example.com>,bob@example.com (Bob), <anon@example.com>"#
+            ),
+            vec![
+                address(Some("Jane \"JD\" Doe"), "jane@example.com"),
+                address(Some("Bob"), "bob@example.com"),
+                address(None, "anon@example.com"),
+            ]
+        );
+        assert_eq!(EmailAddress::parse_list(""), vec![]);
+
+        assert_eq!(
+            address(Some("Doe, John"), "john@example.com").to_string(),
+            r#""Doe, John" <john@example.com>"#
+        );
+        assert_eq!(
+            address(Some("Jane Doe"), "jane@example.com").to_string(),
+            "Jane Doe <jane@example.com>"
+        );
+    }
+}
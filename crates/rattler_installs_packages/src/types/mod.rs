@@ -19,6 +19,10 @@ mod project_info;
 mod direct_url_json;
 mod rfc822ish;
 
+mod version;
+
+mod requirement;
+
 pub use artifact::Artifact;
 
 pub use artifact_name::{
@@ -26,9 +30,13 @@ pub use artifact_name::{
     SDistFormat, STreeFilename, SourceArtifactName, WheelFilename,
 };
 
+pub(crate) use artifact_name::split_sdist_extension;
+
 pub use direct_url_json::{DirectUrlHashes, DirectUrlJson, DirectUrlSource, DirectUrlVcs};
 
-pub use core_metadata::{MetadataVersion, WheelCoreMetaDataError, WheelCoreMetadata};
+pub use core_metadata::{
+    EmailAddress, MetadataVersion, ProvidesDist, WheelCoreMetaDataError, WheelCoreMetadata,
+};
 
 pub use record::{Record, RecordEntry};
 
@@ -38,9 +46,18 @@ pub use extra::Extra;
 
 pub use entry_points::{EntryPoint, ParseEntryPointError};
 
-pub use project_info::{ArtifactHashes, ArtifactInfo, DistInfoMetadata, Meta, ProjectInfo, Yanked};
+pub use project_info::{
+    ArtifactHashes, ArtifactInfo, DistInfoMetadata, Meta, ProjectInfo, Sha384Hash, Sha512Hash,
+    Yanked,
+};
 
+pub use rfc822ish::MetadataHeaders;
 pub(crate) use rfc822ish::RFC822ish;
 
+pub use version::{VersionExt, VersionSpecifierExt, VersionSpecifiersExt};
+
+pub(crate) use requirement::canonical_requirement;
+pub use requirement::normalize_requirement;
+
 pub use pep440_rs::{Version, VersionSpecifier, VersionSpecifiers};
-pub use pep508_rs::{MarkerEnvironment, Requirement};
+pub use pep508_rs::{MarkerEnvironment, Requirement, StringVersion, VersionOrUrl};
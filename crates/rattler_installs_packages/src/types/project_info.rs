@@ -6,6 +6,7 @@ use pep440_rs::VersionSpecifiers;
 use rattler_digest::{serde::SerializableHash, Sha256};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, skip_serializing_none, DisplayFromStr, VecSkipError};
+use sha2::{Sha384, Sha512};
 
 /// Represents the result of the response from the Simple API.
 #[serde_as]
@@ -40,6 +41,10 @@ pub struct ArtifactInfo {
     /// Yanked information
     #[serde(default)]
     pub yanked: Yanked,
+    /// The url of the index on which the artifact was found, `None` if the artifact was found in
+    /// a find-links directory or was referenced by a direct url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_url: Option<url::Url>,
 }
 
 impl ArtifactInfo {
@@ -49,8 +54,14 @@ impl ArtifactInfo {
     }
 }
 
+/// A sha384 hash of an artifact.
+pub type Sha384Hash = sha2::digest::Output<Sha384>;
+
+/// A sha512 hash of an artifact.
+pub type Sha512Hash = sha2::digest::Output<Sha512>;
+
 /// Describes a set of hashes for a certain artifact. In theory all hash algorithms available via
-/// Pythons `hashlib` are supported but we only support some common ones.
+/// Pythons `hashlib` are supported but we only support the ones that pip accepts with `--hash`.
 #[serde_as]
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -58,12 +69,33 @@ pub struct ArtifactHashes {
     #[serde_as(as = "Option<SerializableHash<Sha256>>")]
     /// Contains the optional sha256 hash of the artifact
     pub sha256: Option<rattler_digest::Sha256Hash>,
+
+    #[serde_as(as = "Option<SerializableHash<Sha384>>")]
+    /// Contains the optional sha384 hash of the artifact
+    pub sha384: Option<Sha384Hash>,
+
+    #[serde_as(as = "Option<SerializableHash<Sha512>>")]
+    /// Contains the optional sha512 hash of the artifact
+    pub sha512: Option<Sha512Hash>,
 }
 
 impl ArtifactHashes {
     /// Returns true if this instance does not contain a single hash.
     pub fn is_empty(&self) -> bool {
-        self.sha256.is_none()
+        self.sha256.is_none() && self.sha384.is_none() && self.sha512.is_none()
+    }
+
+    /// Returns the name of the algorithm and the hex encoded digest of every hash, sorted by the
+    /// name of the algorithm, e.g. `("sha256", "0a1b...")`.
+    pub fn hex_digests(&self) -> Vec<(&'static str, String)> {
+        [
+            ("sha256", self.sha256.map(|h| format!("{h:x}"))),
+            ("sha384", self.sha384.map(|h| format!("{h:x}"))),
+            ("sha512", self.sha512.map(|h| format!("{h:x}"))),
+        ]
+        .into_iter()
+        .filter_map(|(algorithm, digest)| Some((algorithm, digest?)))
+        .collect()
     }
 }
 
@@ -0,0 +1,129 @@
+use crate::types::{Extra, NormalizedPackageName, PackageName};
+use itertools::Itertools;
+use pep508_rs::{MarkerTree, MarkerValue, Pep508Error, Requirement, VersionOrUrl};
+use std::str::FromStr;
+
+/// Parses a PEP 508 requirement and formats it in a canonical form, so that requirements that
+/// are spelled differently but mean the same compare equal, e.g. `Foo_Bar [B,a] >= 1.0 , <2` and
+/// `foo-bar[a,b]<2,>=1.0` both become `foo-bar[a,b]<2,>=1.0`.
+///
+/// The canonical form has a normalized name, normalized, sorted and deduplicated extras, sorted
+/// and deduplicated specifiers with normalized versions and the markers formatted with single
+/// spaces and quotes. The extras that are compared in the markers are normalized as well.
+pub fn normalize_requirement(requirement: &str) -> Result<String, Pep508Error> {
+    Ok(canonical_requirement(&Requirement::from_str(requirement)?))
+}
+
+/// Formats a parsed requirement in the canonical form of [`normalize_requirement`].
+pub(crate) fn canonical_requirement(requirement: &Requirement) -> String {
+    let mut canonical = PackageName::from_str(&requirement.name)
+        .map(|name| NormalizedPackageName::from(name).to_string())
+        .unwrap_or_else(|_| requirement.name.to_lowercase());
+
+    let extras = requirement
+        .extras
+        .iter()
+        .flatten()
+        .map(|extra| normalize_extra(extra))
+        .sorted()
+        .dedup()
+        .collect::<Vec<_>>();
+    if !extras.is_empty() {
+        canonical.push_str(&format!("[{}]", extras.join(",")));
+    }
+
+    match &requirement.version_or_url {
+        Some(VersionOrUrl::VersionSpecifier(specifiers)) => {
+            let specifiers = specifiers
+                .iter()
+                .map(ToString::to_string)
+                .sorted()
+                .dedup()
+                .join(",");
+            canonical.push_str(&specifiers);
+        }
+        Some(VersionOrUrl::Url(url)) => {
+            // The space before the markers is required because a url can contain a `;`
+            canonical.push_str(&format!(" @ {url}"));
+            if requirement.marker.is_some() {
+                canonical.push(' ');
+            }
+        }
+        None => {}
+    }
+
+    if let Some(marker) = &requirement.marker {
+        canonical.push_str(&format!("; {}", normalize_marker_extras(marker)));
+    }
+
+    canonical
+}
+
+fn normalize_extra(extra: &str) -> String {
+    Extra::from_str(extra)
+        .map(|extra| extra.as_str().to_string())
+        .unwrap_or_else(|_| extra.to_lowercase())
+}
+
+/// Returns the markers with the names of the extras they compare against normalized.
+fn normalize_marker_extras(marker: &MarkerTree) -> MarkerTree {
+    match marker {
+        MarkerTree::Expression(expression) => {
+            let mut expression = expression.clone();
+            if let (MarkerValue::Extra, MarkerValue::QuotedString(extra)) =
+                (&expression.l_value, &mut expression.r_value)
+            {
+                *extra = normalize_extra(extra);
+            }
+            if let (MarkerValue::QuotedString(extra), MarkerValue::Extra) =
+                (&mut expression.l_value, &expression.r_value)
+            {
+                *extra = normalize_extra(extra);
+            }
+            MarkerTree::Expression(expression)
+        }
+        MarkerTree::And(markers) => {
+            MarkerTree::And(markers.iter().map(normalize_marker_extras).collect())
+        }
+        MarkerTree::Or(markers) => {
+            MarkerTree::Or(markers.iter().map(normalize_marker_extras).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize_requirement() {
+        let equivalent = [
+            "foo-bar[a,b]<2,>=1.0; python_version >= '3.8' and extra == 'dev-tools'",
+            "Foo_Bar [B,a] >= 1.0 , <2 ; python_version>=\"3.8\" and extra==\"Dev_Tools\"",
+            "foo.bar[b,a,A]>=1.0,<2,>=1.0;python_version >= '3.8' and extra == 'dev.tools'",
+        ];
+        for requirement in equivalent {
+            assert_eq!(
+                normalize_requirement(requirement).unwrap(),
+                equivalent[0],
+                "{requirement}"
+            );
+        }
+
+        // Versions are normalized, but not padded or stripped because that changes the meaning
+        // of prefix matches
+        assert_eq!(
+            normalize_requirement("foo == 1.0.0-Beta1").unwrap(),
+            "foo==1.0.0b1"
+        );
+        assert_eq!(normalize_requirement("foo==1.0.*").unwrap(), "foo==1.0.*");
+
+        assert_eq!(
+            normalize_requirement("Foo @ https://example.com/foo.whl  ;sys_platform=='linux'")
+                .unwrap(),
+            "foo @ https://example.com/foo.whl ; sys_platform == 'linux'"
+        );
+        assert_eq!(normalize_requirement("FOO").unwrap(), "foo");
+        assert!(normalize_requirement("foo >=").is_err());
+    }
+}
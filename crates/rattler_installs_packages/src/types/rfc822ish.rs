@@ -40,7 +40,56 @@ impl FromStr for RFC822ish {
     type Err = peg::error::ParseError<peg::str::LineCol>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        rfc822ish_parser::rfc822ish(s)
+        MetadataHeaders::from_str(s).map(Into::into)
+    }
+}
+
+impl From<MetadataHeaders> for RFC822ish {
+    fn from(headers: MetadataHeaders) -> Self {
+        let mut fields = Fields::new();
+        for (name, value) in headers.fields {
+            fields.entry(name).or_default().push(value)
+        }
+        RFC822ish {
+            fields,
+            body: headers.body,
+        }
+    }
+}
+
+/// The headers of a `METADATA` or `PKG-INFO` file exactly as they are seen by the metadata parser,
+/// before any of the values are interpreted. This is useful to diagnose why the metadata of an
+/// artifact cannot be parsed or is parsed differently than expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataHeaders {
+    fields: Vec<(String, String)>,
+    body: Option<String>,
+}
+
+impl MetadataHeaders {
+    /// Parses the headers from the contents of a metadata file. Invalid utf-8 is replaced, like
+    /// when the metadata itself is parsed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, peg::error::ParseError<peg::str::LineCol>> {
+        Self::from_str(&String::from_utf8_lossy(bytes))
+    }
+
+    /// Returns the fields in the order in which they appear in the file. The names are lowercase
+    /// and the values of fields that span multiple lines contain the embedded newlines.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    /// Returns the body of the file that follows the headers, if there is one.
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+}
+
+impl FromStr for MetadataHeaders {
+    type Err = peg::error::ParseError<peg::str::LineCol>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        rfc822ish_parser::headers(s)
     }
 }
 
@@ -107,14 +156,10 @@ peg::parser! {
         // trailing_body matches then the input will be fully consumed by
         // then; if not, then we might have a stray trailing newline to
         // absorb.
-        pub rule rfc822ish() -> RFC822ish
-            = f:fields() body:(trailing_body()?) line_ending()?
+        pub rule headers() -> MetadataHeaders
+            = fields:fields() body:(trailing_body()?) line_ending()?
                  {
-                     let mut fields = Fields::new();
-                     for (name, value) in f {
-                         fields.entry(name).or_default().push(value)
-                     };
-                     RFC822ish { fields, body, }
+                     MetadataHeaders { fields, body, }
                  }
     }
 }
@@ -0,0 +1,537 @@
+use pep440_rs::{Operator, Version, VersionSpecifier, VersionSpecifiers};
+
+/// Additional operations on [`Version`] that are useful when building tooling on top of it.
+///
+/// All operations are pure, they return a new [`Version`] and leave the original untouched.
+pub trait VersionExt {
+    /// Returns the canonical form of the version as defined by PEP 440.
+    ///
+    /// Parsing a version already normalizes its spelling (e.g. `1.0-post1`, `1.0.rev1` and
+    /// `1.0post1` all become `1.0.post1`, leading zeros are dropped and local segments are
+    /// lowercased). On top of that the canonical form strips trailing zeros from the release
+    /// segment, so `1.0.0` and `1` have the same canonical form.
+    fn canonicalize(&self) -> Version;
+
+    /// Returns the next major version, e.g. `1.2.3` becomes `2.0.0`.
+    fn bump_major(&self) -> Version;
+
+    /// Returns the next minor version, e.g. `1.2.3` becomes `1.3.0`.
+    fn bump_minor(&self) -> Version;
+
+    /// Returns the next micro version, e.g. `1.2.3` becomes `1.2.4`.
+    fn bump_micro(&self) -> Version;
+}
+
+impl VersionExt for Version {
+    fn canonicalize(&self) -> Version {
+        let mut version = self.clone();
+        while version.release.len() > 1 && version.release.last() == Some(&0) {
+            version.release.pop();
+        }
+        version
+    }
+
+    fn bump_major(&self) -> Version {
+        bump(self, 0)
+    }
+
+    fn bump_minor(&self) -> Version {
+        bump(self, 1)
+    }
+
+    fn bump_micro(&self) -> Version {
+        bump(self, 2)
+    }
+}
+
+/// Version matching that follows PEP 440 more closely than [`VersionSpecifier::contains`].
+///
+/// The prefix matching of `==1.2.*` and `!=1.2.*` compares the release segments of the candidate
+/// as if it was padded with zeros, so `1.2` matches `==1.2.0.*` while `1` does not match
+/// `==1.2.*`. `<` only rejects the pre-releases of the release of its bound if the bound is not a
+/// pre-release itself, so `1.0a1` matches `<1.0rc1`. All other operators are evaluated by
+/// `pep440_rs`.
+pub trait VersionSpecifierExt {
+    /// Returns true if `version` satisfies the specifier(s).
+    fn satisfied_by(&self, version: &Version) -> bool;
+}
+
+impl VersionSpecifierExt for VersionSpecifier {
+    fn satisfied_by(&self, version: &Version) -> bool {
+        match self.operator() {
+            Operator::EqualStar => prefix_matches(self.version(), version),
+            Operator::NotEqualStar => !prefix_matches(self.version(), version),
+            Operator::LessThan => less_than(self.version(), version),
+            _ => self.contains(version),
+        }
+    }
+}
+
+impl VersionSpecifierExt for VersionSpecifiers {
+    fn satisfied_by(&self, version: &Version) -> bool {
+        self.iter().all(|specifier| specifier.satisfied_by(version))
+    }
+}
+
+/// Set operations on [`VersionSpecifiers`], e.g. to combine the requirements on a package and to
+/// detect conflicting requirements before solving.
+///
+/// The versions that satisfy specifiers are determined like [`VersionSpecifierExt`] does. The
+/// methods are named so they do not shadow the methods of the slice that [`VersionSpecifiers`]
+/// dereferences to, in particular an empty list of specifiers matches every version.
+pub trait VersionSpecifiersExt {
+    /// Returns the specifiers that match exactly the versions that match both `self` and `other`.
+    /// Lower and upper bounds that are implied by a tighter bound of the other specifiers are
+    /// removed, so `>=1,<3` and `>=2,<4` become `>=2,<3`. Bounds on the same release (e.g. `>1.0`
+    /// and `>=1.0.post1`) are kept because the pre- and post-release rules make it hard to tell
+    /// which one is tighter.
+    ///
+    /// The result is not checked for satisfiability, use [`Self::is_satisfiable`] for that.
+    fn intersect(&self, other: &VersionSpecifiers) -> VersionSpecifiers;
+
+    /// Returns false if no version can satisfy the specifiers, e.g. `>=2,<1` or `==1.0,!=1.0`.
+    ///
+    /// Exclusions are only taken into account if the specifiers allow a single version, so
+    /// `>=1.0,<=1.0,!=1.0` is unsatisfiable but `>1.0,<1.1,!=1.0.*` is considered satisfiable
+    /// even though it only allows versions that are excluded. Specifiers that are reported as
+    /// unsatisfiable never match a version.
+    fn is_satisfiable(&self) -> bool;
+}
+
+impl VersionSpecifiersExt for VersionSpecifiers {
+    fn intersect(&self, other: &VersionSpecifiers) -> VersionSpecifiers {
+        let mut specifiers: Vec<VersionSpecifier> = Vec::new();
+        for specifier in self.iter().chain(other.iter()) {
+            if !specifiers.contains(specifier) {
+                specifiers.push(specifier.clone());
+            }
+        }
+
+        // A bound is implied by a bound in the same direction on a later (or earlier) release
+        let is_lower = |s: &VersionSpecifier| {
+            matches!(
+                s.operator(),
+                Operator::GreaterThan | Operator::GreaterThanEqual
+            )
+        };
+        let is_upper = |s: &VersionSpecifier| {
+            matches!(s.operator(), Operator::LessThan | Operator::LessThanEqual)
+        };
+        let implied = |specifier: &VersionSpecifier| {
+            let release = release_of(specifier.version());
+            specifiers.iter().any(|other| {
+                let other_release = release_of(other.version());
+                (is_lower(specifier) && is_lower(other) && other_release > release)
+                    || (is_upper(specifier) && is_upper(other) && other_release < release)
+            })
+        };
+        specifiers
+            .iter()
+            .filter(|specifier| !implied(specifier))
+            .cloned()
+            .collect()
+    }
+
+    fn is_satisfiable(&self) -> bool {
+        // Exact versions can simply be checked against all specifiers
+        if let Some(exact) = self
+            .iter()
+            .find(|s| matches!(s.operator(), Operator::Equal | Operator::ExactEqual))
+        {
+            return self.satisfied_by(exact.version());
+        }
+
+        // Otherwise the highest lower bound must not be above the lowest upper bound, each bound
+        // is a version and whether it is inclusive
+        let mut lower: Option<(Version, bool)> = None;
+        let mut upper: Option<(Version, bool)> = None;
+        for specifier in self.iter() {
+            let version = specifier.version();
+            let (lower_bound, upper_bound) = match specifier.operator() {
+                Operator::GreaterThan => (Some((version.clone(), false)), None),
+                Operator::GreaterThanEqual => (Some((version.clone(), true)), None),
+                Operator::LessThanEqual => (None, Some((version.clone(), true))),
+                // The pre-releases of the release of a bound that is not a pre-release itself are
+                // excluded, so `<1.0` is `<1.0.dev0`
+                Operator::LessThan if version.any_prerelease() || version.post.is_some() => {
+                    (None, Some((version.clone(), false)))
+                }
+                Operator::LessThan => (None, Some((first_of_release(version), false))),
+                Operator::EqualStar => (
+                    Some((first_of_release(version), true)),
+                    Some((
+                        first_of_release(&bump(version, version.release.len() - 1)),
+                        false,
+                    )),
+                ),
+                Operator::TildeEqual => (
+                    Some((version.clone(), true)),
+                    Some((
+                        first_of_release(&bump(version, version.release.len().saturating_sub(2))),
+                        false,
+                    )),
+                ),
+                _ => (None, None),
+            };
+            if let Some(bound) = lower_bound {
+                let tighter = lower.as_ref().map_or(true, |(v, inclusive)| {
+                    bound.0 > *v || (bound.0 == *v && *inclusive)
+                });
+                if tighter {
+                    lower = Some(bound);
+                }
+            }
+            if let Some(bound) = upper_bound {
+                let tighter = upper.as_ref().map_or(true, |(v, inclusive)| {
+                    bound.0 < *v || (bound.0 == *v && *inclusive)
+                });
+                if tighter {
+                    upper = Some(bound);
+                }
+            }
+        }
+
+        match (lower, upper) {
+            (Some((lower, lower_inclusive)), Some((upper, upper_inclusive))) => {
+                if lower < upper {
+                    true
+                } else if lower == upper && lower_inclusive && upper_inclusive {
+                    self.satisfied_by(&lower)
+                } else {
+                    false
+                }
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Returns the epoch and the release of `version` without trailing zeros, e.g. `1.2` for
+/// `1.2.0.post1`.
+fn release_of(version: &Version) -> Version {
+    Version {
+        epoch: version.epoch,
+        release: version.release.clone(),
+        pre: None,
+        post: None,
+        dev: None,
+        local: None,
+    }
+    .canonicalize()
+}
+
+/// Returns the lowest version of the release of `version`, which is its first dev-release.
+fn first_of_release(version: &Version) -> Version {
+    Version {
+        dev: Some(0),
+        ..release_of(version)
+    }
+}
+
+/// Returns true if the release of `version` starts with the release of `prefix`. Missing release
+/// segments of `version` are treated as zeros, pre-, post-, dev-releases and local versions of
+/// `version` are ignored.
+fn prefix_matches(prefix: &Version, version: &Version) -> bool {
+    prefix.epoch == version.epoch
+        && prefix
+            .release
+            .iter()
+            .enumerate()
+            .all(|(index, segment)| version.release.get(index).copied().unwrap_or(0) == *segment)
+}
+
+/// Returns true if `version` is older than `bound`. Unless `bound` is a pre-release, the pre-
+/// and dev-releases of the release of `bound` are not older, so `3.1.dev0` does not match `<3.1`.
+/// The local version of `version` is ignored.
+fn less_than(bound: &Version, version: &Version) -> bool {
+    let public = Version {
+        local: None,
+        ..version.clone()
+    };
+    if public >= *bound {
+        return false;
+    }
+    bound.any_prerelease() || !version.any_prerelease() || !prefix_matches(bound, version)
+}
+
+/// Increments the release segment at `index` and resets all segments after it to zero. The
+/// release is padded with zeros if it has fewer segments. The epoch is kept, but any pre-, post-,
+/// dev-release or local version is dropped because they refer to the previous release.
+fn bump(version: &Version, index: usize) -> Version {
+    let mut release = version.release.clone();
+    if release.len() <= index {
+        release.resize(index + 1, 0);
+    }
+    release[index] += 1;
+    for segment in &mut release[index + 1..] {
+        *segment = 0;
+    }
+
+    Version {
+        epoch: version.epoch,
+        release,
+        pre: None,
+        post: None,
+        dev: None,
+        local: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn canonical(version: &str) -> String {
+        Version::from_str(version)
+            .unwrap()
+            .canonicalize()
+            .to_string()
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        for (spellings, expected) in [
+            (&["1", "1.0", "1.0.0", "01.00.0", "v1.0"][..], "1"),
+            (&["1.2.0", "1.02", "1.2"][..], "1.2"),
+            (
+                &[
+                    "1.0-post1",
+                    "1.0.post1",
+                    "1.0post1",
+                    "1.0-1",
+                    "1.0.rev1",
+                    "1.0-r1",
+                ][..],
+                "1.post1",
+            ),
+            (
+                &["1.0.dev2", "1.0-DEV2", "1.0dev2", "1.0_dev2"][..],
+                "1.dev2",
+            ),
+            (&["1.0RC1", "1.0-rc.1", "1.0c1", "1.0.pre1"][..], "1rc1"),
+            (&["1.0alpha2", "1.0.a2", "1.0-A2"][..], "1a2"),
+            (&["2!1.0.0+Ubuntu-1", "2!1.0+ubuntu.1"][..], "2!1+ubuntu.1"),
+            (&["0.0"][..], "0"),
+        ] {
+            for spelling in spellings {
+                assert_eq!(canonical(spelling), expected, "{spelling}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_bump() {
+        let bumps = |version: &str| {
+            let version = Version::from_str(version).unwrap();
+            [
+                version.bump_major().to_string(),
+                version.bump_minor().to_string(),
+                version.bump_micro().to_string(),
+            ]
+        };
+
+        assert_eq!(bumps("1.2.3"), ["2.0.0", "1.3.0", "1.2.4"]);
+        assert_eq!(bumps("1"), ["2", "1.1", "1.0.1"]);
+        assert_eq!(bumps("1.2.3.4"), ["2.0.0.0", "1.3.0.0", "1.2.4.0"]);
+        assert_eq!(
+            bumps("1.2.3rc1.post2.dev3+local"),
+            ["2.0.0", "1.3.0", "1.2.4"]
+        );
+        assert_eq!(bumps("1!0.9"), ["1!1.0", "1!0.10", "1!0.9.1"]);
+
+        // A bumped version is always newer
+        let version = Version::from_str("1.2.3.post1").unwrap();
+        assert!(version.bump_micro() > version);
+    }
+
+    #[test]
+    fn test_wildcard_specifiers() {
+        let satisfied_by = |specifiers: &str, version: &str| {
+            VersionSpecifiers::from_str(specifiers)
+                .unwrap()
+                .satisfied_by(&Version::from_str(version).unwrap())
+        };
+
+        for version in [
+            "1.2",
+            "1.2.0",
+            "1.2.9",
+            "1.2.3.4",
+            "1.2.0+local",
+            "1.2.1.post1",
+        ] {
+            assert!(satisfied_by("==1.2.*", version), "{version}");
+            assert!(!satisfied_by("!=1.2.*", version), "{version}");
+        }
+        for version in ["1.3.0", "1.20", "1", "2.1", "0!1.1.9", "1!1.2.0"] {
+            assert!(!satisfied_by("==1.2.*", version), "{version}");
+            assert!(satisfied_by("!=1.2.*", version), "{version}");
+        }
+
+        // Missing segments of the candidate are zeros
+        assert!(!satisfied_by("==2.1.*", "2"));
+        assert!(satisfied_by("==2.0.*", "2"));
+        assert!(satisfied_by("!=2.1.*", "2"));
+
+        // Prefix matching ignores the pre-release segment, whether pre-releases are acceptable
+        // is decided by the resolver
+        assert!(satisfied_by("==1.2.*", "1.2.0rc1"));
+        assert!(satisfied_by("==1.2.*", "1.2.dev0"));
+        assert!(!satisfied_by("!=1.2.*", "1.2.0a1"));
+
+        // Combined with other specifiers
+        assert!(satisfied_by(">=1.0, !=1.2.*, <2", "1.3"));
+        assert!(!satisfied_by(">=1.0, !=1.2.*, <2", "1.2.5"));
+    }
+
+    #[test]
+    fn test_prefix_exclusions_with_bounds() {
+        let satisfied_by = |specifiers: &str, version: &str| {
+            VersionSpecifiers::from_str(specifiers)
+                .unwrap()
+                .satisfied_by(&Version::from_str(version).unwrap())
+        };
+
+        let matrix: &[(&str, &[(&str, bool)])] = &[
+            (
+                ">=1.4.0, !=1.4.*",
+                &[
+                    ("1.3.9", false),
+                    ("1.4", false),
+                    ("1.4.0", false),
+                    ("1.4.0+local", false),
+                    ("1.4.0.post1", false),
+                    ("1.4.0.dev1", false),
+                    ("1.4.1rc1", false),
+                    ("1.4.9", false),
+                    ("1.5.dev0", true),
+                    ("1.5.0", true),
+                    ("2.0", true),
+                    ("1!1.3", true),
+                ],
+            ),
+            (
+                "!=1.4.*, <1.5",
+                &[
+                    ("1.3", true),
+                    ("1.3.9", true),
+                    ("1.3.9.post1", true),
+                    ("1.4.0.dev1", false),
+                    ("1.4.0", false),
+                    ("1.4.9", false),
+                    ("1.5.dev0", false),
+                    ("1.5.0a1", false),
+                    ("1.5.0", false),
+                ],
+            ),
+            (
+                "!=1.4.*, >1.3",
+                &[
+                    ("1.3", false),
+                    ("1.3+local", false),
+                    ("1.3.0.post1", false),
+                    ("1.3.1", true),
+                    ("1.4", false),
+                    ("1.5", true),
+                ],
+            ),
+            (
+                "!=1.4.*, <=1.4",
+                &[("1.3", true), ("1.4", false), ("1.4+local", false)],
+            ),
+            (
+                "!=1.4.0.*, >=1.4",
+                &[("1.4", false), ("1.4.0.1", false), ("1.4.1", true)],
+            ),
+            (
+                "~=1.4.0, !=1.4.2.*",
+                &[
+                    ("1.4.1", true),
+                    ("1.4.2", false),
+                    ("1.4.2.1", false),
+                    ("1.4.3", true),
+                    ("1.5", false),
+                ],
+            ),
+            (
+                "!=1.4.*, ~=1.3",
+                &[
+                    ("1.3", true),
+                    ("1.4", false),
+                    ("1.4.2", false),
+                    ("1.5", true),
+                    ("2.0", false),
+                ],
+            ),
+            // The pre-releases of a pre-release upper bound are older than it
+            (
+                "!=1.4.*, <1.5.0rc1",
+                &[
+                    ("1.3", true),
+                    ("1.4.1", false),
+                    ("1.5.0a1", true),
+                    ("1.5.0rc1.dev0", true),
+                    ("1.5.0rc1", false),
+                    ("1.5.0", false),
+                ],
+            ),
+        ];
+        for (specifiers, versions) in matrix {
+            for (version, expected) in *versions {
+                assert_eq!(
+                    satisfied_by(specifiers, version),
+                    *expected,
+                    "{version} {specifiers}"
+                );
+            }
+        }
+    }
+    #[test]
+    fn test_intersect_specifiers() {
+        let intersect = |a: &str, b: &str| {
+            VersionSpecifiers::from_str(a)
+                .unwrap()
+                .intersect(&VersionSpecifiers::from_str(b).unwrap())
+        };
+
+        // Overlapping ranges are merged
+        for (a, b, expected) in [
+            (">=1,<3", ">=2,<4", "<3, >=2"),
+            (">=1", "<2", ">=1, <2"),
+            (">=1,<3", ">=1,<3", ">=1, <3"),
+            ("~=1.4.5", ">=1.4.7,!=1.4.8", "~=1.4.5, >=1.4.7, !=1.4.8"),
+            (">1.0", ">=1.0.post1", ">1.0, >=1.0.post1"),
+            ("<=2", "<2.1", "<=2"),
+        ] {
+            let intersection = intersect(a, b);
+            assert_eq!(intersection.to_string(), expected, "{a} and {b}");
+            assert!(intersection.is_satisfiable(), "{a} and {b}");
+        }
+
+        // Disjoint ranges cannot be satisfied
+        for (a, b) in [
+            (">=2", "<1"),
+            (">=2", "<2"),
+            (">2", "<=2"),
+            ("==1.0", "==2.0"),
+            ("==1.0", "!=1.0"),
+            (">=1.0,<=1.0", "!=1.0"),
+            ("==1.*", ">=2"),
+            ("~=1.4.5", "<1.4"),
+            (">=1.0a1", "<1.0"),
+        ] {
+            assert!(!intersect(a, b).is_satisfiable(), "{a} and {b}");
+        }
+
+        // Bounds that touch are satisfiable if they are inclusive
+        for (a, b) in [
+            (">=2", "<=2"),
+            ("==1.*", "<1.1"),
+            (">=1.0a1", "<1.0rc1"),
+            ("!=1.0", "!=2.0"),
+        ] {
+            assert!(intersect(a, b).is_satisfiable(), "{a} and {b}");
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! A decoder for Deflate64, also known as "enhanced deflate" (zip compression method 9).
+//!
+//! Deflate64 is a variant of deflate with a 64KiB window. The only differences with deflate are
+//! that length code 285 encodes lengths up to 65538 using 16 extra bits and that the distance codes
+//! 30 and 31 are used for distances beyond 32KiB. Some zip tools (e.g. the Windows explorer) use it
+//! for larger files, `zip` and `async_zip` do not support it.
+
+use std::io;
+
+const MAX_BITS: usize = 15;
+
+/// The base lengths of the length codes 257..=285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 3,
+];
+
+/// The number of extra bits of the length codes 257..=285.
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 16,
+];
+
+/// The base distances of the distance codes 0..=31.
+const DISTANCE_BASE: [u32; 32] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577, 32769, 49153,
+];
+
+/// The number of extra bits of the distance codes 0..=31.
+const DISTANCE_EXTRA: [u8; 32] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13, 14, 14,
+];
+
+/// The order in which the code lengths of the code length alphabet are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid deflate64 data: {message}"),
+    )
+}
+
+/// Reads bits from a byte slice, starting at the least significant bit of each byte.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u64,
+    buffered_bits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            buffer: 0,
+            buffered_bits: 0,
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> io::Result<u32> {
+        while self.buffered_bits < count {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+            self.position += 1;
+            self.buffer |= u64::from(byte) << self.buffered_bits;
+            self.buffered_bits += 8;
+        }
+        let value = (self.buffer & ((1 << count) - 1)) as u32;
+        self.buffer >>= count;
+        self.buffered_bits -= count;
+        Ok(value)
+    }
+
+    /// Discards the remaining bits of the current byte.
+    fn align_to_byte(&mut self) {
+        self.buffer = 0;
+        self.buffered_bits = 0;
+    }
+
+    fn bytes(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        debug_assert_eq!(self.buffered_bits, 0);
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        self.position += count;
+        Ok(bytes)
+    }
+}
+
+/// A canonical Huffman code, stored as the number of codes per length and the symbols ordered by
+/// their code.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Codes of a length cannot be used more often than there are bit patterns left
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(invalid_data("over-subscribed huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for length in 1..MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid_data("invalid huffman code"))
+    }
+}
+
+/// Decompresses a complete Deflate64 stream.
+pub(crate) fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut reader = BitReader::new(data);
+    let mut output = Vec::new();
+    loop {
+        let is_last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let header = reader.bytes(4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err(invalid_data("stored block length mismatch"));
+                }
+                output.extend_from_slice(reader.bytes(length as usize)?);
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths)?;
+                let distances = Huffman::new(&[5; 32])?;
+                inflate_block(&mut reader, &mut output, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut output, &literals, &distances)?;
+            }
+            _ => return Err(invalid_data("invalid block type")),
+        }
+        if is_last {
+            return Ok(output);
+        }
+    }
+}
+
+/// Reads the literal/length and distance codes from the header of a dynamic block.
+fn read_dynamic_codes(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+    if literal_count > 286 {
+        return Err(invalid_data("too many length codes"));
+    }
+
+    let mut code_length_lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_length_lengths[symbol] = reader.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_length_lengths)?;
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (length, repeat) = match code_lengths.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or_else(|| invalid_data("repeated code length without a previous one"))?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        if lengths.len() + repeat as usize > literal_count + distance_count {
+            return Err(invalid_data("too many code lengths"));
+        }
+        lengths.extend(std::iter::repeat(length).take(repeat as usize));
+    }
+    if lengths[256] == 0 {
+        return Err(invalid_data("missing end-of-block code"));
+    }
+
+    let (literal_lengths, distance_lengths) = lengths.split_at(literal_count);
+    Ok((
+        Huffman::new(literal_lengths)?,
+        Huffman::new(distance_lengths)?,
+    ))
+}
+
+/// Decodes the compressed data of a block up to and including its end-of-block code.
+fn inflate_block(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> io::Result<()> {
+    loop {
+        let length_code = match literals.decode(reader)? {
+            literal @ 0..=255 => {
+                output.push(literal as u8);
+                continue;
+            }
+            256 => return Ok(()),
+            symbol => usize::from(symbol - 257),
+        };
+        if length_code >= LENGTH_BASE.len() {
+            return Err(invalid_data("invalid length code"));
+        }
+        let length = usize::from(LENGTH_BASE[length_code])
+            + reader.bits(LENGTH_EXTRA[length_code].into())? as usize;
+
+        let distance_code = distances.decode(reader)? as usize;
+        if distance_code >= DISTANCE_BASE.len() {
+            return Err(invalid_data("invalid distance code"));
+        }
+        let distance = (DISTANCE_BASE[distance_code]
+            + reader.bits(DISTANCE_EXTRA[distance_code].into())?) as usize;
+        if distance > output.len() {
+            return Err(invalid_data("distance too far back"));
+        }
+
+        // The referenced bytes can overlap with the bytes that are being copied
+        let start = output.len() - distance;
+        for idx in start..start + length {
+            output.push(output[idx]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    /// Writes bits starting at the least significant bit of each byte, like deflate expects.
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        buffer: u64,
+        buffered_bits: u32,
+    }
+
+    impl BitWriter {
+        fn bits(&mut self, value: u32, count: u32) -> &mut Self {
+            self.buffer |= u64::from(value) << self.buffered_bits;
+            self.buffered_bits += count;
+            while self.buffered_bits >= 8 {
+                self.bytes.push(self.buffer as u8);
+                self.buffer >>= 8;
+                self.buffered_bits -= 8;
+            }
+            self
+        }
+
+        /// Huffman codes are stored starting with their most significant bit.
+        fn code(&mut self, code: u32, count: u32) -> &mut Self {
+            self.bits(code.reverse_bits() >> (32 - count), count)
+        }
+
+        fn finish(&mut self) -> Vec<u8> {
+            if self.buffered_bits > 0 {
+                self.bytes.push(self.buffer as u8);
+            }
+            std::mem::take(&mut self.bytes)
+        }
+    }
+
+    #[test]
+    fn test_deflate_compatible() {
+        let data = (0..2000)
+            .map(|i| format!("line {i}\n"))
+            .collect::<String>()
+            .into_bytes();
+
+        // Deflate streams without matches of 258 bytes are valid deflate64 streams
+        for level in [
+            Compression::none(),
+            Compression::fast(),
+            Compression::best(),
+        ] {
+            let mut encoder = DeflateEncoder::new(Vec::new(), level);
+            encoder.write_all(&data).unwrap();
+            let compressed = encoder.finish().unwrap();
+            assert_eq!(inflate(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_long_length_and_distance() {
+        // A stored block with 40000 bytes, followed by a block with fixed codes
+        let pattern = (0..40000u32)
+            .map(|i| (i * 7 % 251) as u8)
+            .collect::<Vec<_>>();
+        let mut stream = BitWriter::default().bits(0, 1).bits(0, 2).finish();
+        stream.extend_from_slice(&(pattern.len() as u16).to_le_bytes());
+        stream.extend_from_slice(&(!(pattern.len() as u16)).to_le_bytes());
+        stream.extend_from_slice(&pattern);
+
+        let mut writer = BitWriter::default();
+        writer
+            .bits(1, 1)
+            .bits(1, 2)
+            // Literal 'a'
+            .code(0x30 + u32::from(b'a'), 8)
+            // A length of 300 repeating the 'a': code 285 with 16 extra bits, distance code 0
+            .code(0xc0 + 285 - 280, 8)
+            .bits(300 - 3, 16)
+            .code(0, 5)
+            // A length of 10 at distance 40301: distance code 30 with 14 extra bits
+            .code(264 - 256, 7)
+            .code(30, 5)
+            .bits(40301 - 32769, 14)
+            // End of block
+            .code(0, 7);
+        stream.extend(writer.finish());
+
+        let mut expected = pattern.clone();
+        expected.extend(std::iter::repeat(b'a').take(301));
+        expected.extend_from_slice(&pattern[..10]);
+        assert_eq!(inflate(&stream).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        // Block type 3 does not exist
+        let stream = BitWriter::default().bits(1, 1).bits(3, 2).finish();
+        assert_eq!(
+            inflate(&stream).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // A distance before the start of the output
+        let stream = BitWriter::default()
+            .bits(1, 1)
+            .bits(1, 2)
+            .code(264 - 256, 7)
+            .code(0, 5)
+            .code(0, 7)
+            .finish();
+        assert_eq!(
+            inflate(&stream).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // Truncated data
+        assert_eq!(
+            inflate(&[0b011]).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+}
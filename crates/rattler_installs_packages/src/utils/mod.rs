@@ -1,3 +1,4 @@
+pub(crate) mod deflate64;
 mod read_and_seek;
 mod streaming_or_local;
 
@@ -40,7 +40,7 @@ impl StreamingOrLocal {
                     if bytes_read == 0 {
                         break;
                     }
-                    local_file.write_all(&buf)?;
+                    local_file.write_all(&buf[..bytes_read])?;
                 }
 
                 // Restart the file from the start so we can start reading from it.
@@ -77,3 +77,19 @@ impl StreamingOrLocal {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_into_local() {
+        // Only the bytes that were read are copied, the last read does not fill the whole buffer
+        let data = (0..10_000u32).map(|i| i as u8).collect::<Vec<_>>();
+        let streaming = StreamingOrLocal::Streaming(Box::new(io::Cursor::new(data.clone())));
+        let mut local = streaming.into_local().await.unwrap();
+        let mut bytes = Vec::new();
+        local.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, data);
+    }
+}
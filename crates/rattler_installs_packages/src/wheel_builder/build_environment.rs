@@ -4,7 +4,7 @@ use crate::artifacts::SourceArtifact;
 use crate::python_env::{PythonLocation, VEnv};
 use crate::resolve::{resolve, PinnedPackage};
 use crate::utils::normalize_path;
-use crate::wheel_builder::{WheelBuildError, WheelBuilder};
+use crate::wheel_builder::{BuildDependencyCycle, WheelBuildError, WheelBuilder};
 use fs_err as fs;
 use fs_err::read_dir;
 use parking_lot::RwLock;
@@ -187,7 +187,7 @@ impl BuildEnvironment {
         let output = self.run_command("GetRequiresForBuildWheel")?;
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(WheelBuildError::Error(stderr.to_string()));
+            return Err(WheelBuildError::BackendFailed(stderr.to_string()));
         }
 
         // The extra requirements are stored in a file called extra_requirements.json
@@ -244,7 +244,7 @@ impl BuildEnvironment {
                 self.env_variables.clone(),
             )
             .await
-            .map_err(|e| WheelBuildError::CouldNotResolveEnvironment(all_requirements, e))?;
+            .map_err(|e| WheelBuildError::CouldNotResolveEnvironment(all_requirements, e.into()))?;
 
             // install extra wheels
             for package_info in extra_resolved_wheels {
@@ -347,6 +347,15 @@ impl BuildEnvironment {
         }
     }
 
+    /// Returns the build system of the source artifact. Source artifacts without a
+    /// `pyproject.toml` or without a build backend are built with the legacy setuptools backend.
+    pub(crate) fn build_system(sdist: &impl SourceArtifact) -> pyproject_toml::BuildSystem {
+        match sdist.read_build_info() {
+            Ok(build_system) if build_system.build_backend.is_some() => build_system,
+            _ => Self::default_build_system(),
+        }
+    }
+
     /// Setup the build environment so that we can build a wheel from an sdist
     pub(crate) async fn setup(
         sdist: &impl SourceArtifact,
@@ -360,15 +369,7 @@ impl BuildEnvironment {
         )?;
 
         // Find the build system
-        let build_system = sdist
-            .read_build_info()
-            .unwrap_or_else(|_| Self::default_build_system());
-
-        let build_system = if build_system.build_backend.is_none() {
-            Self::default_build_system()
-        } else {
-            build_system
-        };
+        let build_system = Self::build_system(sdist);
 
         let entry_point = build_system
             .build_backend
@@ -401,7 +402,7 @@ impl BuildEnvironment {
                 "could not resolve build requirements when trying to build a wheel for : {}",
                 sdist.artifact_name()
             );
-            WheelBuildError::CouldNotResolveEnvironment(build_requirements.to_vec(), e)
+            WheelBuildError::CouldNotResolveEnvironment(build_requirements.to_vec(), e.into())
         })?;
 
         // Install into venv
@@ -412,7 +413,10 @@ impl BuildEnvironment {
                 .package_db
                 .get_wheel(artifact_info, Some(wheel_builder))
                 .await
-                .map_err(WheelBuildError::CouldNotGetArtifact)?;
+                .map_err(|e| match e.downcast::<BuildDependencyCycle>() {
+                    Ok(cycle) => WheelBuildError::BuildDependencyCycle(cycle),
+                    Err(e) => WheelBuildError::CouldNotGetArtifact(e),
+                })?;
 
             venv.install_wheel(
                 &artifact,
@@ -0,0 +1,123 @@
+use crate::types::canonical_requirement;
+use itertools::Itertools;
+use pep508_rs::Requirement;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// The dependencies that a source distribution declares without building it (in its `PKG-INFO` or
+/// statically in its `pyproject.toml`) differ from the dependencies of the metadata that the build
+/// backend produced. The declared metadata of such a package cannot be trusted, even if it claims
+/// to be reliable (PEP 643).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataMismatch {
+    /// The name and version of the source distribution
+    pub package: String,
+
+    /// The requirements that are only declared by the source distribution
+    pub only_declared: Vec<Requirement>,
+
+    /// The requirements that are only part of the built metadata
+    pub only_built: Vec<Requirement>,
+}
+
+impl MetadataMismatch {
+    /// Compares the declared and the built `Requires-Dist` of a package. Returns `None` if they
+    /// contain the same requirements, regardless of their order and formatting.
+    pub fn compare(
+        package: impl Into<String>,
+        declared: &[Requirement],
+        built: &[Requirement],
+    ) -> Option<Self> {
+        let declared = by_normalized_form(declared);
+        let built = by_normalized_form(built);
+        let only_declared = declared
+            .iter()
+            .filter(|(key, _)| !built.contains_key(*key))
+            .map(|(_, requirement)| (*requirement).clone())
+            .collect::<Vec<_>>();
+        let only_built = built
+            .iter()
+            .filter(|(key, _)| !declared.contains_key(*key))
+            .map(|(_, requirement)| (*requirement).clone())
+            .collect::<Vec<_>>();
+        if only_declared.is_empty() && only_built.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            package: package.into(),
+            only_declared,
+            only_built,
+        })
+    }
+}
+
+impl Display for MetadataMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the dependencies that the source distribution of {} declares differ from the dependencies of the built package",
+            self.package
+        )?;
+        if !self.only_declared.is_empty() {
+            write!(
+                f,
+                "; only declared: {}",
+                self.only_declared.iter().join(", ")
+            )?;
+        }
+        if !self.only_built.is_empty() {
+            write!(f, "; only built: {}", self.only_built.iter().join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Indexes requirements by their canonical form.
+fn by_normalized_form(requirements: &[Requirement]) -> BTreeMap<String, &Requirement> {
+    requirements
+        .iter()
+        .map(|requirement| (canonical_requirement(requirement), requirement))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn requirements(requirements: &[&str]) -> Vec<Requirement> {
+        requirements
+            .iter()
+            .map(|r| Requirement::from_str(r).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_compare() {
+        let declared = requirements(&[
+            "Foo_Bar[b,a]>=1.0",
+            "requests>=2",
+            "tomli; python_version < \"3.11\"",
+        ]);
+
+        // Formatting, order and the spelling of names and extras do not matter
+        let same = requirements(&[
+            "tomli ; python_version < '3.11'",
+            "foo-bar[a,b] >=1.0",
+            "requests >=2",
+        ]);
+        assert_eq!(MetadataMismatch::compare("foo 1.0", &declared, &same), None);
+
+        let built = requirements(&["foo-bar[a,b]>=1.0", "requests>=2.1", "urllib3"]);
+        let mismatch = MetadataMismatch::compare("foo 1.0", &declared, &built).unwrap();
+        assert_eq!(
+            mismatch.only_declared,
+            requirements(&["requests>=2", "tomli; python_version < '3.11'"])
+        );
+        assert_eq!(
+            mismatch.only_built,
+            requirements(&["requests>=2.1", "urllib3"])
+        );
+    }
+}
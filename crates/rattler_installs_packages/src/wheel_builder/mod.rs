@@ -1,6 +1,7 @@
 //! Turn an sdist into a wheel by creating a virtualenv and building the sdist in it
 
 mod build_environment;
+mod metadata_mismatch;
 mod wheel_cache;
 
 use fs_err as fs;
@@ -11,6 +12,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::{collections::HashMap, path::PathBuf};
 
+use miette::Diagnostic;
 use parking_lot::Mutex;
 use pep508_rs::{MarkerEnvironment, Requirement};
 
@@ -20,7 +22,8 @@ use crate::resolve::{OnWheelBuildFailure, ResolveOptions};
 use crate::types::{
     NormalizedPackageName, PackageName, ParseArtifactNameError, SourceArtifactName, WheelFilename,
 };
-use crate::wheel_builder::build_environment::BuildEnvironment;
+pub(crate) use crate::wheel_builder::build_environment::BuildEnvironment;
+pub use crate::wheel_builder::metadata_mismatch::MetadataMismatch;
 pub use crate::wheel_builder::wheel_cache::{WheelCache, WheelCacheKey};
 use crate::{
     artifacts::wheel::UnpackError,
@@ -32,6 +35,13 @@ use crate::{
 
 type BuildCache = Mutex<HashMap<SourceArtifactName, Arc<BuildEnvironment>>>;
 
+tokio::task_local! {
+    /// The packages whose build environments are being set up by the current task, from the
+    /// outermost to the innermost. Building a build dependency from source happens in the same
+    /// task, so this is the chain of builds that led to the current one.
+    static BUILD_CHAIN: Vec<NormalizedPackageName>;
+}
+
 /// A builder for wheels
 pub struct WheelBuilder {
     /// A cache for virtualenvs that might be reused later in the process
@@ -59,10 +69,40 @@ pub struct WheelBuilder {
     /// only if the `save_on_failure` option is set in resolve options
     saved_build_envs: Mutex<HashSet<PathBuf>>,
 
+    /// The packages whose declared dependencies differ from the dependencies of the built package
+    metadata_mismatches: Mutex<Vec<MetadataMismatch>>,
+
     /// Python interpreter version
     python_version: PythonInterpreterVersion,
 }
 
+/// The build backend failed to build a source distribution. This is returned by
+/// [`crate::index::PackageDb::get_metadata`] if none of the artifacts provided metadata and at least
+/// one of the source distributions failed to build.
+#[derive(thiserror::Error, Debug, Diagnostic)]
+#[error("the build backend failed to build '{filename}'")]
+pub struct SDistBuildError {
+    /// The filename of the source distribution
+    pub filename: String,
+
+    /// The output of the build backend
+    pub output: String,
+}
+
+/// The build dependencies of a source distribution require building that same source
+/// distribution, e.g. because its build backend is only available as a source distribution that
+/// is built with itself. This would otherwise never finish.
+#[derive(thiserror::Error, Debug, Diagnostic, Clone)]
+#[error("the build dependencies of {} form a cycle: {}", .cycle[0].as_str(), .cycle.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(" -> "))]
+#[diagnostic(help(
+    "a package in the cycle must be available as a wheel, make sure that wheels are allowed for its build dependencies"
+))]
+pub struct BuildDependencyCycle {
+    /// The packages in the cycle, in the order in which they require each other to be built. The
+    /// first and the last package are the same.
+    pub cycle: Vec<NormalizedPackageName>,
+}
+
 /// An error that can occur while building a wheel
 #[allow(missing_docs)]
 #[derive(thiserror::Error, Debug)]
@@ -70,6 +110,15 @@ pub enum WheelBuildError {
     #[error("could not build wheel: {0}")]
     Error(String),
 
+    #[error("the build backend failed:\n{0}")]
+    BackendFailed(String),
+
+    #[error("not building {0} from source because building is not allowed")]
+    BuildNotAllowed(String),
+
+    #[error("{0}")]
+    MetadataMismatch(MetadataMismatch),
+
     #[error("could not install artifact in virtual environment: {0}")]
     UnpackError(#[from] UnpackError),
 
@@ -110,6 +159,9 @@ pub enum WheelBuildError {
 
     #[error("could not join path: {0}")]
     CouldNotJoinPath(#[from] std::env::JoinPathsError),
+
+    #[error(transparent)]
+    BuildDependencyCycle(#[from] BuildDependencyCycle),
 }
 
 impl WheelBuilder {
@@ -133,10 +185,16 @@ impl WheelBuilder {
             resolve_options,
             env_variables,
             saved_build_envs: Mutex::new(HashSet::new()),
+            metadata_mismatches: Mutex::new(Vec::new()),
             python_version,
         })
     }
 
+    /// Returns the options that are used to resolve build environments
+    pub fn resolve_options(&self) -> &ResolveOptions {
+        &self.resolve_options
+    }
+
     /// Get the python interpreter version
     pub fn python_version(&self) -> &PythonInterpreterVersion {
         &self.python_version
@@ -156,14 +214,32 @@ impl WheelBuilder {
             return Ok(venv.clone());
         }
 
+        // Building one of the build dependencies from source may require building this package
+        // again, which would never finish
+        let name: NormalizedPackageName = PackageName::from_str(&sdist.distribution_name())
+            .map_err(|e| WheelBuildError::Error(e.to_string()))?
+            .into();
+        let mut chain = BUILD_CHAIN.try_with(Clone::clone).unwrap_or_default();
+        if let Some(start) = chain.iter().position(|n| n == &name) {
+            let mut cycle = chain.split_off(start);
+            cycle.push(name);
+            return Err(BuildDependencyCycle { cycle }.into());
+        }
+        chain.push(name);
+
         tracing::debug!("creating virtual env for: {:?}", sdist.distribution_name());
 
-        let mut build_environment = BuildEnvironment::setup(sdist, self).await?;
+        let build_environment = BUILD_CHAIN
+            .scope(chain, async {
+                let mut build_environment = BuildEnvironment::setup(sdist, self).await?;
 
-        build_environment.install_build_files(sdist)?;
+                build_environment.install_build_files(sdist)?;
 
-        // Install extra requirements if any
-        build_environment.install_extra_requirements(self).await?;
+                // Install extra requirements if any
+                build_environment.install_extra_requirements(self).await?;
+                Ok::<_, WheelBuildError>(build_environment)
+            })
+            .await?;
 
         // Insert into the venv cache
         self.venv_cache
@@ -184,6 +260,46 @@ impl WheelBuilder {
         self.saved_build_envs.lock().clone()
     }
 
+    /// Returns the packages that were built so far whose declared dependencies differ from the
+    /// dependencies of the built package, see [`MetadataMismatch`]. A warning is logged for each
+    /// of them as well, unless [`ResolveOptions::strict`] turns them into errors.
+    pub fn metadata_mismatches(&self) -> Vec<MetadataMismatch> {
+        self.metadata_mismatches.lock().clone()
+    }
+
+    /// Compares the dependencies that a source artifact declares without building it with the
+    /// dependencies of the metadata that was produced by the build backend. A mismatch is an error
+    /// if [`ResolveOptions::strict`] is set.
+    fn check_declared_metadata<S: SourceArtifact>(
+        &self,
+        sdist: &S,
+        built: &WheelCoreMetadata,
+    ) -> Result<(), WheelBuildError> {
+        let declared = match sdist.declared_metadata() {
+            Ok(Some(declared)) => declared,
+            Ok(None) => return Ok(()),
+            Err(err) => {
+                tracing::debug!(
+                    "could not read the declared metadata of {}: {err}",
+                    sdist.distribution_name()
+                );
+                return Ok(());
+            }
+        };
+        if let Some(mismatch) = MetadataMismatch::compare(
+            format!("{} {}", sdist.distribution_name(), sdist.version()),
+            &declared.requires_dist,
+            &built.requires_dist,
+        ) {
+            self.metadata_mismatches.lock().push(mismatch.clone());
+            if self.resolve_options.strict {
+                return Err(WheelBuildError::MetadataMismatch(mismatch));
+            }
+            tracing::warn!("{mismatch}");
+        }
+        Ok(())
+    }
+
     /// Handle's a build failure by either saving the build environment or deleting it
     fn handle_build_failure<T>(
         &self,
@@ -209,6 +325,18 @@ impl WheelBuilder {
         }
     }
 
+    /// Returns an error if [`ResolveOptions::no_build`] forbids running the build backend.
+    fn check_build_allowed<S: SourceArtifact>(&self, sdist: &S) -> Result<(), WheelBuildError> {
+        if self.resolve_options.no_build {
+            return Err(WheelBuildError::BuildNotAllowed(format!(
+                "{} {}",
+                sdist.distribution_name(),
+                sdist.version()
+            )));
+        }
+        Ok(())
+    }
+
     /// Get the metadata for a given sdist by using the build_backend in a virtual env
     /// This function uses the `prepare_metadata_for_build_wheel` entry point of the build backend.
 
@@ -225,6 +353,7 @@ impl WheelBuilder {
                 WheelBuildError::Error(format!("Could not parse wheel metadata: {}", e))
             });
         }
+        self.check_build_allowed(sdist)?;
 
         let build_environment = self.setup_build_venv(sdist).await?;
 
@@ -251,8 +380,8 @@ impl WheelBuilder {
                     WheelBuildError::Error(format!("Could not parse wheel metadata: {}", e))
                 });
             }
-            let stdout = String::from_utf8_lossy(&output.stderr);
-            return Err(WheelBuildError::Error(stdout.to_string()));
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(WheelBuildError::BackendFailed(stderr.to_string()));
         }
 
         let result = fs::read_to_string(build_environment.work_dir().join("metadata_result"))?;
@@ -261,6 +390,7 @@ impl WheelBuilder {
 
         let metadata = fs::read(path)?;
         let wheel_metadata = WheelCoreMetadata::try_from(metadata.as_slice())?;
+        self.check_declared_metadata(sdist, &wheel_metadata)?;
         Ok((metadata, wheel_metadata))
     }
 
@@ -276,12 +406,21 @@ impl WheelBuilder {
         if let Some(wheel) = self.package_db.local_wheel_cache().wheel_for_key(&key)? {
             return Ok(wheel);
         }
+        self.check_build_allowed(sdist)?;
 
         // Setup a new virtualenv for building the wheel or use an existing
         let build_environment = self.setup_build_venv(sdist).await?;
         // Capture the result of the build
         // to handle different failure modes
-        let result = self.build_wheel_internal(&build_environment, sdist).await;
+        let result = self
+            .build_wheel_internal(&build_environment, sdist)
+            .await
+            .and_then(|wheel| {
+                if let Ok((_, metadata)) = wheel.metadata() {
+                    self.check_declared_metadata(sdist, &metadata)?;
+                }
+                Ok(wheel)
+            });
 
         self.handle_build_failure(result, &build_environment)
     }
@@ -296,8 +435,8 @@ impl WheelBuilder {
 
         // Check for success
         if !output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stderr);
-            return Err(WheelBuildError::Error(stdout.to_string()));
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(WheelBuildError::BackendFailed(stderr.to_string()));
         }
 
         // This is where the wheel file is located
@@ -346,13 +485,16 @@ mod tests {
     use crate::artifacts::SDist;
     use crate::index::PackageDb;
     use crate::python_env::{Pep508EnvMakers, PythonInterpreterVersion};
+    use crate::resolve::test_support::{pkg_info, write_sdist};
     use crate::resolve::ResolveOptions;
     use crate::wheel_builder::wheel_cache::WheelCacheKey;
-    use crate::wheel_builder::WheelBuilder;
+    use crate::wheel_builder::{WheelBuildError, WheelBuilder};
+    use pep508_rs::Requirement;
     use reqwest::Client;
     use reqwest_middleware::ClientWithMiddleware;
     use std::collections::HashMap;
     use std::path::Path;
+    use std::str::FromStr;
     use std::sync::Arc;
     use tempfile::TempDir;
 
@@ -450,4 +592,165 @@ mod tests {
         // Check if the build env is there
         assert!(path.exists());
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn warn_about_metadata_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lying-1.0.tar.gz");
+        let files = [
+            (
+                "PKG-INFO",
+                "Metadata-Version: 2.1\nName: lying\nVersion: 1.0\nRequires-Dist: requests>=2\nRequires-Dist: Foo_Bar[b,a]\n",
+            ),
+            (
+                "pyproject.toml",
+                r#"
+[build-system]
+requires = []
+build-backend = "lying_backend"
+backend-path = ["."]
+"#,
+            ),
+            (
+                "lying_backend.py",
+                r#"
+import pathlib
+
+def get_requires_for_build_wheel(config_settings=None):
+    return []
+
+def prepare_metadata_for_build_wheel(metadata_directory, config_settings=None):
+    dist_info = pathlib.Path(metadata_directory, "lying-1.0.dist-info")
+    dist_info.mkdir()
+    (dist_info / "METADATA").write_text(
+        "Metadata-Version: 2.1\nName: lying\nVersion: 1.0\nRequires-Dist: foo-bar[a,b]\nRequires-Dist: urllib3\n"
+    )
+    return dist_info.name
+"#,
+            ),
+        ];
+        let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(
+            fs_err::File::create(&path).unwrap(),
+            Default::default(),
+        ));
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            archive
+                .append_data(
+                    &mut header,
+                    format!("lying-1.0/{name}"),
+                    contents.as_bytes(),
+                )
+                .unwrap();
+        }
+        archive.into_inner().unwrap().finish().unwrap();
+
+        let sdist = SDist::from_path(&path, &"lying".parse().unwrap()).unwrap();
+        let package_db = get_package_db();
+        let env_markers = Arc::new(Pep508EnvMakers::from_env().await.unwrap().0);
+        let wheel_builder = WheelBuilder::new(
+            package_db.0,
+            env_markers,
+            None,
+            ResolveOptions::default(),
+            Default::default(),
+        )
+        .unwrap();
+
+        let (_, metadata) = wheel_builder.get_sdist_metadata(&sdist).await.unwrap();
+        assert_eq!(metadata.requires_dist.len(), 2);
+
+        let mismatches = wheel_builder.metadata_mismatches();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].package, "lying 1.0");
+        assert_eq!(
+            mismatches[0].only_declared,
+            [Requirement::from_str("requests>=2").unwrap()]
+        );
+        assert_eq!(
+            mismatches[0].only_built,
+            [Requirement::from_str("urllib3").unwrap()]
+        );
+
+        // In strict mode the mismatch is an error
+        let wheel_builder = WheelBuilder::new(
+            get_package_db().0,
+            Arc::new(Pep508EnvMakers::from_env().await.unwrap().0),
+            None,
+            ResolveOptions {
+                strict: true,
+                ..Default::default()
+            },
+            Default::default(),
+        )
+        .unwrap();
+        let err = wheel_builder.get_sdist_metadata(&sdist).await.unwrap_err();
+        assert!(
+            matches!(&err, WheelBuildError::MetadataMismatch(m) if m == &mismatches[0]),
+            "{err}"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn build_dependency_cycle() {
+        // `a` is built with `b`, which is built with `a`, and neither has a wheel
+        let find_links = tempfile::tempdir().unwrap();
+        for (name, build_requires) in [("a", "b"), ("b", "a")] {
+            let pyproject = format!(
+                "[build-system]\nrequires = [\"{build_requires}\"]\nbuild-backend = \"{build_requires}.build\"\n"
+            );
+            write_sdist(
+                find_links.path(),
+                name,
+                "1.0",
+                &[
+                    ("PKG-INFO", &pkg_info(name, "1.0")),
+                    ("pyproject.toml", &pyproject),
+                ],
+            );
+        }
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_db = Arc::new(
+            PackageDb::new(Client::new().into(), &[], cache_dir.path())
+                .unwrap()
+                .with_find_links([find_links.path().to_path_buf()]),
+        );
+        let env_markers = Arc::new(Pep508EnvMakers::from_env().await.unwrap().0);
+        let wheel_builder = WheelBuilder::new(
+            package_db,
+            env_markers,
+            None,
+            ResolveOptions::default(),
+            HashMap::default(),
+        )
+        .unwrap();
+
+        let sdist = SDist::from_path(
+            &find_links.path().join("a-1.0.tar.gz"),
+            &"a".parse().unwrap(),
+        )
+        .unwrap();
+        let Err(err) = wheel_builder.build_wheel(&sdist).await else {
+            panic!("expected the build of a to fail");
+        };
+        let WheelBuildError::BuildDependencyCycle(cycle) = &err else {
+            panic!("expected a build dependency cycle, got {err:?}");
+        };
+        assert_eq!(
+            cycle
+                .cycle
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>(),
+            ["a", "b", "a"]
+        );
+        assert_eq!(
+            err.to_string(),
+            "the build dependencies of a form a cycle: a -> b -> a"
+        );
+    }
 }
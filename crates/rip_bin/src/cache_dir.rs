@@ -0,0 +1,100 @@
+use miette::{Context, IntoDiagnostic};
+use std::path::{Path, PathBuf};
+
+/// Returns the directory in which the caches are stored: `cache_dir` if it is passed explicitly
+/// and otherwise `rattler/pypi` in `user_cache_dir`, the cache directory of the user.
+///
+/// Some minimal containers do not have a cache directory for the user. In that case the caches
+/// are stored in a new directory in `temp_dir` that only the current user can access, so they are
+/// not kept between runs. A fixed directory in `temp_dir` would be shared with all other users,
+/// who could put tampered artifacts in it.
+pub fn determine_cache_dir(
+    cache_dir: Option<PathBuf>,
+    user_cache_dir: Option<PathBuf>,
+    temp_dir: &Path,
+) -> miette::Result<PathBuf> {
+    if let Some(cache_dir) = cache_dir {
+        return Ok(cache_dir);
+    }
+    match user_cache_dir {
+        Some(user_cache_dir) => Ok(user_cache_dir.join("rattler/pypi")),
+        None => {
+            let cache_dir = tempfile::Builder::new()
+                .prefix("rattler-pypi-")
+                .tempdir_in(temp_dir)
+                .into_diagnostic()
+                .wrap_err_with(|| {
+                    format!(
+                        "failed to create a cache directory in {}, pass one with --cache-dir",
+                        temp_dir.display()
+                    )
+                })?
+                .into_path();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs_err::set_permissions(&cache_dir, std::fs::Permissions::from_mode(0o700))
+                    .into_diagnostic()?;
+            }
+            tracing::warn!(
+                "failed to determine the cache directory of the user, caching in {} instead",
+                cache_dir.display()
+            );
+            Ok(cache_dir)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rattler_installs_packages::index::PackageDb;
+    use rattler_installs_packages::types::PackageName;
+
+    #[test]
+    fn test_determine_cache_dir() {
+        let temp_dir = Path::new("/does/not/exist");
+        assert_eq!(
+            determine_cache_dir(None, Some(PathBuf::from("/home/user/.cache")), temp_dir).unwrap(),
+            Path::new("/home/user/.cache").join("rattler/pypi")
+        );
+        assert_eq!(
+            determine_cache_dir(
+                Some(PathBuf::from("/cache")),
+                Some(PathBuf::from("/home/user/.cache")),
+                temp_dir
+            )
+            .unwrap(),
+            Path::new("/cache")
+        );
+        assert!(determine_cache_dir(None, None, temp_dir).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_without_user_cache_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_dir = determine_cache_dir(None, None, temp_dir.path()).unwrap();
+        assert!(cache_dir.starts_with(temp_dir.path()));
+
+        // Every run gets its own directory that other users cannot access
+        let other_cache_dir = determine_cache_dir(None, None, temp_dir.path()).unwrap();
+        assert_ne!(cache_dir, other_cache_dir);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs_err::metadata(&cache_dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        // The packages are still found
+        let package_db = PackageDb::builder(&cache_dir)
+            .find_links([Path::new(env!("CARGO_MANIFEST_DIR")).join("../../test-data/wheels")])
+            .build()
+            .unwrap();
+        let artifacts = package_db
+            .available_artifacts("miniblack".parse::<PackageName>().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(artifacts.len(), 1);
+    }
+}
@@ -0,0 +1,90 @@
+use fs_err as fs;
+use miette::{Context, IntoDiagnostic};
+use rattler_installs_packages::artifacts::{SDist, Wheel};
+use rattler_installs_packages::types::{ArtifactName, MetadataHeaders, NormalizedPackageName};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Reads the unparsed metadata of the artifact at `path`: the `METADATA` of a wheel or the
+/// `PKG-INFO` of a source distribution. Any other file is assumed to be a metadata file itself.
+pub fn read_raw_metadata(path: &Path) -> miette::Result<Vec<u8>> {
+    let filename = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| miette::miette!("'{}' does not have a filename", path.display()))?;
+
+    let Some((name, artifact_name)) = parse_artifact_name(filename) else {
+        return fs::read(path).into_diagnostic();
+    };
+    match artifact_name {
+        ArtifactName::Wheel(_) => Wheel::from_path(path, &name)?
+            .raw_metadata()
+            .into_diagnostic(),
+        ArtifactName::SDist(_) => SDist::from_path(path, &name)?
+            .raw_package_info()
+            .into_diagnostic(),
+        ArtifactName::STree(_) => unreachable!("a filename never describes a source tree"),
+    }
+    .wrap_err_with(|| format!("failed to read the metadata of '{}'", path.display()))
+}
+
+/// Parses the filename of a wheel or source distribution. The package name is not known
+/// upfront, so every prefix of the filename that ends before a dash is tried as the name.
+fn parse_artifact_name(filename: &str) -> Option<(NormalizedPackageName, ArtifactName)> {
+    filename.match_indices('-').find_map(|(index, _)| {
+        let name: NormalizedPackageName = filename[..index].parse().ok()?;
+        let artifact_name = ArtifactName::from_filename(filename, &name).ok()?;
+        Some((name, artifact_name))
+    })
+}
+
+/// Writes the fields of parsed metadata headers one per line, in the order of the file. The
+/// values are quoted and escaped so that whitespace and embedded newlines are visible.
+pub fn write_metadata_headers(
+    writer: &mut impl Write,
+    headers: &MetadataHeaders,
+) -> io::Result<()> {
+    for (name, value) in headers.fields() {
+        writeln!(writer, "{name}: {value:?}")?;
+    }
+    match headers.body() {
+        Some(body) => writeln!(writer, "(body of {} bytes)", body.len()),
+        None => writeln!(writer, "(no body)"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn test_data(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test-data")
+            .join(path)
+    }
+
+    #[test]
+    fn test_dump_metadata_headers() {
+        let metadata =
+            read_raw_metadata(&test_data("wheels/miniblack-23.1.0-py3-none-any.whl")).unwrap();
+        let headers = MetadataHeaders::from_bytes(&metadata).unwrap();
+        let mut output = Vec::new();
+        write_metadata_headers(&mut output, &headers).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some(r#"metadata-version: "2.1""#));
+        assert_eq!(lines.next(), Some(r#"name: "miniblack""#));
+        assert_eq!(lines.next(), Some(r#"version: "23.1.0""#));
+        assert!(output.contains(r#"requires-dist: "click>=8.0.0""#));
+        assert!(output.ends_with(" bytes)\n"));
+
+        // The PKG-INFO of source distributions is read as well
+        let metadata = read_raw_metadata(&test_data("sdists/rich-13.6.0.tar.gz")).unwrap();
+        assert!(MetadataHeaders::from_bytes(&metadata)
+            .unwrap()
+            .fields()
+            .contains(&(String::from("name"), String::from("rich"))));
+    }
+}
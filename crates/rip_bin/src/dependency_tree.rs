@@ -0,0 +1,252 @@
+use rattler_installs_packages::resolve::PinnedPackage;
+use rattler_installs_packages::types::NormalizedPackageName;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Why a package in a tree is not expanded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeated {
+    /// The package was already expanded elsewhere in the tree
+    Seen,
+
+    /// The package is one of its own ancestors
+    Cycle,
+}
+
+/// A single line of a dependency tree, in the order in which the lines are printed.
+#[derive(Debug, Clone)]
+pub struct TreeLine<'a> {
+    /// The distance to the root of the tree
+    pub depth: usize,
+
+    /// The package on this line
+    pub package: &'a PinnedPackage,
+
+    /// Whether this is the last child of its parent
+    pub last: bool,
+
+    /// Set if the children of the package are omitted because it is repeated
+    pub repeated: Option<Repeated>,
+}
+
+/// Walks the tree that starts at `roots` and in which the children of a package are given by
+/// `children`. Every package is only expanded once and a package that is one of its own ancestors
+/// is not expanded at all, so the walk also terminates for cyclic graphs.
+///
+/// The walk uses an explicit stack instead of recursion so that arbitrarily long dependency chains
+/// do not overflow the stack.
+fn walk<'a>(
+    roots: Vec<&'a PinnedPackage>,
+    children: impl Fn(&'a PinnedPackage) -> Vec<&'a PinnedPackage>,
+) -> Vec<TreeLine<'a>> {
+    let mut lines = Vec::new();
+    let mut expanded = HashSet::new();
+    let mut path: Vec<&NormalizedPackageName> = Vec::new();
+
+    let root_count = roots.len();
+    let mut stack = roots
+        .into_iter()
+        .enumerate()
+        .rev()
+        .map(|(i, root)| (root, 0, i + 1 == root_count))
+        .collect::<Vec<_>>();
+    while let Some((package, depth, last)) = stack.pop() {
+        // The stack is processed depth first, so the path only contains the ancestors
+        path.truncate(depth);
+        let repeated = if path.contains(&&package.name) {
+            Some(Repeated::Cycle)
+        } else if !expanded.insert(&package.name) {
+            Some(Repeated::Seen)
+        } else {
+            None
+        };
+        lines.push(TreeLine {
+            depth,
+            package,
+            last,
+            repeated,
+        });
+        if repeated.is_some() {
+            continue;
+        }
+
+        path.push(&package.name);
+        let children = children(package);
+        let child_count = children.len();
+        stack.extend(
+            children
+                .into_iter()
+                .enumerate()
+                .rev()
+                .map(|(i, child)| (child, depth + 1, i + 1 == child_count)),
+        );
+    }
+    lines
+}
+
+/// Returns the lines of the dependency tree of each of the `roots`, the dependencies of a package
+/// are sorted by name.
+pub fn dependency_tree<'a>(
+    packages: &'a [PinnedPackage],
+    roots: &[NormalizedPackageName],
+) -> Vec<TreeLine<'a>> {
+    let by_name = packages
+        .iter()
+        .map(|p| (&p.name, p))
+        .collect::<HashMap<_, _>>();
+    let roots = roots
+        .iter()
+        .filter_map(|name| by_name.get(name).copied())
+        .collect();
+    walk(roots, |package| {
+        let mut dependencies = package
+            .dependencies
+            .iter()
+            .filter_map(|name| by_name.get(name).copied())
+            .collect::<Vec<_>>();
+        dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+        dependencies
+    })
+}
+
+/// Returns the lines of the inverted dependency tree of `target`: the children of a package are
+/// the packages that depend on it, so every path leads from `target` to a package that was
+/// requested directly. Returns `None` if `target` is not part of the resolution.
+pub fn dependents_tree<'a>(
+    packages: &'a [PinnedPackage],
+    target: &NormalizedPackageName,
+) -> Option<Vec<TreeLine<'a>>> {
+    let root = packages.iter().find(|p| &p.name == target)?;
+    let mut dependents: HashMap<&NormalizedPackageName, Vec<&PinnedPackage>> = HashMap::new();
+    for package in packages {
+        for dependency in &package.dependencies {
+            dependents.entry(dependency).or_default().push(package);
+        }
+    }
+    for packages in dependents.values_mut() {
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    Some(walk(vec![root], |package| {
+        dependents.get(&package.name).cloned().unwrap_or_default()
+    }))
+}
+
+/// Writes the lines of a tree, e.g.
+///
+/// ```text
+/// flask 3.0.0
+/// ├── click 8.1.7
+/// └── jinja2 3.1.2
+///     └── markupsafe 2.1.3
+/// ```
+///
+/// Packages that were already expanded are marked with `(*)`, packages that depend on themselves
+/// with `(cycle)`.
+pub fn write_tree(mut writer: impl Write, lines: &[TreeLine<'_>]) -> io::Result<()> {
+    // Whether the ancestor at each depth is the last child of its parent
+    let mut last_ancestors: Vec<bool> = Vec::new();
+    let mut prefix = String::new();
+    for line in lines {
+        last_ancestors.truncate(line.depth);
+        prefix.clear();
+        if line.depth > 0 {
+            for &last in &last_ancestors[1..] {
+                prefix.push_str(if last { "    " } else { "│   " });
+            }
+            prefix.push_str(if line.last {
+                "└── "
+            } else {
+                "├── "
+            });
+        }
+        last_ancestors.push(line.last);
+
+        write!(
+            writer,
+            "{prefix}{} {}",
+            line.package.name.as_str(),
+            line.package.version
+        )?;
+        match line.repeated {
+            Some(Repeated::Seen) => writeln!(writer, " (*)")?,
+            Some(Repeated::Cycle) => writeln!(writer, " (cycle)")?,
+            None => writeln!(writer)?,
+        }
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rattler_installs_packages::resolve::PypiVersion;
+
+    fn package(name: &str, dependencies: &[&str]) -> PinnedPackage {
+        PinnedPackage {
+            name: name.parse().unwrap(),
+            version: PypiVersion::Version {
+                version: "1.0".parse().unwrap(),
+                package_allows_prerelease: false,
+            },
+            extras: Default::default(),
+            artifacts: Vec::new(),
+            dependencies: dependencies.iter().map(|d| d.parse().unwrap()).collect(),
+        }
+    }
+
+    fn render(lines: &[TreeLine<'_>]) -> String {
+        let mut output = Vec::new();
+        write_tree(&mut output, lines).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_dependency_tree() {
+        let packages = [
+            package("app", &["web", "db"]),
+            package("web", &["util", "app"]),
+            package("db", &["util"]),
+            package("util", &[]),
+        ];
+
+        assert_eq!(
+            render(&dependency_tree(&packages, &["app".parse().unwrap()])),
+            "app 1.0\n\
+             ├── db 1.0\n\
+             │   └── util 1.0\n\
+             └── web 1.0\n    \
+                 ├── app 1.0 (cycle)\n    \
+                 └── util 1.0 (*)\n"
+        );
+
+        assert_eq!(
+            render(&dependents_tree(&packages, &"util".parse().unwrap()).unwrap()),
+            "util 1.0\n\
+             ├── db 1.0\n\
+             │   └── app 1.0\n\
+             │       └── web 1.0\n\
+             │           └── app 1.0 (cycle)\n\
+             └── web 1.0 (*)\n"
+        );
+        assert!(dependents_tree(&packages, &"missing".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        // A chain that would overflow the stack with a recursive traversal, closed into a cycle
+        const LENGTH: usize = 10_000;
+        let names = (0..LENGTH).map(|i| format!("p{i}")).collect::<Vec<_>>();
+        let packages = (0..LENGTH)
+            .map(|i| package(&names[i], &[&names[(i + 1) % LENGTH]]))
+            .collect::<Vec<_>>();
+
+        let lines = dependency_tree(&packages, &["p0".parse().unwrap()]);
+        assert_eq!(lines.len(), LENGTH + 1);
+        assert_eq!(lines.last().unwrap().depth, LENGTH);
+        assert_eq!(lines.last().unwrap().repeated, Some(Repeated::Cycle));
+
+        let lines = dependents_tree(&packages, &"p0".parse().unwrap()).unwrap();
+        assert_eq!(lines.len(), LENGTH + 1);
+        write_tree(io::sink(), &lines).unwrap();
+    }
+}
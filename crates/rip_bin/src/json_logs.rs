@@ -0,0 +1,90 @@
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Returns a [`Layer`] that writes every event as a single line of JSON, which makes the logs
+/// easy to ingest by log pipelines.
+///
+/// This is the JSON format of [`tracing_subscriber::fmt`]. Each line contains the `timestamp`,
+/// `level`, `target` and `fields` of the event. The fields of the spans the event occurred in are
+/// included under `spans`, from the outermost to the innermost span, e.g.
+/// `{"timestamp":"...","level":"INFO","fields":{"message":"..."},"target":"rip","spans":[{"package":"flask","name":"resolve"}]}`.
+pub fn json_logs_layer<S, W>(make_writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .json()
+        .with_current_span(false)
+        .with_writer(make_writer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_json_logs() {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(json_logs_layer(buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("starting");
+            let span = tracing::info_span!(
+                "resolve",
+                package = "flask",
+                version = tracing::field::Empty
+            );
+            let _guard = span.enter();
+            span.record("version", "3.0.0");
+            tracing::warn!(candidates = 3, "no wheel found for \"flask\"");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["fields"]["message"], "starting");
+        assert!(lines[0]["timestamp"].is_string());
+
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(
+            lines[1]["fields"],
+            serde_json::json!({"message": "no wheel found for \"flask\"", "candidates": 3})
+        );
+        assert_eq!(
+            lines[1]["spans"],
+            serde_json::json!([{"name": "resolve", "package": "flask", "version": "3.0.0"}])
+        );
+    }
+}
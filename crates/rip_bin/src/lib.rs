@@ -3,6 +3,35 @@ use std::io;
 use std::sync::OnceLock;
 use tracing_subscriber::fmt::MakeWriter;
 
+mod cache_dir;
+mod debug_metadata;
+mod dependency_tree;
+mod json_logs;
+mod metadata_diff;
+mod package_source;
+mod platform_requirements;
+mod progress;
+mod pyproject;
+mod requirements_file;
+mod resolution_table;
+
+pub use cache_dir::determine_cache_dir;
+pub use debug_metadata::{read_raw_metadata, write_metadata_headers};
+pub use dependency_tree::{dependency_tree, dependents_tree, write_tree, Repeated, TreeLine};
+pub use json_logs::json_logs_layer;
+pub use metadata_diff::{diff_metadata, MetadataDiff};
+pub use package_source::{package_source, ArtifactKind, PackageSource, ResolvedPackage};
+pub use platform_requirements::{
+    common_marker_environments, requirements_by_platform, PlatformRequirement,
+};
+pub use progress::{DownloadCounter, IndicatifProgress, DEFAULT_STEADY_TICK};
+pub use pyproject::parse_pyproject_requirements;
+pub use requirements_file::parse_requirements_file;
+pub use resolution_table::{
+    write_conda_pip_section, write_inline_script_metadata, write_requirements,
+    write_requirements_with_hashes, write_resolution_table, write_universal_requirements,
+};
+
 /// Returns a global instance of [`indicatif::MultiProgress`].
 ///
 /// Although you can always create an instance yourself any logging will interrupt pending
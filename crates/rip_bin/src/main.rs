@@ -1,14 +1,25 @@
 use fs_err as fs;
+use indicatif::ProgressDrawTarget;
 use rattler_installs_packages::resolve::PreReleaseResolution;
-use rip_bin::{global_multi_progress, IndicatifWriter};
+use rip_bin::{
+    common_marker_environments, dependency_tree, dependents_tree, determine_cache_dir,
+    diff_metadata, global_multi_progress, json_logs_layer, parse_pyproject_requirements,
+    parse_requirements_file, read_raw_metadata, requirements_by_platform, write_conda_pip_section,
+    write_inline_script_metadata, write_metadata_headers, write_requirements,
+    write_requirements_with_hashes, write_resolution_table, write_tree,
+    write_universal_requirements, DownloadCounter, IndicatifProgress, IndicatifWriter,
+    PlatformRequirement, ResolvedPackage, DEFAULT_STEADY_TICK,
+};
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Write;
+use std::num::{NonZeroU32, NonZeroU64};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use clap::Parser;
+use clap::{ArgGroup, Parser, ValueEnum};
 use itertools::Itertools;
 use miette::{Context, IntoDiagnostic};
 use tracing_subscriber::filter::Directive;
@@ -16,8 +27,21 @@ use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, Env
 use url::Url;
 
 use rattler_installs_packages::artifacts::wheel::UnpackWheelOptions;
-use rattler_installs_packages::python_env::{PythonLocation, WheelTags};
-use rattler_installs_packages::resolve::OnWheelBuildFailure;
+use rattler_installs_packages::index::{self, ProxyConfig, RateLimit, TlsConfig};
+use rattler_installs_packages::python_env::{
+    check_environment, CPythonAbi, PythonInterpreterVersion, PythonLocation, TargetPlatform, VEnv,
+    WheelTags,
+};
+use rattler_installs_packages::resolve::{
+    minimal_conflict_set, verify_lock, BuildDependencies, CandidateSorter, IgnoredDependency,
+    InterpreterConstraint, LockDiff, LockedVersions, OnWheelBuildFailure, ProgressObserver,
+    PypiVersion, PythonVersionRange, ResolutionCache, ResolutionSummary, SkippedArtifacts,
+    SummaryCounts, UniversalEnvironment, VersionPreferences,
+};
+use rattler_installs_packages::types::{
+    ArtifactInfo, MarkerEnvironment, MetadataHeaders, NormalizedPackageName, PackageName,
+    StringVersion, VersionOrUrl, VersionSpecifierExt, WheelCoreMetadata,
+};
 use rattler_installs_packages::wheel_builder::WheelBuilder;
 use rattler_installs_packages::{
     normalize_index_url, python_env::Pep508EnvMakers, resolve, resolve::resolve,
@@ -27,30 +51,124 @@ use rattler_installs_packages::{
 #[derive(Serialize, Debug)]
 struct Solution {
     resolved: bool,
-    packages: HashMap<String, String>,
+    packages: BTreeMap<String, String>,
+    /// The resolved packages including the activated extras and dependencies of each package,
+    /// and the index and the kind of the artifact that is installed
+    resolution: Vec<ResolvedPackage>,
     error: Option<String>,
+    /// The yanked artifacts that were not considered, per package the filename and the reason
+    /// why it was yanked. Only collected with `--explain-skips`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    yanked: BTreeMap<String, BTreeMap<String, Option<String>>>,
+    /// The build-system requirements of the packages that are built from source, only resolved
+    /// with `--include-build-deps`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    build_dependencies: Vec<BuildDependencies>,
+}
+
+/// The report of a failed resolution that is printed with `--json-errors`.
+#[derive(Serialize, Debug)]
+struct UnsatisfiableReport {
+    error: String,
+    /// A minimal set of the requirements that cannot be resolved together, `None` if the
+    /// resolution failed for another reason, e.g. a network failure
+    minimal_conflict_set: Option<Vec<String>>,
+}
+
+/// The summary of a resolution that is printed with `--json-summary`.
+#[derive(Serialize, Debug)]
+struct SummaryReport {
+    resolved: bool,
+    #[serde(flatten)]
+    counts: SummaryCounts,
+    bytes_downloaded: u64,
+    elapsed_seconds: f64,
 }
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
+#[clap(group(ArgGroup::new("lock_mode").args(["diff", "verify_only", "frozen"])))]
 struct Args {
-    #[clap(num_args = 1.., required = true)]
+    #[clap(num_args = 1.., required_unless_present_any = ["check", "requirement", "from_pyproject", "verify_only", "debug_metadata"])]
     specs: Vec<Requirement>,
 
+    /// Also resolve the requirements listed in this requirements file
+    #[clap(long, short = 'r', value_name = "FILE")]
+    requirement: Vec<PathBuf>,
+
+    /// Also resolve the dependencies of the project in this `pyproject.toml`
+    #[clap(long, value_name = "FILE")]
+    from_pyproject: Option<PathBuf>,
+
+    /// Also resolve the requirements of this group of the project, from its `[dependency-groups]`
+    /// (PEP 735) or `[project.optional-dependencies]`, e.g. `dev` or `test`. Can be passed
+    /// multiple times.
+    #[clap(long, value_name = "NAME", requires = "from_pyproject")]
+    group: Vec<String>,
+
     /// Create a venv and install into this environment
     /// Does not check for any installed packages for now
     #[clap(long)]
     install_into: Option<PathBuf>,
 
+    /// Install into this existing virtual environment. Its site-packages directory is determined
+    /// from its pyvenv.cfg and, unless `--python-interpreter` is specified, its interpreter is
+    /// used to determine the environment markers and compatible wheels
+    #[clap(long, value_name = "PATH", conflicts_with = "install_into")]
+    venv: Option<PathBuf>,
+
+    /// Download the selected artifact of each resolved package into this directory under its
+    /// original filename instead of installing it, e.g. to fill an offline mirror
+    #[clap(long, value_name = "DIR", conflicts_with_all = ["install_into", "venv", "json"])]
+    download: Option<PathBuf>,
+
+    /// Only download the packages that are requested in the specs, not their dependencies. The
+    /// dependencies are still resolved to select compatible versions.
+    #[clap(long, requires = "download")]
+    no_deps: bool,
+
     /// Base URL of the Python Package Index (default <https://pypi.org/simple>). This should point
     /// to a repository compliant with PEP 503 (the simple repository API).
     #[clap(default_value = "https://pypi.org/simple/", long)]
     index_url: Url,
 
+    /// A local directory with wheels and source distributions that are considered in addition to
+    /// the index. Can be passed multiple times.
+    #[clap(long, short = 'f', value_name = "DIR")]
+    find_links: Vec<PathBuf>,
+
+    /// Do not use any index, only consider the artifacts in the `--find-links` directories
+    #[clap(long, conflicts_with = "index_url")]
+    no_index: bool,
+
+    /// Query the index with the PyPI JSON API instead of the simple repository API. The
+    /// `--index-url` must then point to the base of the JSON API, e.g. <https://pypi.org/pypi/>
+    #[clap(long, conflicts_with = "no_index")]
+    json_api: bool,
+
+    /// The directory in which downloaded artifacts, metadata and built wheels are cached.
+    /// Defaults to `rattler/pypi` in the cache directory of the user
+    #[clap(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Download the pages of the index again instead of using the cached pages, to see newly
+    /// published versions. Artifacts that were downloaded before are still read from the cache
+    #[clap(long, conflicts_with = "no_index")]
+    refresh: bool,
+
     /// Verbose logging from resolvo
     #[clap(short)]
     verbose: bool,
 
+    /// Write the logs to stderr as newline delimited JSON. Progress bars are disabled.
+    #[clap(long)]
+    json_logs: bool,
+
+    /// The interval in milliseconds at which progress spinners are redrawn, 0 disables redrawing
+    /// without progress. Defaults to 100 when stderr is a terminal and 0 otherwise.
+    #[clap(long, value_name = "MS")]
+    progress_tick: Option<u64>,
+
     /// How to handle sidsts
     #[clap(flatten)]
     sdist_resolution: SDistResolution,
@@ -73,6 +191,301 @@ struct Args {
 
     #[clap(long)]
     json: bool,
+
+    /// If the resolution fails, print a JSON report with the error and a minimal set of the
+    /// requirements that cannot be resolved together. Finding the set resolves the requirements
+    /// again once for every requirement
+    #[clap(long, conflicts_with = "json")]
+    json_errors: bool,
+
+    /// Do not build source distributions without reliable metadata (PEP 643) to determine their
+    /// dependencies, such source distributions are not selected instead. Building is slow and
+    /// runs the build backend of the package.
+    #[clap(long)]
+    skip_unreliable_sdists: bool,
+
+    /// Never run the build backend of a package, neither to determine the dependencies of a
+    /// source distribution nor to build a wheel for installation. Fails if the dependencies of a
+    /// package can only be determined by building it.
+    #[clap(long)]
+    no_build: bool,
+
+    /// Add a column with the url of the selected artifact of each package to the resolution table
+    #[clap(long)]
+    show_urls: bool,
+
+    /// Print the dependency tree of the requested packages after the resolution table
+    #[clap(long)]
+    tree: bool,
+
+    /// Print why this package is part of the resolution: the tree of the packages that depend on
+    /// it, up to the requested packages
+    #[clap(long, value_name = "PACKAGE")]
+    why: Option<PackageName>,
+
+    /// How to print the resolved packages
+    #[clap(long, value_enum, default_value_t = OutputFormat::Table, conflicts_with_all = ["json", "include_build_deps", "install_into", "venv", "download", "tree", "why"])]
+    format: OutputFormat,
+
+    /// Add the hashes of the artifacts of each package to the requirements of `--format
+    /// requirements`, for use with pip's `--require-hashes`. Every available hash algorithm is
+    /// included
+    #[clap(long)]
+    generate_hashes: bool,
+
+    /// Print a report of all artifacts that were not considered during resolution and why
+    #[clap(long)]
+    explain_skips: bool,
+
+    /// Print a JSON summary of the resolution to stderr: the number of wheels and sdists that were
+    /// considered, the number of skipped artifacts per reason, the number of versions whose
+    /// metadata was fetched, the number of downloaded bytes and the elapsed time
+    #[clap(long)]
+    json_summary: bool,
+
+    /// Retrieve credentials for the index from the system keyring. With `subprocess` the
+    /// `keyring` command line tool is used.
+    #[clap(long, value_enum, default_value_t = KeyringProvider::Disabled)]
+    keyring_provider: KeyringProvider,
+
+    /// Use this proxy for all requests instead of the one configured through the `HTTP_PROXY` or
+    /// `HTTPS_PROXY` environment variables. Hosts listed in `NO_PROXY` still bypass the proxy.
+    #[clap(long)]
+    proxy: Option<Url>,
+
+    /// Also trust the certificates in this PEM encoded bundle, e.g. of the private certificate
+    /// authority of an internal index. Defaults to the bundle in `REQUESTS_CA_BUNDLE` or
+    /// `SSL_CERT_FILE`.
+    #[clap(long, value_name = "PEM")]
+    ca_cert: Vec<PathBuf>,
+
+    /// Authenticate to the index with this PEM encoded client certificate
+    #[clap(long, value_name = "PEM")]
+    client_cert: Option<PathBuf>,
+
+    /// The PKCS#8 private key of the client certificate, if it is not part of the certificate file
+    #[clap(long, value_name = "PEM", requires = "client_cert")]
+    client_key: Option<PathBuf>,
+
+    /// Start at most this many requests per second, to stay below the limits of an index.
+    /// Unlimited by default
+    #[clap(long, value_name = "REQUESTS_PER_SECOND")]
+    rate_limit: Option<NonZeroU32>,
+
+    /// Limit the combined download speed of all requests. Unlimited by default
+    #[clap(long, value_name = "BYTES_PER_SECOND")]
+    max_bandwidth: Option<NonZeroU64>,
+
+    /// Only consider the N newest versions of each package. This speeds up resolution of packages
+    /// with many releases, but might fail to find a solution even if one exists. Versions that are
+    /// pinned with `==` in the specs are always considered.
+    #[clap(long, value_name = "N")]
+    only_newest: Option<usize>,
+
+    /// Abort the resolution if it needs the metadata of more than N package versions in total.
+    /// This guards against resolutions that explore far more of the index than expected, e.g.
+    /// because of an unconstrained requirement.
+    #[clap(long, value_name = "N")]
+    max_versions_fetched: Option<usize>,
+
+    /// A comma separated list of platform tags, e.g. `manylinux2014_x86_64,manylinux_2_28_x86_64`.
+    /// If multiple wheels of a version are compatible, a wheel for a platform that is listed
+    /// earlier is preferred over all others, instead of the most specific compatible wheel
+    #[clap(long, value_name = "PLATFORMS", value_delimiter = ',')]
+    platform_priority: Vec<String>,
+
+    /// The maximum number of seconds that obtaining the metadata of a single version of a package
+    /// may take, including downloading and building it. Slower versions are excluded with a
+    /// warning, or fail the resolution with `--strict`.
+    #[clap(long, value_name = "SECONDS")]
+    timeout_per_package: Option<u64>,
+
+    /// Fail if a dependency of a package uses an environment marker variable that is not defined
+    /// by PEP 508, instead of ignoring that dependency
+    #[clap(long)]
+    strict_markers: bool,
+
+    /// Fail instead of warning about or silently working around problems, for use in CI. This
+    /// implies `--strict-markers`, also fails on a dependency that cannot be parsed, on a source
+    /// distribution whose declared dependencies differ from the dependencies of the built package,
+    /// and when an older version of a package is selected because none of the artifacts of a
+    /// newer version are compatible.
+    #[clap(long)]
+    strict: bool,
+
+    /// Only select packages that are compatible with every Python version in this inclusive
+    /// range, e.g. `3.9..3.12`. Environment markers are evaluated for each of the versions.
+    #[clap(long, value_name = "MIN..MAX")]
+    python_range: Option<PythonVersionRange>,
+
+    /// Only select packages that are compatible with every one of these interpreters, e.g.
+    /// `cpython3.9` or `pypy3.10`. Dependencies that only apply to some of the interpreters are
+    /// excluded. Can be passed multiple times
+    #[clap(long, value_name = "INTERPRETER", conflicts_with = "python_range")]
+    interpreter_constraint: Vec<InterpreterConstraint>,
+
+    /// Only use pure Python wheels (`none` abi and `any` platform), even if a platform specific
+    /// wheel is compatible. Versions without a pure Python wheel fall back to their sdist
+    #[clap(long)]
+    pure_python_only: bool,
+
+    /// Allow a requirement on a package to be satisfied by a package that provides it with a
+    /// `Provides-Dist` field. The providing package must be required itself
+    #[clap(long)]
+    honor_provides: bool,
+
+    /// Drop the requirement of a package on a dependency from its metadata, e.g. `'foo: bar'`. This
+    /// is an escape hatch for packages that over-constrain a dependency by mistake. Can be passed
+    /// multiple times
+    #[clap(long, value_name = "PACKAGE: DEPENDENCY")]
+    ignore_dependency: Vec<IgnoredDependency>,
+
+    /// A comma separated list of packages that may not be part of the resolution, e.g.
+    /// `pkg1,pkg2`. The resolution fails as soon as one of them is required, showing the packages
+    /// through which it is required
+    #[clap(long, value_name = "PACKAGES", value_delimiter = ',')]
+    block: Vec<PackageName>,
+
+    /// Instead of resolving, list the extras of the newest version that matches each spec and
+    /// the dependencies that each extra pulls in. This is `rip --show-extras pkg` instead of
+    /// `rip show pkg --extras` because rip has no subcommands, every mode is selected with a flag
+    #[clap(long)]
+    show_extras: bool,
+
+    /// Instead of resolving, list the dependencies of the newest version that matches each spec
+    /// on common platforms and Python versions, annotated with the platforms that need them
+    #[clap(long, conflicts_with = "show_extras")]
+    all_platforms: bool,
+
+    /// Instead of resolving, print the newest version that matches each spec and can be installed
+    /// with the Python interpreter, without resolving its dependencies. Older versions are only
+    /// checked if the newer ones are incompatible
+    #[clap(long, conflicts_with_all = ["show_extras", "all_platforms"])]
+    only_newest_compatible: bool,
+
+    /// Instead of resolving, compare the dependencies and the `Requires-Python` of the newest
+    /// versions that match two specs, e.g. `flask==2.3.0 flask==3.0.0`
+    #[clap(long, conflicts_with_all = ["show_extras", "all_platforms", "only_newest_compatible"])]
+    diff_metadata: bool,
+
+    /// Look for versions that are compatible with this Python version, e.g. `3.8`, instead of the
+    /// version of the interpreter. A `t` suffix selects a free-threaded build, e.g. `3.13t`.
+    /// Wheels must still match the platform of the interpreter
+    #[clap(long, value_name = "X.Y[t]", value_parser = parse_cpython_target, requires = "only_newest_compatible")]
+    python_version: Option<CPythonTarget>,
+
+    /// Resolve a single lock that is valid for every `--universal-target` instead of only for the
+    /// Python interpreter. The lock is printed as requirements, packages that are not needed by
+    /// every target get a marker that selects the targets that need them
+    #[clap(long, conflicts_with_all = ["json", "install_into", "venv", "download", "tree", "why", "include_build_deps", "lock_input", "show_extras", "all_platforms", "only_newest_compatible", "generate_hashes"])]
+    universal: bool,
+
+    /// A platform to resolve for with `--universal`, optionally with a Python version, e.g.
+    /// `linux-x86_64` or `windows-amd64:3.12`. A `t` suffix selects a free-threaded build, e.g.
+    /// `linux-x86_64:3.13t`. The Python version defaults to the version and the build of the
+    /// interpreter. Can be passed multiple times, defaults to `linux-x86_64` and `windows-amd64`
+    #[clap(long, value_name = "PLATFORM[:X.Y[t]]", value_parser = parse_universal_target, requires = "universal")]
+    universal_target: Vec<UniversalTarget>,
+
+    /// Also resolve the build-system requirements of the packages that are built from source
+    /// distributions. These are reported separately from the runtime dependencies.
+    #[clap(long)]
+    include_build_deps: bool,
+
+    /// Reuse the result of a previous resolution with the exact same input, as long as the index
+    /// did not change for any of the resolved packages
+    #[clap(long)]
+    resolution_cache: bool,
+
+    /// Instead of resolving, check that the requirements of the packages installed in this
+    /// virtual environment are satisfied by the other installed packages. The requirements are
+    /// evaluated for the interpreter of the environment, as recorded in its `pyvenv.cfg`
+    #[clap(long, value_name = "ENV", conflicts_with_all = ["specs", "requirement", "from_pyproject"])]
+    check: Option<PathBuf>,
+
+    /// Instead of resolving, print the raw fields of the `METADATA` of this wheel or the
+    /// `PKG-INFO` of this source distribution as the metadata parser sees them, to diagnose
+    /// metadata that cannot be parsed. A plain metadata file can be passed as well.
+    #[clap(long, value_name = "ARTIFACT", conflicts_with_all = ["specs", "requirement", "from_pyproject", "check"])]
+    debug_metadata: Option<PathBuf>,
+
+    /// A file with a `name==version` pin per line. The pinned versions are preferred over other
+    /// versions of the same package, unless they conflict with the requirements
+    #[clap(long, value_name = "FILE")]
+    preferences: Option<PathBuf>,
+
+    /// A lock file with a `name==version` pin per line, e.g. of the project to which a requirement
+    /// is added. The locked versions are preferred, so only the packages that conflict with the
+    /// new requirements move away from them. Pins of `--preferences` take precedence
+    #[clap(long, value_name = "FILE")]
+    constraint_from_lock: Option<PathBuf>,
+
+    /// A lock file with a `name==version` pin per line, e.g. from a previous resolution
+    #[clap(long, requires = "lock_mode")]
+    lock_input: Option<PathBuf>,
+
+    /// Only report the packages that were added, removed or changed compared to `--lock-input`
+    #[clap(long, requires = "lock_input")]
+    diff: bool,
+
+    /// Fail if the resolution differs from `--lock-input` in any way, e.g. to check in CI that the
+    /// lock file is up to date. Nothing is written
+    #[clap(long, requires = "lock_input")]
+    frozen: bool,
+
+    /// Instead of resolving, verify that the pins of `--lock-input` are still available on the
+    /// index, are not yanked and satisfy each other's requirements
+    #[clap(long, requires = "lock_input", conflicts_with_all = ["specs", "requirement", "from_pyproject", "diff"])]
+    verify_only: bool,
+}
+
+/// A platform and Python version to resolve for with `--universal`.
+#[derive(Clone)]
+struct UniversalTarget {
+    platform: TargetPlatform,
+    python: Option<CPythonTarget>,
+}
+
+/// A CPython version and the ABI of the build, e.g. `3.13` or `3.13t`.
+#[derive(Clone)]
+struct CPythonTarget {
+    version: PythonInterpreterVersion,
+    abi: CPythonAbi,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// A table with the name and version of each package
+    Table,
+
+    /// A requirement per package with its activated extras, e.g. `flask[async]==3.0.0`, that can
+    /// be used as a requirements file
+    Requirements,
+
+    /// The requirements as the `pip` section of the dependencies of a conda environment file
+    Conda,
+
+    /// The requirements as the dependencies of inline script metadata (PEP 723) that can be
+    /// pasted into a single-file script
+    Script,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum KeyringProvider {
+    /// Do not use the keyring
+    Disabled,
+
+    /// Use the `keyring` command line tool
+    Subprocess,
+}
+
+impl From<KeyringProvider> for index::KeyringProvider {
+    fn from(value: KeyringProvider) -> Self {
+        match value {
+            KeyringProvider::Disabled => index::KeyringProvider::Disabled,
+            KeyringProvider::Subprocess => index::KeyringProvider::Subprocess,
+        }
+    }
 }
 
 #[derive(Parser)]
@@ -112,43 +525,164 @@ impl From<SDistResolution> for resolve::SDistResolution {
 }
 
 async fn actual_main() -> miette::Result<()> {
-    use reqwest::Client;
-    use reqwest_middleware::ClientWithMiddleware;
-
-    let args = Args::parse();
+    let mut args = Args::parse();
+    if args.generate_hashes && args.format != OutputFormat::Requirements {
+        miette::bail!("--generate-hashes can only be used with --format requirements");
+    }
 
-    // Setup tracing subscriber
+    // Setup tracing subscriber, the JSON logs are not meant to be read interactively so they
+    // are not combined with progress bars.
+    if args.json_logs {
+        global_multi_progress().set_draw_target(ProgressDrawTarget::hidden());
+    }
     tracing_subscriber::registry()
-        .with(fmt::layer().with_writer(IndicatifWriter::new(global_multi_progress())))
+        .with(
+            (!args.json_logs)
+                .then(|| fmt::layer().with_writer(IndicatifWriter::new(global_multi_progress()))),
+        )
+        .with(args.json_logs.then(|| json_logs_layer(std::io::stderr)))
         .with(
             EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| get_default_env_filter(args.verbose)),
         )
         .init();
 
+    // Only print the metadata of an artifact
+    if let Some(path) = &args.debug_metadata {
+        let metadata = read_raw_metadata(path)?;
+        let headers = MetadataHeaders::from_bytes(&metadata)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to parse the metadata of '{}'", path.display()))?;
+        write_metadata_headers(&mut std::io::stdout().lock(), &headers).into_diagnostic()?;
+        return Ok(());
+    }
+
+    // Add the requirements from the requirements files to the specs
+    for path in &args.requirement {
+        let contents = fs::read_to_string(path).into_diagnostic()?;
+        let requirements = parse_requirements_file(&contents)
+            .wrap_err_with(|| format!("failed to parse '{}'", path.display()))?;
+        args.specs.extend(requirements);
+    }
+    if let Some(path) = &args.from_pyproject {
+        let contents = fs::read_to_string(path).into_diagnostic()?;
+        let requirements = parse_pyproject_requirements(&contents, &args.group)
+            .wrap_err_with(|| format!("failed to parse '{}'", path.display()))?;
+        args.specs.extend(requirements);
+    }
+
     // Determine cache directory
-    let cache_dir = dirs::cache_dir()
-        .ok_or_else(|| miette::miette!("failed to determine cache directory"))?
-        .join("rattler/pypi");
+    let cache_dir = determine_cache_dir(
+        args.cache_dir.clone(),
+        dirs::cache_dir(),
+        &std::env::temp_dir(),
+    )?;
     tracing::info!("cache directory: {}", cache_dir.display());
 
     // Construct a package database
-    let client = ClientWithMiddleware::from(Client::new());
+    let mut proxy_config = ProxyConfig::from_env();
+    if let Some(proxy) = args.proxy.clone() {
+        proxy_config = proxy_config.with_proxy(proxy);
+    }
+    let mut tls_config = if args.ca_cert.is_empty() {
+        TlsConfig::from_env()
+    } else {
+        args.ca_cert
+            .iter()
+            .fold(TlsConfig::default(), |config, path| {
+                config.with_ca_cert(path)
+            })
+    };
+    if let Some(cert) = &args.client_cert {
+        tls_config = tls_config.with_client_cert(cert, args.client_key.as_ref().unwrap_or(cert));
+    }
+    let mut rate_limit = RateLimit::default();
+    if let Some(requests_per_second) = args.rate_limit {
+        rate_limit = rate_limit.with_requests_per_second(requests_per_second);
+    }
+    if let Some(bytes_per_second) = args.max_bandwidth {
+        rate_limit = rate_limit.with_bytes_per_second(bytes_per_second);
+    }
+    let index_urls = if args.no_index {
+        if args.find_links.is_empty() {
+            tracing::warn!("no index is used and no find-links directories are passed");
+        }
+        Vec::new()
+    } else {
+        vec![normalize_index_url(args.index_url.clone())]
+    };
     let package_db = Arc::new(
-        rattler_installs_packages::index::PackageDb::new(
-            client,
-            &[normalize_index_url(args.index_url.clone())],
-            &cache_dir,
-        )
-        .into_diagnostic()
-        .wrap_err_with(|| {
-            format!(
-                "failed to construct package database for index {}",
-                args.index_url
-            )
-        })?,
+        index::PackageDb::builder(&cache_dir)
+            .index_urls(index_urls)
+            .index_api(if args.json_api {
+                index::IndexApi::Json
+            } else {
+                index::IndexApi::Simple
+            })
+            .refresh(args.refresh)
+            .find_links(args.find_links.clone())
+            .proxy_config(proxy_config)
+            .tls_config(tls_config)
+            .keyring_provider(args.keyring_provider.into())
+            .rate_limit(rate_limit)
+            .build()
+            .into_diagnostic()
+            .wrap_err_with(|| {
+                format!(
+                    "failed to construct package database for index {}",
+                    args.index_url
+                )
+            })?,
     );
 
+    // Resolve for the interpreter of the environment that is installed into
+    let existing_venv = match &args.venv {
+        Some(path) => {
+            let venv = VEnv::open(path)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to open {}", path.display()))?;
+            if args.python_interpreter.is_none() {
+                args.python_interpreter = Some(venv.python_executable());
+            }
+            Some(venv)
+        }
+        None => None,
+    };
+
+    // Only check the consistency of an existing environment, for the interpreter of that environment
+    if let Some(path) = &args.check {
+        let venv = VEnv::open(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to open {}", path.display()))?;
+        let python = venv.python_executable();
+        let env_markers = Pep508EnvMakers::from_python(&python)
+            .await
+            .into_diagnostic()
+            .wrap_err_with(|| {
+                format!(
+                    "failed to determine the environment markers of {} (could not run Python in path: {:?})",
+                    path.display(),
+                    python
+                )
+            })?
+            .0;
+        let problems = check_environment(venv.root(), venv.install_paths(), &env_markers)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to check {}", path.display()))?;
+        if problems.is_empty() {
+            println!("{}", console::style("No broken requirements found").bold());
+            return Ok(());
+        }
+        for problem in &problems {
+            println!("{problem}");
+        }
+        miette::bail!(
+            "found {} broken requirement(s) in {}",
+            problems.len(),
+            path.display()
+        );
+    }
+
     // Determine the environment markers for the current machine
     let env_markers = Arc::new(match args.python_interpreter {
         Some(ref python) => {
@@ -171,6 +705,21 @@ async fn actual_main() -> miette::Result<()> {
         env_markers
     );
 
+    // Only list the extras of the requested packages
+    if args.show_extras {
+        return show_extras(&package_db, &args.specs, &env_markers, args.pre).await;
+    }
+
+    // Only list the dependencies of the requested packages on all common platforms
+    if args.all_platforms {
+        return show_platform_requirements(&package_db, &args.specs, args.pre).await;
+    }
+
+    // Only compare the dependencies of two versions
+    if args.diff_metadata {
+        return show_metadata_diff(&package_db, &args.specs, args.pre).await;
+    }
+
     let python_location = match args.python_interpreter {
         Some(python_interpreter) => PythonLocation::Custom(python_interpreter),
         None => PythonLocation::System,
@@ -179,13 +728,59 @@ async fn actual_main() -> miette::Result<()> {
     let compatible_tags =
         WheelTags::from_python(python_location.executable().into_diagnostic()?.as_path())
             .await
-            .into_diagnostic()
-            .map(Arc::new)?;
+            .into_diagnostic()?
+            .with_platform_priority(&args.platform_priority);
+    let compatible_tags = Arc::new(compatible_tags);
     tracing::debug!(
         "extracted the following compatible wheel tags from the system python interpreter: {}",
         compatible_tags.tags().format(", ")
     );
 
+    // Only print the newest compatible version of the requested packages
+    if args.only_newest_compatible {
+        return match &args.python_version {
+            Some(CPythonTarget {
+                version: python_version,
+                abi,
+            }) => {
+                // The tags of CPython with the requested version on the platforms of the interpreter
+                let platforms = compatible_tags
+                    .tags()
+                    .map(|tag| tag.platform.as_str())
+                    .filter(|platform| *platform != "any")
+                    .unique()
+                    .collect::<Vec<_>>();
+                let tags = WheelTags::cpython(python_version, *abi, &platforms);
+                let PythonInterpreterVersion {
+                    major,
+                    minor,
+                    patch,
+                } = python_version;
+                let env_markers = MarkerEnvironment {
+                    python_version: StringVersion::from_str(&format!("{major}.{minor}"))
+                        .expect("a version of two numbers is always valid"),
+                    python_full_version: StringVersion::from_str(&format!(
+                        "{major}.{minor}.{patch}"
+                    ))
+                    .expect("a version of three numbers is always valid"),
+                    ..(*env_markers).clone()
+                };
+                show_newest_compatible(&package_db, &args.specs, &env_markers, &tags, args.pre)
+                    .await
+            }
+            None => {
+                show_newest_compatible(
+                    &package_db,
+                    &args.specs,
+                    &env_markers,
+                    &compatible_tags,
+                    args.pre,
+                )
+                .await
+            }
+        };
+    }
+
     let on_wheel_build_failure = if args.save_on_failure {
         OnWheelBuildFailure::SaveBuildEnv
     } else {
@@ -198,16 +793,175 @@ async fn actual_main() -> miette::Result<()> {
         PreReleaseResolution::from_specs(&args.specs)
     };
 
+    let mut preferences = match &args.preferences {
+        Some(path) => Some(
+            VersionPreferences::from_str(&fs::read_to_string(path).into_diagnostic()?)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to parse preferences {}", path.display()))?,
+        ),
+        None => None,
+    };
+    if let Some(path) = &args.constraint_from_lock {
+        let locked = VersionPreferences::from_str(&fs::read_to_string(path).into_diagnostic()?)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to parse lock file {}", path.display()))?;
+        preferences
+            .get_or_insert_with(Default::default)
+            .merge(locked);
+    }
+
+    // Progress bars are not shown together with the JSON logs
+    let steady_tick = match args.progress_tick {
+        Some(0) => None,
+        Some(millis) => Some(Duration::from_millis(millis)),
+        None => console::user_attended_stderr().then_some(DEFAULT_STEADY_TICK),
+    };
+    let progress = (!args.json_logs).then(|| {
+        Arc::new(IndicatifProgress::new(global_multi_progress()).with_steady_tick(steady_tick))
+    });
+    let progress_observer = progress
+        .clone()
+        .map(|progress| progress as Arc<dyn ProgressObserver>);
+    let download_counter = args
+        .json_summary
+        .then(|| Arc::new(DownloadCounter::new(progress_observer.clone())));
+
     let resolve_opts = ResolveOptions {
         sdist_resolution: args.sdist_resolution.into(),
         python_location: python_location.clone(),
         clean_env: args.clean_env,
         on_wheel_build_failure,
         pre_release_resolution,
+        skipped_artifacts: args.explain_skips.then(SkippedArtifacts::default),
+        summary: args.json_summary.then(ResolutionSummary::default),
+        skip_unreliable_sdists: args.skip_unreliable_sdists,
+        no_build: args.no_build,
+        only_newest: args.only_newest,
+        max_versions_fetched: args.max_versions_fetched,
+        timeout_per_package: args.timeout_per_package.map(Duration::from_secs),
+        strict_markers: args.strict_markers,
+        strict: args.strict,
+        pure_python_only: args.pure_python_only,
+        candidate_sorter: preferences.map(|p| Arc::new(p) as Arc<dyn CandidateSorter>),
+        python_range: args.python_range,
+        interpreter_constraints: args.interpreter_constraint.clone(),
+        progress_observer: match &download_counter {
+            Some(counter) => Some(counter.clone() as Arc<dyn ProgressObserver>),
+            None => progress_observer,
+        },
+        resolution_cache: args
+            .resolution_cache
+            .then(|| ResolutionCache::new(cache_dir.join("resolutions"))),
+        honor_provides: args.honor_provides,
+        ignored_dependencies: args.ignore_dependency.clone(),
+        blocked_packages: args
+            .block
+            .iter()
+            .cloned()
+            .map(NormalizedPackageName::from)
+            .collect(),
     };
+    if let Some(only_newest) = args.only_newest {
+        tracing::warn!(
+            "only the {only_newest} newest versions of each package are considered, the resolution might not find a solution even if one exists"
+        );
+    }
+
+    // Only verify the pins of the lock file
+    if args.verify_only {
+        let lock_input = args
+            .lock_input
+            .as_ref()
+            .expect("--verify-only requires --lock-input");
+        let lock = LockedVersions::from_str(&fs::read_to_string(lock_input).into_diagnostic()?)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to parse lock file {}", lock_input.display()))?;
+        let problems = verify_lock(
+            package_db.clone(),
+            &lock,
+            env_markers.clone(),
+            Some(compatible_tags.clone()),
+            resolve_opts.clone(),
+        )
+        .await;
+        if let Some(progress) = &progress {
+            progress.finish();
+        }
+        let problems = problems.map_err(|err| {
+            miette::Report::new(err).wrap_err(format!(
+                "failed to verify lock file {}",
+                lock_input.display()
+            ))
+        })?;
+        if problems.is_empty() {
+            println!("{}", console::style("The lock file is valid").bold());
+            return Ok(());
+        }
+        for problem in &problems {
+            println!("{problem}");
+        }
+        miette::bail!(
+            "found {} problem(s) in lock file {}",
+            problems.len(),
+            lock_input.display()
+        );
+    }
+
+    // Solve a single lock for all targets
+    if args.universal {
+        let interpreter = CPythonTarget {
+            version: python_location.version().into_diagnostic()?,
+            abi: CPythonAbi::from_tags(&compatible_tags).unwrap_or_default(),
+        };
+        let targets = if args.universal_target.is_empty() {
+            ["linux-x86_64", "windows-amd64"]
+                .map(|platform| UniversalTarget {
+                    platform: platform.parse().expect("the platform is valid"),
+                    python: None,
+                })
+                .to_vec()
+        } else {
+            args.universal_target.clone()
+        };
+        let environments = targets
+            .iter()
+            .map(|target| {
+                let python = target.python.as_ref().unwrap_or(&interpreter);
+                let platform_tags = target.platform.platform_tags();
+                let platform_tags = platform_tags.iter().map(String::as_str).collect::<Vec<_>>();
+                UniversalEnvironment {
+                    env_markers: Arc::new(
+                        Pep508EnvMakers::for_target_platform(target.platform, &python.version).0,
+                    ),
+                    compatible_tags: Some(Arc::new(WheelTags::cpython(
+                        &python.version,
+                        python.abi,
+                        &platform_tags,
+                    ))),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let packages = resolve::resolve_universal(
+            package_db.clone(),
+            &args.specs,
+            &environments,
+            resolve_opts.clone(),
+        )
+        .await;
+        if let Some(progress) = &progress {
+            progress.finish();
+        }
+        let packages = packages.map_err(|err| {
+            miette::Report::new(err).wrap_err("Could not solve for requested requirements")
+        })?;
+        write_universal_requirements(std::io::stdout(), &packages).into_diagnostic()?;
+        return Ok(());
+    }
 
     // Solve the environment
-    let blueprint = match resolve(
+    let started = Instant::now();
+    let blueprint = resolve(
         package_db.clone(),
         &args.specs,
         env_markers.clone(),
@@ -217,70 +971,238 @@ async fn actual_main() -> miette::Result<()> {
         resolve_opts.clone(),
         HashMap::default(),
     )
-    .await
-    {
+    .await;
+    // The progress bars only cover the resolution
+    if let Some(progress) = &progress {
+        progress.finish();
+    }
+    let resolve_opts = ResolveOptions {
+        progress_observer: None,
+        ..resolve_opts
+    };
+
+    // Report the artifacts that were skipped, this is also useful if the resolution failed
+    if let Some(skipped) = &resolve_opts.skipped_artifacts {
+        if skipped.is_empty() {
+            eprintln!("{}", console::style("No artifacts were skipped").bold());
+        } else {
+            eprintln!("{}:\n{skipped}", console::style("Skipped artifacts").bold());
+        }
+    }
+    let yanked = yanked_artifacts(resolve_opts.skipped_artifacts.as_ref());
+    if let Some(summary) = &resolve_opts.summary {
+        let report = SummaryReport {
+            resolved: blueprint.is_ok(),
+            counts: summary.counts(),
+            bytes_downloaded: download_counter
+                .as_ref()
+                .map_or(0, |counter| counter.bytes_downloaded()),
+            elapsed_seconds: started.elapsed().as_secs_f64(),
+        };
+        eprintln!("{}", serde_json::to_string_pretty(&report).unwrap());
+    }
+
+    let blueprint = match blueprint {
         Ok(blueprint) => blueprint,
         Err(err) => {
+            if args.json_errors {
+                let conflict_set = minimal_conflict_set(
+                    package_db.clone(),
+                    &args.specs,
+                    env_markers.clone(),
+                    Some(compatible_tags.clone()),
+                    resolve_opts.clone(),
+                )
+                .await;
+                let report = UnsatisfiableReport {
+                    error: err.to_string(),
+                    minimal_conflict_set: conflict_set
+                        .ok()
+                        .flatten()
+                        .map(|requirements| requirements.iter().map(ToString::to_string).collect()),
+                };
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            }
             return if args.json {
                 let solution = Solution {
                     resolved: false,
-                    packages: HashMap::default(),
+                    packages: BTreeMap::default(),
+                    resolution: Vec::new(),
                     error: Some(format!("{}", err)),
+                    yanked,
+                    build_dependencies: Vec::new(),
                 };
                 println!("{}", serde_json::to_string_pretty(&solution).unwrap());
                 Ok(())
             } else {
-                Err(err.wrap_err("Could not solve for requested requirements"))
-            }
+                Err(miette::Report::new(err).wrap_err("Could not solve for requested requirements"))
+            };
         }
     };
 
+    // Only report the changes compared to the lock file
+    if let Some(lock_input) = &args.lock_input {
+        let old = LockedVersions::from_str(&fs::read_to_string(lock_input).into_diagnostic()?)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to parse lock file {}", lock_input.display()))?;
+        let new = LockedVersions::from_iter(&blueprint);
+        let diff = LockDiff::new(&old, &new);
+        if args.frozen {
+            return diff.ensure_unchanged().into_diagnostic().wrap_err_with(|| {
+                format!(
+                    "--frozen is set but {} differs from the resolution",
+                    lock_input.display()
+                )
+            });
+        }
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&diff).unwrap());
+        } else if diff.is_empty() {
+            println!("{}", console::style("No changes").bold());
+        } else {
+            print!("{diff}");
+        }
+        return Ok(());
+    }
+
     // Output the selected versions
-    println!("{}:", console::style("Resolved environment").bold());
-    for spec in args.specs.iter() {
-        println!("- {}", spec);
-    }
-
-    println!();
-    let mut tabbed_stdout = tabwriter::TabWriter::new(std::io::stdout());
-    writeln!(
-        tabbed_stdout,
-        "{}\t{}",
-        console::style("Name").bold(),
-        console::style("Version").bold()
-    )
-    .into_diagnostic()?;
-    for pinned_package in blueprint.iter().sorted_by(|a, b| a.name.cmp(&b.name)) {
-        write!(tabbed_stdout, "{name}", name = pinned_package.name.as_str()).into_diagnostic()?;
-        if !pinned_package.extras.is_empty() {
-            write!(
+    match args.format {
+        OutputFormat::Table => {
+            println!("{}:", console::style("Resolved environment").bold());
+            for spec in args.specs.iter() {
+                println!("- {}", spec);
+            }
+
+            println!();
+            write_resolution_table(std::io::stdout(), &blueprint, args.show_urls)
+                .into_diagnostic()?;
+
+            if args.tree {
+                let roots = args
+                    .specs
+                    .iter()
+                    .map(|spec| PackageName::from_str(&spec.name).map(NormalizedPackageName::from))
+                    .collect::<Result<Vec<_>, _>>()
+                    .into_diagnostic()?;
+                println!("\n{}:", console::style("Dependency tree").bold());
+                write_tree(std::io::stdout(), &dependency_tree(&blueprint, &roots))
+                    .into_diagnostic()?;
+            }
+            if let Some(name) = &args.why {
+                let name = NormalizedPackageName::from(name.clone());
+                let Some(lines) = dependents_tree(&blueprint, &name) else {
+                    miette::bail!("{} is not part of the resolution", name.as_str());
+                };
+                println!(
+                    "\n{} {}:",
+                    console::style("Why").bold(),
+                    console::style(name.as_str()).bold()
+                );
+                write_tree(std::io::stdout(), &lines).into_diagnostic()?;
+            }
+        }
+        OutputFormat::Requirements if args.generate_hashes => {
+            write_requirements_with_hashes(std::io::stdout(), &blueprint).into_diagnostic()?
+        }
+        OutputFormat::Requirements => {
+            write_requirements(std::io::stdout(), &blueprint).into_diagnostic()?
+        }
+        OutputFormat::Conda => {
+            write_conda_pip_section(std::io::stdout(), &blueprint).into_diagnostic()?
+        }
+        OutputFormat::Script => {
+            write_inline_script_metadata(std::io::stdout(), &blueprint).into_diagnostic()?
+        }
+    }
+
+    // Resolve the environments that are needed to build the source distributions
+    let build_dependencies = if args.include_build_deps {
+        resolve::resolve_build_dependencies(
+            package_db.clone(),
+            &blueprint,
+            env_markers.clone(),
+            Some(compatible_tags.clone()),
+            resolve_opts.clone(),
+        )
+        .await
+        .map_err(|err| {
+            miette::Report::new(err).wrap_err("Could not solve for the build requirements")
+        })?
+    } else {
+        Vec::new()
+    };
+    for build in &build_dependencies {
+        println!(
+            "\n{} {} {}:",
+            console::style("Build dependencies of").bold(),
+            console::style(build.name.as_str()).bold(),
+            console::style(&build.version).bold()
+        );
+        for requirement in &build.requires {
+            println!("- {requirement}");
+        }
+        let mut tabbed_stdout = tabwriter::TabWriter::new(std::io::stdout());
+        for package in &build.packages {
+            writeln!(
                 tabbed_stdout,
-                "[{}]",
-                pinned_package.extras.iter().map(|e| e.as_str()).join(",")
+                "{}\t{}",
+                package.name.as_str(),
+                package.version
             )
             .into_diagnostic()?;
         }
-        writeln!(
-            tabbed_stdout,
-            "\t{version}",
-            version = pinned_package.version
-        )
-        .into_diagnostic()?;
+        tabbed_stdout.flush().into_diagnostic()?;
     }
-    tabbed_stdout.flush().into_diagnostic()?;
 
-    // Try to install into this environment
-    if let Some(install) = args.install_into {
+    // Download the artifacts instead of installing them
+    if let Some(dest) = &args.download {
+        let requested = args
+            .specs
+            .iter()
+            .map(|spec| PackageName::from_str(&spec.name).map(NormalizedPackageName::from))
+            .collect::<Result<HashSet<_>, _>>()
+            .into_diagnostic()?;
         println!(
-            "\n\nInstalling into: {}",
-            console::style(install.display()).bold()
+            "\n{} {}",
+            console::style("Downloading into").bold(),
+            console::style(dest.display()).bold()
         );
-        if !install.exists() {
-            std::fs::create_dir_all(&install).into_diagnostic()?;
+        for pinned_package in blueprint
+            .iter()
+            .filter(|p| !args.no_deps || requested.contains(&p.name))
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+        {
+            let Some(artifact_info) = pinned_package.artifacts.first() else {
+                miette::bail!(
+                    "no artifact of {} {} is available to download",
+                    pinned_package.name,
+                    pinned_package.version
+                );
+            };
+            let path = package_db
+                .download_artifact(artifact_info, dest, None)
+                .await
+                .wrap_err_with(|| format!("failed to download {}", artifact_info.filename))?;
+            println!("- {}", path.display());
         }
+        return Ok(());
+    }
 
-        let venv = rattler_installs_packages::python_env::VEnv::create(&install, python_location)
-            .into_diagnostic()?;
+    // Try to install into this environment
+    let venv = match (args.install_into, existing_venv) {
+        (Some(install), _) => {
+            if !install.exists() {
+                std::fs::create_dir_all(&install).into_diagnostic()?;
+            }
+            Some(VEnv::create(&install, python_location).into_diagnostic()?)
+        }
+        (None, venv) => venv,
+    };
+    if let Some(venv) = venv {
+        println!(
+            "\n\nInstalling into: {}",
+            console::style(venv.root().display()).bold()
+        );
         let wheel_builder = WheelBuilder::new(
             package_db.clone(),
             env_markers,
@@ -305,24 +1227,40 @@ async fn actual_main() -> miette::Result<()> {
                 .get_wheel(artifact_info, Some(&wheel_builder))
                 .await
                 .expect("could not get artifact");
-            venv.install_wheel(&artifact, &UnpackWheelOptions::default())
-                .into_diagnostic()?;
+            venv.install_wheel(
+                &artifact,
+                &UnpackWheelOptions {
+                    installer: Some(String::from("rip")),
+                    ..Default::default()
+                },
+            )
+            .into_diagnostic()?;
         }
     }
 
-    println!(
-        "\n{}",
-        console::style("Successfully installed environment!").bold()
-    );
+    // Keep the requirements output usable as a requirements file
+    if args.format == OutputFormat::Table {
+        println!(
+            "\n{}",
+            console::style("Successfully installed environment!").bold()
+        );
+    }
 
     if args.json {
         let solution = Solution {
             resolved: true,
             packages: blueprint
-                .into_iter()
+                .iter()
                 .map(|p| (p.name.to_string(), p.version.to_string()))
                 .collect(),
+            resolution: blueprint
+                .into_iter()
+                .sorted_by(|a, b| a.name.cmp(&b.name))
+                .map(ResolvedPackage::from)
+                .collect(),
             error: None,
+            yanked,
+            build_dependencies,
         };
         println!("{}", serde_json::to_string_pretty(&solution).unwrap());
     }
@@ -330,6 +1268,255 @@ async fn actual_main() -> miette::Result<()> {
     Ok(())
 }
 
+/// Returns the filenames and yanked reasons of the yanked artifacts that were skipped, per package.
+fn yanked_artifacts(
+    skipped: Option<&SkippedArtifacts>,
+) -> BTreeMap<String, BTreeMap<String, Option<String>>> {
+    let Some(skipped) = skipped else {
+        return BTreeMap::new();
+    };
+    skipped
+        .yanked()
+        .into_iter()
+        .map(|(name, artifacts)| {
+            (
+                name.as_str().to_owned(),
+                artifacts
+                    .into_iter()
+                    .map(|(artifact, reason)| (artifact.to_string(), reason))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Returns the newest version that matches `spec` together with its artifacts and metadata. The
+/// specifiers are matched like the resolver does, e.g. `<2` does not match `2.0a1`.
+async fn newest_metadata(
+    package_db: &index::PackageDb,
+    spec: &Requirement,
+    allow_prerelease: bool,
+) -> miette::Result<(
+    PackageName,
+    PypiVersion,
+    Vec<Arc<ArtifactInfo>>,
+    WheelCoreMetadata,
+)> {
+    let name = PackageName::from_str(&spec.name).into_diagnostic()?;
+    let artifacts = package_db.available_artifacts(name.clone()).await?;
+
+    // The artifacts are sorted from the newest to the oldest version
+    let newest = artifacts.iter().find(|(version, _)| {
+        let PypiVersion::Version { version, .. } = version else {
+            return false;
+        };
+        let matches = match &spec.version_or_url {
+            Some(VersionOrUrl::VersionSpecifier(specifiers)) => specifiers.satisfied_by(version),
+            Some(VersionOrUrl::Url(_)) => false,
+            None => true,
+        };
+        matches && (allow_prerelease || !version.any_prerelease())
+    });
+    let Some((version, artifacts)) = newest else {
+        miette::bail!(
+            "there is no version of '{}' that matches '{spec}'",
+            name.as_str()
+        );
+    };
+    let Some((_, metadata)) = package_db.get_metadata(artifacts, None).await? else {
+        miette::bail!(
+            "the metadata of {} {version} cannot be determined without building it",
+            name.as_str()
+        );
+    };
+    Ok((name, version.clone(), artifacts.clone(), metadata))
+}
+
+async fn show_platform_requirements(
+    package_db: &index::PackageDb,
+    specs: &[Requirement],
+    allow_prerelease: bool,
+) -> miette::Result<()> {
+    let environments = common_marker_environments();
+    for spec in specs {
+        let (name, version, _, metadata) =
+            newest_metadata(package_db, spec, allow_prerelease).await?;
+        println!(
+            "{}",
+            console::style(format!("{} {version}", name.as_str())).bold()
+        );
+        let requirements = requirements_by_platform(&metadata.requires_dist, &environments);
+        if requirements.is_empty() {
+            println!("  (no dependencies)");
+        }
+        for PlatformRequirement {
+            requirement,
+            environments,
+        } in requirements
+        {
+            let requirement = Requirement {
+                marker: None,
+                ..requirement.clone()
+            };
+            match environments {
+                Some(environments) => println!(
+                    "  {requirement} {}",
+                    console::style(format!("({})", environments.join(", "))).dim()
+                ),
+                None => println!("  {requirement} {}", console::style("(all)").dim()),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the extras of the newest version that matches each spec, together with the requirements
+/// that each extra adds.
+async fn show_extras(
+    package_db: &index::PackageDb,
+    specs: &[Requirement],
+    env_markers: &MarkerEnvironment,
+    allow_prerelease: bool,
+) -> miette::Result<()> {
+    for spec in specs {
+        let (name, version, artifacts, metadata) =
+            newest_metadata(package_db, spec, allow_prerelease).await?;
+
+        print!(
+            "{}",
+            console::style(format!("{} {version}", name.as_str())).bold()
+        );
+        if artifacts.iter().all(|a| a.yanked.yanked) {
+            match artifacts.iter().find_map(|a| a.yanked.reason.as_deref()) {
+                Some(reason) => print!(" {}", console::style(format!("(yanked: {reason})")).red()),
+                None => print!(" {}", console::style("(yanked)").red()),
+            }
+        }
+        println!();
+        let extras = metadata.extra_requirements(env_markers);
+        if extras.is_empty() {
+            println!("  (no extras)");
+        }
+        for (extra, requirements) in extras {
+            println!("  [{}]", extra.as_str());
+            for requirement in requirements {
+                match &requirement.version_or_url {
+                    Some(VersionOrUrl::VersionSpecifier(specifiers)) => {
+                        println!("    {} {specifiers}", requirement.name)
+                    }
+                    Some(VersionOrUrl::Url(url)) => println!("    {} @ {url}", requirement.name),
+                    None => println!("    {}", requirement.name),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the differences between the dependencies of the newest versions that match two specs.
+async fn show_metadata_diff(
+    package_db: &index::PackageDb,
+    specs: &[Requirement],
+    allow_prerelease: bool,
+) -> miette::Result<()> {
+    let [old, new] = specs else {
+        miette::bail!(
+            "--diff-metadata compares exactly two specs, e.g. `flask==2.3.0 flask==3.0.0`, but {} were passed",
+            specs.len()
+        );
+    };
+    let (old_name, old_version, _, old_metadata) =
+        newest_metadata(package_db, old, allow_prerelease).await?;
+    let (new_name, new_version, _, new_metadata) =
+        newest_metadata(package_db, new, allow_prerelease).await?;
+
+    println!(
+        "{}",
+        console::style(format!(
+            "{} {old_version} -> {} {new_version}",
+            old_name.as_str(),
+            new_name.as_str()
+        ))
+        .bold()
+    );
+    let diff = diff_metadata(&old_metadata, &new_metadata);
+    if diff.is_empty() {
+        println!("the dependencies did not change");
+    } else {
+        print!("{diff}");
+    }
+    Ok(())
+}
+
+/// Prints the newest version that matches each spec and is compatible with the environment.
+async fn show_newest_compatible(
+    package_db: &index::PackageDb,
+    specs: &[Requirement],
+    env_markers: &MarkerEnvironment,
+    compatible_tags: &WheelTags,
+    allow_prerelease: bool,
+) -> miette::Result<()> {
+    for spec in specs {
+        match resolve::newest_compatible_version(
+            package_db,
+            spec,
+            env_markers,
+            Some(compatible_tags),
+            allow_prerelease,
+        )
+        .await?
+        {
+            Some(version) => println!("{} {version}", spec.name),
+            None => miette::bail!(
+                "there is no version of '{}' that matches '{spec}' and is compatible with Python {}",
+                spec.name,
+                env_markers.python_full_version.string
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Parses a Python version like `3.8` or `3.8.10`, the patch version defaults to 0.
+fn parse_python_version(s: &str) -> Result<PythonInterpreterVersion, String> {
+    let parts = s
+        .split('.')
+        .map(u32::from_str)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid Python version '{s}'"))?;
+    match parts[..] {
+        [major, minor] => Ok(PythonInterpreterVersion::new(major, minor, 0)),
+        [major, minor, patch] => Ok(PythonInterpreterVersion::new(major, minor, patch)),
+        _ => Err(format!(
+            "invalid Python version '{s}', expected something like '3.8'"
+        )),
+    }
+}
+
+/// Parses a CPython version like `3.13`, or `3.13t` for a free-threaded build.
+fn parse_cpython_target(s: &str) -> Result<CPythonTarget, String> {
+    let (version, abi) = match s.strip_suffix('t') {
+        Some(version) => (version, CPythonAbi::FreeThreaded),
+        None => (s, CPythonAbi::Gil),
+    };
+    Ok(CPythonTarget {
+        version: parse_python_version(version)?,
+        abi,
+    })
+}
+
+/// Parses a target of `--universal` like `linux-x86_64` or `windows-amd64:3.12`.
+fn parse_universal_target(s: &str) -> Result<UniversalTarget, String> {
+    let (platform, python) = match s.split_once(':') {
+        Some((platform, python)) => (platform, Some(parse_cpython_target(python)?)),
+        None => (s, None),
+    };
+    Ok(UniversalTarget {
+        platform: platform.parse().map_err(|err| format!("{err}"))?,
+        python,
+    })
+}
+
 #[tokio::main]
 async fn main() {
     if let Err(e) = actual_main().await {
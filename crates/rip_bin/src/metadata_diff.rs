@@ -0,0 +1,177 @@
+use indexmap::IndexMap;
+use rattler_installs_packages::types::{
+    normalize_requirement, NormalizedPackageName, PackageName, Requirement, VersionSpecifiers,
+    WheelCoreMetadata,
+};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The differences between the dependencies of two versions of a package, see [`diff_metadata`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataDiff {
+    /// The `Requires-Dist` entries of the new version that the old version does not have
+    pub added: Vec<Requirement>,
+
+    /// The `Requires-Dist` entries of the old version that the new version does not have
+    pub removed: Vec<Requirement>,
+
+    /// The `Requires-Dist` entries on the same package and with the same marker whose version or
+    /// extras changed, as pairs of the old and the new entry
+    pub changed: Vec<(Requirement, Requirement)>,
+
+    /// The old and the new `Requires-Python`, only if it changed
+    pub requires_python: Option<(Option<VersionSpecifiers>, Option<VersionSpecifiers>)>,
+}
+
+impl MetadataDiff {
+    /// Returns true if the dependencies did not change
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.requires_python.is_none()
+    }
+}
+
+/// Identifies a `Requires-Dist` entry by the package it requires and the environments in which
+/// it applies.
+type RequirementKey = (String, Option<String>);
+
+fn requirement_key(requirement: &Requirement) -> RequirementKey {
+    let name = PackageName::from_str(&requirement.name)
+        .map(|name| NormalizedPackageName::from(name).to_string())
+        .unwrap_or_else(|_| requirement.name.to_lowercase());
+    (name, requirement.marker.as_ref().map(ToString::to_string))
+}
+
+/// Returns the canonical form of a requirement, so that e.g. reordered specifiers are not
+/// reported as a change.
+fn canonical(requirement: &Requirement) -> String {
+    let requirement = requirement.to_string();
+    normalize_requirement(&requirement).unwrap_or(requirement)
+}
+
+/// Compares the dependencies of two versions of a package. Entries of `Requires-Dist` are
+/// matched by the package they require and their marker, so an entry whose marker changed is
+/// reported as removed and added. The entries are reported in the order of the metadata.
+pub fn diff_metadata(old: &WheelCoreMetadata, new: &WheelCoreMetadata) -> MetadataDiff {
+    let mut old_requirements: IndexMap<RequirementKey, &Requirement> = old
+        .requires_dist
+        .iter()
+        .map(|requirement| (requirement_key(requirement), requirement))
+        .collect();
+
+    let mut diff = MetadataDiff::default();
+    for requirement in &new.requires_dist {
+        match old_requirements.shift_remove(&requirement_key(requirement)) {
+            None => diff.added.push(requirement.clone()),
+            Some(old_requirement) => {
+                if canonical(old_requirement) != canonical(requirement) {
+                    diff.changed
+                        .push((old_requirement.clone(), requirement.clone()));
+                }
+            }
+        }
+    }
+    diff.removed = old_requirements.into_values().cloned().collect();
+
+    if old.requires_python != new.requires_python {
+        diff.requires_python = Some((old.requires_python.clone(), new.requires_python.clone()));
+    }
+    diff
+}
+
+impl Display for MetadataDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some((old, new)) = &self.requires_python {
+            let display = |specifiers: &Option<VersionSpecifiers>| {
+                specifiers
+                    .as_ref()
+                    .map_or_else(|| String::from("(none)"), ToString::to_string)
+            };
+            writeln!(f, "Requires-Python: {} -> {}", display(old), display(new))?;
+        }
+        for requirement in &self.added {
+            writeln!(f, "+ {requirement}")?;
+        }
+        for requirement in &self.removed {
+            writeln!(f, "- {requirement}")?;
+        }
+        for (old, new) in &self.changed {
+            writeln!(f, "~ {old} -> {new}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn metadata(version: &str, requires_python: &str, requires_dist: &[&str]) -> WheelCoreMetadata {
+        let mut metadata =
+            format!("Metadata-Version: 2.1\nName: Flask\nVersion: {version}\nRequires-Python: {requires_python}\n");
+        for requirement in requires_dist {
+            metadata.push_str(&format!("Requires-Dist: {requirement}\n"));
+        }
+        WheelCoreMetadata::try_from(metadata.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_diff_metadata() {
+        let old = metadata(
+            "2.3.0",
+            ">=3.7",
+            &[
+                "Werkzeug>=2.3.3",
+                "Jinja2>=3.1.2",
+                "itsdangerous>=2.1.2",
+                "click>=8.1.3",
+                "importlib-metadata>=3.6.0; python_version < \"3.10\"",
+                "asgiref>=3.2; extra == \"async\"",
+            ],
+        );
+        let new = metadata(
+            "3.0.0",
+            ">=3.8",
+            &[
+                "Werkzeug>=3.0.0",
+                "Jinja2>=3.1.2",
+                "itsdangerous>=2.1.2",
+                "click>=8.1.3",
+                "blinker>=1.6.2",
+                "importlib_metadata>=3.6.0; python_version < \"3.10\"",
+                "asgiref[tz]>=3.2; extra == \"async\"",
+            ],
+        );
+
+        let diff = diff_metadata(&old, &new);
+        assert_eq!(
+            diff.to_string(),
+            "Requires-Python: >=3.7 -> >=3.8\n\
+             + blinker >=1.6.2\n\
+             ~ Werkzeug >=2.3.3 -> Werkzeug >=3.0.0\n\
+             ~ asgiref >=3.2 ; extra == 'async' -> asgiref[tz] >=3.2 ; extra == 'async'\n"
+        );
+
+        // A changed marker is reported as a removed and an added dependency
+        let newer = metadata(
+            "3.1.0",
+            ">=3.8",
+            &[
+                "Werkzeug>=3.0.0",
+                "Jinja2>=3.1.2",
+                "itsdangerous>=2.1.2",
+                "click>=8.1.3",
+                "blinker>=1.6.2",
+                "importlib-metadata>=3.6.0; python_version < \"3.9\"",
+                "asgiref[tz]>=3.2; extra == \"async\"",
+            ],
+        );
+        let diff = diff_metadata(&new, &newer);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.removed.len(), 1);
+        assert!(diff.changed.is_empty() && diff.requires_python.is_none());
+        assert!(diff_metadata(&new, &new).is_empty());
+    }
+}
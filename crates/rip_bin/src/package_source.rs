@@ -0,0 +1,160 @@
+use rattler_installs_packages::resolve::PinnedPackage;
+use rattler_installs_packages::types::ArtifactName;
+use serde::Serialize;
+use url::Url;
+
+/// The kind of artifact a package is installed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactKind {
+    /// A wheel, which is installed as is
+    Wheel,
+    /// A source distribution, from which a wheel is built
+    Sdist,
+    /// A source tree, from which a wheel is built
+    Stree,
+}
+
+/// Where a resolved package comes from, to audit the origin of the packages of a resolution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageSource {
+    /// The index on which the artifact was found, `None` if it was found in a find-links
+    /// directory or was referenced by a direct url
+    pub index: Option<Url>,
+    /// The url of the artifact
+    pub url: Url,
+    /// The kind of the artifact
+    pub kind: ArtifactKind,
+    /// True if a wheel is built from the artifact when the package is installed
+    pub built: bool,
+}
+
+/// A resolved package together with its source, as written by `--json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedPackage {
+    /// The resolved package
+    #[serde(flatten)]
+    pub package: PinnedPackage,
+    /// The source of the artifact that is installed, `None` if the package has no artifacts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<PackageSource>,
+}
+
+impl From<PinnedPackage> for ResolvedPackage {
+    fn from(package: PinnedPackage) -> Self {
+        let source = package_source(&package);
+        Self { package, source }
+    }
+}
+
+/// Returns the source of the artifact of `package` that is installed, which is the first and
+/// most compatible one. Returns `None` if the package was locked without artifacts.
+pub fn package_source(package: &PinnedPackage) -> Option<PackageSource> {
+    let artifact = package.artifacts.first()?;
+    let kind = match &artifact.filename {
+        ArtifactName::Wheel(_) => ArtifactKind::Wheel,
+        ArtifactName::SDist(_) => ArtifactKind::Sdist,
+        ArtifactName::STree(_) => ArtifactKind::Stree,
+    };
+    Some(PackageSource {
+        index: artifact.index_url.clone(),
+        url: artifact.url.clone(),
+        kind,
+        built: kind != ArtifactKind::Wheel,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rattler_installs_packages::resolve::PypiVersion;
+    use rattler_installs_packages::types::{
+        ArtifactInfo, DistInfoMetadata, NormalizedPackageName, Yanked,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn package(name: &str, filename: &str, index_url: Option<&str>) -> PinnedPackage {
+        let name: NormalizedPackageName = name.parse().unwrap();
+        PinnedPackage {
+            artifacts: vec![Arc::new(ArtifactInfo {
+                filename: ArtifactName::from_filename(filename, &name).unwrap(),
+                url: format!("https://files.example.com/{filename}")
+                    .parse()
+                    .unwrap(),
+                hashes: None,
+                requires_python: None,
+                dist_info_metadata: DistInfoMetadata::default(),
+                yanked: Yanked::default(),
+                index_url: index_url.map(|url| url.parse().unwrap()),
+            })],
+            name,
+            version: PypiVersion::Version {
+                version: "1.0".parse().unwrap(),
+                package_allows_prerelease: false,
+            },
+            extras: Default::default(),
+            dependencies: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_package_source_json() {
+        let resolution = [
+            package(
+                "foo",
+                "foo-1.0-py3-none-any.whl",
+                Some("https://pypi.org/simple/"),
+            ),
+            package(
+                "bar",
+                "bar-1.0.tar.gz",
+                Some("https://private.example.com/simple/"),
+            ),
+            package("baz", "baz-1.0.tar.gz", None),
+        ]
+        .into_iter()
+        .map(ResolvedPackage::from)
+        .collect::<Vec<_>>();
+
+        let sources = serde_json::to_value(&resolution)
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|package| (package["name"].clone(), package["source"].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            [
+                (
+                    json!("foo"),
+                    json!({
+                        "index": "https://pypi.org/simple/",
+                        "url": "https://files.example.com/foo-1.0-py3-none-any.whl",
+                        "kind": "wheel",
+                        "built": false,
+                    })
+                ),
+                (
+                    json!("bar"),
+                    json!({
+                        "index": "https://private.example.com/simple/",
+                        "url": "https://files.example.com/bar-1.0.tar.gz",
+                        "kind": "sdist",
+                        "built": true,
+                    })
+                ),
+                (
+                    json!("baz"),
+                    json!({
+                        "index": null,
+                        "url": "https://files.example.com/baz-1.0.tar.gz",
+                        "kind": "sdist",
+                        "built": true,
+                    })
+                ),
+            ]
+        );
+    }
+}
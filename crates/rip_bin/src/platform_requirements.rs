@@ -0,0 +1,120 @@
+use rattler_installs_packages::python_env::{
+    Pep508EnvMakers, PythonInterpreterVersion, TargetPlatform,
+};
+use rattler_installs_packages::types::{MarkerEnvironment, Requirement};
+
+/// The platforms for which `--all-platforms` evaluates the requirements of a package.
+const COMMON_PLATFORMS: [&str; 3] = ["linux-x86_64", "macos-arm64", "windows-amd64"];
+
+/// The Python versions for which `--all-platforms` evaluates the requirements of a package, the
+/// oldest and the newest commonly supported version.
+const COMMON_PYTHON_VERSIONS: [(u32, u32, u32); 2] = [(3, 9, 0), (3, 12, 0)];
+
+/// Returns the environment markers of the common combinations of platforms and Python versions,
+/// labeled like `linux-x86_64 py3.9`.
+pub fn common_marker_environments() -> Vec<(String, MarkerEnvironment)> {
+    COMMON_PLATFORMS
+        .iter()
+        .flat_map(|platform| {
+            let platform: TargetPlatform = platform.parse().expect("the platform is valid");
+            COMMON_PYTHON_VERSIONS
+                .iter()
+                .map(move |&(major, minor, patch)| {
+                    let markers = Pep508EnvMakers::for_target_platform(
+                        platform,
+                        &PythonInterpreterVersion::from((major, minor, patch)),
+                    );
+                    (format!("{platform} py{major}.{minor}"), markers.0)
+                })
+        })
+        .collect()
+}
+
+/// A requirement of a package together with the environments in which it applies.
+#[derive(Debug, Clone)]
+pub struct PlatformRequirement<'a> {
+    /// The requirement as declared by the package
+    pub requirement: &'a Requirement,
+
+    /// The labels of the environments in which the marker of the requirement holds, or `None`
+    /// if it holds in all of them
+    pub environments: Option<Vec<&'a str>>,
+}
+
+/// Evaluates the markers of `requires_dist` in each of the labeled `environments` and returns
+/// the requirements that apply to at least one of them, in the order in which they are declared.
+/// Requirements that are only added by an extra never apply.
+pub fn requirements_by_platform<'a>(
+    requires_dist: &'a [Requirement],
+    environments: &'a [(String, MarkerEnvironment)],
+) -> Vec<PlatformRequirement<'a>> {
+    requires_dist
+        .iter()
+        .filter_map(|requirement| {
+            let enabled = environments
+                .iter()
+                .filter(|(_, env)| {
+                    requirement
+                        .marker
+                        .as_ref()
+                        .map_or(true, |marker| marker.evaluate(env, &[]))
+                })
+                .map(|(label, _)| label.as_str())
+                .collect::<Vec<_>>();
+            if enabled.is_empty() {
+                return None;
+            }
+            Some(PlatformRequirement {
+                requirement,
+                environments: (enabled.len() < environments.len()).then_some(enabled),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_requirements_by_platform() {
+        let requires_dist = [
+            "colorama; platform_system == 'Windows'",
+            "tomli>=1.1.0; python_version < '3.11'",
+            "packaging>=22.0",
+            "uvloop; sys_platform != 'win32' and python_version >= '3.12'",
+            "pytest; extra == 'test'",
+        ]
+        .map(|r| Requirement::from_str(r).unwrap());
+        let environments = common_marker_environments();
+        assert_eq!(environments.len(), 6);
+
+        let requirements = requirements_by_platform(&requires_dist, &environments)
+            .into_iter()
+            .map(|r| (r.requirement.name.as_str(), r.environments))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            requirements,
+            [
+                (
+                    "colorama",
+                    Some(vec!["windows-amd64 py3.9", "windows-amd64 py3.12"])
+                ),
+                (
+                    "tomli",
+                    Some(vec![
+                        "linux-x86_64 py3.9",
+                        "macos-arm64 py3.9",
+                        "windows-amd64 py3.9"
+                    ])
+                ),
+                ("packaging", None),
+                (
+                    "uvloop",
+                    Some(vec!["linux-x86_64 py3.12", "macos-arm64 py3.12"])
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use rattler_installs_packages::resolve::{ProgressObserver, PypiVersion};
+use rattler_installs_packages::types::NormalizedPackageName;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+
+/// Shows the progress of a resolution with progress bars: a spinner with the package whose
+/// metadata is retrieved and a progress bar for each artifact that is being downloaded.
+pub struct IndicatifProgress {
+    multi_progress: MultiProgress,
+    packages: ProgressBar,
+    downloads: Mutex<HashMap<Url, ProgressBar>>,
+    steady_tick: Option<Duration>,
+}
+
+/// The interval at which the spinners are redrawn by default.
+pub const DEFAULT_STEADY_TICK: Duration = Duration::from_millis(100);
+
+impl IndicatifProgress {
+    /// Constructs the progress bars, they are added to `multi_progress`. The spinners are redrawn
+    /// every [`DEFAULT_STEADY_TICK`], use [`Self::with_steady_tick`] to change that.
+    pub fn new(multi_progress: MultiProgress) -> Self {
+        let packages = multi_progress.add(
+            ProgressBar::new_spinner().with_style(
+                ProgressStyle::with_template("{spinner:.green} {pos} packages {wide_msg:.dim}")
+                    .expect("the template is valid"),
+            ),
+        );
+        Self {
+            multi_progress,
+            packages,
+            downloads: Mutex::default(),
+            steady_tick: None,
+        }
+        .with_steady_tick(Some(DEFAULT_STEADY_TICK))
+    }
+
+    /// Sets the interval at which the spinners are redrawn, even if there is no progress. With
+    /// `None` the spinners only move when progress is reported, which avoids continuous output in
+    /// terminals that do not handle redrawing well.
+    pub fn with_steady_tick(mut self, interval: Option<Duration>) -> Self {
+        match interval {
+            Some(interval) => self.packages.enable_steady_tick(interval),
+            None => self.packages.disable_steady_tick(),
+        }
+        self.steady_tick = interval;
+        self
+    }
+
+    /// Returns the interval at which the spinners are redrawn, if any.
+    pub fn steady_tick(&self) -> Option<Duration> {
+        self.steady_tick
+    }
+
+    /// Removes all progress bars.
+    pub fn finish(&self) {
+        self.packages.finish_and_clear();
+        for (_, download) in self.downloads.lock().unwrap().drain() {
+            download.finish_and_clear();
+        }
+    }
+}
+
+impl ProgressObserver for IndicatifProgress {
+    fn on_package_started(&self, name: &NormalizedPackageName, version: &PypiVersion) {
+        self.packages.set_message(format!("{name} {version}"));
+    }
+
+    fn on_package_finished(&self, _name: &NormalizedPackageName, _version: &PypiVersion) {
+        self.packages.inc(1);
+    }
+
+    fn on_download_progress(&self, url: &Url, bytes: u64, total: Option<u64>) {
+        let mut downloads = self.downloads.lock().unwrap();
+        let download = downloads.entry(url.clone()).or_insert_with(|| {
+            let filename = url.path_segments().and_then(Iterator::last).unwrap_or("");
+            let download = match total {
+                Some(total) => ProgressBar::new(total).with_style(
+                    ProgressStyle::with_template(
+                        "  {bar:20.green/dim} {bytes:>10}/{total_bytes:<10} {wide_msg:.dim}",
+                    )
+                    .expect("the template is valid"),
+                ),
+                None => {
+                    let spinner = ProgressBar::new_spinner().with_style(
+                        ProgressStyle::with_template(
+                            "  {spinner:.green} {bytes:>10} {wide_msg:.dim}",
+                        )
+                        .expect("the template is valid"),
+                    );
+                    if let Some(interval) = self.steady_tick {
+                        spinner.enable_steady_tick(interval);
+                    }
+                    spinner
+                }
+            };
+            self.multi_progress
+                .add(download.with_message(filename.to_owned()))
+        });
+        download.set_position(bytes);
+    }
+
+    fn on_download_finished(&self, url: &Url) {
+        if let Some(download) = self.downloads.lock().unwrap().remove(url) {
+            download.finish_and_clear();
+        }
+    }
+}
+
+/// Counts the bytes of the artifacts that are downloaded during a resolution and forwards every
+/// event to another observer, if any.
+pub struct DownloadCounter {
+    inner: Option<Arc<dyn ProgressObserver>>,
+    downloads: Mutex<HashMap<Url, u64>>,
+    finished_bytes: Mutex<u64>,
+}
+
+impl DownloadCounter {
+    /// Constructs a counter that forwards the events to `inner`.
+    pub fn new(inner: Option<Arc<dyn ProgressObserver>>) -> Self {
+        Self {
+            inner,
+            downloads: Mutex::default(),
+            finished_bytes: Mutex::default(),
+        }
+    }
+
+    /// Returns the number of bytes that were downloaded so far, including downloads that are
+    /// still in progress or that failed.
+    pub fn bytes_downloaded(&self) -> u64 {
+        let in_progress = self.downloads.lock().unwrap().values().sum::<u64>();
+        *self.finished_bytes.lock().unwrap() + in_progress
+    }
+}
+
+impl ProgressObserver for DownloadCounter {
+    fn on_package_started(&self, name: &NormalizedPackageName, version: &PypiVersion) {
+        if let Some(inner) = &self.inner {
+            inner.on_package_started(name, version);
+        }
+    }
+
+    fn on_package_finished(&self, name: &NormalizedPackageName, version: &PypiVersion) {
+        if let Some(inner) = &self.inner {
+            inner.on_package_finished(name, version);
+        }
+    }
+
+    fn on_download_progress(&self, url: &Url, bytes: u64, total: Option<u64>) {
+        self.downloads.lock().unwrap().insert(url.clone(), bytes);
+        if let Some(inner) = &self.inner {
+            inner.on_download_progress(url, bytes, total);
+        }
+    }
+
+    fn on_download_finished(&self, url: &Url) {
+        if let Some(bytes) = self.downloads.lock().unwrap().remove(url) {
+            *self.finished_bytes.lock().unwrap() += bytes;
+        }
+        if let Some(inner) = &self.inner {
+            inner.on_download_finished(url);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use indicatif::ProgressDrawTarget;
+
+    #[test]
+    fn test_disable_steady_tick() {
+        let multi_progress = MultiProgress::with_draw_target(ProgressDrawTarget::hidden());
+        assert_eq!(
+            IndicatifProgress::new(multi_progress.clone()).steady_tick(),
+            Some(DEFAULT_STEADY_TICK)
+        );
+
+        let progress = IndicatifProgress::new(multi_progress).with_steady_tick(None);
+        assert_eq!(progress.steady_tick(), None);
+
+        // Progress is still reported without ticking
+        let name = "flask".parse().unwrap();
+        let version = PypiVersion::Version {
+            version: "3.0.0".parse().unwrap(),
+            package_allows_prerelease: false,
+        };
+        progress.on_package_started(&name, &version);
+        progress.on_package_finished(&name, &version);
+        assert_eq!(progress.packages.position(), 1);
+
+        let url: Url = "https://example.com/flask-3.0.0-py3-none-any.whl"
+            .parse()
+            .unwrap();
+        progress.on_download_progress(&url, 10, None);
+        assert_eq!(progress.downloads.lock().unwrap()[&url].position(), 10);
+        progress.on_download_finished(&url);
+        assert!(progress.downloads.lock().unwrap().is_empty());
+        progress.finish();
+    }
+
+    #[test]
+    fn test_download_counter() {
+        let multi_progress = MultiProgress::with_draw_target(ProgressDrawTarget::hidden());
+        let progress = Arc::new(IndicatifProgress::new(multi_progress));
+        let counter = DownloadCounter::new(Some(progress.clone()));
+
+        let first: Url = "https://example.com/a-1.0-py3-none-any.whl"
+            .parse()
+            .unwrap();
+        let second: Url = "https://example.com/b-1.0-py3-none-any.whl"
+            .parse()
+            .unwrap();
+        counter.on_download_progress(&first, 10, Some(30));
+        counter.on_download_progress(&first, 30, Some(30));
+        counter.on_download_finished(&first);
+        counter.on_download_progress(&second, 5, None);
+        assert_eq!(counter.bytes_downloaded(), 35);
+
+        // The events are forwarded
+        assert_eq!(progress.downloads.lock().unwrap().len(), 1);
+        counter.on_download_finished(&second);
+        assert!(progress.downloads.lock().unwrap().is_empty());
+        assert_eq!(counter.bytes_downloaded(), 35);
+    }
+}
@@ -0,0 +1,208 @@
+use miette::{Context, IntoDiagnostic};
+use rattler_installs_packages::types::{NormalizedPackageName, PackageName, Requirement};
+use std::str::FromStr;
+
+/// Returns the requirements of a project from the contents of its `pyproject.toml`: the
+/// `dependencies` of its `[project]` table followed by the requirements of each of the `groups`.
+///
+/// A group is looked up in the `[dependency-groups]` table (PEP 735) and otherwise in the
+/// `[project.optional-dependencies]` table (PEP 621), e.g. `dev` or `test`. Group names are
+/// compared after normalization, like package names. Dependency groups can include other groups
+/// with `{include-group = "name"}`.
+pub fn parse_pyproject_requirements(
+    contents: &str,
+    groups: &[String],
+) -> miette::Result<Vec<Requirement>> {
+    let pyproject: toml::Table = contents.parse().into_diagnostic()?;
+    let project = pyproject.get("project").and_then(toml::Value::as_table);
+
+    let mut requirements = match project.and_then(|project| project.get("dependencies")) {
+        Some(dependencies) => parse_requirements(dependencies)
+            .wrap_err("invalid dependencies in the [project] table")?,
+        None => Vec::new(),
+    };
+
+    let dependency_groups = pyproject
+        .get("dependency-groups")
+        .and_then(toml::Value::as_table);
+    let optional_dependencies = project
+        .and_then(|project| project.get("optional-dependencies"))
+        .and_then(toml::Value::as_table);
+    for group in groups {
+        if let Some(dependency_groups) = dependency_groups
+            .filter(|dependency_groups| find_group(dependency_groups, group).is_some())
+        {
+            requirements.extend(dependency_group_requirements(dependency_groups, group)?);
+        } else if let Some(extra) = optional_dependencies.and_then(|o| find_group(o, group)) {
+            requirements.extend(
+                parse_requirements(extra).wrap_err_with(|| {
+                    format!("invalid optional dependencies in group '{group}'")
+                })?,
+            );
+        } else {
+            miette::bail!(
+                "there is no dependency group or optional dependency group named '{group}'"
+            );
+        }
+    }
+
+    Ok(requirements)
+}
+
+/// Returns the entry of `table` whose key normalizes to the same name as `group`.
+fn find_group<'a>(table: &'a toml::Table, group: &str) -> Option<&'a toml::Value> {
+    let group = normalize(group);
+    table
+        .iter()
+        .find(|(name, _)| normalize(name) == group)
+        .map(|(_, value)| value)
+}
+
+/// Normalizes a group name like a package name, falling back to lowercase for names that are not
+/// valid package names.
+fn normalize(name: &str) -> String {
+    PackageName::from_str(name)
+        .map(|name| NormalizedPackageName::from(name).to_string())
+        .unwrap_or_else(|_| name.to_lowercase())
+}
+
+/// Returns the requirements of a dependency group, following `include-group` entries.
+fn dependency_group_requirements(
+    dependency_groups: &toml::Table,
+    group: &str,
+) -> miette::Result<Vec<Requirement>> {
+    let mut requirements = Vec::new();
+
+    // The groups that are being expanded, to detect groups that include themselves
+    let mut path = vec![normalize(group)];
+    let mut stack = vec![find_group(dependency_groups, group)
+        .and_then(toml::Value::as_array)
+        .ok_or_else(|| miette::miette!("dependency group '{group}' must be an array"))?
+        .iter()];
+    while let Some(entries) = stack.last_mut() {
+        let Some(entry) = entries.next() else {
+            stack.pop();
+            path.pop();
+            continue;
+        };
+
+        if let Some(requirement) = entry.as_str() {
+            requirements.push(
+                Requirement::from_str(requirement)
+                    .into_diagnostic()
+                    .wrap_err_with(|| {
+                        format!("invalid requirement in dependency group '{group}'")
+                    })?,
+            );
+            continue;
+        }
+
+        let Some(included) = entry
+            .as_table()
+            .and_then(|table| table.get("include-group"))
+            .and_then(toml::Value::as_str)
+        else {
+            miette::bail!(
+                "entries of dependency group '{group}' must be requirements or include-group tables"
+            );
+        };
+        if path.contains(&normalize(included)) {
+            miette::bail!("dependency group '{included}' includes itself");
+        }
+        let entries = find_group(dependency_groups, included)
+            .ok_or_else(|| miette::miette!("there is no dependency group named '{included}'"))?
+            .as_array()
+            .ok_or_else(|| miette::miette!("dependency group '{included}' must be an array"))?;
+        path.push(normalize(included));
+        stack.push(entries.iter());
+    }
+
+    Ok(requirements)
+}
+
+/// Parses an array of requirement strings.
+fn parse_requirements(value: &toml::Value) -> miette::Result<Vec<Requirement>> {
+    value
+        .as_array()
+        .ok_or_else(|| miette::miette!("expected an array of requirements"))?
+        .iter()
+        .map(|requirement| {
+            let requirement = requirement
+                .as_str()
+                .ok_or_else(|| miette::miette!("expected a requirement string"))?;
+            Requirement::from_str(requirement).into_diagnostic()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PYPROJECT: &str = r#"
+[project]
+name = "app"
+version = "1.0"
+dependencies = ["flask>=3", "click"]
+
+[project.optional-dependencies]
+docs = ["sphinx"]
+Test = ["pytest>=8"]
+
+[dependency-groups]
+lint = ["ruff"]
+dev = ["coverage[toml]", {include-group = "lint"}, "mypy ; python_version >= '3.8'"]
+test = ["pytest-xdist"]
+loop = [{include-group = "Loop"}]
+"#;
+
+    fn requirements(groups: &[&str]) -> miette::Result<Vec<String>> {
+        let groups = groups.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        Ok(parse_pyproject_requirements(PYPROJECT, &groups)?
+            .iter()
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    #[test]
+    fn test_dependency_groups() {
+        assert_eq!(requirements(&[]).unwrap(), ["flask >=3", "click"]);
+
+        // Included groups are expanded in place
+        assert_eq!(
+            requirements(&["dev"]).unwrap(),
+            [
+                "flask >=3",
+                "click",
+                "coverage[toml]",
+                "ruff",
+                "mypy ; python_version >= '3.8'"
+            ]
+        );
+
+        // Dependency groups take precedence over optional dependencies of the same name
+        assert_eq!(
+            requirements(&["docs", "TEST"]).unwrap(),
+            ["flask >=3", "click", "sphinx", "pytest-xdist"]
+        );
+    }
+
+    #[test]
+    fn test_dependency_group_errors() {
+        let err = requirements(&["missing"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "there is no dependency group or optional dependency group named 'missing'"
+        );
+
+        let err = requirements(&["loop"]).unwrap_err();
+        assert_eq!(err.to_string(), "dependency group 'Loop' includes itself");
+
+        let err = parse_pyproject_requirements(
+            "[dependency-groups]\ndev = [{include-group = \"lint\"}]\n",
+            &["dev".to_string()],
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "there is no dependency group named 'lint'");
+    }
+}
@@ -0,0 +1,118 @@
+use miette::{Context, IntoDiagnostic};
+use rattler_installs_packages::types::Requirement;
+use std::str::FromStr;
+
+/// Parses the contents of a requirements file, e.g. a `requirements.txt`. Every line is a PEP 508
+/// requirement that can have extras and an environment marker, for example
+/// `requests[security] ; python_version < '3.9'`.
+///
+/// Comments, empty lines and lines continued with a trailing backslash are supported. Options
+/// such as `--index-url` or `-r other.txt` are not and result in an error.
+pub fn parse_requirements_file(contents: &str) -> miette::Result<Vec<Requirement>> {
+    let mut requirements = Vec::new();
+    let mut logical_line = String::new();
+    let mut first_line_number = 1;
+    for (idx, line) in contents.lines().enumerate() {
+        if logical_line.is_empty() {
+            first_line_number = idx + 1;
+        }
+
+        // Lines ending with a backslash are joined with the next line
+        if let Some(continued) = line.strip_suffix('\\') {
+            logical_line.push_str(continued);
+            continue;
+        }
+        logical_line.push_str(line);
+
+        let line = strip_comment(&logical_line).trim();
+        if !line.is_empty() {
+            if line.starts_with('-') {
+                miette::bail!(
+                    "line {first_line_number}: options are not supported in requirements files: '{line}'"
+                );
+            }
+            requirements.push(
+                Requirement::from_str(line)
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("line {first_line_number}: invalid requirement"))?,
+            );
+        }
+        logical_line.clear();
+    }
+
+    Ok(requirements)
+}
+
+/// Removes a comment from a line. A comment starts with a `#` at the start of the line or after
+/// whitespace, so that the fragment of a url (e.g. `#egg=foo`) is kept.
+fn strip_comment(line: &str) -> &str {
+    line.char_indices()
+        .find(|&(idx, c)| c == '#' && line[..idx].chars().last().map_or(true, char::is_whitespace))
+        .map_or(line, |(idx, _)| &line[..idx])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rattler_installs_packages::python_env::{Pep508EnvMakers, PythonInterpreterVersion};
+
+    #[test]
+    fn test_parse_requirements_file() {
+        let requirements = parse_requirements_file(
+            "# The dependencies of the project\n\
+             \n\
+             requests[security,socks]>=2.31\n\
+             importlib-metadata ; python_version < '3.10'  # only needed for old versions\n\
+             requests[security] ; python_version < '3.9'\n\
+             flask[async]==3.0.0; \\\n    sys_platform == 'linux'\n",
+        )
+        .unwrap();
+        assert_eq!(requirements.len(), 4);
+
+        // Extras only
+        assert_eq!(requirements[0].name, "requests");
+        assert_eq!(
+            requirements[0].extras,
+            Some(vec!["security".into(), "socks".into()])
+        );
+        assert!(requirements[0].marker.is_none());
+
+        // Marker only
+        assert_eq!(requirements[1].name, "importlib-metadata");
+        assert_eq!(requirements[1].extras, None);
+        assert_eq!(
+            requirements[1].marker.as_ref().unwrap().to_string(),
+            "python_version < '3.10'"
+        );
+
+        // Extras and a marker
+        assert_eq!(requirements[2].name, "requests");
+        assert_eq!(requirements[2].extras, Some(vec!["security".into()]));
+        let marker = requirements[2].marker.as_ref().unwrap();
+        assert_eq!(marker.to_string(), "python_version < '3.9'");
+        let env = Pep508EnvMakers::for_target_platform(
+            "linux-x86_64".parse().unwrap(),
+            &PythonInterpreterVersion::from((3, 8, 18)),
+        )
+        .0;
+        assert!(marker.evaluate(&env, &[]));
+
+        // Continued on the next line
+        assert_eq!(requirements[3].name, "flask");
+        assert_eq!(requirements[3].extras, Some(vec!["async".into()]));
+        assert_eq!(
+            requirements[3].marker.as_ref().unwrap().to_string(),
+            "sys_platform == 'linux'"
+        );
+    }
+
+    #[test]
+    fn test_parse_requirements_file_errors() {
+        let err = parse_requirements_file("flask\n-r other.txt\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"), "{err}");
+
+        let err = parse_requirements_file("flask\n\nrequests[security ; python_version < '3.9'\n")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "line 3: invalid requirement");
+    }
+}
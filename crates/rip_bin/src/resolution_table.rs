@@ -0,0 +1,417 @@
+use itertools::Itertools;
+use rattler_installs_packages::resolve::{ConditionalPackage, PinnedPackage, PypiVersion};
+use std::io::{self, Write};
+
+/// Writes the resolved packages as a table with a name and a version column, sorted by name. With
+/// `show_urls` a third column contains the url of the artifact that would be installed.
+pub fn write_resolution_table(
+    writer: impl Write,
+    packages: &[PinnedPackage],
+    show_urls: bool,
+) -> io::Result<()> {
+    let mut tabbed = tabwriter::TabWriter::new(writer);
+    write!(
+        tabbed,
+        "{}\t{}",
+        console::style("Name").bold(),
+        console::style("Version").bold()
+    )?;
+    if show_urls {
+        write!(tabbed, "\t{}", console::style("Url").bold())?;
+    }
+    writeln!(tabbed)?;
+
+    for package in packages.iter().sorted_by(|a, b| a.name.cmp(&b.name)) {
+        write!(tabbed, "{}", package.name.as_str())?;
+        if !package.extras.is_empty() {
+            write!(
+                tabbed,
+                "[{}]",
+                package.extras.iter().map(|e| e.as_str()).sorted().join(",")
+            )?;
+        }
+        write!(tabbed, "\t{}", package.version)?;
+        if show_urls {
+            // Locked packages do not carry their artifacts
+            match package.artifacts.first() {
+                Some(artifact) => write!(tabbed, "\t{}", artifact.url)?,
+                None => write!(tabbed, "\t-")?,
+            }
+        }
+        writeln!(tabbed)?;
+    }
+    tabbed.flush()
+}
+
+/// Writes the resolved packages as requirements, one per line and sorted by name, e.g.
+/// `flask[async]==3.0.0`. The extras that were activated during resolution are kept, markers are
+/// omitted because the resolution already applies to a concrete environment. Packages that were
+/// resolved from a url are written as `name @ url`.
+pub fn write_requirements(mut writer: impl Write, packages: &[PinnedPackage]) -> io::Result<()> {
+    for requirement in pinned_requirements(packages) {
+        writeln!(writer, "{requirement}")?;
+    }
+    writer.flush()
+}
+
+/// Writes the requirements of [`write_requirements`] followed by the hashes of the artifacts of
+/// each package, in the format of pip's `--require-hashes` mode, e.g.
+///
+/// ```text
+/// flask==3.0.0 \
+///     --hash=sha256:... \
+///     --hash=sha512:...
+/// ```
+///
+/// All available hashes of every artifact are written, sorted by the name of the algorithm, so
+/// that consumers can verify whichever artifact and algorithm they support.
+pub fn write_requirements_with_hashes(
+    mut writer: impl Write,
+    packages: &[PinnedPackage],
+) -> io::Result<()> {
+    for package in packages.iter().sorted_by(|a, b| a.name.cmp(&b.name)) {
+        write!(writer, "{}", pinned_requirement(package))?;
+        let hashes = package
+            .artifacts
+            .iter()
+            .filter_map(|artifact| artifact.hashes.as_ref())
+            .flat_map(|hashes| hashes.hex_digests())
+            .sorted()
+            .dedup();
+        for (algorithm, digest) in hashes {
+            write!(writer, " \\\n    --hash={algorithm}:{digest}")?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Writes the packages of a universal resolution as requirements like [`write_requirements`], in
+/// the order in which they are given. Packages that are not needed in every environment get the
+/// marker of the environments that need them, e.g. `colorama==0.4.6 ; sys_platform == 'win32'`,
+/// so a package can occur multiple times with a different version for each environment.
+pub fn write_universal_requirements(
+    mut writer: impl Write,
+    packages: &[ConditionalPackage],
+) -> io::Result<()> {
+    for package in packages {
+        write!(writer, "{}", pinned_requirement(&package.package))?;
+        if let Some(marker) = &package.marker {
+            write!(writer, " ; {marker}")?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Writes the resolved packages as the `dependencies` of a conda environment file, with the
+/// requirements of [`write_requirements`] in its `pip` section. Merge it into an existing
+/// `environment.yml` to install the resolution with pip in a conda environment.
+pub fn write_conda_pip_section(
+    mut writer: impl Write,
+    packages: &[PinnedPackage],
+) -> io::Result<()> {
+    writeln!(writer, "dependencies:")?;
+    writeln!(writer, "  - pip")?;
+    writeln!(writer, "  - pip:")?;
+    for requirement in pinned_requirements(packages) {
+        // Names, extras, versions and urls never contain `: ` or ` #`, so a plain scalar works
+        writeln!(writer, "      - {requirement}")?;
+    }
+    writer.flush()
+}
+
+/// Writes the resolved packages as inline script metadata (PEP 723), with the requirements of
+/// [`write_requirements`] as its `dependencies`. Paste it at the top of a single-file script to
+/// run the script with the resolved packages.
+pub fn write_inline_script_metadata(
+    mut writer: impl Write,
+    packages: &[PinnedPackage],
+) -> io::Result<()> {
+    writeln!(writer, "# /// script")?;
+    writeln!(writer, "# dependencies = [")?;
+    for requirement in pinned_requirements(packages) {
+        writeln!(writer, "#   {},", quoted(&requirement))?;
+    }
+    writeln!(writer, "# ]")?;
+    writeln!(writer, "# ///")?;
+    writer.flush()
+}
+
+/// Returns the resolved packages as pinned requirements sorted by name, see
+/// [`write_requirements`].
+fn pinned_requirements(packages: &[PinnedPackage]) -> impl Iterator<Item = String> + '_ {
+    packages
+        .iter()
+        .sorted_by(|a, b| a.name.cmp(&b.name))
+        .map(pinned_requirement)
+}
+
+/// Returns a resolved package as a pinned requirement, see [`write_requirements`].
+fn pinned_requirement(package: &PinnedPackage) -> String {
+    let mut requirement = package.name.as_str().to_owned();
+    if !package.extras.is_empty() {
+        let extras = package.extras.iter().map(|e| e.as_str()).sorted().join(",");
+        requirement.push_str(&format!("[{extras}]"));
+    }
+    match &package.version {
+        PypiVersion::Version { version, .. } => requirement.push_str(&format!("=={version}")),
+        PypiVersion::Url(url) => requirement.push_str(&format!(" @ {url}")),
+    }
+    requirement
+}
+
+/// Returns `s` as a double quoted string, JSON and TOML basic strings are escaped the same way.
+fn quoted(s: &str) -> String {
+    serde_json::to_string(s).expect("a string can always be serialized")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rattler_installs_packages::index::parse_hash;
+    use rattler_installs_packages::python_env::{Pep508EnvMakers, PythonInterpreterVersion};
+    use rattler_installs_packages::resolve::{environment_markers, merge_resolutions};
+    use rattler_installs_packages::types::{
+        ArtifactInfo, ArtifactName, DistInfoMetadata, NormalizedPackageName, Yanked,
+    };
+    use std::sync::Arc;
+    use url::Url;
+
+    fn package(name: &str, version: &str, url: Option<&str>) -> PinnedPackage {
+        let name: NormalizedPackageName = name.parse().unwrap();
+        PinnedPackage {
+            artifacts: url
+                .map(|url| {
+                    let url: Url = url.parse().unwrap();
+                    let filename = url.path_segments().unwrap().last().unwrap().to_owned();
+                    Arc::new(ArtifactInfo {
+                        filename: ArtifactName::from_filename(&filename, &name).unwrap(),
+                        url,
+                        hashes: None,
+                        requires_python: None,
+                        dist_info_metadata: DistInfoMetadata::default(),
+                        yanked: Yanked::default(),
+                        index_url: None,
+                    })
+                })
+                .into_iter()
+                .collect(),
+            name,
+            version: PypiVersion::Version {
+                version: version.parse().unwrap(),
+                package_allows_prerelease: false,
+            },
+            extras: Default::default(),
+            dependencies: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_write_requirements() {
+        let mut flask = package("flask", "3.0.0", None);
+        flask.extras = ["async".parse().unwrap(), "dotenv".parse().unwrap()]
+            .into_iter()
+            .collect();
+        let mut local = package("local", "1.0", None);
+        local.version = PypiVersion::Url("https://example.com/local-1.0.tar.gz".parse().unwrap());
+        let packages = [package("jinja2", "3.1.2", None), flask, local];
+
+        let mut output = Vec::new();
+        write_requirements(&mut output, &packages).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "flask[async,dotenv]==3.0.0\n\
+             jinja2==3.1.2\n\
+             local @ https://example.com/local-1.0.tar.gz\n"
+        );
+    }
+
+    #[test]
+    fn test_write_requirements_with_hashes() {
+        let sha256 = "a".repeat(64);
+        let sha512 = "b".repeat(128);
+        let hashes = |fragment: &str| {
+            let mut package = package(
+                "flask",
+                "3.0.0",
+                Some("https://example.com/flask-3.0.0-py3-none-any.whl"),
+            );
+            Arc::get_mut(&mut package.artifacts[0]).unwrap().hashes = parse_hash(fragment);
+            package
+        };
+        let mut flask = hashes(&format!("sha512={sha512}&sha256={sha256}"));
+        let sdist = hashes(&format!("sha256={}", "c".repeat(64)));
+        flask.artifacts.extend(sdist.artifacts);
+        let packages = [package("jinja2", "3.1.2", None), flask];
+
+        let mut output = Vec::new();
+        write_requirements_with_hashes(&mut output, &packages).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "flask==3.0.0 \\\n    \
+                 --hash=sha256:{sha256} \\\n    \
+                 --hash=sha256:{} \\\n    \
+                 --hash=sha512:{sha512}\n\
+                 jinja2==3.1.2\n",
+                "c".repeat(64)
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_universal_requirements() {
+        let environments = ["linux-x86_64", "windows-amd64"].map(|platform| {
+            Pep508EnvMakers::for_target_platform(
+                platform.parse().unwrap(),
+                &PythonInterpreterVersion::new(3, 11, 0),
+            )
+            .0
+        });
+        let markers = environment_markers(&environments);
+        let packages = merge_resolutions(
+            &markers,
+            vec![
+                vec![
+                    package("click", "8.1.7", None),
+                    package("numpy", "1.26.4", None),
+                ],
+                vec![
+                    package("click", "8.1.7", None),
+                    package("colorama", "0.4.6", None),
+                    package("numpy", "1.26.3", None),
+                ],
+            ],
+        );
+
+        let mut output = Vec::new();
+        write_universal_requirements(&mut output, &packages).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "click==8.1.7\n\
+             colorama==0.4.6 ; sys_platform == 'win32' and platform_machine == 'AMD64'\n\
+             numpy==1.26.3 ; sys_platform == 'win32' and platform_machine == 'AMD64'\n\
+             numpy==1.26.4 ; sys_platform == 'linux' and platform_machine == 'x86_64'\n"
+        );
+    }
+
+    fn test_packages() -> [PinnedPackage; 3] {
+        let mut flask = package("flask", "3.0.0", None);
+        flask.extras = ["async".parse().unwrap()].into_iter().collect();
+        let mut local = package("local", "1.0", None);
+        local.version = PypiVersion::Url(
+            "https://example.com/local-1.0.tar.gz#sha256=0a1b"
+                .parse()
+                .unwrap(),
+        );
+        [package("jinja2", "3.1.2", None), flask, local]
+    }
+
+    #[test]
+    fn test_write_conda_pip_section() {
+        let mut output = Vec::new();
+        write_conda_pip_section(&mut output, &test_packages()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let docs = yaml_rust::YamlLoader::load_from_str(&output).unwrap();
+        let dependencies = docs[0]["dependencies"].as_vec().unwrap();
+        assert_eq!(dependencies[0].as_str(), Some("pip"));
+        let pip = dependencies[1]["pip"]
+            .as_vec()
+            .unwrap()
+            .iter()
+            .map(|r| r.as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pip,
+            [
+                "flask[async]==3.0.0",
+                "jinja2==3.1.2",
+                "local @ https://example.com/local-1.0.tar.gz#sha256=0a1b"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_inline_script_metadata() {
+        let mut output = Vec::new();
+        write_inline_script_metadata(&mut output, &test_packages()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        // The metadata block is TOML with every line prefixed with `# `
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.first(), Some(&"# /// script"));
+        assert_eq!(lines.last(), Some(&"# ///"));
+        let toml = lines[1..lines.len() - 1]
+            .iter()
+            .map(|line| line.strip_prefix("# ").unwrap())
+            .join("\n");
+        let metadata: toml::Table = toml.parse().unwrap();
+        let dependencies = metadata["dependencies"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r.as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dependencies,
+            [
+                "flask[async]==3.0.0",
+                "jinja2==3.1.2",
+                "local @ https://example.com/local-1.0.tar.gz#sha256=0a1b"
+            ]
+        );
+    }
+
+    fn table(packages: &[PinnedPackage], show_urls: bool) -> Vec<Vec<String>> {
+        let mut output = Vec::new();
+        write_resolution_table(&mut output, packages, show_urls).unwrap();
+        console::strip_ansi_codes(&String::from_utf8(output).unwrap())
+            .lines()
+            .map(|line| line.split_whitespace().map(ToOwned::to_owned).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_url_column() {
+        let packages = [
+            package(
+                "zeta",
+                "2.0",
+                Some("https://files.example.com/packages/zeta-2.0-py3-none-any.whl"),
+            ),
+            package(
+                "alpha",
+                "1.0",
+                Some("https://files.example.com/packages/alpha-1.0.tar.gz"),
+            ),
+            package("locked", "3.0", None),
+        ];
+
+        assert_eq!(
+            table(&packages, false),
+            [
+                vec!["Name", "Version"],
+                vec!["alpha", "1.0"],
+                vec!["locked", "3.0"],
+                vec!["zeta", "2.0"],
+            ]
+        );
+
+        let rows = table(&packages, true);
+        assert_eq!(rows[0], ["Name", "Version", "Url"]);
+        assert_eq!(
+            rows[1],
+            [
+                "alpha",
+                "1.0",
+                "https://files.example.com/packages/alpha-1.0.tar.gz"
+            ]
+        );
+        assert_eq!(rows[2], ["locked", "3.0", "-"]);
+        for row in [&rows[1], &rows[3]] {
+            assert!(Url::parse(&row[2]).is_ok(), "{} is not absolute", row[2]);
+        }
+    }
+}